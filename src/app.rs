@@ -2,21 +2,34 @@ use crossterm::{
     execute,
     terminal::{Clear, ClearType},
 };
-use dialoguer::Select;
-use rand::Rng;
+use dialoguer::{Input, Select};
 use sap_scripting::*;
 use std::env;
 use std::io::{self, stdout, Write};
 use std::thread::{self};
 use std::time::Duration;
 
+use crate::utils::config_ops::{active_profile, set_active_profile};
 use crate::utils::config_types::SapConfig;
-use crate::utils::utils::{decrypt_data, encrypt_data, KEY_FILE_SUFFIX};
+use crate::utils::credential_store::{open_credential_store, Credentials};
+use crate::utils::sap_password_change::{
+    change_expired_password, check_new_password, is_unattended, password_change_required,
+};
+use crate::utils::sap_real_impl::RealSapSession;
+use crate::utils::sap_session_pool::connection_session_ids;
+use crate::utils::sap_session_state::{return_to_easy_access, SessionState};
+use crate::utils::sap_session_watchdog::SessionWatchdog;
+use crate::utils::sap_status::{
+    read_status, CLIENT_NOT_AVAILABLE, LOGON_INCORRECT, NEW_PASSWORD_REQUIRED,
+};
+use crate::utils::sap_wait::{wait_timeouts, wait_until, WaitCondition};
 use crate::utils::*;
 
 // Struct to hold login parameters
 pub struct LoginParams {
     pub instance_id: String,
+    /// Entry of the credential store the user and password belong to
+    pub credential_id: String,
     pub client_id: String,
     pub user: String,
     pub password: String,
@@ -48,9 +61,9 @@ pub fn handle_configure_reports_dir() -> anyhow::Result<()> {
 
 pub fn handle_login(session: &GuiSession) -> anyhow::Result<()> {
     // Check if already logged in
-    let transaction = session.info()?.transaction()?;
-    if !transaction.contains("S000") {
-        println!("Already logged in. Current transaction: {}", transaction);
+    let state = SessionState::detect(&RealSapSession::new(session))?;
+    if state.is_logged_in() {
+        println!("Already logged in. Current state: {}", state);
         thread::sleep(Duration::from_secs(2));
         // ask if refresh session (close popups)
         let options = vec!["Y", "N"];
@@ -62,17 +75,26 @@ pub fn handle_login(session: &GuiSession) -> anyhow::Result<()> {
             .unwrap();
         match choice {
             0 => {
-                close_popups(session, None, None)?;
-                session.start_transaction("SESSION_MANAGER".into())?;
+                let state = return_to_easy_access(&RealSapSession::new(session))?;
+                println!("Session refreshed: {}", state);
             }
             _ => {} // no-op
         }
         return Ok(());
     }
 
-    // Not logged in, perform login
-    let login_params = get_login_parameters()?;
-    login(session, &login_params)?;
+    if state == SessionState::Disconnected {
+        return Err(anyhow::anyhow!("SAP session is not available"));
+    }
+
+    // Not logged in, pick the logon profile (unless given with --profile) and perform login
+    if active_profile().is_none() {
+        if let Err(e) = choose_profile() {
+            eprintln!("Could not read the logon profiles: {}", e);
+        }
+    }
+    let mut login_params = get_login_parameters()?;
+    login(session, &mut login_params)?;
     println!("Login successful!");
     thread::sleep(Duration::from_secs(2));
 
@@ -90,30 +112,141 @@ pub fn handle_logout(session: &GuiSession) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn get_or_create_connection(engine: &GuiApplication) -> windows::core::Result<GuiConnection> {
+/// Create a watchdog that logs the session back in with the saved credentials
+///
+/// The credentials are read on the first re-login and reused afterwards.
+pub fn session_watchdog(session: &GuiSession) -> SessionWatchdog<'_> {
+    let mut saved_params: Option<LoginParams> = None;
+    SessionWatchdog::from_config(move |_| {
+        let mut params = match saved_params.take() {
+            Some(params) => params,
+            None => get_login_parameters()?,
+        };
+        let result = login(session, &mut params);
+        saved_params = Some(params);
+        result
+    })
+}
+
+/// Default SAP Logon entry opened when no connection is open
+///
+/// The `connection` of the logon profile in use comes first, then `SAP_CONNECTION_NAME`.
+pub fn default_connection_name() -> String {
+    if let Some(connection) = SapConfig::load().ok().and_then(|config| config.get_connection()) {
+        return connection;
+    }
+    env::var("SAP_CONNECTION_NAME").unwrap_or_else(|_| "Production Instance".to_string())
+}
+
+/// Get the profile given with `--profile <name>` or `--profile=<name>`
+pub fn profile_arg(args: impl IntoIterator<Item = String>) -> Option<String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            return args.next();
+        }
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+/// Let the user pick the logon profile to log in with (if config.toml has any)
+pub fn choose_profile() -> anyhow::Result<()> {
+    let config = SapConfig::load()?;
+    let names = config.profile_names();
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    let mut items: Vec<String> = names
+        .iter()
+        .map(|name| {
+            let mut resolved = config.clone();
+            resolved.active_profile = Some(name.clone());
+            format!("{} ({} client {})", name, resolved.get_instance_id(), resolved.get_client())
+        })
+        .collect();
+    // Without a default profile in [global], logging in without one is also possible
+    let has_default = config.global.as_ref().is_some_and(|g| g.additional_params.contains_key("profile"));
+    if !has_default {
+        items.push("No profile ([global] settings)".to_string());
+    }
+    // Start on "No profile" unless a default profile is set
+    let current = config
+        .active_profile_name()
+        .and_then(|active| names.iter().position(|name| *name == active))
+        .unwrap_or(if has_default { 0 } else { names.len() });
+
+    // Ask again while the chosen profile would share its credentials with another client
+    loop {
+        let selection = Select::new()
+            .with_prompt("Choose a logon profile")
+            .items(&items)
+            .default(current)
+            .interact()
+            .unwrap();
+        let profile = names.get(selection).map(|name| name.as_str());
+        match profile.map(|profile| config.check_profile(profile)) {
+            Some(Err(e)) => eprintln!("{}", e),
+            _ => {
+                set_active_profile(profile);
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Check whether a target is a connection string (e.g. `/H/host/S/3200`) rather than a SAP Logon entry
+pub fn is_connection_string(target: &str) -> bool {
+    let target = target.trim();
+    target.starts_with("/H/")
+        || target.starts_with("/M/")
+        || target.to_lowercase().starts_with("conn=")
+}
+
+/// Get an open connection, or open one by SAP Logon entry or connection string
+///
+/// Without a target the first open connection is used, and `SAP_CONNECTION_NAME` is opened if there is none.
+pub fn get_or_create_connection(engine: &GuiApplication, target: Option<&str>) -> windows::core::Result<GuiConnection> {
     // Try to get existing connection
     if let Ok(children) = GuiApplicationExt::children(engine) {
-        if children.count()? > 0 {
-            if let Ok(component) = children.element_at(0) {
+        for i in 0..children.count()? {
+            if let Ok(component) = children.element_at(i) {
                 if let Some(connection) = component.downcast::<GuiConnection>() {
-                    return Ok(connection);
+                    let matches = match target {
+                        Some(target) => {
+                            let target = target.trim();
+                            connection.description().map(|d| d.eq_ignore_ascii_case(target)).unwrap_or(false)
+                                || connection.connection_string().map(|c| c.eq_ignore_ascii_case(target)).unwrap_or(false)
+                        }
+                        None => true,
+                    };
+                    if matches {
+                        return Ok(connection);
+                    }
                 }
             }
         }
     }
 
     // No existing connection, create a new one
-    println!("No existing SAP connection found. Creating a new connection...");
+    println!("No matching SAP connection found. Creating a new connection...");
 
-    // In a real application, you might want to get the connection name from config
-    // For now, we'll use a default or let the user specify
-    let connection_name =
-        env::var("SAP_CONNECTION_NAME").unwrap_or_else(|_| "Production Instance".to_string());
+    let connection_name = match target {
+        Some(target) => target.trim().to_string(),
+        None => default_connection_name(),
+    };
 
     println!("Opening connection: {}", connection_name);
 
     // Open the connection
-    let component = engine.open_connection(connection_name)?;
+    let component = if is_connection_string(&connection_name) {
+        engine.open_connection_by_connection_string(connection_name)?
+    } else {
+        engine.open_connection(connection_name)?
+    };
 
     // Convert to GuiConnection
     match component.downcast::<GuiConnection>() {
@@ -125,11 +258,213 @@ pub fn get_or_create_connection(engine: &GuiApplication) -> windows::core::Resul
     }
 }
 
+/// SAP GUI scripting objects of the session the tool works with
+pub struct SapConnection {
+    // Keep COM and the scripting wrapper alive while the objects below are in use
+    _com_instance: SAPComInstance,
+    _wrapper: SAPWrapper,
+    pub engine: GuiApplication,
+    pub connection: GuiConnection,
+    pub session: GuiSession,
+}
+
+impl SapConnection {
+    /// Attach to the scripting engine and use the first session of a connection
+    ///
+    /// `target` selects the connection, see `get_or_create_connection`.
+    pub fn attach(target: Option<&str>) -> windows::core::Result<Self> {
+        let com_instance = SAPComInstance::new().map_err(|e| {
+            eprintln!("Warning: Couldn't initialize COM environment: {}", e);
+            e
+        })?;
+        let wrapper = com_instance.sap_wrapper().map_err(|e| {
+            eprintln!("Warning: Error getting SAP wrapper: {}", e);
+            eprintln!("Make sure SAP GUI is installed and properly configured.");
+            e
+        })?;
+        let engine = wrapper.scripting_engine().map_err(|e| {
+            eprintln!("Warning: Error getting SAP scripting engine: {}", e);
+            eprintln!("Make sure SAP GUI is running and scripting is enabled.");
+            e
+        })?;
+        let connection = get_or_create_connection(&engine, target).map_err(|e| {
+            eprintln!("Warning: Error getting SAP connection: {}", e);
+            e
+        })?;
+        let session = connection_session(&connection, 0).map_err(|e| {
+            eprintln!("Warning: Failed to get SAP session: {}", e);
+            e
+        })?;
+
+        Ok(Self {
+            _com_instance: com_instance,
+            _wrapper: wrapper,
+            engine,
+            connection,
+            session,
+        })
+    }
+}
+
+/// Get a session of a connection by index
+pub fn connection_session(connection: &GuiConnection, index: usize) -> windows::core::Result<GuiSession> {
+    let children = GuiConnectionExt::children(connection)?;
+    match children.element_at(index as i32)?.downcast::<GuiSession>() {
+        Some(session) => Ok(session),
+        None => Err(windows::core::Error::new(
+            windows::core::HRESULT(-2147467259),
+            format!("Connection has no session {}", index).into(),
+        )),
+    }
+}
+
+/// Describe a session for selection, e.g. `/app/con[0]/ses[1] - ES1 025 USER (VT11)`
+fn describe_session(session: &GuiSession) -> String {
+    let id = session.id().unwrap_or_default();
+    match RealSapSession::new(session).info() {
+        Ok(info) => format!(
+            "{} - {} {} {} ({})",
+            id,
+            info.system_name().unwrap_or_default(),
+            info.client().unwrap_or_default(),
+            info.user().unwrap_or_default(),
+            info.transaction().unwrap_or_default()
+        ),
+        Err(_) => id,
+    }
+}
+
+/// Offer to change `instance_id` in config.toml to the system of the session
+///
+/// Saved credentials and `[instance.<id>]` settings belong to the instance ID, so it is
+/// only changed when the user agrees.
+pub fn sync_instance_id(session: &GuiSession) -> anyhow::Result<()> {
+    let system_name = session.info()?.system_name()?;
+    if system_name.is_empty() {
+        return Ok(());
+    }
+
+    let mut config = SapConfig::load()?;
+    if config.get_instance_id().eq_ignore_ascii_case(&system_name) {
+        return Ok(());
+    }
+    // The logon profile in use decides the instance ID
+    if config.get_profile().is_some_and(|profile| profile.instance_id.is_some()) {
+        return Ok(());
+    }
+    let instance_id = config.get_instance_id();
+    println!("The session is on system {}, config.toml uses instance ID {}", system_name, instance_id);
+    println!("Saved credentials and [instance.{}] settings are not used for another instance ID.", instance_id);
+    let options = vec!["No, keep the instance ID", "Yes, change the instance ID"];
+    let choice = Select::new()
+        .with_prompt(format!("Change the instance ID to {}?", system_name))
+        .items(&options)
+        .default(0)
+        .interact()
+        .unwrap();
+    if choice == 0 {
+        return Ok(());
+    }
+
+    println!("Setting instance ID from {} to {}", instance_id, system_name);
+    config.set_instance_id(&system_name);
+    config.save()?;
+
+    Ok(())
+}
+
+/// Attach to SAP GUI again and pick the connection and session to work with
+pub fn handle_connect() -> anyhow::Result<SapConnection> {
+    clear_screen();
+    println!("Connect / Switch SAP System");
+    println!("===========================");
+
+    // Attach again, SAP GUI may have been started or restarted since
+    let com_instance = SAPComInstance::new()?;
+    let wrapper = com_instance.sap_wrapper()?;
+    let engine = wrapper.scripting_engine()?;
+
+    // Offer the open connections and a new one
+    let mut connections = Vec::new();
+    let children = GuiApplicationExt::children(&engine)?;
+    for i in 0..children.count()? {
+        if let Some(connection) = children.element_at(i)?.downcast::<GuiConnection>() {
+            connections.push(connection);
+        }
+    }
+    let mut items: Vec<String> = connections
+        .iter()
+        .map(|connection| {
+            format!(
+                "{} ({} sessions)",
+                connection.description().unwrap_or_default(),
+                connection_session_ids(connection).map(|ids| ids.len()).unwrap_or(0)
+            )
+        })
+        .collect();
+    items.push("Open a new connection (SAP Logon entry or connection string)".to_string());
+
+    let choice = Select::new()
+        .with_prompt("Choose a connection")
+        .items(&items)
+        .default(0)
+        .interact()
+        .unwrap();
+
+    let connection = if choice < connections.len() {
+        connections.swap_remove(choice)
+    } else {
+        let target: String = Input::new()
+            .with_prompt("SAP Logon entry or connection string (e.g. /H/host/S/3200)")
+            .allow_empty(false)
+            .default(default_connection_name())
+            .interact()
+            .unwrap();
+        get_or_create_connection(&engine, Some(&target))?
+    };
+
+    // Pick the session when the connection has more than one
+    let ids = connection_session_ids(&connection)?;
+    let index = if ids.len() > 1 {
+        let sessions: Vec<String> = (0..ids.len())
+            .map(|i| {
+                connection_session(&connection, i)
+                    .map(|session| describe_session(&session))
+                    .unwrap_or_else(|_| ids[i].clone())
+            })
+            .collect();
+        Select::new()
+            .with_prompt("Choose a session")
+            .items(&sessions)
+            .default(0)
+            .interact()
+            .unwrap()
+    } else {
+        0
+    };
+    let session = connection_session(&connection, index)?;
+
+    if let Err(e) = sync_instance_id(&session) {
+        eprintln!("Could not update the instance ID: {}", e);
+    }
+    println!("Using {}", describe_session(&session));
+    thread::sleep(Duration::from_secs(2));
+
+    Ok(SapConnection {
+        _com_instance: com_instance,
+        _wrapper: wrapper,
+        engine,
+        connection,
+        session,
+    })
+}
+
 pub fn get_login_parameters() -> windows::core::Result<LoginParams> {
     // Default values
     let mut params = LoginParams {
         client_id: "025".to_string(),
         instance_id: "rs".to_string(),
+        credential_id: "rs".to_string(),
         user: String::new(),
         password: String::new(),
         language: "EN".to_string(),
@@ -144,47 +479,39 @@ pub fn get_login_parameters() -> windows::core::Result<LoginParams> {
         }
     };
 
-    // Update instance_id from config
+    // Update instance_id, client and language from config (and the logon profile in use)
     params.instance_id = config.get_instance_id();
+    params.credential_id = config.get_credential_id();
+    params.client_id = config.get_client();
+    params.language = config.get_language();
+    if let Some(profile) = config.active_profile_name() {
+        println!("Logon profile: {} ({} client {})", profile, params.instance_id, params.client_id);
+    }
 
-    // Try to read from auth file
-    let auth_path = match env::var("USERPROFILE") {
-        Ok(profile) => format!("{}\\Documents\\SAP\\", profile),
-        Err(_) => {
-            eprintln!("Could not determine user profile directory");
-            String::from(".\\")
+    // Try to read credentials from the configured store
+    let mut ask_for_credentials = true;
+    let mut store = match open_credential_store(&config, &params.client_id) {
+        Ok(store) => Some(store),
+        Err(e) => {
+            eprintln!("Could not open the credential store: {}", e);
+            None
         }
     };
-
-    // Use instance_id from params (which was updated from config)
-    let auth_file = format!("{}sap_auto_{}.txt", auth_path, params.instance_id);
-    let key_file = format!("{}sap_auto_{}{}", auth_path, params.instance_id, KEY_FILE_SUFFIX);
-
-    // Try to read credentials from file
-    let mut ask_for_credentials = true;
-    if let Ok(encrypted_data) =
-        std::fs::read_to_string(&auth_file).map_err(|_| windows::core::Error::from_win32())
-    {
-        if let Ok(key_data) =
-            std::fs::read(&key_file).map_err(|_| windows::core::Error::from_win32())
-        {
-            match decrypt_data(&encrypted_data, &key_data) {
-                Ok(decrypted_data) => {
-                    let lines: Vec<&str> = decrypted_data.split('\n').collect();
-                    if lines.len() >= 2 {
-                        params.user = lines[0].to_string();
-                        params.password = lines[1].to_string();
-                        ask_for_credentials = false;
-                    }
-                }
-                Err(_) => {
-                    eprintln!("Failed to decrypt credentials. They may be corrupted or using an old format.");
-                }
+    if let Some(store) = store.as_ref() {
+        match store.get(&params.credential_id) {
+            Ok(Some(credentials)) => {
+                params.user = credentials.user;
+                params.password = credentials.password;
+                ask_for_credentials = false;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("{}", e);
             }
         }
     }
 
-    // If credentials not found in file, ask user
+    // If credentials not found in the store, ask user
     if ask_for_credentials {
         println!("Please enter your SAP credentials:");
 
@@ -204,27 +531,70 @@ pub fn get_login_parameters() -> windows::core::Result<LoginParams> {
             params.password = rpassword::read_password().unwrap();
         }
 
-        // Ask if user wants to save credentials
-        print!("Save credentials for future use? (y/n): ");
-        io::stdout().flush().unwrap();
-        let mut save_choice = String::new();
-        io::stdin().read_line(&mut save_choice).unwrap();
-
-        if save_choice.trim().to_lowercase() == "y" {
-            save_credentials(
-                &auth_path,
-                &auth_file,
-                &key_file,
-                &params.user,
-                &params.password,
-            )?;
+        // Ask if user wants to save credentials (unless the store is read only)
+        if let Some(store) = store.as_mut().filter(|store| !store.is_read_only()) {
+            print!("Save credentials for future use? (y/n): ");
+            io::stdout().flush().unwrap();
+            let mut save_choice = String::new();
+            io::stdin().read_line(&mut save_choice).unwrap();
+
+            if save_choice.trim().to_lowercase() == "y" {
+                let credentials = Credentials::new(&params.user, &params.password);
+                if let Err(e) = store.set(&params.credential_id, &credentials) {
+                    eprintln!("Failed to save credentials: {}", e);
+                    return Err(windows::core::Error::from_win32());
+                }
+                println!("Credentials saved to the {} credential store", store.name());
+            }
         }
     }
 
     Ok(params)
 }
 
-pub fn login(session: &GuiSession, params: &LoginParams) -> windows::core::Result<()> {
+/// Ask for a new password when SAP asks for one during logon
+///
+/// Unattended runs fail with `E_PASSWORD_CHANGE_REQUIRED` instead.
+fn ask_new_password(params: &LoginParams) -> windows::core::Result<String> {
+    let unattended = SapConfig::load().map(|config| is_unattended(&config)).unwrap_or(false);
+    if unattended {
+        return Err(password_change_required(&params.user));
+    }
+
+    println!("The password of {} has expired.", params.user);
+    print!("New password: ");
+    io::stdout().flush().unwrap();
+    let new_password = rpassword::read_password().unwrap();
+    print!("Repeat new password: ");
+    io::stdout().flush().unwrap();
+    let repeated = rpassword::read_password().unwrap();
+    check_new_password(&params.password, &new_password, &repeated)?;
+    Ok(new_password)
+}
+
+/// Save a changed password where the old one was saved, so the next run uses it
+fn update_saved_password(params: &LoginParams) {
+    let result = SapConfig::load().and_then(|config| {
+        let mut store = open_credential_store(&config, &params.client_id)?;
+        if store.is_read_only() {
+            println!(
+                "Update the password in the {} credential store, it cannot be changed from here",
+                store.name()
+            );
+            return Ok(());
+        }
+        if store.get(&params.credential_id)?.is_some() {
+            store.rotate_password(&params.credential_id, &params.password)?;
+            println!("Saved password of {} updated", params.credential_id);
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("The password was changed in SAP but could not be saved: {}", e);
+    }
+}
+
+pub fn login(session: &GuiSession, params: &mut LoginParams) -> windows::core::Result<()> {
     println!("Logging in to SAP...");
 
     // Find and fill client field
@@ -262,8 +632,24 @@ pub fn login(session: &GuiSession, params: &LoginParams) -> windows::core::Resul
         }
     }
 
-    // Wait a bit for login to process
-    thread::sleep(Duration::from_millis(1000));
+    // Wait for login to process
+    let timeouts = wait_timeouts();
+    wait_until(
+        &RealSapSession::new(session),
+        &WaitCondition::NotBusy,
+        timeouts.login,
+        timeouts.poll,
+    )?;
+
+    // Expired or initial password, SAP asks for a new one before logging on
+    let sap_session = RealSapSession::new(session);
+    if SessionState::detect(&sap_session)? == SessionState::PasswordChange {
+        let new_password = ask_new_password(params)?;
+        change_expired_password(&sap_session, &new_password)?;
+        params.password = new_password;
+        println!("Password changed");
+        update_saved_password(params);
+    }
 
     // Check for multiple logon popup
     if let Ok(popup) = session.find_by_id("wnd[1]".to_string()) {
@@ -297,27 +683,23 @@ pub fn login(session: &GuiSession, params: &LoginParams) -> windows::core::Resul
     }
 
     // Check for error messages in status bar
-    if let Ok(statusbar) = session.find_by_id("wnd[0]/sbar".to_string()) {
-        if let Some(status) = statusbar.downcast::<GuiStatusbar>() {
-            let message = status.text()?;
-
-            match message {
-                msg if msg.contains("incorrect") => {
-                    eprintln!("Login failed: {}", msg);
-                    return Err(windows::core::Error::from_win32());
-                }
-                msg if msg.contains("new password") => {
-                    eprintln!("Password update required: {}", msg);
-                    return Err(windows::core::Error::from_win32());
-                }
-
-                msg if msg.contains("exist") => {
-                    eprintln!("Incorrect info or something not found");
-                    return Err(windows::core::Error::from_win32());
-                }
+    if let Some(message) = read_status(&sap_session, 0)? {
+        match message {
+            msg if msg.is(LOGON_INCORRECT) => {
+                eprintln!("Login failed: {}", msg);
+                return Err(windows::core::Error::from_win32());
+            }
+            msg if msg.is(NEW_PASSWORD_REQUIRED) => {
+                eprintln!("Password update required: {}", msg);
+                return Err(password_change_required(&params.user));
+            }
 
-                _ => {} // no-op
+            msg if msg.is(CLIENT_NOT_AVAILABLE) => {
+                eprintln!("Incorrect info or something not found");
+                return Err(windows::core::Error::from_win32());
             }
+
+            _ => {} // no-op
         }
     }
 
@@ -330,41 +712,3 @@ pub fn login(session: &GuiSession, params: &LoginParams) -> windows::core::Resul
 
     Ok(())
 }
-
-pub fn save_credentials(
-    auth_path: &str,
-    auth_file: &str,
-    key_file: &str,
-    username: &str,
-    password: &str,
-) -> windows::core::Result<()> {
-    // Create directory if it doesn't exist
-    std::fs::create_dir_all(auth_path).map_err(|_| windows::core::Error::from_win32())?;
-
-    // Generate or load encryption key
-    let key = if let Ok(existing_key) = std::fs::read(key_file) {
-        existing_key
-    } else {
-        // Generate a new random key
-        let mut key = vec![0u8; 32]; // 256 bits for AES-256
-        rand::thread_rng().fill(&mut key[..]);
-
-        // Save the key
-        std::fs::write(key_file, &key).map_err(|_| windows::core::Error::from_win32())?;
-        key
-    };
-
-    // Encrypt and save credentials
-    let content = format!("{}\n{}", username, password);
-    match encrypt_data(&content, &key) {
-        Ok(encrypted) => {
-            std::fs::write(auth_file, encrypted).map_err(|_| windows::core::Error::from_win32())?;
-            println!("Encrypted credentials saved to {}", auth_file);
-            Ok(())
-        }
-        Err(_) => {
-            eprintln!("Failed to encrypt credentials");
-            Err(windows::core::Error::from_win32())
-        }
-    }
-}
@@ -1,5 +1,5 @@
 use dialoguer::Select;
-use sap_scripting::*;
+use std::env;
 use std::thread;
 use std::time::Duration;
 
@@ -14,9 +14,15 @@ mod zmdesnr;
 mod zmdesnr_module;
 
 use app::*;
-use utils::config_ops::handle_configure_reports_dir;
+use utils::config_ops::{handle_configure_reports_dir, set_active_profile};
+use utils::config_types::SapConfig;
 use utils::excel_file_ops::handle_read_excel_file;
 use utils::loop_config::{handle_configure_loop, run_loop};
+use utils::sap_popups::run_with_popup_rules;
+use utils::sap_real_impl::RealSapSession;
+use utils::sap_recording::run_with_trace;
+use utils::sap_session_pool::session_ids;
+use utils::sap_session_state::SessionState;
 use utils::sequence_config::{handle_configure_sequence, run_sequence};
 use vl06o_module::{run_vl06o_auto, run_vl06o_date_update_module, run_vl06o_module};
 use vl06o_delivery_module::{run_vl06o_delivery_packages_module, run_vl06o_delivery_packages_auto};
@@ -27,95 +33,53 @@ fn main() -> anyhow::Result<()> {
     // Initialize logging if needed
     // pretty_env_logger::init();
 
-    // Flag to track if SAP is connected
-    let mut sap_connected = false;
-
-    // Optional variables to hold SAP components if connection is successful
-    let mut com_instance: Option<SAPComInstance> = None;
-    let mut wrapper: Option<SAPWrapper> = None;
-    let mut engine: Option<GuiApplication> = None;
-    let mut connection: Option<GuiConnection> = None;
-    let mut session: Option<GuiSession> = None;
-
-    // Try to initialize COM environment
-    match SAPComInstance::new() {
-        Ok(instance) => {
-            com_instance = Some(instance);
-
-            // Try to get SAP wrapper
-            match com_instance.as_ref().unwrap().sap_wrapper() {
-                Ok(w) => {
-                    wrapper = Some(w);
-
-                    // Try to get the scripting engine
-                    match wrapper.as_ref().unwrap().scripting_engine() {
-                        Ok(e) => {
-                            engine = Some(e);
+    // Use a logon profile for the whole run (--profile <name>), e.g. for scheduled runs
+    if let Some(profile) = profile_arg(env::args().skip(1)) {
+        SapConfig::load()?.check_profile(&profile)?;
+        set_active_profile(Some(&profile));
+        println!("Using logon profile {}", profile);
+    }
 
-                            // Try to get connection or create a new one
-                            match get_or_create_connection(engine.as_ref().unwrap()) {
-                                Ok(conn) => {
-                                    connection = Some(conn);
+    // Attach to SAP GUI and use the first session of the profile's connection (or of the first connection)
+    let config = SapConfig::load().ok();
 
-                                    // Try to get the first session
-                                    match GuiConnectionExt::children(connection.as_ref().unwrap()) {
-                                        Ok(children) => {
-                                            if let Ok(element) = children.element_at(0) {
-                                                if let Some(s) = element.downcast() {
-                                                    session = Some(s);
-                                                    sap_connected = true;
-                                                }
-                                            }
-                                        }
-                                        Err(e) => {
-                                            eprintln!("Warning: Failed to get SAP session: {}", e);
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("Warning: Error getting SAP connection: {}", e);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Warning: Error getting SAP scripting engine: {}", e);
-                            eprintln!("Make sure SAP GUI is running and scripting is enabled.");
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Warning: Error getting SAP wrapper: {}", e);
-                    eprintln!("Make sure SAP GUI is installed and properly configured.");
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("Warning: Couldn't initialize COM environment: {}", e);
-        }
+    // Say which keys of config.toml were left out (wrong type or value)
+    for key in config.iter().flat_map(|config| &config.dropped_keys) {
+        eprintln!("Warning: Ignoring {} in config.toml", key);
     }
 
-    if !sap_connected {
+    let connection = config.and_then(|config| config.get_connection());
+    let mut sap = SapConnection::attach(connection.as_deref()).ok();
+
+    if sap.is_none() {
         println!("SAP connection not available. Some features will be disabled.");
+        println!("Use \"Connect / Switch SAP System\" once SAP GUI is running.");
         thread::sleep(Duration::from_secs(2));
     }
 
+    // List the sessions of all open connections
+    if let Some(sap) = sap.as_ref() {
+        if let Ok(ids) = session_ids(&sap.engine) {
+            println!("Open SAP sessions: {}", ids.join(", "));
+        }
+    }
+
     // Main application loop
     loop {
         clear_screen();
 
-        // Check if already logged in (only if SAP is connected)
-        let is_logged_in = if sap_connected {
-            let transaction = session
-                .as_ref()
-                .unwrap()
-                .info()
-                .unwrap()
-                .transaction()
-                .unwrap();
-            !transaction.contains("S000")
-        } else {
-            false
+        // Check what the SAP session is showing (only if SAP is connected)
+        let session = sap.as_ref().map(|sap| &sap.session);
+        let state = match session {
+            Some(s) => SessionState::detect(&RealSapSession::new(s))
+                .unwrap_or(SessionState::Disconnected),
+            None => SessionState::Disconnected,
         };
+        let sap_connected = state.is_connected();
+        let is_logged_in = state.is_logged_in();
+        if sap_connected {
+            println!("SAP session: {}", state);
+        }
 
         // Create menu options based on SAP connection and login status
         let options = if sap_connected {
@@ -139,6 +103,7 @@ fn main() -> anyhow::Result<()> {
                     "Configure Sequence",
                     "Read Excel File",
                     "Log out of SAP",
+                    "Connect / Switch SAP System",
                     "Exit",
                 ]
             } else {
@@ -161,6 +126,7 @@ fn main() -> anyhow::Result<()> {
                     "Configure Sequence",
                     "Read Excel File",
                     "Log out of SAP (Not available - Login required)",
+                    "Connect / Switch SAP System",
                     "Exit",
                 ]
             }
@@ -184,6 +150,7 @@ fn main() -> anyhow::Result<()> {
                     "Configure Sequence",
                     "Read Excel File",
                     "Log out of SAP (Not available - SAP connection required)",
+                    "Connect / Switch SAP System",
                     "Exit",
                 ]
         };
@@ -199,7 +166,7 @@ fn main() -> anyhow::Result<()> {
             0 => {
                 // Log in to SAP
                 if sap_connected {
-                    if let Err(e) = handle_login(session.as_ref().unwrap()) {
+                    if let Err(e) = handle_login(session.unwrap()) {
                         eprintln!("Error logging in: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -211,7 +178,7 @@ fn main() -> anyhow::Result<()> {
             1 => {
                 // Run VT11 module (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_vt11_module(session.as_ref().unwrap()) {
+                    if let Err(e) = run_with_trace(&RealSapSession::new(session.unwrap()), "VT11", |s| run_with_popup_rules(s, run_vt11_module)) {
                         eprintln!("Error running VT11 module: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -226,7 +193,7 @@ fn main() -> anyhow::Result<()> {
             2 => {
                 // Run VT11 Auto module (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_vt11_auto(session.as_ref().unwrap()) {
+                    if let Err(e) = run_with_trace(&RealSapSession::new(session.unwrap()), "VT11", |s| run_with_popup_rules(s, run_vt11_auto)) {
                         eprintln!("Error running VT11 auto module: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -241,7 +208,7 @@ fn main() -> anyhow::Result<()> {
             3 => {
                 // Run VL06O module (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_vl06o_module(session.as_ref().unwrap()) {
+                    if let Err(e) = run_with_trace(&RealSapSession::new(session.unwrap()), "VL06O", |s| run_with_popup_rules(s, run_vl06o_module)) {
                         eprintln!("Error running VL06O module: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -256,7 +223,7 @@ fn main() -> anyhow::Result<()> {
             4 => {
                 // Run VL06O Auto module (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_vl06o_auto(session.as_ref().unwrap()) {
+                    if let Err(e) = run_with_trace(&RealSapSession::new(session.unwrap()), "VL06O", |s| run_with_popup_rules(s, run_vl06o_auto)) {
                         eprintln!("Error running VL06O auto module: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -271,7 +238,7 @@ fn main() -> anyhow::Result<()> {
             5 => {
                 // Run VL06O Date Update module (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_vl06o_date_update_module(session.as_ref().unwrap()) {
+                    if let Err(e) = run_with_trace(&RealSapSession::new(session.unwrap()), "VL06O", |s| run_with_popup_rules(s, run_vl06o_date_update_module)) {
                         eprintln!("Error running VL06O date update module: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -286,7 +253,7 @@ fn main() -> anyhow::Result<()> {
             6 => {
                 // Run VL06O Delivery Packages module (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_vl06o_delivery_packages_module(session.as_ref().unwrap()) {
+                    if let Err(e) = run_with_trace(&RealSapSession::new(session.unwrap()), "VL06O", |s| run_with_popup_rules(s, run_vl06o_delivery_packages_module)) {
                         eprintln!("Error running VL06O delivery packages module: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -301,7 +268,7 @@ fn main() -> anyhow::Result<()> {
             7 => {
                 // Run VL06O Delivery Packages Auto module (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_vl06o_delivery_packages_auto(session.as_ref().unwrap()) {
+                    if let Err(e) = run_with_trace(&RealSapSession::new(session.unwrap()), "VL06O", |s| run_with_popup_rules(s, run_vl06o_delivery_packages_auto)) {
                         eprintln!("Error running VL06O delivery packages auto module: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -316,7 +283,7 @@ fn main() -> anyhow::Result<()> {
             8 => {
                 // Run ZMDESNR module (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_zmdesnr_module(session.as_ref().unwrap()) {
+                    if let Err(e) = run_with_trace(&RealSapSession::new(session.unwrap()), "ZMDESNR", |s| run_with_popup_rules(s, run_zmdesnr_module)) {
                         eprintln!("Error running ZMDESNR module: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -331,7 +298,7 @@ fn main() -> anyhow::Result<()> {
             9 => {
                 // Run ZMDESNR Auto module (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_zmdesnr_auto(session.as_ref().unwrap()) {
+                    if let Err(e) = run_with_trace(&RealSapSession::new(session.unwrap()), "ZMDESNR", |s| run_with_popup_rules(s, run_zmdesnr_auto)) {
                         eprintln!("Error running ZMDESNR auto module: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -346,7 +313,7 @@ fn main() -> anyhow::Result<()> {
             10 => {
                 // Run Loop (using config) (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_loop(session.as_ref().unwrap()) {
+                    if let Err(e) = run_loop(session.unwrap()) {
                         eprintln!("Error running loop: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -361,7 +328,7 @@ fn main() -> anyhow::Result<()> {
             11 => {
                 // Run Sequence (using config) (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = run_sequence(session.as_ref().unwrap()) {
+                    if let Err(e) = run_sequence(session.unwrap()) {
                         eprintln!("Error running sequence: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -411,7 +378,7 @@ fn main() -> anyhow::Result<()> {
             17 => {
                 // Log out of SAP (only if logged in and SAP connected)
                 if sap_connected && is_logged_in {
-                    if let Err(e) = handle_logout(session.as_ref().unwrap()) {
+                    if let Err(e) = handle_logout(session.unwrap()) {
                         eprintln!("Error logging out: {}", e);
                         thread::sleep(Duration::from_secs(2));
                     }
@@ -424,6 +391,16 @@ fn main() -> anyhow::Result<()> {
                 }
             }
             18 => {
+                // Connect / Switch SAP System (available regardless of SAP connection)
+                match handle_connect() {
+                    Ok(connected) => sap = Some(connected),
+                    Err(e) => {
+                        eprintln!("Error connecting to SAP: {}", e);
+                        thread::sleep(Duration::from_secs(2));
+                    }
+                }
+            }
+            19 => {
                 // Exit application
                 clear_screen();
                 println!("Exiting application...");
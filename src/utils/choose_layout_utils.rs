@@ -1,8 +1,8 @@
-use sap_scripting::*;
 use std::thread;
 use std::time::Duration;
 
-use crate::utils::sap_ctrl_utils::*;
+use crate::utils::sap_interfaces::{exist_ctrl, SapSession};
+use crate::utils::sap_status::read_status;
 use crate::utils::sap_wnd_utils::*;
 use crate::utils::utils::*;
 
@@ -20,7 +20,7 @@ pub struct LayoutParams {
 /// This function is a port of the VBA function choose_layout
 /// If layout not found, it will ask the user to type in another layout name or exit
 pub fn choose_layout(
-    session: &GuiSession,
+    session: &dyn SapSession,
     tcode: &str,
     layout_row: &str,
 ) -> windows::core::Result<String> {
@@ -73,22 +73,18 @@ pub fn choose_layout(
         let mut button_found = false;
 
         // First try the standard button ID
-        if let Ok(button) = session.find_by_id("wnd[1]/tbar[0]/btn[71]".to_string()) {
-            if let Some(btn) = button.downcast::<GuiButton>() {
-                eprintln!("DEBUG: Button found at wnd[1]/tbar[0]/btn[71], pressing it");
-                btn.press()?;
-                button_found = true;
-            }
+        if let Ok(btn) = session.find_by_id("wnd[1]/tbar[0]/btn[71]".to_string()) {
+            eprintln!("DEBUG: Button found at wnd[1]/tbar[0]/btn[71], pressing it");
+            btn.press()?;
+            button_found = true;
         }
 
         // If standard button not found, try alternative button ID for vl06o
         if !button_found {
-            if let Ok(button) = session.find_by_id("wnd[1]/tbar[0]/btn[16]".to_string()) {
-                if let Some(btn) = button.downcast::<GuiButton>() {
-                    eprintln!("DEBUG: Button found at wnd[1]/tbar[0]/btn[16], pressing it");
-                    btn.press()?;
-                    button_found = true;
-                }
+            if let Ok(btn) = session.find_by_id("wnd[1]/tbar[0]/btn[16]".to_string()) {
+                eprintln!("DEBUG: Button found at wnd[1]/tbar[0]/btn[16], pressing it");
+                btn.press()?;
+                button_found = true;
             }
         }
 
@@ -105,21 +101,17 @@ pub fn choose_layout(
             eprintln!("DEBUG: Window 2 does not exist, trying alternative approach");
 
             // Try to find the search field directly in window 1
-            if let Ok(text_field) = session.find_by_id("wnd[1]/usr/txtRSYSF-STRING".to_string()) {
-                if let Some(txt) = text_field.downcast::<GuiTextField>() {
-                    eprintln!(
-                        "DEBUG: Text field found in window 1, setting text to '{}'",
-                        current_layout
-                    );
-                    txt.set_text(current_layout.clone())?;
-
-                    // Press Enter
-                    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                        if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                            eprintln!("DEBUG: Pressing Enter on window 1");
-                            wnd.send_v_key(0)?;
-                        }
-                    }
+            if let Ok(txt) = session.find_by_id("wnd[1]/usr/txtRSYSF-STRING".to_string()) {
+                eprintln!(
+                    "DEBUG: Text field found in window 1, setting text to '{}'",
+                    current_layout
+                );
+                txt.set_text(current_layout.clone())?;
+
+                // Press Enter
+                if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                    eprintln!("DEBUG: Pressing Enter on window 1");
+                    wnd.send_v_key(0)?;
                 }
             } else {
                 eprintln!("DEBUG: Text field not found in window 1");
@@ -133,14 +125,10 @@ pub fn choose_layout(
             let checkbox_exists = exist_ctrl(session, 2, "/usr/chkSCAN_STRING-START", true)?;
             if checkbox_exists.cband {
                 eprintln!("DEBUG: Checkbox exists, attempting to unselect it");
-                if let Ok(checkbox) =
-                    session.find_by_id("wnd[2]/usr/chkSCAN_STRING-START".to_string())
-                {
-                    if let Some(chk) = checkbox.downcast::<GuiCheckBox>() {
-                        eprintln!("DEBUG: Checkbox found, setting to unselected");
-                        chk.set_selected(false)?;
-                    } else {
-                        eprintln!("DEBUG: Checkbox found but downcast failed");
+                if let Ok(chk) = session.find_by_id("wnd[2]/usr/chkSCAN_STRING-START".to_string()) {
+                    eprintln!("DEBUG: Checkbox found, setting to unselected");
+                    if let Err(e) = chk.set_selected(false) {
+                        eprintln!("DEBUG: Checkbox found but could not be unselected: {}", e);
                     }
                 } else {
                     eprintln!("DEBUG: Failed to find checkbox by ID");
@@ -151,31 +139,28 @@ pub fn choose_layout(
 
             // Set layout name in text field
             eprintln!("DEBUG: Setting layout name in text field");
-            if let Ok(text_field) = session.find_by_id("wnd[2]/usr/txtRSYSF-STRING".to_string()) {
-                if let Some(txt) = text_field.downcast::<GuiTextField>() {
-                    eprintln!(
-                        "DEBUG: Text field found, setting text to '{}'",
-                        current_layout
-                    );
-                    txt.set_text(current_layout.clone())?;
-                } else {
-                    eprintln!("DEBUG: Text field found but downcast failed");
+            if let Ok(txt) = session.find_by_id("wnd[2]/usr/txtRSYSF-STRING".to_string()) {
+                eprintln!(
+                    "DEBUG: Text field found, setting text to '{}'",
+                    current_layout
+                );
+                if let Err(e) = txt.set_text(current_layout.clone()) {
+                    eprintln!("DEBUG: Text field found but text could not be set: {}", e);
                 }
             } else {
                 eprintln!("DEBUG: Text field not found");
 
                 // Try alternative text field ID
-                if let Ok(text_field) =
-                    session.find_by_id("wnd[2]/usr/txtGS_SEARCH-VALUE".to_string())
-                {
-                    if let Some(txt) = text_field.downcast::<GuiTextField>() {
+                if let Ok(txt) = session.find_by_id("wnd[2]/usr/txtGS_SEARCH-VALUE".to_string()) {
+                    eprintln!(
+                        "DEBUG: Alternative text field found, setting text to '{}'",
+                        current_layout
+                    );
+                    if let Err(e) = txt.set_text(current_layout.clone()) {
                         eprintln!(
-                            "DEBUG: Alternative text field found, setting text to '{}'",
-                            current_layout
+                            "DEBUG: Alternative text field found but text could not be set: {}",
+                            e
                         );
-                        txt.set_text(current_layout.clone())?;
-                    } else {
-                        eprintln!("DEBUG: Alternative text field found but downcast failed");
                     }
                 } else {
                     eprintln!("DEBUG: Alternative text field not found");
@@ -185,12 +170,10 @@ pub fn choose_layout(
 
             // Press Enter
             eprintln!("DEBUG: Pressing Enter on window 2");
-            if let Ok(window) = session.find_by_id("wnd[2]".to_string()) {
-                if let Some(wnd) = window.downcast::<GuiModalWindow>() {
-                    eprintln!("DEBUG: Window found, sending v_key(0)");
-                    wnd.send_v_key(0)?;
-                } else {
-                    eprintln!("DEBUG: Window found but downcast failed");
+            if let Ok(wnd) = session.find_by_id("wnd[2]".to_string()) {
+                eprintln!("DEBUG: Window found, sending v_key(0)");
+                if let Err(e) = wnd.send_v_key(0) {
+                    eprintln!("DEBUG: Window found but v_key(0) failed: {}", e);
                 }
             } else {
                 eprintln!("DEBUG: Window 2 not found");
@@ -215,12 +198,10 @@ pub fn choose_layout(
                 );
                 // Highlight
                 eprintln!("DEBUG: Setting focus on result label");
-                if let Ok(label) = session.find_by_id("wnd[3]/usr/lbl[1,2]".to_string()) {
-                    if let Some(lbl) = label.downcast::<GuiLabel>() {
-                        eprintln!("DEBUG: Label found, setting focus");
-                        lbl.set_focus()?;
-                    } else {
-                        eprintln!("DEBUG: Label found but downcast failed");
+                if let Ok(lbl) = session.find_by_id("wnd[3]/usr/lbl[1,2]".to_string()) {
+                    eprintln!("DEBUG: Label found, setting focus");
+                    if let Err(e) = lbl.set_focus() {
+                        eprintln!("DEBUG: Label found but focus could not be set: {}", e);
                     }
                 } else {
                     eprintln!("DEBUG: Failed to find label by ID");
@@ -228,12 +209,10 @@ pub fn choose_layout(
 
                 // Click
                 eprintln!("DEBUG: Clicking on window 3 (send_v_key(2))");
-                if let Ok(window) = session.find_by_id("wnd[3]".to_string()) {
-                    if let Some(wnd) = window.downcast::<GuiModalWindow>() {
-                        eprintln!("DEBUG: Window found, sending v_key(2)");
-                        wnd.send_v_key(2)?;
-                    } else {
-                        eprintln!("DEBUG: Window found but downcast failed");
+                if let Ok(wnd) = session.find_by_id("wnd[3]".to_string()) {
+                    eprintln!("DEBUG: Window found, sending v_key(2)");
+                    if let Err(e) = wnd.send_v_key(2) {
+                        eprintln!("DEBUG: Window found but v_key(2) failed: {}", e);
                     }
                 } else {
                     eprintln!("DEBUG: Window 3 not found for clicking");
@@ -259,12 +238,10 @@ pub fn choose_layout(
 
                 // click on wnd1
                 eprintln!("DEBUG: Clicking on window 1 (send_v_key(2))");
-                if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                    if let Some(wnd) = window.downcast::<GuiModalWindow>() {
-                        eprintln!("DEBUG: Window found, sending v_key(2)");
-                        wnd.send_v_key(2)?;
-                    } else {
-                        eprintln!("DEBUG: Window found but downcast failed");
+                if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                    eprintln!("DEBUG: Window found, sending v_key(2)");
+                    if let Err(e) = wnd.send_v_key(2) {
+                        eprintln!("DEBUG: Window found but v_key(2) failed: {}", e);
                     }
                 } else {
                     eprintln!("DEBUG: Window 1 not found for clicking");
@@ -343,7 +320,9 @@ pub fn choose_layout(
 
     // Get status bar message
     eprintln!("DEBUG: Getting status bar message");
-    let msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
+    let msg = read_status(session, 0)?
+        .map(|message| message.to_string())
+        .unwrap_or_default();
 
     eprintln!("DEBUG: Status bar message: {}", msg);
     println!("{}", msg);
@@ -357,42 +336,36 @@ pub fn choose_layout(
 /// Trigger layout popup based on transaction code
 ///
 /// This function is a port of the VBA function layout_popup
-pub fn layout_popup(session: &GuiSession, tcode: &str) -> windows::core::Result<bool> {
+pub fn layout_popup(session: &dyn SapSession, tcode: &str) -> windows::core::Result<bool> {
     match tcode.to_lowercase().as_str() {
         "lx03" | "lx02" => {
             // Select Layout
-            if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
-                if let Some(btn) = button.downcast::<GuiButton>() {
-                    btn.press()?;
-                }
+            if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
+                btn.press()?;
             }
         }
         "vt11" => {
             // Choose Layout Button
-            if let Ok(menu) = session.find_by_id("wnd[0]/mbar/menu[3]/menu[0]/menu[1]".to_string())
+            if let Ok(menu_item) =
+                session.find_by_id("wnd[0]/mbar/menu[3]/menu[0]/menu[1]".to_string())
             {
-                if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-                    menu_item.select()?;
-                }
+                menu_item.select()?;
             }
         }
         "vl06o" => {
             // Choose Layout Button for VL06O
-            if let Ok(menu) = session.find_by_id("wnd[0]/mbar/menu[3]/menu[2]/menu[1]".to_string())
+            if let Ok(menu_item) =
+                session.find_by_id("wnd[0]/mbar/menu[3]/menu[2]/menu[1]".to_string())
             {
-                if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-                    menu_item.select()?;
-                }
+                menu_item.select()?;
             }
         }
         "zmdesnr" => {
             // Check if button exists
             let err_ctl = exist_ctrl(session, 0, "/tbar[1]/btn[33]", true)?;
             if err_ctl.cband {
-                if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
-                    if let Some(btn) = button.downcast::<GuiButton>() {
-                        btn.press()?;
-                    }
+                if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
+                    btn.press()?;
                 }
             }
         }
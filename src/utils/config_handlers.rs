@@ -7,6 +7,8 @@ use std::time::Duration;
 
 use crate::utils::config_types::SapConfig;
 use crate::utils::config_types::*;
+use crate::utils::config_values::{date_pattern, format_list, parse_list};
+use crate::utils::credential_store::handle_manage_credentials;
 
 /// Handle configuring SAP automation parameters
 pub fn handle_configure_sap_params() -> Result<()> {
@@ -24,6 +26,7 @@ pub fn handle_configure_sap_params() -> Result<()> {
         "Configure TCode-specific Parameters",
         "Configure Loop Parameters",
         "Show Current Configuration",
+        "Manage Saved Credentials",
         "Back to Main Menu",
     ];
 
@@ -77,6 +80,7 @@ pub fn handle_configure_sap_params() -> Result<()> {
                             reports_dir: config.get_reports_dir(),
                             default_tcode: Some(tcode.clone()),
                             date_format: crate::utils::config_types::default_date_format(),
+                            run: RunSettings::default(),
                             additional_params: HashMap::new(),
                         });
                     }
@@ -109,6 +113,7 @@ pub fn handle_configure_sap_params() -> Result<()> {
                         reports_dir: config.get_reports_dir(),
                         default_tcode: None,
                         date_format: date_format.clone(),
+                        run: RunSettings::default(),
                         additional_params: HashMap::new(),
                     });
                 }
@@ -131,6 +136,14 @@ pub fn handle_configure_sap_params() -> Result<()> {
                 io::stdin().read_line(&mut input).unwrap();
                 continue;
             }
+            6 => {
+                // Manage Saved Credentials (stored outside config.toml)
+                if let Err(e) = handle_manage_credentials() {
+                    eprintln!("Error managing credentials: {}", e);
+                    thread::sleep(Duration::from_secs(2));
+                }
+                continue;
+            }
             _ => {
                 // Back to Main Menu
                 break;
@@ -229,6 +242,12 @@ fn configure_tcode_parameters(config: &mut SapConfig, tcode_name: &str) -> Resul
         }
     }
 
+    // Dates are entered in the configured date format
+    let date_format = config.global.as_ref()
+        .map(|g| g.date_format.clone())
+        .unwrap_or_else(default_date_format);
+    let pattern = date_pattern(&date_format);
+
     // Get a clone of the TCode config to avoid borrowing issues
     let mut tcode_config = if let Some(tcode_configs) = &config.tcode {
         tcode_configs.get(tcode_name).cloned().unwrap_or_default()
@@ -245,6 +264,7 @@ fn configure_tcode_parameters(config: &mut SapConfig, tcode_name: &str) -> Resul
         "Configure By Date",
         "Configure Serial Number",
         "Configure Tab Number",
+        "Configure Layout Columns",
         "Add Custom Parameter",
         "Remove Parameter",
         "Delete This TCode Configuration",
@@ -316,18 +336,22 @@ fn configure_tcode_parameters(config: &mut SapConfig, tcode_name: &str) -> Resul
             }
             3 => {
                 // Configure Date Range
-                let current_start = tcode_config.date_range_start.clone().unwrap_or_default();
-                let current_end = tcode_config.date_range_end.clone().unwrap_or_default();
+                let current_start = tcode_config.date_range_start
+                    .map(|date| date.format(pattern).to_string())
+                    .unwrap_or_default();
+                let current_end = tcode_config.date_range_end
+                    .map(|date| date.format(pattern).to_string())
+                    .unwrap_or_default();
 
                 let start_date: String = Input::new()
-                    .with_prompt("Enter Start Date (MM/DD/YYYY)")
+                    .with_prompt(format!("Enter Start Date ({})", date_format.to_uppercase()))
                     .allow_empty(true)
                     .default(current_start)
                     .interact()
                     .unwrap();
 
                 let end_date: String = Input::new()
-                    .with_prompt("Enter End Date (MM/DD/YYYY)")
+                    .with_prompt(format!("Enter End Date ({})", date_format.to_uppercase()))
                     .allow_empty(true)
                     .default(current_end)
                     .interact()
@@ -338,16 +362,23 @@ fn configure_tcode_parameters(config: &mut SapConfig, tcode_name: &str) -> Resul
                     tcode_config.date_range_end = None;
                     println!("Date Range configuration cleared.");
                 } else {
-                    tcode_config.date_range_start = Some(start_date.clone());
-                    tcode_config.date_range_end = Some(end_date.clone());
-                    println!("Date Range set to: {} - {}", start_date, end_date);
+                    match (
+                        chrono::NaiveDate::parse_from_str(start_date.trim(), pattern),
+                        chrono::NaiveDate::parse_from_str(end_date.trim(), pattern),
+                    ) {
+                        (Ok(start), Ok(end)) => {
+                            tcode_config.date_range_start = Some(start);
+                            tcode_config.date_range_end = Some(end);
+                            println!("Date Range set to: {} - {}", start_date, end_date);
+                        }
+                        _ => println!("Invalid date. Keeping current Date Range."),
+                    }
                 }
             }
             4 => {
                 // Configure By Date
-                let current = tcode_config.by_date.clone().unwrap_or_default();
                 let by_date_options = vec!["true", "false"];
-                let default_index = if current == "true" { 0 } else { 1 };
+                let default_index = if tcode_config.by_date == Some(true) { 0 } else { 1 };
                 
                 let by_date_choice = Select::new()
                     .with_prompt("Filter by date?")
@@ -356,8 +387,8 @@ fn configure_tcode_parameters(config: &mut SapConfig, tcode_name: &str) -> Resul
                     .interact()
                     .unwrap();
 
-                let by_date = by_date_options[by_date_choice].to_string();
-                tcode_config.by_date = Some(by_date.clone());
+                let by_date = by_date_choice == 0;
+                tcode_config.by_date = Some(by_date);
                 println!("By Date set to: {}", by_date);
             }
             5 => {
@@ -380,7 +411,7 @@ fn configure_tcode_parameters(config: &mut SapConfig, tcode_name: &str) -> Resul
             }
             6 => {
                 // Configure Tab Number
-                let current = tcode_config.tab_number.clone().unwrap_or_default();
+                let current = tcode_config.tab_number.map(|n| n.to_string()).unwrap_or_default();
                 let tab_number: String = Input::new()
                     .with_prompt("Enter Tab Number")
                     .allow_empty(true)
@@ -391,12 +422,33 @@ fn configure_tcode_parameters(config: &mut SapConfig, tcode_name: &str) -> Resul
                 if tab_number.is_empty() {
                     tcode_config.tab_number = None;
                     println!("Tab Number configuration cleared.");
+                } else if let Ok(number) = tab_number.trim().parse::<i32>() {
+                    tcode_config.tab_number = Some(number);
+                    println!("Tab Number set to: {}", number);
                 } else {
-                    tcode_config.tab_number = Some(tab_number.clone());
-                    println!("Tab Number set to: {}", tab_number);
+                    println!("Invalid number. Keeping current Tab Number.");
                 }
             }
             7 => {
+                // Configure Layout Columns
+                let current = tcode_config.add_layout_columns.clone().unwrap_or_default().join(", ");
+                let columns: String = Input::new()
+                    .with_prompt("Enter Layout Columns (comma separated)")
+                    .allow_empty(true)
+                    .default(current)
+                    .interact()
+                    .unwrap();
+
+                let columns = parse_list(&columns).unwrap_or_default();
+                if columns.is_empty() {
+                    tcode_config.add_layout_columns = None;
+                    println!("Layout Columns configuration cleared.");
+                } else {
+                    println!("Layout Columns set to: {}", format_list(&columns));
+                    tcode_config.add_layout_columns = Some(columns);
+                }
+            }
+            8 => {
                 // Add Custom Parameter
                 let param_name: String = Input::new()
                     .with_prompt("Enter Parameter Name")
@@ -418,7 +470,7 @@ fn configure_tcode_parameters(config: &mut SapConfig, tcode_name: &str) -> Resul
                     println!("Parameter '{}' set to: {}", param_name, param_value);
                 }
             }
-            8 => {
+            9 => {
                 // Remove Parameter
                 let mut param_names: Vec<String> = Vec::new();
 
@@ -444,6 +496,9 @@ fn configure_tcode_parameters(config: &mut SapConfig, tcode_name: &str) -> Resul
                 if tcode_config.tab_number.is_some() {
                     param_names.push("tab_number".to_string());
                 }
+                if tcode_config.add_layout_columns.is_some() {
+                    param_names.push("add_layout_columns".to_string());
+                }
 
                 // Add additional parameters
                 for key in tcode_config.additional_params.keys() {
@@ -502,13 +557,17 @@ fn configure_tcode_parameters(config: &mut SapConfig, tcode_name: &str) -> Resul
                         tcode_config.tab_number = None;
                         println!("Tab Number configuration cleared.");
                     }
+                    "add_layout_columns" => {
+                        tcode_config.add_layout_columns = None;
+                        println!("Layout Columns configuration cleared.");
+                    }
                     _ => {
                         tcode_config.additional_params.remove(param_name);
                         println!("Parameter '{}' removed.", param_name);
                     }
                 }
             }
-            9 => {
+            10 => {
                 // Delete This TCode Configuration
                 println!("Are you sure you want to delete the configuration for TCode '{}'? (y/n)", tcode_name);
                 let mut confirm = String::new();
@@ -618,7 +677,7 @@ fn handle_configure_loop_params(config: &mut SapConfig) -> Result<()> {
             }
             1 => {
                 // Configure Iterations
-                let current = loop_config.iterations.clone();
+                let current = loop_config.iterations.to_string();
                 let iterations_str: String = Input::new()
                     .with_prompt("Enter number of iterations")
                     .allow_empty(false)
@@ -626,12 +685,16 @@ fn handle_configure_loop_params(config: &mut SapConfig) -> Result<()> {
                     .interact()
                     .unwrap();
 
-                loop_config.iterations = iterations_str.clone();
-                println!("Iterations set to: {}", iterations_str);
+                if let Ok(iterations) = iterations_str.trim().parse::<usize>() {
+                    loop_config.iterations = iterations;
+                    println!("Iterations set to: {}", iterations);
+                } else {
+                    println!("Invalid number. Keeping current value: {}", loop_config.iterations);
+                }
             }
             2 => {
                 // Configure Delay
-                let current = loop_config.delay_seconds.clone();
+                let current = loop_config.delay_seconds.to_string();
                 let delay_str: String = Input::new()
                     .with_prompt("Enter delay between iterations (seconds)")
                     .allow_empty(false)
@@ -639,8 +702,12 @@ fn handle_configure_loop_params(config: &mut SapConfig) -> Result<()> {
                     .interact()
                     .unwrap();
 
-                loop_config.delay_seconds = delay_str.clone();
-                println!("Delay set to: {} seconds", delay_str);
+                if let Ok(delay) = delay_str.trim().parse::<u64>() {
+                    loop_config.delay_seconds = delay;
+                    println!("Delay set to: {} seconds", delay);
+                } else {
+                    println!("Invalid number. Keeping current value: {} seconds", loop_config.delay_seconds);
+                }
             }
             3 => {
                 // Add/Edit Parameter
@@ -756,6 +823,14 @@ fn show_current_configuration(config: &SapConfig) {
             println!("Default TCode: {}", default_tcode);
         }
         
+        let run_settings = global.run.values();
+        if !run_settings.is_empty() {
+            println!("\nRun Settings:");
+            for (key, value) in run_settings {
+                println!("  {}: {}", key, value);
+            }
+        }
+        
         if !global.additional_params.is_empty() {
             println!("\nGlobal Parameters:");
             for (key, value) in &global.additional_params {
@@ -765,6 +840,9 @@ fn show_current_configuration(config: &SapConfig) {
     }
     
     // Show TCode configurations
+    let pattern = date_pattern(&config.global.as_ref()
+        .map(|g| g.date_format.clone())
+        .unwrap_or_else(default_date_format));
     if let Some(tcode_configs) = &config.tcode {
         if !tcode_configs.is_empty() {
             println!("\nTCode Configurations:");
@@ -786,7 +864,7 @@ fn show_current_configuration(config: &SapConfig) {
                 
                 if let Some(date_range_start) = &tcode_config.date_range_start {
                     if let Some(date_range_end) = &tcode_config.date_range_end {
-                        println!("    Date Range: {} - {}", date_range_start, date_range_end);
+                        println!("    Date Range: {} - {}", date_range_start.format(pattern), date_range_end.format(pattern));
                     }
                 }
                 
@@ -802,6 +880,10 @@ fn show_current_configuration(config: &SapConfig) {
                     println!("    Tab Number: {}", tab_number);
                 }
                 
+                if let Some(add_layout_columns) = &tcode_config.add_layout_columns {
+                    println!("    Layout Columns: {}", add_layout_columns.join(", "));
+                }
+                
                 if !tcode_config.additional_params.is_empty() {
                     println!("    Additional Parameters:");
                     for (key, value) in &tcode_config.additional_params {
@@ -826,4 +908,12 @@ fn show_current_configuration(config: &SapConfig) {
             }
         }
     }
+    
+    // Show keys of the config file that could not be used
+    if !config.dropped_keys.is_empty() {
+        println!("\nIgnored Keys:");
+        for key in &config.dropped_keys {
+            println!("  {}", key);
+        }
+    }
 }
@@ -5,10 +5,75 @@ use std::env;
 use std::fs;
 use std::io::{self};
 use std::path::Path;
+use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 
 use crate::utils::config_types::*;
+use crate::utils::config_values::{date_pattern, format_list, read_text, SectionReader};
+
+/// Keys of `[global]` read into `RunSettings`
+const RUN_SETTING_KEYS: &[&str] = &[
+    "wait_timeout_ms", "wait_poll_ms", "popup_timeout_ms", "save_timeout_ms", "login_timeout_ms",
+    "file_stable_ms", "max_sessions", "max_relogin_attempts", "unattended",
+];
+
+/// Read the wait, session and logon settings of `[global]`
+fn read_run_settings(section: &mut SectionReader) -> RunSettings {
+    RunSettings {
+        wait_timeout_ms: section.integer("wait_timeout_ms"),
+        wait_poll_ms: section.integer("wait_poll_ms"),
+        popup_timeout_ms: section.integer("popup_timeout_ms"),
+        save_timeout_ms: section.integer("save_timeout_ms"),
+        login_timeout_ms: section.integer("login_timeout_ms"),
+        file_stable_ms: section.integer("file_stable_ms"),
+        max_sessions: section.integer("max_sessions"),
+        max_relogin_attempts: section.integer("max_relogin_attempts"),
+        unattended: section.bool("unattended"),
+    }
+}
+
+impl RunSettings {
+    /// Get the settings that are set, as `[global]` key and TOML value
+    pub fn values(&self) -> Vec<(&'static str, String)> {
+        [
+            ("wait_timeout_ms", self.wait_timeout_ms.map(|v| v.to_string())),
+            ("wait_poll_ms", self.wait_poll_ms.map(|v| v.to_string())),
+            ("popup_timeout_ms", self.popup_timeout_ms.map(|v| v.to_string())),
+            ("save_timeout_ms", self.save_timeout_ms.map(|v| v.to_string())),
+            ("login_timeout_ms", self.login_timeout_ms.map(|v| v.to_string())),
+            ("file_stable_ms", self.file_stable_ms.map(|v| v.to_string())),
+            ("max_sessions", self.max_sessions.map(|v| v.to_string())),
+            ("max_relogin_attempts", self.max_relogin_attempts.map(|v| v.to_string())),
+            ("unattended", self.unattended.map(|v| v.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .collect()
+    }
+}
+
+/// Get the `[loop]`/`[sequence]` key of a parameter, e.g. `param_retries` for `retries`
+fn param_key(key: &str) -> String {
+    if key.starts_with("param_") {
+        key.to_string()
+    } else {
+        format!("param_{}", key)
+    }
+}
+
+/// Profile chosen for this run, applied by `SapConfig::load`
+static ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Use a logon profile for the rest of the run (None for the `[global]` settings)
+pub fn set_active_profile(name: Option<&str>) {
+    *ACTIVE_PROFILE.lock().unwrap() = name.map(|name| name.to_string());
+}
+
+/// Get the profile chosen for this run, if any
+pub fn active_profile() -> Option<String> {
+    ACTIVE_PROFILE.lock().unwrap().clone()
+}
 
 impl Default for SapConfig {
     fn default() -> Self {
@@ -19,12 +84,16 @@ impl Default for SapConfig {
                 reports_dir: get_default_reports_dir(),
                 default_tcode: None,
                 date_format: default_date_format(),
+                run: RunSettings::default(),
                 additional_params: HashMap::new(),
             }),
             build: None,
             tcode: Some(HashMap::new()),
             loop_config: None,
             sequence: None,
+            profiles: None,
+            active_profile: None,
+            dropped_keys: Vec::new(),
             raw_config: None,
         }
     }
@@ -38,13 +107,19 @@ impl SapConfig {
 
     /// Load configuration from config.toml file
     pub fn load() -> Result<Self> {
-        Self::load_from_path("config.toml")
+        let mut config = Self::load_from_path("config.toml")?;
+        config.active_profile = active_profile();
+        Ok(config)
     }
 
     /// Load configuration from a specific path
+    ///
+    /// Keys with a type or value that cannot be used are left out and listed in
+    /// `dropped_keys`.
     pub fn load_from_path(path: &str) -> Result<Self> {
         let mut config = Self::default();
         config.config_path = path.to_string();
+        let mut dropped = Vec::new();
         
         // Try to read from config file
         if let Ok(content) = fs::read_to_string(path) {
@@ -55,24 +130,35 @@ impl SapConfig {
                     
                     // Extract build section
                     if let Some(build) = parsed.get("build").and_then(|v| v.as_table()) {
-                        let mut build_config = BuildConfig {
-                            target: build.get("target")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("i686-pc-windows-msvc")
-                                .to_string(),
-                            additional_params: HashMap::new(),
-                        };
+                        let mut section = SectionReader::new(build, "build", &mut dropped);
                         
-                        // Extract additional build parameters
-                        for (key, value) in build {
-                            if key != "target" {
-                                if let Some(val_str) = value.as_str() {
-                                    build_config.additional_params.insert(key.clone(), val_str.to_string());
-                                }
+                        config.build = Some(BuildConfig {
+                            target: section.text("target")
+                                .unwrap_or_else(|| "i686-pc-windows-msvc".to_string()),
+                            additional_params: section.others(&["target"]),
+                        });
+                    }
+                    
+                    // Extract profile sections
+                    if let Some(profile_table) = parsed.get("profile").and_then(|v| v.as_table()) {
+                        let mut profiles = HashMap::new();
+                        
+                        for (profile_name, profile_value) in profile_table {
+                            if let Some(profile_table) = profile_value.as_table() {
+                                let name = format!("profile.{}", profile_name);
+                                let mut section = SectionReader::new(profile_table, &name, &mut dropped);
+                                
+                                profiles.insert(profile_name.clone(), ProfileConfig {
+                                    instance_id: section.text("instance_id"),
+                                    client: section.text("client"),
+                                    language: section.text("language"),
+                                    connection: section.text("connection"),
+                                    credentials: section.text("credentials"),
+                                });
                             }
                         }
                         
-                        config.build = Some(build_config);
+                        config.profiles = Some(profiles);
                     }
                     
                     // Check for new format (with global and tcode sections)
@@ -81,88 +167,50 @@ impl SapConfig {
                     if is_new_format {
                         // Extract global section
                         if let Some(global) = parsed.get("global").and_then(|v| v.as_table()) {
-                            let mut global_config = GlobalConfig {
-                                instance_id: global.get("instance_id")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or(&default_instance_id())
-                                    .to_string(),
-                                reports_dir: global.get("reports_dir")
-                                    .and_then(|v| v.as_str())
-                                    .map(|s| s.replace("\\", "\\\\"))
-                                    .unwrap_or_else(|| get_default_reports_dir()),
-                                default_tcode: global.get("default_tcode")
-                                    .and_then(|v| v.as_str())
-                                    .map(|s| s.to_string()),
-                                date_format: global.get("date_format")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or(&default_date_format())
-                                    .to_string(),
-                                additional_params: HashMap::new(),
-                            };
+                            let mut section = SectionReader::new(global, "global", &mut dropped);
                             
-                            // Extract additional global parameters
-                            for (key, value) in global {
-                                if !["instance_id", "reports_dir", "default_tcode", "date_format"].contains(&key.as_str()) {
-                                    if let Some(val_str) = value.as_str() {
-                                        global_config.additional_params.insert(key.clone(), val_str.to_string());
-                                    }
-                                }
-                            }
-                            
-                            config.global = Some(global_config);
+                            config.global = Some(GlobalConfig {
+                                instance_id: section.text("instance_id")
+                                    .unwrap_or_else(default_instance_id),
+                                reports_dir: section.text("reports_dir")
+                                    .map(|s| s.replace("\\", "\\\\"))
+                                    .unwrap_or_else(get_default_reports_dir),
+                                default_tcode: section.text("default_tcode"),
+                                date_format: section.text("date_format")
+                                    .unwrap_or_else(default_date_format),
+                                run: read_run_settings(&mut section),
+                                additional_params: section.others(&[&["instance_id", "reports_dir", "default_tcode", "date_format"], RUN_SETTING_KEYS].concat()),
+                            });
                         }
                         
+                        // Dates in tcode sections may be written in the configured format
+                        let date_format = config.global.as_ref()
+                            .map(|g| g.date_format.clone())
+                            .unwrap_or_else(default_date_format);
+                        
                         // Extract tcode sections
                         if let Some(tcode_table) = parsed.get("tcode").and_then(|v| v.as_table()) {
                             let mut tcode_configs = HashMap::new();
                             
                             for (tcode_name, tcode_value) in tcode_table {
                                 if let Some(tcode_table) = tcode_value.as_table() {
-                                    let mut tcode_config = TcodeConfig::default();
+                                    let name = format!("tcode.{}", tcode_name);
+                                    let mut section = SectionReader::new(tcode_table, &name, &mut dropped);
                                     
-                                    // Extract standard fields
-                                    tcode_config.variant = tcode_table.get("variant")
-                                        .and_then(|v| v.as_str())
-                                        .map(|s| s.to_string());
-                                        
-                                    tcode_config.layout = tcode_table.get("layout")
-                                        .and_then(|v| v.as_str())
-                                        .map(|s| s.to_string());
-                                        
-                                    tcode_config.column_name = tcode_table.get("column_name")
-                                        .and_then(|v| v.as_str())
-                                        .map(|s| s.to_string());
-                                        
-                                    tcode_config.date_range_start = tcode_table.get("date_range_start")
-                                        .and_then(|v| v.as_str())
-                                        .map(|s| s.to_string());
-                                        
-                                    tcode_config.date_range_end = tcode_table.get("date_range_end")
-                                        .and_then(|v| v.as_str())
-                                        .map(|s| s.to_string());
-                                        
-                                    tcode_config.by_date = tcode_table.get("by_date")
-                                        .and_then(|v| v.as_str())
-                                        .map(|s| s.to_string());
-                                        
-                                    tcode_config.serial_number = tcode_table.get("serial_number")
-                                        .and_then(|v| v.as_str())
-                                        .map(|s| s.to_string());
-                                        
-                                    tcode_config.tab_number = tcode_table.get("tab_number")
-                                        .and_then(|v| v.as_str())
-                                        .map(|s| s.to_string());
-
-                                    // Extract additional parameters
-                                    for (key, value) in tcode_table {
-                                        if !["variant", "layout", "column_name", "date_range_start", 
-                                             "date_range_end", "by_date", "serial_number", "tab_number", "add_layout_columns"]
-                                            .contains(&key.as_str()) {
-                                            if let Some(val_str) = value.as_str() {
-                                                tcode_config.additional_params.insert(key.clone(), val_str.to_string());
-                                            }
-                                        }
-                                    }
+                                    let tcode_config = TcodeConfig {
+                                        variant: section.text("variant"),
+                                        layout: section.text("layout"),
+                                        column_name: section.text("column_name"),
+                                        date_range_start: section.date("date_range_start", &date_format),
+                                        date_range_end: section.date("date_range_end", &date_format),
+                                        by_date: section.bool("by_date"),
+                                        serial_number: section.text("serial_number"),
+                                        tab_number: section.integer("tab_number"),
+                                        subdir: None,
+                                        add_layout_columns: section.list("add_layout_columns"),
+                                        additional_params: section.others(&["variant", "layout", "column_name", "date_range_start", 
+                                             "date_range_end", "by_date", "serial_number", "tab_number", "add_layout_columns"]),
+                                    };
                                     
                                     tcode_configs.insert(tcode_name.clone(), tcode_config);
                                 }
@@ -173,76 +221,38 @@ impl SapConfig {
                         
                         // Extract loop section
                         if let Some(loop_table) = parsed.get("loop").and_then(|v| v.as_table()) {
-                            let mut loop_config = LoopConfig {
-                                tcode: loop_table.get("tcode")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or("")
-                                    .to_string(),
-                                iterations: loop_table.get("iterations")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or(&default_iterations())
-                                    .to_string(),
-                                delay_seconds: loop_table.get("delay_seconds")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or(&default_delay_seconds())
-                                    .to_string(),
-                                params: HashMap::new(),
-                            };
-                            
-                            // Extract additional loop parameters
-                            for (key, value) in loop_table {
-                                if !["tcode", "iterations", "delay_seconds"].contains(&key.as_str()) {
-                                    if let Some(val_str) = value.as_str() {
-                                        loop_config.params.insert(key.clone(), val_str.to_string());
-                                    }
-                                }
-                            }
+                            let mut section = SectionReader::new(loop_table, "loop", &mut dropped);
                             
-                            config.loop_config = Some(loop_config);
+                            config.loop_config = Some(LoopConfig {
+                                tcode: section.text("tcode").unwrap_or_default(),
+                                iterations: section.integer("iterations")
+                                    .unwrap_or_else(default_iterations),
+                                delay_seconds: section.integer("delay_seconds")
+                                    .unwrap_or_else(default_delay_seconds),
+                                params: section.others(&["tcode", "iterations", "delay_seconds"]),
+                            });
                         }
                         
                         // Extract sequence section
                         if let Some(sequence_table) = parsed.get("sequence").and_then(|v| v.as_table()) {
-                            let mut sequence_config = SequenceConfig {
-                                options: Vec::new(),
-                                iterations: sequence_table.get("iterations")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or(&default_iterations())
-                                    .to_string(),
-                                delay_seconds: sequence_table.get("delay_seconds")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or(&default_delay_seconds())
-                                    .to_string(),
-                                interval_seconds: sequence_table.get("interval_seconds")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or(&default_interval_seconds())
-                                    .to_string(),
-                                params: HashMap::new(),
-                            };
-                            
-                            // Extract options array
-                            if let Some(options_array) = sequence_table.get("options").and_then(|v| v.as_array()) {
-                                for option in options_array {
-                                    if let Some(option_str) = option.as_str() {
-                                        sequence_config.options.push(option_str.to_string());
-                                    }
-                                }
-                            }
-                            
-                            // Extract additional sequence parameters
-                            for (key, value) in sequence_table {
-                                if !["options", "iterations", "delay_seconds", "interval_seconds"].contains(&key.as_str()) {
-                                    if let Some(val_str) = value.as_str() {
-                                        sequence_config.params.insert(key.clone(), val_str.to_string());
-                                    }
-                                }
-                            }
+                            let mut section = SectionReader::new(sequence_table, "sequence", &mut dropped);
                             
-                            config.sequence = Some(sequence_config);
+                            config.sequence = Some(SequenceConfig {
+                                options: section.list("options")
+                                    .unwrap_or_else(default_sequence_options),
+                                iterations: section.integer("iterations")
+                                    .unwrap_or_else(default_iterations),
+                                delay_seconds: section.integer("delay_seconds")
+                                    .unwrap_or_else(default_delay_seconds),
+                                interval_seconds: section.integer("interval_seconds")
+                                    .unwrap_or_else(default_interval_seconds),
+                                parallel: section.bool("param_parallel"),
+                                params: section.others(&["options", "iterations", "delay_seconds", "interval_seconds", "param_parallel"]),
+                            });
                         }
                     } else {
                         // Handle legacy format (with sap_config section)
-                        config = Self::load_legacy_format(parsed, config)?;
+                        config = Self::load_legacy_format(parsed, config, &mut dropped)?;
                     }
                 },
                 Err(e) => {
@@ -251,30 +261,27 @@ impl SapConfig {
             }
         }
         
+        config.dropped_keys = dropped;
         Ok(config)
     }
     
     /// Load configuration from legacy format
-    fn load_legacy_format(parsed: toml::Value, mut config: SapConfig) -> Result<SapConfig> {
+    fn load_legacy_format(parsed: toml::Value, mut config: SapConfig, dropped: &mut Vec<String>) -> Result<SapConfig> {
         // Extract sap_config section
         if let Some(sap_config) = parsed.get("sap_config").and_then(|v| v.as_table()) {
+            let mut section = SectionReader::new(sap_config, "sap_config", dropped);
+            
             // Create global config
             let mut global_config = GlobalConfig {
-                instance_id: sap_config.get("instance_id")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(&default_instance_id())
-                    .to_string(),
-                reports_dir: sap_config.get("reports_dir")
-                    .and_then(|v| v.as_str())
+                instance_id: section.text("instance_id")
+                    .unwrap_or_else(default_instance_id),
+                reports_dir: section.text("reports_dir")
                     .map(|s| s.replace("\\", "\\\\"))
-                    .unwrap_or_else(|| get_default_reports_dir()),
-                default_tcode: sap_config.get("tcode")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string()),
-                date_format: sap_config.get("date_format")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(&default_date_format())
-                    .to_string(),
+                    .unwrap_or_else(get_default_reports_dir),
+                default_tcode: section.text("tcode"),
+                date_format: section.text("date_format")
+                    .unwrap_or_else(default_date_format),
+                run: read_run_settings(&mut section),
                 additional_params: HashMap::new(),
             };
             
@@ -282,69 +289,46 @@ impl SapConfig {
             let default_tcode = global_config.default_tcode.clone().unwrap_or_else(|| "".to_string());
             
             // Create tcode config for the default tcode
-            let mut tcode_config = TcodeConfig::default();
-            
-            // Extract standard fields
-            tcode_config.variant = sap_config.get("variant")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-                
-            tcode_config.layout = sap_config.get("layout")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-                
-            tcode_config.column_name = sap_config.get("column_name")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-                
-            tcode_config.date_range_start = sap_config.get("date_range_start")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-                
-            tcode_config.date_range_end = sap_config.get("date_range_end")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
+            let mut tcode_config = TcodeConfig {
+                variant: section.text("variant"),
+                layout: section.text("layout"),
+                column_name: section.text("column_name"),
+                date_range_start: section.date("date_range_start", &global_config.date_format),
+                date_range_end: section.date("date_range_end", &global_config.date_format),
+                ..TcodeConfig::default()
+            };
             
             // Create loop config
             let mut loop_config = LoopConfig {
-                tcode: sap_config.get("loop_tcode")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(&default_tcode)
-                    .to_string(),
-                iterations: sap_config.get("loop_iterations")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(&default_iterations())
-                    .to_string(),
-                delay_seconds: sap_config.get("loop_delay_seconds")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(&default_delay_seconds())
-                    .to_string(),
+                tcode: section.text("loop_tcode")
+                    .unwrap_or_else(|| default_tcode.clone()),
+                iterations: section.integer("loop_iterations")
+                    .unwrap_or_else(default_iterations),
+                delay_seconds: section.integer("loop_delay_seconds")
+                    .unwrap_or_else(default_delay_seconds),
                 params: HashMap::new(),
             };
             
             // Extract additional parameters
-            for (key, value) in sap_config {
-                if !["instance_id", "reports_dir", "tcode", "variant", "layout", "column_name", 
-                     "date_range_start", "date_range_end", "loop_tcode", "loop_iterations", 
-                     "loop_delay_seconds", "date_format"].contains(&key.as_str()) {
-                    if let Some(val_str) = value.as_str() {
-                        // Check if it's a loop parameter
-                        if key.starts_with("loop_param_") {
-                            let param_name = key.replacen("loop_param_", "", 1);
-                            loop_config.params.insert(param_name, val_str.to_string());
-                        } else if key.starts_with("loop_") {
-                            // Other loop-related parameters
-                            let param_name = key.replacen("loop_", "", 1);
-                            loop_config.params.insert(param_name, val_str.to_string());
-                        } else if !default_tcode.is_empty() && key.starts_with(&format!("{}_", default_tcode)) {
-                            // TCode-specific parameters
-                            let param_name = key.replacen(&format!("{}_", default_tcode), "", 1);
-                            tcode_config.additional_params.insert(param_name, val_str.to_string());
-                        } else {
-                            // Global parameters
-                            global_config.additional_params.insert(key.clone(), val_str.to_string());
-                        }
-                    }
+            let additional_params = section.others(&[&["instance_id", "reports_dir", "tcode", "variant", "layout", "column_name", 
+                 "date_range_start", "date_range_end", "loop_tcode", "loop_iterations", 
+                 "loop_delay_seconds", "date_format"], RUN_SETTING_KEYS].concat());
+            for (key, val_str) in additional_params {
+                // Check if it's a loop parameter
+                if key.starts_with("loop_param_") {
+                    let param_name = key.replacen("loop_param_", "", 1);
+                    loop_config.params.insert(param_name, val_str);
+                } else if key.starts_with("loop_") {
+                    // Other loop-related parameters
+                    let param_name = key.replacen("loop_", "", 1);
+                    loop_config.params.insert(param_name, val_str);
+                } else if !default_tcode.is_empty() && key.starts_with(&format!("{}_", default_tcode)) {
+                    // TCode-specific parameters
+                    let param_name = key.replacen(&format!("{}_", default_tcode), "", 1);
+                    tcode_config.additional_params.insert(param_name, val_str);
+                } else {
+                    // Global parameters
+                    global_config.additional_params.insert(key, val_str);
                 }
             }
             
@@ -376,9 +360,9 @@ impl SapConfig {
         
         // Preserve any sections from the original config that we don't explicitly handle
         if let Some(raw_config) = &self.raw_config {
-            // Get all top-level keys that aren't "build", "global", "tcode", or "loop"
+            // Get all top-level keys that aren't written below
             let preserved_keys: Vec<&String> = raw_config.as_table()
-                .map(|t| t.keys().filter(|k| !["build", "global", "tcode", "loop", "sap_config"].contains(&k.as_str())).collect())
+                .map(|t| t.keys().filter(|k| !["build", "global", "tcode", "loop", "sequence", "sap_config", "profile"].contains(&k.as_str())).collect())
                 .unwrap_or_default();
             
             // Add preserved sections to the content
@@ -407,7 +391,7 @@ impl SapConfig {
             
             // Add additional build parameters
             for (key, value) in &build.additional_params {
-                content.push_str(&format!("{} = \"{}\"\n", key, value));
+                content.push_str(&format!("{} = {}\n", key, self.format_param(&["build", key], value)));
             }
             
             content.push('\n');
@@ -424,14 +408,43 @@ impl SapConfig {
                 content.push_str(&format!("default_tcode = \"{}\"\n", default_tcode));
             }
             
+            // Add the wait, session and logon settings
+            for (key, value) in global.run.values() {
+                content.push_str(&format!("{} = {}\n", key, value));
+            }
+            
             // Add additional global parameters
             for (key, value) in &global.additional_params {
-                content.push_str(&format!("{} = \"{}\"\n", key, value));
+                content.push_str(&format!("{} = {}\n", key, self.format_param(&["global", key], value)));
             }
             
             content.push('\n');
         }
         
+        // Add profile sections
+        if let Some(profiles) = &self.profiles {
+            let mut names: Vec<&String> = profiles.keys().collect();
+            names.sort();
+            for name in names {
+                let profile = &profiles[name];
+                content.push_str(&format!("[profile.{}]\n", name));
+                
+                for (key, value) in [
+                    ("instance_id", &profile.instance_id),
+                    ("client", &profile.client),
+                    ("language", &profile.language),
+                    ("connection", &profile.connection),
+                    ("credentials", &profile.credentials),
+                ] {
+                    if let Some(value) = value {
+                        content.push_str(&format!("{} = \"{}\"\n", key, value));
+                    }
+                }
+                
+                content.push('\n');
+            }
+        }
+        
         // Add tcode sections
         if let Some(tcode_configs) = &self.tcode {
             for (tcode_name, tcode_config) in tcode_configs {
@@ -449,16 +462,17 @@ impl SapConfig {
                     content.push_str(&format!("column_name = \"{}\"\n", column_name));
                 }
                 
+                // Dates are written as TOML dates (YYYY-MM-DD)
                 if let Some(date_range_start) = &tcode_config.date_range_start {
-                    content.push_str(&format!("date_range_start = \"{}\"\n", date_range_start));
+                    content.push_str(&format!("date_range_start = {}\n", date_range_start.format("%Y-%m-%d")));
                 }
                 
                 if let Some(date_range_end) = &tcode_config.date_range_end {
-                    content.push_str(&format!("date_range_end = \"{}\"\n", date_range_end));
+                    content.push_str(&format!("date_range_end = {}\n", date_range_end.format("%Y-%m-%d")));
                 }
                 
                 if let Some(by_date) = &tcode_config.by_date {
-                    content.push_str(&format!("by_date = \"{}\"\n", by_date));
+                    content.push_str(&format!("by_date = {}\n", by_date));
                 }
                 
                 if let Some(serial_number) = &tcode_config.serial_number {
@@ -466,12 +480,16 @@ impl SapConfig {
                 }
                 
                 if let Some(tab_number) = &tcode_config.tab_number {
-                    content.push_str(&format!("tab_number = \"{}\"\n", tab_number));
+                    content.push_str(&format!("tab_number = {}\n", tab_number));
+                }
+                
+                if let Some(add_layout_columns) = &tcode_config.add_layout_columns {
+                    content.push_str(&format!("add_layout_columns = {}\n", format_list(add_layout_columns)));
                 }
                 
                 // Add additional tcode parameters
                 for (key, value) in &tcode_config.additional_params {
-                    content.push_str(&format!("{} = \"{}\"\n", key, value));
+                    content.push_str(&format!("{} = {}\n", key, self.format_param(&["tcode", tcode_name, key], value)));
                 }
                 
                 content.push('\n');
@@ -482,12 +500,13 @@ impl SapConfig {
         if let Some(loop_config) = &self.loop_config {
             content.push_str("[loop]\n");
             content.push_str(&format!("tcode = \"{}\"\n", loop_config.tcode));
-            content.push_str(&format!("iterations = \"{}\"\n", loop_config.iterations));
-            content.push_str(&format!("delay_seconds = \"{}\"\n", loop_config.delay_seconds));
+            content.push_str(&format!("iterations = {}\n", loop_config.iterations));
+            content.push_str(&format!("delay_seconds = {}\n", loop_config.delay_seconds));
             
             // Add additional loop parameters
             for (key, value) in &loop_config.params {
-                content.push_str(&format!("param_{} = \"{}\"\n", key, value));
+                let key = param_key(key);
+                content.push_str(&format!("{} = {}\n", key, self.format_param(&["loop", &key], value)));
             }
             
             content.push('\n');
@@ -509,13 +528,21 @@ impl SapConfig {
                 content.push_str("]\n");
             }
             
-            content.push_str(&format!("iterations = \"{}\"\n", sequence_config.iterations));
-            content.push_str(&format!("delay_seconds = \"{}\"\n", sequence_config.delay_seconds));
-            content.push_str(&format!("interval_seconds = \"{}\"\n", sequence_config.interval_seconds));
+            content.push_str(&format!("iterations = {}\n", sequence_config.iterations));
+            content.push_str(&format!("delay_seconds = {}\n", sequence_config.delay_seconds));
+            content.push_str(&format!("interval_seconds = {}\n", sequence_config.interval_seconds));
+            
+            if let Some(parallel) = sequence_config.parallel {
+                content.push_str(&format!("param_parallel = {}\n", parallel));
+            }
             
             // Add additional sequence parameters
             for (key, value) in &sequence_config.params {
-                content.push_str(&format!("param_{} = \"{}\"\n", key, value));
+                let key = param_key(key);
+                if key == "param_parallel" && sequence_config.parallel.is_some() {
+                    continue;
+                }
+                content.push_str(&format!("{} = {}\n", key, self.format_param(&["sequence", &key], value)));
             }
             
             content.push('\n');
@@ -527,6 +554,19 @@ impl SapConfig {
         Ok(())
     }
 
+    /// Write a free-form parameter as a TOML value, keeping the type it was loaded with
+    ///
+    /// `path` is where the key was read from, e.g. `["tcode", "VT11", "retries"]`. Values
+    /// that were changed or added since loading are written as strings.
+    fn format_param(&self, path: &[&str], value: &str) -> String {
+        let original = self.raw_config.as_ref()
+            .and_then(|raw| path.iter().try_fold(raw, |table, key| table.get(*key)));
+        match original {
+            Some(original) if read_text(original).as_deref() == Some(value) => original.to_string(),
+            _ => toml::Value::String(value.to_string()).to_string(),
+        }
+    }
+
     /// Get configuration for a specific tcode
     pub fn get_tcode_config(&self, tcode: &str, is_loop_run: Option<bool>) -> Option<HashMap<String, String>> {
         let is_loop_run = is_loop_run.unwrap_or(false);
@@ -563,16 +603,21 @@ impl SapConfig {
                     config.insert("column_name".to_string(), column_name.clone());
                 }
                 
+                // Dates are passed on in the configured date format
+                let pattern = date_pattern(&self.global.as_ref()
+                    .map(|g| g.date_format.clone())
+                    .unwrap_or_else(default_date_format));
+                
                 if let Some(date_range_start) = &tcode_config.date_range_start {
-                    config.insert("date_range_start".to_string(), date_range_start.clone());
+                    config.insert("date_range_start".to_string(), date_range_start.format(pattern).to_string());
                 }
                 
                 if let Some(date_range_end) = &tcode_config.date_range_end {
-                    config.insert("date_range_end".to_string(), date_range_end.clone());
+                    config.insert("date_range_end".to_string(), date_range_end.format(pattern).to_string());
                 }
                 
                 if let Some(by_date) = &tcode_config.by_date {
-                    config.insert("by_date".to_string(), by_date.clone());
+                    config.insert("by_date".to_string(), by_date.to_string());
                 }
                 
                 if let Some(serial_number) = &tcode_config.serial_number {
@@ -580,7 +625,11 @@ impl SapConfig {
                 }
                 
                 if let Some(tab_number) = &tcode_config.tab_number {
-                    config.insert("tab_number".to_string(), tab_number.clone());
+                    config.insert("tab_number".to_string(), tab_number.to_string());
+                }
+                
+                if let Some(add_layout_columns) = &tcode_config.add_layout_columns {
+                    config.insert("add_layout_columns".to_string(), format_list(add_layout_columns));
                 }
 
                 if let Some(subdir) = &tcode_config.subdir {
@@ -625,9 +674,98 @@ impl SapConfig {
     
     /// Get the instance ID
     pub fn get_instance_id(&self) -> String {
+        if let Some(instance_id) = self.get_profile().and_then(|p| p.instance_id.clone()) {
+            return instance_id;
+        }
         self.global.as_ref().map(|g| g.instance_id.clone()).unwrap_or_else(default_instance_id)
     }
     
+    /// Get the logon client (`client` of the profile or `[global]`, default 025)
+    pub fn get_client(&self) -> String {
+        self.get_profile()
+            .and_then(|p| p.client.clone())
+            .or_else(|| self.global.as_ref().and_then(|g| g.additional_params.get("client")).cloned())
+            .map(|client| client.trim().to_string())
+            .filter(|client| !client.is_empty())
+            .unwrap_or_else(|| "025".to_string())
+    }
+    
+    /// Get the logon language (`language` of the profile or `[global]`, default EN)
+    pub fn get_language(&self) -> String {
+        self.get_profile()
+            .and_then(|p| p.language.clone())
+            .or_else(|| self.global.as_ref().and_then(|g| g.additional_params.get("language")).cloned())
+            .map(|language| language.trim().to_uppercase())
+            .filter(|language| !language.is_empty())
+            .unwrap_or_else(|| "EN".to_string())
+    }
+    
+    /// Get the SAP Logon entry or connection string to open (`connection` of the profile or `[global]`)
+    pub fn get_connection(&self) -> Option<String> {
+        self.get_profile()
+            .and_then(|p| p.connection.clone())
+            .or_else(|| self.global.as_ref().and_then(|g| g.additional_params.get("connection")).cloned())
+            .filter(|connection| !connection.trim().is_empty())
+    }
+    
+    /// Get the credential store entry to log on with (`credentials` of the profile, default the instance ID)
+    pub fn get_credential_id(&self) -> String {
+        self.get_profile()
+            .and_then(|p| p.credentials.clone())
+            .unwrap_or_else(|| self.get_instance_id())
+    }
+    
+    /// Get the names of the logon profiles, sorted
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.profiles.iter().flat_map(|p| p.keys().cloned()).collect();
+        names.sort();
+        names
+    }
+    
+    /// Get the name of the profile in use (chosen for this run, else `profile` in `[global]`)
+    pub fn active_profile_name(&self) -> Option<String> {
+        self.active_profile.clone()
+            .or_else(|| self.global.as_ref().and_then(|g| g.additional_params.get("profile")).cloned())
+    }
+    
+    /// Get the profile in use, if any
+    pub fn get_profile(&self) -> Option<&ProfileConfig> {
+        let name = self.active_profile_name()?;
+        self.profiles.as_ref()?.get(&name)
+    }
+    
+    /// Check that a profile exists and has a credential store entry of its own
+    ///
+    /// Entries are bound to one client, so profiles of the same instance but another client
+    /// must not log on with the same entry (set `credentials` in one of them).
+    pub fn check_profile(&self, name: &str) -> Result<()> {
+        if !self.profiles.as_ref().is_some_and(|p| p.contains_key(name)) {
+            let names = self.profile_names();
+            return if names.is_empty() {
+                Err(anyhow!("Unknown profile '{}', config.toml has no [profile.<name>] sections", name))
+            } else {
+                Err(anyhow!("Unknown profile '{}', use one of: {}", name, names.join(", ")))
+            };
+        }
+        
+        let logon = |profile: &str| {
+            let mut resolved = self.clone();
+            resolved.active_profile = Some(profile.to_string());
+            (resolved.get_credential_id(), resolved.get_client())
+        };
+        let (credential_id, client) = logon(name);
+        for other in self.profile_names() {
+            let (other_id, other_client) = logon(&other);
+            if other_id.trim().eq_ignore_ascii_case(credential_id.trim()) && other_client != client {
+                return Err(anyhow!(
+                    "Profiles '{}' (client {}) and '{}' (client {}) both use the credentials '{}', set credentials in one of them",
+                    name, client, other, other_client, credential_id
+                ));
+            }
+        }
+        Ok(())
+    }
+    
     /// Get the reports directory
     pub fn get_reports_dir(&self) -> String {
         self.global.as_ref().map(|g| g.reports_dir.clone()).unwrap_or_else(get_default_reports_dir)
@@ -643,6 +781,7 @@ impl SapConfig {
                 reports_dir: get_default_reports_dir(),
                 default_tcode: None,
                 date_format: default_date_format(),
+                run: RunSettings::default(),
                 additional_params: HashMap::new(),
             });
         }
@@ -658,6 +797,7 @@ impl SapConfig {
                 reports_dir: reports_dir.to_string(),
                 default_tcode: None,
                 date_format: default_date_format(),
+                run: RunSettings::default(),
                 additional_params: HashMap::new(),
             });
         }
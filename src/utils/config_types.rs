@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
@@ -25,6 +26,17 @@ pub struct SapConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sequence: Option<SequenceConfig>,
     
+    #[serde(rename = "profile", skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<HashMap<String, ProfileConfig>>,
+    
+    /// Profile chosen for this run (`--profile` or the login menu), before `profile` in `[global]`
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+    
+    /// Keys of the config file that were left out, with the reason
+    #[serde(skip)]
+    pub dropped_keys: Vec<String>,
+    
     #[serde(skip)]
     pub raw_config: Option<toml::Value>,
 }
@@ -44,10 +56,45 @@ pub struct GlobalConfig {
     #[serde(default = "default_date_format")]
     pub date_format: String,
     
+    #[serde(flatten)]
+    pub run: RunSettings,
+    
     #[serde(flatten)]
     pub additional_params: HashMap<String, String>,
 }
 
+/// Wait, session and logon settings of `[global]` (None when not set)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_timeout_ms: Option<u64>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_poll_ms: Option<u64>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub popup_timeout_ms: Option<u64>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub save_timeout_ms: Option<u64>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub login_timeout_ms: Option<u64>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_stable_ms: Option<u64>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_sessions: Option<usize>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_relogin_attempts: Option<usize>,
+    
+    /// Fail instead of asking when SAP wants a new password
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unattended: Option<bool>,
+}
+
 /// Build configuration settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildConfig {
@@ -70,23 +117,27 @@ pub struct TcodeConfig {
     pub column_name: Option<String>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub date_range_start: Option<String>,
+    pub date_range_start: Option<NaiveDate>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub date_range_end: Option<String>,
+    pub date_range_end: Option<NaiveDate>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub by_date: Option<String>,
+    pub by_date: Option<bool>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
     pub serial_number: Option<String>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tab_number: Option<String>,
+    pub tab_number: Option<i32>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub subdir: Option<String>,
     
+    /// Columns ZMDESNR adds to its layout
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub add_layout_columns: Option<Vec<String>>,
+    
     #[serde(flatten)]
     pub additional_params: HashMap<String, String>,
 }
@@ -97,15 +148,38 @@ pub struct LoopConfig {
     pub tcode: String,
     
     #[serde(default = "default_iterations")]
-    pub iterations: String,
+    pub iterations: usize,
     
     #[serde(default = "default_delay_seconds")]
-    pub delay_seconds: String,
+    pub delay_seconds: u64,
     
     #[serde(flatten)]
     pub params: HashMap<String, String>,
 }
 
+/// Logon profile (`[profile.<name>]`), e.g. one per system and client a user works in
+///
+/// Settings not given fall back to `[global]`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProfileConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    
+    /// SAP Logon entry or connection string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection: Option<String>,
+    
+    /// Entry of the credential store to log on with (default: the instance ID)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credentials: Option<String>,
+}
+
 /// Sequence configuration settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SequenceConfig {
@@ -113,13 +187,17 @@ pub struct SequenceConfig {
     pub options: Vec<String>,
     
     #[serde(default = "default_iterations")]
-    pub iterations: String,
+    pub iterations: usize,
     
     #[serde(default = "default_delay_seconds")]
-    pub delay_seconds: String,
+    pub delay_seconds: u64,
     
     #[serde(default = "default_interval_seconds")]
-    pub interval_seconds: String,
+    pub interval_seconds: u64,
+    
+    /// Run consecutive read-only exports side by side (`param_parallel`)
+    #[serde(rename = "param_parallel", skip_serializing_if = "Option::is_none")]
+    pub parallel: Option<bool>,
     
     #[serde(flatten)]
     pub params: HashMap<String, String>,
@@ -140,18 +218,18 @@ pub fn default_instance_id() -> String {
 }
 
 /// Default iterations
-pub fn default_iterations() -> String {
-    "1".to_string()
+pub fn default_iterations() -> usize {
+    1
 }
 
 /// Default delay seconds
-pub fn default_delay_seconds() -> String {
-    "60".to_string()
+pub fn default_delay_seconds() -> u64 {
+    60
 }
 
 /// Default interval seconds between sequence steps
-pub fn default_interval_seconds() -> String {
-    "10".to_string()
+pub fn default_interval_seconds() -> u64 {
+    10
 }
 
 /// Default sequence options
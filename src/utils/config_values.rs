@@ -0,0 +1,167 @@
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::str::FromStr;
+use toml::{Table, Value};
+
+/// Get the chrono pattern for the `date_format` of `[global]`
+pub fn date_pattern(date_format: &str) -> &'static str {
+    if date_format.eq_ignore_ascii_case("yyyy-mm-dd") {
+        "%Y-%m-%d"
+    } else {
+        "%m/%d/%Y"
+    }
+}
+
+/// Read a free-form value as text (strings, numbers, booleans and dates)
+pub fn read_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Integer(_) | Value::Float(_) | Value::Boolean(_) | Value::Datetime(_) => {
+            Some(value.to_string())
+        }
+        Value::Array(_) | Value::Table(_) => None,
+    }
+}
+
+/// Read a whole number, written as `5` or as the legacy `"5"`
+pub fn read_integer<T: TryFrom<i64> + FromStr>(value: &Value) -> Option<T> {
+    match value {
+        Value::Integer(number) => T::try_from(*number).ok(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Read a boolean, written as `true` or as the legacy `"true"`
+pub fn read_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Boolean(flag) => Some(*flag),
+        Value::String(text) if text.trim().eq_ignore_ascii_case("true") => Some(true),
+        Value::String(text) if text.trim().eq_ignore_ascii_case("false") => Some(false),
+        _ => None,
+    }
+}
+
+/// Read a date, written as `2025-04-01` or as a string in `date_format`
+///
+/// Quoted ISO dates (`"2025-04-01"`) are accepted whatever the `date_format`.
+pub fn read_date(value: &Value, date_format: &str) -> Option<NaiveDate> {
+    match value {
+        Value::Datetime(datetime) => match (datetime.date, datetime.time) {
+            (Some(date), None) => {
+                NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())
+            }
+            _ => None,
+        },
+        Value::String(text) => NaiveDate::parse_from_str(text.trim(), date_pattern(date_format))
+            .or_else(|_| NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d"))
+            .ok(),
+        _ => None,
+    }
+}
+
+/// Read a list of strings, written as an array or as a legacy string
+pub fn read_list(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| item.as_str().map(|text| text.to_string()))
+            .collect(),
+        Value::String(text) => parse_list(text),
+        _ => None,
+    }
+}
+
+/// Parse a list kept as text, either a TOML array or comma separated
+///
+/// E.g. `["Created By", "Shipment Number"]` or `Created By, Shipment Number`.
+pub fn parse_list(text: &str) -> Option<Vec<String>> {
+    let text = text.trim();
+    if text.starts_with('[') {
+        let table = format!("list = {}", text).parse::<Table>().ok()?;
+        return table.get("list").and_then(read_list);
+    }
+    Some(
+        text.split(',')
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .map(|item| item.to_string())
+            .collect(),
+    )
+}
+
+/// Write a list as a TOML array, e.g. `["Created By", "Shipment Number"]`
+pub fn format_list(items: &[String]) -> String {
+    Value::Array(items.iter().cloned().map(Value::String).collect()).to_string()
+}
+
+/// Reads the keys of one config section, noting the ones that cannot be used
+///
+/// A key is dropped when its type or value does not fit, e.g. `by_date = "maybe"`.
+/// Missing keys are not noted.
+pub struct SectionReader<'a> {
+    table: &'a Table,
+    section: String,
+    dropped: &'a mut Vec<String>,
+}
+
+impl<'a> SectionReader<'a> {
+    /// Read `table`, naming dropped keys after `section` (e.g. `tcode.VT11`)
+    pub fn new(table: &'a Table, section: &str, dropped: &'a mut Vec<String>) -> Self {
+        Self {
+            table,
+            section: section.to_string(),
+            dropped,
+        }
+    }
+
+    pub fn text(&mut self, key: &str) -> Option<String> {
+        self.read(key, "a string", read_text)
+    }
+
+    pub fn integer<T: TryFrom<i64> + FromStr>(&mut self, key: &str) -> Option<T> {
+        self.read(key, "a whole number", read_integer::<T>)
+    }
+
+    pub fn bool(&mut self, key: &str) -> Option<bool> {
+        self.read(key, "true or false", read_bool)
+    }
+
+    pub fn date(&mut self, key: &str, date_format: &str) -> Option<NaiveDate> {
+        let expected = format!("a date (YYYY-MM-DD or {})", date_format);
+        self.read(key, &expected, |value| read_date(value, date_format))
+    }
+
+    pub fn list(&mut self, key: &str) -> Option<Vec<String>> {
+        self.read(key, "a list of strings", read_list)
+    }
+
+    /// Read the keys not in `known` as text, for the free-form parameters of a section
+    pub fn others(&mut self, known: &[&str]) -> HashMap<String, String> {
+        let table = self.table;
+        let keys: Vec<&String> = table
+            .keys()
+            .filter(|key| !known.contains(&key.as_str()))
+            .collect();
+        keys.into_iter()
+            .filter_map(|key| Some((key.clone(), self.text(key)?)))
+            .collect()
+    }
+
+    fn read<T>(
+        &mut self,
+        key: &str,
+        expected: &str,
+        read: impl Fn(&Value) -> Option<T>,
+    ) -> Option<T> {
+        let value = self.table.get(key)?;
+        let result = read(value);
+        if result.is_none() {
+            self.dropped.push(format!(
+                "{}.{} (expected {}, found {})",
+                self.section, key, expected, value
+            ));
+        }
+        result
+    }
+}
@@ -0,0 +1,405 @@
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    aes::{cipher::BlockEncrypt, Aes256, Block},
+    Aes256Gcm, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Current format version (version 1 is the bare base64 `nonce + ciphertext` of older releases)
+pub const ENVELOPE_VERSION: u32 = 2;
+
+/// Why a credential file could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialError {
+    /// Written by an older release without envelope, needs the legacy reader
+    OldFormat,
+    /// Written by a newer release
+    UnsupportedVersion(u32),
+    /// Not a credential file at all
+    Malformed(String),
+    /// Saved for another instance or client
+    WrongScope { expected: String, found: String },
+    /// The key (or master password) is not the one the file was written with
+    WrongKey,
+    /// The key is right but the file was changed after it was written
+    Tampered,
+    /// Encryption or key derivation failed
+    Crypto(String),
+}
+
+impl fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialError::OldFormat => write!(f, "Credentials use the old file format"),
+            CredentialError::UnsupportedVersion(version) => write!(
+                f,
+                "Credentials use format version {}, this release reads up to version {}",
+                version, ENVELOPE_VERSION
+            ),
+            CredentialError::Malformed(reason) => {
+                write!(f, "Credential file is not readable: {}", reason)
+            }
+            CredentialError::WrongScope { expected, found } => write!(
+                f,
+                "Credentials were saved for {}, not for {}",
+                found, expected
+            ),
+            CredentialError::WrongKey => write!(f, "Credentials were saved with another key"),
+            CredentialError::Tampered => {
+                write!(f, "Credential file was changed after it was saved")
+            }
+            CredentialError::Crypto(reason) => write!(f, "Encryption failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CredentialError {}
+
+/// Accepted Argon2id memory cost in KiB (1 MiB to 1 GiB)
+pub const KDF_MEMORY_KIB: RangeInclusive<u32> = 1024..=1024 * 1024;
+
+/// Accepted Argon2id iterations
+pub const KDF_ITERATIONS: RangeInclusive<u32> = 1..=16;
+
+/// Accepted Argon2id lanes
+pub const KDF_PARALLELISM: RangeInclusive<u32> = 1..=16;
+
+/// Cost settings of the Argon2id key derivation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Check that the settings are within the accepted ranges
+    ///
+    /// Vault files carry their own settings, which must not make Argon2 allocate
+    /// unbounded memory or run for hours.
+    pub fn validate(&self) -> Result<(), CredentialError> {
+        let check = |value: u32, range: &RangeInclusive<u32>, name: &str| {
+            if range.contains(&value) {
+                Ok(())
+            } else {
+                Err(CredentialError::Malformed(format!(
+                    "key derivation {} {} is outside {} to {}",
+                    name,
+                    value,
+                    range.start(),
+                    range.end()
+                )))
+            }
+        };
+        check(self.memory_kib, &KDF_MEMORY_KIB, "memory (KiB)")?;
+        check(self.iterations, &KDF_ITERATIONS, "iterations")?;
+        check(self.parallelism, &KDF_PARALLELISM, "parallelism")
+    }
+
+    /// Derive a 256 bit key from a password with Argon2id
+    pub fn derive_key(&self, password: &str, salt: &[u8]) -> Result<[u8; 32], CredentialError> {
+        self.validate()?;
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| {
+                CredentialError::Crypto(format!("invalid key derivation settings: {}", e))
+            })?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password.as_bytes(), salt, &mut key)
+            .map_err(|e| CredentialError::Crypto(format!("key derivation failed: {}", e)))?;
+        Ok(key)
+    }
+}
+
+/// How the key of an envelope is obtained
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "lowercase")]
+pub enum EnvelopeKdf {
+    /// Random key kept in a key file
+    None,
+    /// Key derived from a password
+    Argon2id {
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+        salt: String,
+    },
+}
+
+impl EnvelopeKdf {
+    /// Argon2id with the given settings and a new random salt
+    pub fn argon2id(params: KdfParams) -> Self {
+        let salt: [u8; 16] = rand::thread_rng().gen();
+        EnvelopeKdf::Argon2id {
+            memory_kib: params.memory_kib,
+            iterations: params.iterations,
+            parallelism: params.parallelism,
+            salt: general_purpose::STANDARD.encode(salt),
+        }
+    }
+
+    /// Derive the key from a password (fails for key file envelopes)
+    pub fn derive_key(&self, password: &str) -> Result<[u8; 32], CredentialError> {
+        match self {
+            EnvelopeKdf::None => Err(CredentialError::Crypto(
+                "envelope uses a key file, not a password".to_string(),
+            )),
+            EnvelopeKdf::Argon2id {
+                memory_kib,
+                iterations,
+                parallelism,
+                salt,
+            } => {
+                let salt = general_purpose::STANDARD
+                    .decode(salt)
+                    .map_err(|_| CredentialError::Malformed("invalid salt".to_string()))?;
+                KdfParams {
+                    memory_kib: *memory_kib,
+                    iterations: *iterations,
+                    parallelism: *parallelism,
+                }
+                .derive_key(password, &salt)
+            }
+        }
+    }
+}
+
+/// Encrypted credentials with the header they are bound to
+///
+/// Everything but nonce and ciphertext is authenticated as associated data, so a file
+/// cannot be moved to another instance or client, or have its dates changed, unnoticed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialEnvelope {
+    pub version: u32,
+    pub kdf: EnvelopeKdf,
+    pub instance_id: String,
+    pub client: String,
+    pub created: String,
+    pub rotated: String,
+    /// Identifies the key without revealing it, to tell a wrong key from a changed file
+    pub key_check: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Header fields authenticated with the ciphertext
+#[derive(Serialize)]
+struct Header<'a> {
+    version: u32,
+    kdf: &'a EnvelopeKdf,
+    instance_id: &'a str,
+    client: &'a str,
+    created: &'a str,
+    rotated: &'a str,
+    key_check: &'a str,
+}
+
+/// Get the key check value (AES of a zero block, first 8 bytes)
+fn key_check(key: &[u8]) -> Result<String, CredentialError> {
+    let cipher = Aes256::new_from_slice(key)
+        .map_err(|_| CredentialError::Crypto("key must be 256 bits".to_string()))?;
+    let mut block = Block::default();
+    cipher.encrypt_block(&mut block);
+    Ok(general_purpose::STANDARD.encode(&block[..8]))
+}
+
+/// Describe the scope of an envelope for messages, e.g. `ES1 client 025`
+fn scope(instance_id: &str, client: &str) -> String {
+    format!("{} client {}", instance_id, client)
+}
+
+/// Normalize an instance or client binding so sealing and opening compare the same form
+fn normalize_binding(value: &str) -> String {
+    value.trim().to_ascii_uppercase()
+}
+
+impl CredentialEnvelope {
+    /// Encrypt `plaintext` for an instance and client
+    pub fn seal(
+        plaintext: &str,
+        key: &[u8],
+        kdf: EnvelopeKdf,
+        instance_id: &str,
+        client: &str,
+    ) -> Result<Self, CredentialError> {
+        let now = Utc::now().to_rfc3339();
+        Self::seal_at(plaintext, key, kdf, instance_id, client, &now, &now)
+    }
+
+    /// Encrypt new contents for the same instance and client, keeping the creation date
+    pub fn reseal(&self, plaintext: &str, key: &[u8]) -> Result<Self, CredentialError> {
+        Self::seal_at(
+            plaintext,
+            key,
+            self.kdf.clone(),
+            &self.instance_id,
+            &self.client,
+            &self.created,
+            &Utc::now().to_rfc3339(),
+        )
+    }
+
+    fn seal_at(
+        plaintext: &str,
+        key: &[u8],
+        kdf: EnvelopeKdf,
+        instance_id: &str,
+        client: &str,
+        created: &str,
+        rotated: &str,
+    ) -> Result<Self, CredentialError> {
+        let mut envelope = Self {
+            version: ENVELOPE_VERSION,
+            kdf,
+            instance_id: normalize_binding(instance_id),
+            client: normalize_binding(client),
+            created: created.to_string(),
+            rotated: rotated.to_string(),
+            key_check: key_check(key)?,
+            nonce: String::new(),
+            ciphertext: String::new(),
+        };
+
+        let cipher = Aes256Gcm::new_from_slice(key)
+            .map_err(|_| CredentialError::Crypto("key must be 256 bits".to_string()))?;
+        let nonce_bytes: [u8; 12] = rand::thread_rng().gen();
+        let aad = envelope.aad()?;
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce_bytes),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| CredentialError::Crypto("AES-GCM encryption failed".to_string()))?;
+
+        envelope.nonce = general_purpose::STANDARD.encode(nonce_bytes);
+        envelope.ciphertext = general_purpose::STANDARD.encode(ciphertext);
+        Ok(envelope)
+    }
+
+    /// Decrypt the contents, checking that they were saved for this instance and client
+    pub fn open(
+        &self,
+        key: &[u8],
+        instance_id: &str,
+        client: &str,
+    ) -> Result<String, CredentialError> {
+        if normalize_binding(&self.instance_id) != normalize_binding(instance_id)
+            || normalize_binding(&self.client) != normalize_binding(client)
+        {
+            return Err(CredentialError::WrongScope {
+                expected: scope(instance_id, client),
+                found: scope(&self.instance_id, &self.client),
+            });
+        }
+        self.open_unscoped(key)
+    }
+
+    /// Decrypt the contents without checking instance and client
+    pub fn open_unscoped(&self, key: &[u8]) -> Result<String, CredentialError> {
+        if key_check(key)? != self.key_check {
+            return Err(CredentialError::WrongKey);
+        }
+
+        let decode = |value: &str, field: &str| {
+            general_purpose::STANDARD
+                .decode(value)
+                .map_err(|_| CredentialError::Malformed(format!("invalid {}", field)))
+        };
+        let nonce = decode(&self.nonce, "nonce")?;
+        if nonce.len() != 12 {
+            return Err(CredentialError::Malformed("invalid nonce".to_string()));
+        }
+        let ciphertext = decode(&self.ciphertext, "ciphertext")?;
+
+        let cipher = Aes256Gcm::new_from_slice(key)
+            .map_err(|_| CredentialError::Crypto("key must be 256 bits".to_string()))?;
+        let aad = self.aad()?;
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| CredentialError::Tampered)?;
+        String::from_utf8(plaintext).map_err(|_| CredentialError::Tampered)
+    }
+
+    /// Read an envelope from the contents of a credential file
+    pub fn parse(content: &str) -> Result<Self, CredentialError> {
+        let content = content.trim();
+        if !content.starts_with('{') {
+            // Older releases wrote the bare base64 of nonce and ciphertext
+            return match general_purpose::STANDARD.decode(content) {
+                Ok(data) if data.len() > 12 => Err(CredentialError::OldFormat),
+                _ => Err(CredentialError::Malformed(
+                    "neither an envelope nor the old format".to_string(),
+                )),
+            };
+        }
+
+        let value: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| CredentialError::Malformed(e.to_string()))?;
+        let version = value
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| CredentialError::Malformed("no version".to_string()))?;
+        match u32::try_from(version) {
+            Ok(ENVELOPE_VERSION) => {}
+            // Version 1 never had a JSON envelope
+            Ok(version) if version < ENVELOPE_VERSION => {
+                return Err(CredentialError::Malformed(format!(
+                    "format version {} has no envelope",
+                    version
+                )))
+            }
+            Ok(version) => return Err(CredentialError::UnsupportedVersion(version)),
+            Err(_) => {
+                return Err(CredentialError::Malformed(format!(
+                    "invalid format version {}",
+                    version
+                )))
+            }
+        }
+        serde_json::from_value(value).map_err(|e| CredentialError::Malformed(e.to_string()))
+    }
+
+    /// Write the envelope as the contents of a credential file
+    pub fn to_file_string(&self) -> Result<String, CredentialError> {
+        serde_json::to_string_pretty(self).map_err(|e| CredentialError::Malformed(e.to_string()))
+    }
+
+    fn aad(&self) -> Result<Vec<u8>, CredentialError> {
+        serde_json::to_vec(&Header {
+            version: self.version,
+            kdf: &self.kdf,
+            instance_id: &self.instance_id,
+            client: &self.client,
+            created: &self.created,
+            rotated: &self.rotated,
+            key_check: &self.key_check,
+        })
+        .map_err(|e| CredentialError::Malformed(e.to_string()))
+    }
+}
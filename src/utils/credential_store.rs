@@ -0,0 +1,797 @@
+use anyhow::{anyhow, Context, Result};
+use dialoguer::{Input, Select};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::utils::config_types::SapConfig;
+use crate::utils::credential_envelope::{
+    CredentialEnvelope, CredentialError, EnvelopeKdf, ENVELOPE_VERSION,
+};
+use crate::utils::sap_crypto_utils::{decrypt_data, generate_key};
+use crate::utils::utils::KEY_FILE_SUFFIX;
+
+/// Environment variable holding the vault master password (asked for if not set)
+pub const VAULT_PASSWORD_VAR: &str = "SAP_VAULT_PASSWORD";
+
+/// Vault file name used unless `vault_path` is set in `[global]`
+pub const VAULT_FILE: &str = "sap_vault.json";
+
+pub use crate::utils::credential_envelope::KdfParams;
+
+/// SAP user name and password for one instance
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub user: String,
+    pub password: String,
+}
+
+impl Credentials {
+    pub fn new(user: &str, password: &str) -> Self {
+        Self {
+            user: user.to_string(),
+            password: password.to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("user", &self.user)
+            .field("password", &"********")
+            .finish()
+    }
+}
+
+/// Place where SAP credentials are kept, one entry per instance ID
+pub trait CredentialStore {
+    /// Short name of the backend, as used for `credential_store` in `[global]`
+    fn name(&self) -> &'static str;
+
+    /// Get the credentials of an instance (None if there are none)
+    fn get(&self, instance_id: &str) -> Result<Option<Credentials>>;
+
+    /// Add or replace the credentials of an instance
+    fn set(&mut self, instance_id: &str, credentials: &Credentials) -> Result<()>;
+
+    /// Remove the credentials of an instance, returns false if there were none
+    fn remove(&mut self, instance_id: &str) -> Result<bool>;
+
+    /// Get the instance IDs that have credentials
+    fn list(&self) -> Result<Vec<String>>;
+
+    /// Check whether entries can only be read, not changed
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// Replace the password of an instance, keeping the user
+    fn rotate_password(&mut self, instance_id: &str, password: &str) -> Result<()> {
+        let mut credentials = self
+            .get(instance_id)?
+            .ok_or_else(|| anyhow!("No credentials saved for {}", instance_id))?;
+        credentials.password = password.to_string();
+        self.set(instance_id, &credentials)
+    }
+
+    /// Encrypt every entry with a new key, returns the rotated instance IDs
+    fn rotate_keys(&mut self) -> Result<Vec<String>> {
+        Err(anyhow!(
+            "The {} credential store has no keys to rotate",
+            self.name()
+        ))
+    }
+
+    /// Rewrite entries saved in the file format of an older release, returns their instance IDs
+    fn upgrade_entries(&mut self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Replace a file by writing a temporary file next to it and renaming it
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = append_extension(path, "tmp");
+    fs::write(&tmp, contents).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+/// Get `path` with an extra extension, e.g. `sap_auto_ES1.txt.bak`
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// Directory the credential files are kept in (`%USERPROFILE%\Documents\SAP`)
+pub fn default_credentials_dir() -> PathBuf {
+    match env::var("USERPROFILE") {
+        Ok(profile) => Path::new(&profile).join("Documents").join("SAP"),
+        Err(_) => {
+            eprintln!("Could not determine user profile directory");
+            PathBuf::from(".")
+        }
+    }
+}
+
+/// One encrypted file plus a raw key file per instance (`sap_auto_<instance>.txt`)
+///
+/// Files are written as a [`CredentialEnvelope`] bound to the instance and client.
+/// Files of older releases (bare base64 without header) can still be read, and are
+/// rewritten by [`CredentialStore::upgrade_entries`].
+pub struct FileCredentialStore {
+    dir: PathBuf,
+    client: String,
+}
+
+impl FileCredentialStore {
+    /// Create a store whose entries are bound to the logon client
+    pub fn new(dir: impl Into<PathBuf>, client: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            client: client.to_string(),
+        }
+    }
+
+    /// Get the path of the encrypted credentials of an instance
+    pub fn auth_file(&self, instance_id: &str) -> PathBuf {
+        self.dir.join(format!("sap_auto_{}.txt", instance_id))
+    }
+
+    /// Get the path of the key file of an instance
+    pub fn key_file(&self, instance_id: &str) -> PathBuf {
+        self.dir
+            .join(format!("sap_auto_{}{}", instance_id, KEY_FILE_SUFFIX))
+    }
+
+    /// Get the path of the backup written while the key of an instance is rotated
+    pub fn backup_file(path: &Path) -> PathBuf {
+        append_extension(path, "bak")
+    }
+
+    /// Read the key and decrypted contents of an instance, without changing any file
+    ///
+    /// The client binding is not checked: rotation covers the entries of every client and
+    /// keeps the binding each one was saved with.
+    fn read_entry(&self, instance_id: &str) -> Result<(CredentialEnvelope, String)> {
+        let key_file = self.key_file(instance_id);
+        let key = fs::read(&key_file)
+            .with_context(|| format!("Failed to read {}", key_file.display()))?;
+        let (envelope, _) = self
+            .load_envelope(instance_id, &key)?
+            .ok_or_else(|| anyhow!("No credentials saved for {}", instance_id))?;
+        let plaintext = envelope
+            .open_unscoped(&key)
+            .with_context(|| format!("Failed to read {}", self.auth_file(instance_id).display()))?;
+        Ok((envelope, plaintext))
+    }
+
+    /// Encrypt one entry with a new key in memory and check that it opens again
+    fn prepare_rotation(&self, instance_id: &str) -> Result<RotatedEntry> {
+        let (envelope, plaintext) = self.read_entry(instance_id)?;
+        let key = generate_key();
+        let rotated = envelope.reseal(&plaintext, &key)?;
+        if rotated.open(&key, &envelope.instance_id, &envelope.client)? != plaintext {
+            return Err(anyhow!(
+                "Re-encrypted credentials of {} differ",
+                instance_id
+            ));
+        }
+        Ok(RotatedEntry {
+            instance_id: instance_id.to_string(),
+            key: key.to_vec(),
+            content: rotated.to_file_string()?,
+            plaintext,
+        })
+    }
+
+    /// Get the auth and key file of an instance
+    fn entry_files(&self, instance_id: &str) -> [PathBuf; 2] {
+        [self.auth_file(instance_id), self.key_file(instance_id)]
+    }
+
+    /// Replace the files of all rotated entries
+    ///
+    /// The old files are kept as `.bak` until every new entry has been read back, and
+    /// all of them are put back if one fails, so no entry is left on another key.
+    fn swap_rotated(&self, entries: &[RotatedEntry]) -> Result<()> {
+        let files: Vec<PathBuf> = entries
+            .iter()
+            .flat_map(|entry| self.entry_files(&entry.instance_id))
+            .collect();
+
+        let mut backed_up = Vec::new();
+        for file in &files {
+            let backup = Self::backup_file(file);
+            if let Err(e) = fs::copy(file, &backup) {
+                for file in backed_up {
+                    let _ = fs::remove_file(Self::backup_file(file));
+                }
+                return Err(anyhow::Error::new(e)
+                    .context(format!("Failed to back up {}", file.display()))
+                    .context("Key rotation cancelled, no key was changed"));
+            }
+            backed_up.push(file);
+        }
+
+        let written = entries.iter().try_for_each(|entry| {
+            write_atomic(&self.key_file(&entry.instance_id), &entry.key)?;
+            write_atomic(
+                &self.auth_file(&entry.instance_id),
+                entry.content.as_bytes(),
+            )
+        });
+        let verified = written.and_then(|_| {
+            entries.iter().try_for_each(|entry| {
+                let (_, read_back) = self.read_entry(&entry.instance_id)?;
+                if read_back != entry.plaintext {
+                    return Err(anyhow!(
+                        "Re-encrypted credentials of {} differ",
+                        entry.instance_id
+                    ));
+                }
+                Ok(())
+            })
+        });
+        if let Err(e) = verified {
+            for file in &files {
+                let _ = fs::rename(Self::backup_file(file), file);
+            }
+            return Err(e.context("Failed to rotate the keys, the old keys were kept"));
+        }
+
+        // The new keys work, only now the old ones are deleted
+        for file in &files {
+            let backup = Self::backup_file(file);
+            if let Err(e) = fs::remove_file(&backup) {
+                eprintln!("Failed to remove {}: {}", backup.display(), e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the envelope of an instance, converting a file of an older release in memory
+    ///
+    /// Returns whether the file is of an older release and should be rewritten.
+    fn load_envelope(
+        &self,
+        instance_id: &str,
+        key: &[u8],
+    ) -> Result<Option<(CredentialEnvelope, bool)>> {
+        let auth_file = self.auth_file(instance_id);
+        let Ok(content) = fs::read_to_string(&auth_file) else {
+            return Ok(None);
+        };
+
+        match CredentialEnvelope::parse(&content) {
+            Ok(envelope) => Ok(Some((envelope, false))),
+            Err(CredentialError::OldFormat) => {
+                let plaintext = decrypt_data(content.trim(), key)
+                    .map_err(|_| CredentialError::WrongKey)
+                    .with_context(|| format!("Failed to read {}", auth_file.display()))?;
+                let envelope = CredentialEnvelope::seal(
+                    &plaintext,
+                    key,
+                    EnvelopeKdf::None,
+                    instance_id,
+                    &self.client,
+                )?;
+                Ok(Some((envelope, true)))
+            }
+            Err(e) => Err(anyhow::Error::new(e))
+                .with_context(|| format!("Failed to read {}", auth_file.display())),
+        }
+    }
+}
+
+/// An entry encrypted with a new key, not yet written
+struct RotatedEntry {
+    instance_id: String,
+    key: Vec<u8>,
+    content: String,
+    plaintext: String,
+}
+
+impl CredentialStore for FileCredentialStore {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn get(&self, instance_id: &str) -> Result<Option<Credentials>> {
+        let Ok(key) = fs::read(self.key_file(instance_id)) else {
+            return Ok(None);
+        };
+        let Some((envelope, _)) = self.load_envelope(instance_id, &key)? else {
+            return Ok(None);
+        };
+
+        let decrypted_data = envelope
+            .open(&key, instance_id, &self.client)
+            .with_context(|| format!("Failed to read {}", self.auth_file(instance_id).display()))?;
+        match decrypted_data.split_once('\n') {
+            Some((user, password)) => Ok(Some(Credentials::new(user, password))),
+            None => Err(anyhow!(
+                "Saved credentials of {} are incomplete",
+                instance_id
+            )),
+        }
+    }
+
+    fn set(&mut self, instance_id: &str, credentials: &Credentials) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+
+        // Keep the existing key, generate one for new entries
+        let key_file = self.key_file(instance_id);
+        let key = match fs::read(&key_file) {
+            Ok(key) => key,
+            Err(_) => {
+                let key = generate_key().to_vec();
+                write_atomic(&key_file, &key)?;
+                key
+            }
+        };
+
+        // Keep the creation date of an entry that is still readable with its key
+        let content = format!("{}\n{}", credentials.user, credentials.password);
+        let envelope = match self.load_envelope(instance_id, &key) {
+            Ok(Some((existing, _))) if existing.open(&key, instance_id, &self.client).is_ok() => {
+                existing.reseal(&content, &key)?
+            }
+            _ => CredentialEnvelope::seal(
+                &content,
+                &key,
+                EnvelopeKdf::None,
+                instance_id,
+                &self.client,
+            )?,
+        };
+        write_atomic(
+            &self.auth_file(instance_id),
+            envelope.to_file_string()?.as_bytes(),
+        )
+    }
+
+    fn remove(&mut self, instance_id: &str) -> Result<bool> {
+        let auth_file = self.auth_file(instance_id);
+        if !auth_file.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(&auth_file)
+            .with_context(|| format!("Failed to remove {}", auth_file.display()))?;
+        let key_file = self.key_file(instance_id);
+        if key_file.exists() {
+            fs::remove_file(&key_file)
+                .with_context(|| format!("Failed to remove {}", key_file.display()))?;
+        }
+        Ok(true)
+    }
+
+    fn list(&self) -> Result<Vec<String>> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Ok(Vec::new());
+        };
+        let mut ids: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name.strip_prefix("sap_auto_")?
+                    .strip_suffix(".txt")
+                    .map(str::to_string)
+            })
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn rotate_keys(&mut self) -> Result<Vec<String>> {
+        // Encrypt everything with new keys in memory first, so nothing is changed if
+        // one entry is unreadable
+        let ids = self.list()?;
+        let entries = ids
+            .iter()
+            .map(|id| self.prepare_rotation(id))
+            .collect::<Result<Vec<_>>>()
+            .context("Key rotation cancelled, no key was changed")?;
+
+        self.swap_rotated(&entries)?;
+        Ok(ids)
+    }
+
+    fn upgrade_entries(&mut self) -> Result<Vec<String>> {
+        let mut upgraded = Vec::new();
+        for id in self.list()? {
+            let key_file = self.key_file(&id);
+            let key = fs::read(&key_file)
+                .with_context(|| format!("Failed to read {}", key_file.display()))?;
+            if let Some((envelope, true)) = self.load_envelope(&id, &key)? {
+                write_atomic(&self.auth_file(&id), envelope.to_file_string()?.as_bytes())?;
+                upgraded.push(id);
+            }
+        }
+        Ok(upgraded)
+    }
+}
+
+/// Credentials from environment variables, for CI and service accounts
+///
+/// `SAP_USER_<INSTANCE>`/`SAP_PASSWORD_<INSTANCE>` (instance ID in upper case) are used
+/// before the instance independent `SAP_USER`/`SAP_PASSWORD`.
+pub struct EnvCredentialStore;
+
+impl EnvCredentialStore {
+    /// Get the variable name suffix of an instance, e.g. `ES1` for `es1`
+    pub fn var_suffix(instance_id: &str) -> String {
+        instance_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+}
+
+impl CredentialStore for EnvCredentialStore {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn get(&self, instance_id: &str) -> Result<Option<Credentials>> {
+        let suffix = Self::var_suffix(instance_id);
+        let pair = |user_var: &str, password_var: &str| {
+            Some(Credentials {
+                user: env::var(user_var).ok()?,
+                password: env::var(password_var).ok()?,
+            })
+        };
+        Ok(pair(
+            &format!("SAP_USER_{}", suffix),
+            &format!("SAP_PASSWORD_{}", suffix),
+        )
+        .or_else(|| pair("SAP_USER", "SAP_PASSWORD")))
+    }
+
+    fn set(&mut self, _instance_id: &str, _credentials: &Credentials) -> Result<()> {
+        Err(anyhow!(
+            "Credentials from environment variables cannot be changed here"
+        ))
+    }
+
+    fn remove(&mut self, _instance_id: &str) -> Result<bool> {
+        Err(anyhow!(
+            "Credentials from environment variables cannot be changed here"
+        ))
+    }
+
+    fn list(&self) -> Result<Vec<String>> {
+        let mut ids: Vec<String> = env::vars()
+            .filter_map(|(name, _)| name.strip_prefix("SAP_USER_").map(str::to_string))
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
+
+/// All entries in one file, encrypted with a key derived from a master password
+///
+/// The file is a [`CredentialEnvelope`] holding the Argon2id settings and salt.
+pub struct VaultCredentialStore {
+    path: PathBuf,
+    kdf: EnvelopeKdf,
+    key: [u8; 32],
+    envelope: Option<CredentialEnvelope>,
+    entries: BTreeMap<String, Credentials>,
+}
+
+/// Instance and client of the vault envelope, which holds entries of all instances
+const VAULT_SCOPE: &str = "*";
+
+impl VaultCredentialStore {
+    /// Open the vault, creating an empty one if the file does not exist
+    pub fn open(path: impl Into<PathBuf>, master_password: &str) -> Result<Self> {
+        Self::open_with(path, master_password, KdfParams::default())
+    }
+
+    /// Open the vault, creating an empty one with the given key derivation settings if needed
+    pub fn open_with(
+        path: impl Into<PathBuf>,
+        master_password: &str,
+        kdf: KdfParams,
+    ) -> Result<Self> {
+        let path = path.into();
+        if !path.exists() {
+            let kdf = EnvelopeKdf::argon2id(kdf);
+            let key = kdf.derive_key(master_password)?;
+            return Ok(Self {
+                path,
+                kdf,
+                key,
+                envelope: None,
+                entries: BTreeMap::new(),
+            });
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let envelope = CredentialEnvelope::parse(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        let key = envelope.kdf.derive_key(master_password)?;
+        let entries = match envelope.open(&key, VAULT_SCOPE, VAULT_SCOPE) {
+            Ok(entries) => entries,
+            Err(CredentialError::WrongKey) => {
+                return Err(anyhow!(
+                    "Wrong master password for vault {}",
+                    path.display()
+                ))
+            }
+            Err(e) => {
+                return Err(anyhow::Error::new(e))
+                    .with_context(|| format!("Damaged vault {}", path.display()))
+            }
+        };
+        let entries = serde_json::from_str(&entries)
+            .with_context(|| format!("Failed to parse the entries of {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            kdf: envelope.kdf.clone(),
+            key,
+            envelope: Some(envelope),
+            entries,
+        })
+    }
+
+    /// Get the path of the vault file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn save(&mut self) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let entries = serde_json::to_string(&self.entries)?;
+        let envelope = match &self.envelope {
+            Some(envelope) => envelope.reseal(&entries, &self.key)?,
+            None => CredentialEnvelope::seal(
+                &entries,
+                &self.key,
+                self.kdf.clone(),
+                VAULT_SCOPE,
+                VAULT_SCOPE,
+            )?,
+        };
+        write_atomic(&self.path, envelope.to_file_string()?.as_bytes())?;
+        self.envelope = Some(envelope);
+        Ok(())
+    }
+}
+
+impl CredentialStore for VaultCredentialStore {
+    fn name(&self) -> &'static str {
+        "vault"
+    }
+
+    fn get(&self, instance_id: &str) -> Result<Option<Credentials>> {
+        Ok(self.entries.get(instance_id).cloned())
+    }
+
+    fn set(&mut self, instance_id: &str, credentials: &Credentials) -> Result<()> {
+        self.entries
+            .insert(instance_id.to_string(), credentials.clone());
+        self.save()
+    }
+
+    fn remove(&mut self, instance_id: &str) -> Result<bool> {
+        if self.entries.remove(instance_id).is_none() {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    fn list(&self) -> Result<Vec<String>> {
+        Ok(self.entries.keys().cloned().collect())
+    }
+}
+
+/// Get the vault master password from `SAP_VAULT_PASSWORD` or ask for it
+fn vault_password() -> Result<String> {
+    if let Ok(password) = env::var(VAULT_PASSWORD_VAR) {
+        return Ok(password);
+    }
+    print!("Vault master password: ");
+    io::stdout().flush()?;
+    Ok(rpassword::read_password()?)
+}
+
+/// Open the credential store selected with `credential_store` in `[global]` (default `file`)
+///
+/// File store entries are bound to `client`, the client of the logon.
+pub fn open_credential_store(config: &SapConfig, client: &str) -> Result<Box<dyn CredentialStore>> {
+    let param = |key: &str| {
+        config
+            .global
+            .as_ref()
+            .and_then(|g| g.additional_params.get(key))
+            .map(|value| value.trim().to_string())
+    };
+
+    match param("credential_store")
+        .unwrap_or_else(|| "file".to_string())
+        .to_lowercase()
+        .as_str()
+    {
+        "file" => Ok(Box::new(FileCredentialStore::new(
+            default_credentials_dir(),
+            client,
+        ))),
+        "env" => Ok(Box::new(EnvCredentialStore)),
+        "vault" => {
+            let path = param("vault_path")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| default_credentials_dir().join(VAULT_FILE));
+            Ok(Box::new(VaultCredentialStore::open(
+                path,
+                &vault_password()?,
+            )?))
+        }
+        other => Err(anyhow!(
+            "Unknown credential_store '{}', use file, env or vault",
+            other
+        )),
+    }
+}
+
+/// List, add, remove and rotate saved credentials
+pub fn handle_manage_credentials() -> Result<()> {
+    println!("Manage Saved Credentials");
+    println!("========================");
+
+    let config = SapConfig::load()?;
+    let mut store = open_credential_store(&config, &config.get_client())?;
+    println!("Credential store: {}", store.name());
+
+    let options = vec![
+        "List Entries",
+        "Add/Update Entry",
+        "Remove Entry",
+        "Rotate Password",
+        "Rotate Keys",
+        "Upgrade Old Files",
+        "Back",
+    ];
+
+    loop {
+        let selection = Select::new()
+            .with_prompt("Choose an option")
+            .items(&options)
+            .default(0)
+            .interact()
+            .unwrap();
+
+        let result = match selection {
+            0 => list_entries(store.as_ref()),
+            1 => add_entry(store.as_mut(), &config.get_instance_id()),
+            2 => remove_entry(store.as_mut()),
+            3 => rotate_entry(store.as_mut()),
+            4 => rotate_keys(store.as_mut()),
+            5 => upgrade_entries(store.as_mut()),
+            _ => break,
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+    }
+
+    Ok(())
+}
+
+fn list_entries(store: &dyn CredentialStore) -> Result<()> {
+    let ids = store.list()?;
+    if ids.is_empty() {
+        println!("No saved credentials.");
+    }
+    for id in ids {
+        match store.get(&id) {
+            Ok(Some(credentials)) => println!("  {}: {}", id, credentials.user),
+            Ok(None) => println!("  {}", id),
+            Err(e) => println!("  {}: {}", id, e),
+        }
+    }
+    Ok(())
+}
+
+fn add_entry(store: &mut dyn CredentialStore, instance_id: &str) -> Result<()> {
+    let instance_id: String = Input::new()
+        .with_prompt("Instance ID")
+        .default(instance_id.to_string())
+        .interact()
+        .unwrap();
+    let user: String = Input::new().with_prompt("Username").interact().unwrap();
+    print!("Password: ");
+    io::stdout().flush()?;
+    let password = rpassword::read_password()?;
+
+    store.set(&instance_id, &Credentials::new(&user, &password))?;
+    println!("Credentials of {} saved.", instance_id);
+    Ok(())
+}
+
+/// Let the user pick one of the saved entries
+fn choose_entry(store: &dyn CredentialStore) -> Result<Option<String>> {
+    let mut ids = store.list()?;
+    if ids.is_empty() {
+        println!("No saved credentials.");
+        return Ok(None);
+    }
+    ids.push("Cancel".to_string());
+    let selection = Select::new()
+        .with_prompt("Select an entry")
+        .items(&ids)
+        .default(0)
+        .interact()
+        .unwrap();
+    if selection == ids.len() - 1 {
+        return Ok(None);
+    }
+    Ok(Some(ids.swap_remove(selection)))
+}
+
+fn remove_entry(store: &mut dyn CredentialStore) -> Result<()> {
+    if let Some(id) = choose_entry(store)? {
+        if store.remove(&id)? {
+            println!("Credentials of {} removed.", id);
+        }
+    }
+    Ok(())
+}
+
+fn rotate_entry(store: &mut dyn CredentialStore) -> Result<()> {
+    if let Some(id) = choose_entry(store)? {
+        print!("New password: ");
+        io::stdout().flush()?;
+        let password = rpassword::read_password()?;
+        store.rotate_password(&id, &password)?;
+        println!("Password of {} updated.", id);
+    }
+    Ok(())
+}
+
+fn rotate_keys(store: &mut dyn CredentialStore) -> Result<()> {
+    let ids = store.rotate_keys()?;
+    if ids.is_empty() {
+        println!("No saved credentials.");
+    } else {
+        println!("Encrypted {} with new keys.", ids.join(", "));
+    }
+    Ok(())
+}
+
+fn upgrade_entries(store: &mut dyn CredentialStore) -> Result<()> {
+    let ids = store.upgrade_entries()?;
+    if ids.is_empty() {
+        println!("No credentials in an old file format.");
+    } else {
+        println!(
+            "Upgraded {} to format version {}.",
+            ids.join(", "),
+            ENVELOPE_VERSION
+        );
+    }
+    Ok(())
+}
@@ -6,8 +6,13 @@ use std::io;
 use std::thread;
 use std::time::Duration;
 
+use crate::app::session_watchdog;
 use crate::utils::config_types::SapConfig;
 use crate::utils::config_types::{LoopConfig as ConfigLoopConfig, default_iterations, default_delay_seconds};
+use crate::utils::sap_interfaces::SapSession;
+use crate::utils::sap_popups::run_with_popup_rules;
+use crate::utils::sap_real_impl::RealSapSession;
+use crate::utils::sap_recording::run_with_trace;
 use crate::utils::sap_tcode_utils::{assert_tcode, check_tcode, variant_select};
 use crate::vl06o_module::run_vl06o_auto;
 use crate::vt11_module::run_vt11_auto;
@@ -50,15 +55,9 @@ impl LoopConfig {
                 // Get tcode
                 config.tcode = loop_config.tcode.clone();
                 
-                // Get iterations
-                if let Ok(iter_val) = loop_config.iterations.parse::<usize>() {
-                    config.iterations = iter_val;
-                }
-                
-                // Get delay seconds
-                if let Ok(delay_val) = loop_config.delay_seconds.parse::<u64>() {
-                    config.delay_seconds = delay_val;
-                }
+                // Get iterations and delay seconds
+                config.iterations = loop_config.iterations;
+                config.delay_seconds = loop_config.delay_seconds;
                 
                 // Get parameters - now handling params without loop_ prefix
                 for (key, value) in &loop_config.params {
@@ -119,8 +118,8 @@ impl LoopConfig {
         
         let loop_config = ConfigLoopConfig {
             tcode: self.tcode.clone(),
-            iterations: self.iterations.to_string(),
-            delay_seconds: self.delay_seconds.to_string(),
+            iterations: self.iterations,
+            delay_seconds: self.delay_seconds,
             params: loop_params,
         };
         
@@ -312,10 +311,54 @@ pub fn handle_configure_loop() -> Result<()> {
     Ok(())
 }
 
+/// Run one loop iteration, returns false if the TCode could not be started
+fn run_loop_iteration(session: &dyn SapSession, config: &LoopConfig) -> Result<bool> {
+    // Check if the TCode is active
+    if !check_tcode(session, &config.tcode, Some(true), Some(true))? {
+        println!("Failed to activate TCode '{}'", config.tcode);
+        return Ok(false);
+    }
+    
+    // Run the TCode with the configured parameters
+    match config.tcode.as_str() {
+        "VL06O" => {
+            run_vl06o_auto(session)?;
+        },
+        "VT11" => {
+            run_vt11_auto(session)?;
+        },
+        "ZMDESNR" => {
+            run_zmdesnr_auto(session)?;
+        },
+        _ => {
+            // For other TCodes, just run the TCode and apply variant if specified
+            if !assert_tcode(session, &config.tcode, Some(0))? {
+                println!("Failed to activate TCode '{}'", config.tcode);
+                return Ok(false);
+            }
+            
+            // Apply variant if specified
+            if let Some(variant) = config.params.get("variant") {
+                if !variant.is_empty() && !variant_select(session, &config.tcode, variant)? {
+                    println!("Failed to select variant '{}' for TCode '{}'", variant, config.tcode);
+                }
+            }
+            
+            // Execute the TCode
+            if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
+                wnd.send_v_key(8)?;
+            }
+        }
+    }
+    
+    Ok(true)
+}
+
 /// Run a TCode in a loop with the specified configuration
 pub fn run_loop(session: &GuiSession) -> Result<()> {
     println!("Run Loop from Configuration");
     println!("==========================");
+    let sap_session = RealSapSession::new(session);
     
     // Load loop configuration
     let config = match LoopConfig::load() {
@@ -359,6 +402,7 @@ pub fn run_loop(session: &GuiSession) -> Result<()> {
     io::stdin().read_line(&mut input).unwrap();
     
     // Run the TCode in a loop
+    let mut watchdog = session_watchdog(session);
     let mut iteration = 1;
     loop {
         // Display iteration information
@@ -368,43 +412,17 @@ pub fn run_loop(session: &GuiSession) -> Result<()> {
             println!("\nIteration {}/{}", iteration, config.iterations);
         }
         
-        // Check if the TCode is active
-        if !check_tcode(session, &config.tcode, Some(true), Some(true))? {
-            println!("Failed to activate TCode '{}'", config.tcode);
-            break;
-        }
-        
-        // Run the TCode with the configured parameters
-        match config.tcode.as_str() {
-            "VL06O" => {
-                run_vl06o_auto(session)?;
-            },
-            "VT11" => {
-                run_vt11_auto(session)?;
-            },
-            "ZMDESNR" => {
-                run_zmdesnr_auto(session)?;
-            },
-            _ => {
-                // For other TCodes, just run the TCode and apply variant if specified
-                if !assert_tcode(session, &config.tcode, Some(0))? {
-                    println!("Failed to activate TCode '{}'", config.tcode);
-                    break;
-                }
-                
-                // Apply variant if specified
-                if let Some(variant) = config.params.get("variant") {
-                    if !variant.is_empty() && !variant_select(session, &config.tcode, variant)? {
-                        println!("Failed to select variant '{}' for TCode '{}'", variant, config.tcode);
-                    }
-                }
-                
-                // Execute the TCode
-                if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-                    if let Some(gui) = wnd.downcast::<GuiMainWindow>() {
-                        gui.send_v_key(8)?;
-                    }
-                }
+        // Run the iteration, logging in again and repeating it if SAP logged the session off
+        match watchdog.run_step(&sap_session, || {
+            run_with_trace(&sap_session, &config.tcode, |s| {
+                run_with_popup_rules(s, |s| run_loop_iteration(s, &config))
+            })
+        }) {
+            Ok(true) => {},
+            Ok(false) => break,
+            Err(e) => {
+                println!("Stopping loop: {}", e);
+                break;
             }
         }
         
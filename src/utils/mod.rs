@@ -16,17 +16,35 @@ pub mod choose_layout_utils;
 pub mod config_types;
 pub mod config_ops;
 pub mod config_handlers;
+pub mod config_values;
+pub mod credential_envelope;
+pub mod credential_store;
 pub mod excel_file_ops;
 pub mod excel_fileread_utils;
 pub mod excel_path_utils;
 pub mod sap_constants;
+pub mod sap_control_catalog;
+pub mod sap_control_id;
 pub mod sap_crypto_utils;
 pub mod sap_ctrl_utils;
 pub mod sap_file_utils;
 pub mod sap_interfaces;
+pub mod sap_mock_faults;
+pub mod sap_mock_flow;
 pub mod sap_mock_impl;
+pub mod sap_password_change;
+pub mod sap_popups;
 pub mod sap_real_impl;
+pub mod sap_recording;
+pub mod sap_replay;
+pub mod sap_screen_dump;
+pub mod sap_screens;
+pub mod sap_session_pool;
+pub mod sap_session_state;
+pub mod sap_session_watchdog;
+pub mod sap_status;
 pub mod sap_tcode_utils;
+pub mod sap_wait;
 pub mod sap_wnd_utils;
 pub mod select_layout_utils;
 pub mod setup_layout_li_utils;
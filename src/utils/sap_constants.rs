@@ -15,6 +15,20 @@ pub const NOTEPAD: &str = "Notepad";
 // Windows message constants
 pub const WM_CLOSE: u32 = 0x10;
 
+// SAP GUI opens at most five modal windows (wnd[1] to wnd[5]) on top of the main window
+pub const MAX_MODAL_WINDOWS: i32 = 5;
+
+/// Generic COM failure (E_FAIL), also returned for components that cannot be found
+pub const E_FAIL: i32 = 0x80004005_u32 as i32;
+/// The SAP GUI rejected the call because it is busy (RPC_E_CALL_REJECTED)
+pub const RPC_E_CALL_REJECTED: i32 = 0x80010001_u32 as i32;
+/// The SAP GUI asked the caller to retry later (RPC_E_SERVERCALL_RETRYLATER)
+pub const RPC_E_SERVERCALL_RETRYLATER: i32 = 0x8001010A_u32 as i32;
+/// The connection to the SAP GUI was lost (RPC_E_DISCONNECTED)
+pub const RPC_E_DISCONNECTED: i32 = 0x80010108_u32 as i32;
+/// SAP asks for a new password and none can be entered (customer defined, no COM code)
+pub const E_PASSWORD_CHANGE_REQUIRED: i32 = 0xA0040001_u32 as i32;
+
 // Resource types from VBA
 #[derive(Debug, Clone, Copy)]
 pub enum Resource {
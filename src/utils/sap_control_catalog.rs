@@ -0,0 +1,251 @@
+use anyhow::{anyhow, Context};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use windows::core::{Error, Result, HRESULT};
+
+use crate::utils::config_types::SapConfig;
+use crate::utils::sap_control_id::ControlId;
+
+/// Name of the catalog file, looked up next to config.toml
+pub const CONTROL_CATALOG_FILE: &str = "controls.toml";
+
+/// Built-in control IDs by logical name (<tcode>.<screen>.<control>)
+pub const DEFAULT_CONTROLS: &[(&str, &str)] = &[
+    // VT11 - Shipment List: Planning
+    ("vt11.selection.date_low", "wnd[0]/usr/ctxtK_DATEN-LOW"),
+    ("vt11.selection.date_high", "wnd[0]/usr/ctxtK_DATEN-HIGH"),
+    ("vt11.selection.description_high", "wnd[0]/usr/txtK_TPBEZ-HIGH"),
+    ("vt11.selection.execute_button", "wnd[0]/tbar[1]/btn[8]"),
+    ("vt11.popup.message", "wnd[1]/usr/txtMESSTXT1"),
+    ("vt11.list.choose_layout_menu", "wnd[0]/mbar/menu[3]/menu[0]/menu[1]"),
+    ("vt11.list.export_menu", "wnd[0]/mbar/menu[0]/menu[10]/menu[0]"),
+    // VL06O - Outbound Delivery Monitor
+    ("vl06o.initial.list_deliveries_button", "wnd[0]/usr/btnBUTTON6"),
+    ("vl06o.selection.goods_issue_date_low", "wnd[0]/usr/ctxtIT_WADAT-LOW"),
+    ("vl06o.selection.goods_issue_date_high", "wnd[0]/usr/ctxtIT_WADAT-HIGH"),
+    ("vl06o.selection.shipment_multi_button", "wnd[0]/usr/btn%_IT_TKNUM_%_APP_%-VALU_PUSH"),
+    ("vl06o.selection.delivery_multi_button", "wnd[0]/usr/btn%_IT_VBELN_%_APP_%-VALU_PUSH"),
+    ("vl06o.variant.name", "wnd[1]/usr/txtV-LOW"),
+    ("vl06o.variant.created_by", "wnd[1]/usr/txtENAME-LOW"),
+    ("vl06o.list.item_view_button", "wnd[0]/tbar[1]/btn[18]"),
+    ("vl06o.list.export_menu", "wnd[0]/mbar/menu[0]/menu[5]/menu[1]"),
+    ("vl06o.delivery.number", "wnd[0]/usr/subSUBSCREEN_HEADER:SAPMV50A:1502/ctxtLIKP-VBELN"),
+    ("vl06o.delivery.overview_tab", r"wnd[0]/usr/tabsTAXI_TABSTRIP_OVERVIEW/tabpT\01"),
+    (
+        "vl06o.delivery.goods_issue_date",
+        r"wnd[0]/usr/tabsTAXI_TABSTRIP_OVERVIEW/tabpT\01/ssubSUBSCREEN_BODY:SAPMV50A:1102/ctxtLIKP-WADAT",
+    ),
+    ("vl06o.popup.option1_button", "wnd[1]/usr/btnSPOP-OPTION1"),
+    // ZMDESNR - Serial Number History
+    ("zmdesnr.selection.tab_strip", "wnd[0]/usr/tabsTABSTRIP_TABB1"),
+    (
+        "zmdesnr.selection.parent_low",
+        "wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/txtS_PARENT-LOW",
+    ),
+    (
+        "zmdesnr.selection.parent_multi_button",
+        "wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/btn%_S_PARENT_%_APP_%-VALU_PUSH",
+    ),
+    (
+        "zmdesnr.selection.delivery_low",
+        "wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/txtS_VBELN-LOW",
+    ),
+    (
+        "zmdesnr.selection.delivery_high",
+        "wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/txtS_VBELN-HIGH",
+    ),
+    (
+        "zmdesnr.selection.delivery_multi_button",
+        "wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/btn%_S_VBELN_%_APP_%-VALU_PUSH",
+    ),
+    (
+        "zmdesnr.selection.palletized_low",
+        "wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/ctxtS_PALLTD-LOW",
+    ),
+    ("zmdesnr.selection.execute_button", "wnd[0]/tbar[1]/btn[8]"),
+    ("zmdesnr.popup.exclude_tab", "wnd[1]/usr/tabsTAB_STRIP/tabpNOSV"),
+    ("zmdesnr.list.grid", "wnd[0]/usr/cntlGRID1/shellcont/shell"),
+    ("zmdesnr.list.choose_layout_button", "wnd[0]/tbar[1]/btn[33]"),
+    ("zmdesnr.list.change_layout_menu", "wnd[0]/mbar/menu[4]/menu[0]/menu[0]"),
+    ("zmdesnr.list.export_menu", "wnd[0]/mbar/menu[0]/menu[3]/menu[1]"),
+    (
+        "zmdesnr.layout.columns_grid",
+        "wnd[1]/usr/tabsG_TS_ALV/tabpALV_M_R1/ssubSUB_DYN0510:SAPLSKBH:0620/cntlCONTAINER1_LAYO/shellcont/shell",
+    ),
+];
+
+/// Catalog mapping logical control names to SAP GUI control IDs
+///
+/// The built-in defaults can be overridden from controls.toml, either for every
+/// SAP instance or only for one (`[instance.<instance_id>]`), so that a changed
+/// screen does not need a rebuild.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlCatalog {
+    controls: HashMap<String, String>,
+}
+
+impl Default for ControlCatalog {
+    fn default() -> Self {
+        Self {
+            controls: DEFAULT_CONTROLS
+                .iter()
+                .map(|(name, id)| (name.to_string(), id.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl ControlCatalog {
+    /// Create a catalog with the built-in control IDs
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the catalog file next to the configuration file (defaults if there is none)
+    pub fn load(config: &SapConfig) -> anyhow::Result<Self> {
+        Self::load_from_path(&Self::path_for(config), &config.get_instance_id())
+    }
+
+    /// Load a catalog file, applying the overrides for the given instance
+    pub fn load_from_path(path: &Path, instance_id: &str) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read control catalog {}", path.display()))?;
+        Self::from_toml_str(&content, instance_id)
+            .with_context(|| format!("Invalid control catalog {}", path.display()))
+    }
+
+    /// Parse catalog overrides from TOML, applying the overrides for the given instance
+    pub fn from_toml_str(content: &str, instance_id: &str) -> anyhow::Result<Self> {
+        let mut parsed = toml::from_str::<toml::Table>(content)?;
+        let instances = parsed.remove("instance");
+
+        let mut catalog = Self::default();
+        catalog.apply(&parsed)?;
+
+        if let Some(instances) = instances {
+            let instances = instances
+                .as_table()
+                .ok_or_else(|| anyhow!("[instance] must be a table of instance IDs"))?;
+            if let Some(overrides) = instances.get(instance_id) {
+                let overrides = overrides
+                    .as_table()
+                    .ok_or_else(|| anyhow!("[instance.{}] must be a table", instance_id))?;
+                catalog.apply(overrides)?;
+            }
+        }
+
+        Ok(catalog)
+    }
+
+    /// Get the catalog file path for a configuration (controls.toml next to config.toml)
+    pub fn path_for(config: &SapConfig) -> PathBuf {
+        Path::new(&config.config_path).with_file_name(CONTROL_CATALOG_FILE)
+    }
+
+    /// Override the ID of a control
+    pub fn set(&mut self, name: &str, id: &str) -> anyhow::Result<()> {
+        if !self.controls.contains_key(name) {
+            return Err(anyhow!("Unknown control name: {}", name));
+        }
+        ControlId::parse(id).map_err(|e| anyhow!("{}: {}", name, e))?;
+        self.controls.insert(name.to_string(), id.to_string());
+        Ok(())
+    }
+
+    /// Get the ID of a control as a string (as passed to `find_by_id`)
+    pub fn id(&self, name: &str) -> Result<String> {
+        self.controls.get(name).cloned().ok_or_else(|| {
+            Error::new(
+                HRESULT(-2147467259),
+                format!("Unknown control name: {}", name).as_str().into(),
+            )
+        })
+    }
+
+    /// Get the ID of a control
+    pub fn control(&self, name: &str) -> Result<ControlId> {
+        ControlId::parse(&self.id(name)?)
+    }
+
+    /// Get all logical names, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.controls.keys().map(|name| name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    /// Apply the overrides of a TOML table, nested tables form dotted names
+    fn apply(&mut self, table: &toml::Table) -> anyhow::Result<()> {
+        let mut overrides = Vec::new();
+        flatten(None, table, &mut overrides)?;
+        for (name, id) in overrides {
+            self.set(&name, &id)?;
+        }
+        Ok(())
+    }
+}
+
+fn flatten(
+    prefix: Option<&str>,
+    table: &toml::Table,
+    out: &mut Vec<(String, String)>,
+) -> anyhow::Result<()> {
+    for (key, value) in table {
+        let name = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, key),
+            None => key.clone(),
+        };
+        match value {
+            toml::Value::String(id) => out.push((name, id.clone())),
+            toml::Value::Table(nested) => flatten(Some(&name), nested, out)?,
+            _ => return Err(anyhow!("{} must be a control ID string", name)),
+        }
+    }
+    Ok(())
+}
+
+/// Catalog in use and the instance ID it was loaded for
+static CATALOG: RwLock<Option<(String, ControlCatalog)>> = RwLock::new(None);
+
+/// Run `f` on the catalog of the current instance
+///
+/// The catalog is loaded again when the instance ID has changed since it was loaded,
+/// e.g. after switching the logon profile or the SAP system.
+fn with_catalog<T>(f: impl FnOnce(&ControlCatalog) -> T) -> T {
+    let config = SapConfig::load().unwrap_or_default();
+    let instance_id = config.get_instance_id();
+    if let Some((loaded_for, catalog)) = CATALOG.read().unwrap().as_ref() {
+        if *loaded_for == instance_id {
+            return f(catalog);
+        }
+    }
+
+    let catalog = ControlCatalog::load(&config).unwrap_or_else(|e| {
+        eprintln!("Error loading control catalog, using defaults: {:#}", e);
+        ControlCatalog::default()
+    });
+    let result = f(&catalog);
+    *CATALOG.write().unwrap() = Some((instance_id, catalog));
+    result
+}
+
+/// Get the catalog loaded alongside config.toml for the current instance (built-in defaults if it cannot be loaded)
+pub fn catalog() -> ControlCatalog {
+    with_catalog(ControlCatalog::clone)
+}
+
+/// Look up a control ID by logical name (e.g. "vl06o.selection.delivery_multi_button")
+pub fn control(name: &str) -> Result<ControlId> {
+    with_catalog(|catalog| catalog.control(name))
+}
+
+/// Look up a control ID string by logical name, for `find_by_id`
+pub fn control_id(name: &str) -> Result<String> {
+    with_catalog(|catalog| catalog.id(name))
+}
@@ -0,0 +1,300 @@
+use std::fmt;
+use std::str::FromStr;
+use windows::core::{Error, Result, HRESULT};
+
+use crate::utils::sap_interfaces::normalize_id;
+
+/// Type prefixes used in SAP GUI control IDs (e.g. "ctxt" for GuiCTextField)
+pub const CONTROL_PREFIXES: &[&str] = &[
+    "usr",       // GuiUserArea
+    "sbar",      // GuiStatusbar
+    "titl",      // GuiTitlebar
+    "tbar",      // GuiToolbar
+    "mbar",      // GuiMenubar
+    "menu",      // GuiMenu
+    "okcd",      // GuiOkCodeField
+    "btn",       // GuiButton
+    "txt",       // GuiTextField
+    "ctxt",      // GuiCTextField
+    "pwd",       // GuiPasswordField
+    "chk",       // GuiCheckBox
+    "rad",       // GuiRadioButton
+    "cmb",       // GuiComboBox
+    "lbl",       // GuiLabel
+    "box",       // GuiBox
+    "tbl",       // GuiTableControl
+    "tabs",      // GuiTabStrip
+    "tabp",      // GuiTab
+    "ssub",      // GuiSimpleContainer (subscreen)
+    "sub",       // GuiSimpleContainer
+    "scrl",      // GuiScrollContainer
+    "splc",      // GuiSplitterContainer
+    "cntl",      // GuiCustomControl
+    "shellcont", // GuiContainerShell
+    "shell",     // GuiShell (grid views, trees, ...)
+];
+
+/// Index of a control ID segment, e.g. "btn[8]" or the cell of "ctxtRSCSEL_255-SLOW_I[1,0]"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ControlIndex {
+    Single(i32),
+    Cell { column: i32, row: i32 },
+}
+
+/// One segment of a control ID: type prefix, name and optional index
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ControlSegment {
+    pub prefix: String,
+    pub name: String,
+    pub index: Option<ControlIndex>,
+}
+
+/// Typed SAP GUI control ID (e.g. "wnd[1]/usr/tblSAPLALDBSINGLE/ctxtRSCSEL_255-SLOW_I[1,0]")
+///
+/// IDs can be parsed from strings (with or without the "/app/con[0]/ses[0]/" prefix) or built
+/// segment by segment, and are converted back with `to_string()` for `find_by_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ControlId {
+    window: i32,
+    segments: Vec<ControlSegment>,
+}
+
+fn invalid_id(id: &str, reason: &str) -> Error {
+    Error::new(
+        HRESULT(-2147467259),
+        format!("Invalid control ID ({}): {}", id, reason)
+            .as_str()
+            .into(),
+    )
+}
+
+/// Parse a bracketed index such as "8" or "1,0" (without the brackets)
+fn parse_index(index: &str) -> Option<ControlIndex> {
+    let parse = |s: &str| s.trim().parse::<i32>().ok().filter(|n| *n >= 0);
+    match index.split_once(',') {
+        Some((column, row)) => Some(ControlIndex::Cell {
+            column: parse(column)?,
+            row: parse(row)?,
+        }),
+        None => Some(ControlIndex::Single(parse(index)?)),
+    }
+}
+
+impl fmt::Display for ControlIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlIndex::Single(index) => write!(f, "[{}]", index),
+            ControlIndex::Cell { column, row } => write!(f, "[{},{}]", column, row),
+        }
+    }
+}
+
+impl ControlSegment {
+    /// Create a segment from a type prefix and a control name (e.g. "ctxt", "LIKP-VBELN")
+    pub fn new(prefix: &str, name: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            name: name.to_string(),
+            index: None,
+        }
+    }
+
+    /// Create an indexed segment without a name (e.g. "btn", 8 for "btn[8]")
+    pub fn indexed(prefix: &str, index: i32) -> Self {
+        Self {
+            index: Some(ControlIndex::Single(index)),
+            ..Self::new(prefix, "")
+        }
+    }
+
+    /// Create a table cell segment (e.g. "ctxt", "RSCSEL_255-SLOW_I", 1, 0)
+    pub fn cell(prefix: &str, name: &str, column: i32, row: i32) -> Self {
+        Self {
+            index: Some(ControlIndex::Cell { column, row }),
+            ..Self::new(prefix, name)
+        }
+    }
+
+    /// Parse a single segment (e.g. "tabsTAB_STRIP", "btn[8]" or "lbl[2,4]")
+    pub fn parse(segment: &str) -> Result<Self> {
+        parse_segment(segment).map_err(|reason| invalid_id(segment, reason))
+    }
+}
+
+fn parse_segment(segment: &str) -> std::result::Result<ControlSegment, &'static str> {
+    if segment.is_empty() {
+        return Err("empty segment");
+    }
+    if segment.chars().any(|c| c.is_whitespace()) {
+        return Err("whitespace in segment");
+    }
+
+    let (body, index) = match segment.strip_suffix(']') {
+        Some(rest) => {
+            let (body, index) = rest.rsplit_once('[').ok_or("unbalanced brackets")?;
+            (body, Some(parse_index(index).ok_or("malformed index")?))
+        }
+        None => (segment, None),
+    };
+    if body.contains('[') || body.contains(']') {
+        return Err("unbalanced brackets");
+    }
+
+    let prefix_len = body
+        .find(|c: char| !c.is_ascii_lowercase())
+        .unwrap_or(body.len());
+    let (prefix, name) = body.split_at(prefix_len);
+    if !CONTROL_PREFIXES.contains(&prefix) {
+        return Err("unknown type prefix");
+    }
+
+    Ok(ControlSegment {
+        prefix: prefix.to_string(),
+        name: name.to_string(),
+        index,
+    })
+}
+
+/// Parse a relative path into segments, reporting the first invalid one
+fn parse_path(path: &str) -> Result<Vec<ControlSegment>> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    path.split('/').map(ControlSegment::parse).collect()
+}
+
+impl fmt::Display for ControlSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.prefix, self.name)?;
+        if let Some(index) = &self.index {
+            write!(f, "{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl ControlId {
+    /// Create the ID of a window (e.g. "wnd[1]")
+    pub fn window(index: i32) -> Self {
+        Self {
+            window: index,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Parse a full control ID
+    pub fn parse(id: &str) -> Result<Self> {
+        let normalized = normalize_id(id);
+        let (window, path) = match normalized.split_once('/') {
+            Some((window, path)) => (window, Some(path)),
+            None => (normalized, None),
+        };
+
+        let window = window
+            .strip_prefix("wnd[")
+            .and_then(|rest| rest.strip_suffix(']'))
+            .and_then(|index| index.parse::<i32>().ok())
+            .filter(|index| *index >= 0)
+            .ok_or_else(|| invalid_id(id, "must start with a window (wnd[n])"))?;
+
+        let mut control = Self::window(window);
+        if let Some(path) = path {
+            if path.is_empty() || path.starts_with('/') {
+                return Err(invalid_id(id, "empty segment"));
+            }
+            control.segments = parse_path(path)?;
+        }
+        Ok(control)
+    }
+
+    /// Append a relative path (e.g. "/usr/ctxtLIKP-VBELN", the leading "/" is optional)
+    pub fn join(&self, path: &str) -> Result<Self> {
+        let mut control = self.clone();
+        control.segments.extend(parse_path(path)?);
+        Ok(control)
+    }
+
+    /// Append a segment
+    pub fn child(mut self, segment: ControlSegment) -> Self {
+        self.segments.push(segment);
+        self
+    }
+
+    /// Append the user area ("usr") of a window
+    pub fn usr(self) -> Self {
+        self.child(ControlSegment::new("usr", ""))
+    }
+
+    pub fn window_index(&self) -> i32 {
+        self.window
+    }
+
+    pub fn segments(&self) -> &[ControlSegment] {
+        &self.segments
+    }
+
+    /// Get the last segment (none if the ID is a window)
+    pub fn last(&self) -> Option<&ControlSegment> {
+        self.segments.last()
+    }
+
+    /// Get the (column, row) of the control (if it's a table cell)
+    pub fn cell(&self) -> Option<(i32, i32)> {
+        match self.last()?.index? {
+            ControlIndex::Cell { column, row } => Some((column, row)),
+            ControlIndex::Single(_) => None,
+        }
+    }
+
+    /// Point the last segment at another table cell
+    pub fn with_cell(mut self, column: i32, row: i32) -> Self {
+        if let Some(last) = self.segments.last_mut() {
+            last.index = Some(ControlIndex::Cell { column, row });
+        }
+        self
+    }
+
+    /// Get the ID of the containing control (none if the ID is a window)
+    pub fn parent(&self) -> Option<Self> {
+        let mut parent = self.clone();
+        parent.segments.pop()?;
+        Some(parent)
+    }
+
+    /// Move the ID onto another window (e.g. a control of wnd[1] that reopens on wnd[2])
+    pub fn rebase(&self, window: i32) -> Self {
+        Self {
+            window,
+            segments: self.segments.clone(),
+        }
+    }
+
+    /// Get the path below the window (e.g. "/usr/txtMESSTXT1"), as used by `exist_ctrl`
+    pub fn relative_path(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| format!("/{}", segment))
+            .collect()
+    }
+}
+
+impl fmt::Display for ControlId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "wnd[{}]{}", self.window, self.relative_path())
+    }
+}
+
+impl FromStr for ControlId {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self> {
+        Self::parse(id)
+    }
+}
+
+impl From<ControlId> for String {
+    fn from(id: ControlId) -> Self {
+        id.to_string()
+    }
+}
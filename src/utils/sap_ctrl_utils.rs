@@ -1,6 +1,8 @@
 use sap_scripting::*;
 use windows::core::Result;
 
+use super::sap_control_id::{ControlId, ControlSegment};
+use super::sap_interfaces::{self, SapSession};
 use super::sap_wnd_utils::*;
 
 /// Check if a control exists in the SAP GUI
@@ -126,7 +128,7 @@ pub fn hit_ctrl(
 /// This function gets text from SAP GUI error messages at the specified window index
 /// and with the specified ID suffix.
 pub fn get_sap_text_errors(
-    session: &GuiSession,
+    session: &dyn SapSession,
     wnd_idx: i32,
     id_suffix: &str,
     max_lines: i32,
@@ -137,7 +139,7 @@ pub fn get_sap_text_errors(
 
     for i in 1..=max_lines {
         let id = format!("{}{}", id_suffix, i);
-        let text = hit_ctrl(session, wnd_idx, &id, "Text", "Get", "")?;
+        let text = sap_interfaces::hit_ctrl(session, wnd_idx, &id, "Text", "Get", "")?;
         if !text.is_empty() {
             if !result.is_empty() {
                 result.push_str("\n");
@@ -154,7 +156,7 @@ pub fn get_sap_text_errors(
 /// This function pastes values into a scrollable table in SAP GUI at the specified window index.
 /// It handles scrolling through the table to paste all values, even when there are thousands.
 pub fn paste_values_with_scroll(
-    session: &GuiSession,
+    session: &dyn SapSession,
     wnd_idx: i32,
     table_id: &str,
     values: &[String],
@@ -164,12 +166,12 @@ pub fn paste_values_with_scroll(
         return Ok(true);
     }
 
-    let full_table_id = format!("wnd[{}]/usr/{}", wnd_idx, table_id);
+    let table = ControlId::window(wnd_idx).usr().join(table_id)?;
     
     // Check if table exists
-    let table_exists = exist_ctrl(session, wnd_idx, &format!("/usr/{}", table_id), true)?;
+    let table_exists = sap_interfaces::exist_ctrl(session, wnd_idx, &table.relative_path(), true)?;
     if !table_exists.cband {
-        println!("Table not found: {}", full_table_id);
+        println!("Table not found: {}", table);
         return Ok(false);
     }
 
@@ -188,11 +190,9 @@ pub fn paste_values_with_scroll(
             // Try to set scrollbar position by sending key presses
             // This is a workaround since we can't directly set the scrollbar position
             // Send Page Down key to scroll down
-            if let Ok(window) = session.find_by_id(format!("wnd[{}]", wnd_idx)) {
-                if let Some(wnd) = window.downcast::<GuiModalWindow>() {
-                    wnd.send_v_key(82)?; // Page Down key
-                    page_idx += 1;
-                }
+            if let Ok(wnd) = session.find_control(&ControlId::window(wnd_idx)) {
+                wnd.send_v_key(82)?; // Page Down key
+                page_idx += 1;
             }
             println!("Scrolled down {} pages", page_idx);
         }
@@ -203,21 +203,19 @@ pub fn paste_values_with_scroll(
 
         for i in values_pasted..end_idx {
             // Try both possible field ID patterns
+            let cell = ControlSegment::cell("ctxt", "RSCSEL_255-SLOW_I", 1, local_index as i32);
             let field_ids = [
-                format!("{}/ctxtRSCSEL_255-SLOW_I[1,{}]", table_id, local_index),
-                format!("{}/tblSAPLALDBSINGLE/ctxtRSCSEL_255-SLOW_I[1,{}]", table_id, local_index)
+                table.clone().child(cell.clone()),
+                table.clone().child(ControlSegment::new("tbl", "SAPLALDBSINGLE")).child(cell)
             ];
             
             let mut field_found = false;
             
             for field_id in &field_ids {
-                let full_field_id = format!("wnd[{}]/usr/{}", wnd_idx, field_id);
-                
-                if let Ok(field) = session.find_by_id(full_field_id.clone()) {
-                    if let Some(text_field) = field.downcast::<GuiCTextField>() {
-                        // Make sure we're not adding any trailing commas
-                        let clean_value = clean_values[i].clone();
-                        text_field.set_text(clean_value)?;
+                if let Ok(text_field) = session.find_control(field_id) {
+                    // Make sure we're not adding any trailing commas
+                    let clean_value = clean_values[i].clone();
+                    if text_field.set_text(clean_value).is_ok() {
                         field_found = true;
                         break;
                     }
@@ -1,11 +1,9 @@
 use crate::utils::config_ops::get_reports_dir;
-use crate::utils::sap_ctrl_utils::exist_ctrl;
+use crate::utils::sap_interfaces::{exist_ctrl, SapSession};
+use crate::utils::sap_wait::{poll_until, wait_timeouts, wait_until, WaitCondition};
 use crate::utils::utils::generate_timestamp;
-use sap_scripting::*;
 use std::fs;
 use std::path::Path;
-use std::thread;
-use std::time::Duration;
 use std::ffi::OsString;
 use std::os::windows::ffi::OsStringExt;
 use windows::core::{PCWSTR, Result, HSTRING};
@@ -255,7 +253,7 @@ fn close_all_excel_windows() -> Result<bool> {
 /// # Returns
 ///
 /// * `Result<bool>` - Ok(true) if the file was successfully saved, Ok(false) otherwise
-pub fn save_sap_file(session: &GuiSession, file_path: &str, file_name: &str, close_export_file: Option<bool>) -> Result<bool> {
+pub fn save_sap_file(session: &dyn SapSession, file_path: &str, file_name: &str, close_export_file: Option<bool>) -> Result<bool> {
     let close_export = close_export_file.unwrap_or(false);
     println!("Exporting data from SAP....");
     if close_export {
@@ -275,45 +273,52 @@ pub fn save_sap_file(session: &GuiSession, file_path: &str, file_name: &str, clo
         let msg_err_wnd = exist_ctrl(session, 1, "/usr/txtMESSTXT1", true)?;
         if msg_err_wnd.cband {
             // There's an error message, get the text
-            if let Ok(component) = session.find_by_id("wnd[1]/usr/txtMESSTXT1".to_string()) {
-                if let Some(text_field) = component.downcast::<GuiTextField>() {
-                    let error_msg = text_field.text()?;
-                    println!("Error message: {}", error_msg);
-                    return Ok(false);
-                }
+            if let Ok(text_field) = session.find_by_id("wnd[1]/usr/txtMESSTXT1".to_string()) {
+                let error_msg = text_field.get_text()?;
+                println!("Error message: {}", error_msg);
+                return Ok(false);
             }
         }
 
         // Set the file path
-        if let Ok(component) = session.find_by_id("wnd[1]/usr/ctxtDY_PATH".to_string()) {
-            if let Some(text_field) = component.downcast::<GuiCTextField>() {
-                text_field.set_text(file_path.to_string())?;
-            }
+        if let Ok(text_field) = session.find_by_id("wnd[1]/usr/ctxtDY_PATH".to_string()) {
+            text_field.set_text(file_path.to_string())?;
         }
 
         // Set the file name
-        if let Ok(component) = session.find_by_id("wnd[1]/usr/ctxtDY_FILENAME".to_string()) {
-            if let Some(text_field) = component.downcast::<GuiCTextField>() {
-                text_field.set_text(file_name.to_string())?;
-            }
+        if let Ok(text_field) = session.find_by_id("wnd[1]/usr/ctxtDY_FILENAME".to_string()) {
+            text_field.set_text(file_name.to_string())?;
         }
 
         // Press the save button
-        if let Ok(component) = session.find_by_id("wnd[1]/tbar[0]/btn[0]".to_string()) {
-            if let Some(button) = component.downcast::<GuiButton>() {
-                button.press()?;
-            }
+        if let Ok(button) = session.find_by_id("wnd[1]/tbar[0]/btn[0]".to_string()) {
+            button.press()?;
         }
 
-        // Wait a moment for the save operation to complete
-        thread::sleep(Duration::from_millis(3000));
+        // Wait for the save operation to complete
+        let timeouts = wait_timeouts();
+        wait_until(session, &WaitCondition::NotBusy, timeouts.save, timeouts.poll)?;
+
+        // Wait for the file to be written (a missing directory is reported by SAP in a popup)
+        if Path::new(file_path).is_dir() {
+            let written = WaitCondition::FileStable {
+                path: Path::new(file_path).join(file_name),
+                stable_for: timeouts.file_stable,
+            };
+            if !wait_until(session, &written, timeouts.save, timeouts.poll)? {
+                println!("Error: File ({}\\{}) was not written", file_path, file_name);
+                return Ok(false);
+            }
+        }
 
         println!("File saved successfully");
         
         // If Excel is allowed to open and we want to close it after saving
         if close_export {
-            // Wait a moment for Excel to open
-            thread::sleep(Duration::from_millis(5000));
+            // Wait for Excel to open the file
+            if !poll_until(|| check_excel_file_exists(file_name), timeouts.default, timeouts.poll)? {
+                println!("Excel did not open ({}) within {} ms", file_name, timeouts.default.as_millis());
+            }
             
             // Close Excel windows with the specified file name
             match close_excel_windows(Some(file_name)) {
@@ -1,4 +1,6 @@
 use crate::utils::sap_constants::CtrlCheck;
+use crate::utils::sap_control_id::ControlId;
+use crate::utils::sap_status::StatusMessage;
 use windows::core::Result;
 
 /// Trait representing a SAP GUI component
@@ -18,6 +20,9 @@ pub trait SapComponent {
     /// Set focus to the component (if applicable)
     fn set_focus(&self) -> Result<()>;
 
+    /// Check whether the component accepts input (if it's a text field)
+    fn changeable(&self) -> Result<bool>;
+
     /// Press the component (if it's a button)
     fn press(&self) -> Result<()>;
 
@@ -32,6 +37,59 @@ pub trait SapComponent {
 
     /// Maximize the component (if it's a window)
     fn maximize(&self) -> Result<()>;
+
+    /// Send a virtual key to the component (if it's a window)
+    fn send_v_key(&self, key: i32) -> Result<()>;
+
+    /// Close the component (if it's a window)
+    fn close(&self) -> Result<()>;
+
+    /// Set the selected rows (if it's a grid view)
+    fn set_selected_rows(&self, rows: String) -> Result<()>;
+
+    /// Set the current cell row (if it's a grid view)
+    fn set_current_cell_row(&self, row: i32) -> Result<()>;
+
+    /// Open the context menu (if it's a grid view)
+    fn context_menu(&self) -> Result<()>;
+
+    /// Select an item from the context menu (if it's a grid view)
+    fn select_context_menu_item(&self, function_code: String) -> Result<()>;
+
+    /// Set the current cell (if it's a grid view)
+    fn set_current_cell(&self, row: i32, column: String) -> Result<()>;
+
+    /// Get the current cell row (if it's a grid view or table control)
+    fn current_cell_row(&self) -> Result<i32>;
+
+    /// Double click the current cell (if it's a grid view)
+    fn double_click_current_cell(&self) -> Result<()>;
+
+    /// Get the number of rows (if it's a grid view or table control)
+    fn row_count(&self) -> Result<i32>;
+
+    /// Get the number of visible rows (if it's a grid view or table control)
+    fn visible_row_count(&self) -> Result<i32>;
+
+    /// Get the first visible row (if it's a grid view or table control)
+    fn first_visible_row(&self) -> Result<i32>;
+
+    /// Scroll so the given row is the first visible one (if it's a grid view or table control)
+    fn set_first_visible_row(&self, row: i32) -> Result<()>;
+
+    /// Get the value of a cell (if it's a grid view or table control)
+    ///
+    /// Grid views address columns by name, table controls by column index.
+    fn get_cell_value(&self, row: i32, column: String) -> Result<String>;
+
+    /// Select a column (if it's a grid view)
+    fn select_column(&self, column: String) -> Result<()>;
+
+    /// Press a column header (if it's a grid view)
+    fn press_column_header(&self, column: String) -> Result<()>;
+
+    /// Get the message shown in the component (if it's a status bar)
+    fn status_message(&self) -> Result<StatusMessage>;
 }
 
 /// Trait representing a SAP GUI session
@@ -47,12 +105,82 @@ pub trait SapSession {
 
     /// End a transaction
     fn end_transaction(&self) -> Result<()>;
+
+    /// Send a virtual key to the window with the given index
+    fn send_v_key(&self, wnd: i32, key: i32) -> Result<()>;
+
+    /// Close the window with the given index
+    fn close_window(&self, wnd: i32) -> Result<()>;
+
+    /// Close all open modal windows, highest index first
+    fn close_all_windows(&self) -> Result<()>;
+
+    /// Check whether the session is still processing a request
+    fn busy(&self) -> Result<bool>;
+
+    /// Find a component by typed control ID
+    fn find_control(&self, id: &ControlId) -> Result<Box<dyn SapComponent>> {
+        self.find_by_id(id.to_string())
+    }
+}
+
+/// Allow borrowed sessions to be passed where an owned session is expected (e.g. to decorators)
+impl<T: SapSession + ?Sized> SapSession for &T {
+    fn find_by_id(&self, id: String) -> Result<Box<dyn SapComponent>> {
+        (**self).find_by_id(id)
+    }
+
+    fn info(&self) -> Result<Box<dyn SapSessionInfo>> {
+        (**self).info()
+    }
+
+    fn start_transaction(&self, transaction: String) -> Result<()> {
+        (**self).start_transaction(transaction)
+    }
+
+    fn end_transaction(&self) -> Result<()> {
+        (**self).end_transaction()
+    }
+
+    fn send_v_key(&self, wnd: i32, key: i32) -> Result<()> {
+        (**self).send_v_key(wnd, key)
+    }
+
+    fn close_window(&self, wnd: i32) -> Result<()> {
+        (**self).close_window(wnd)
+    }
+
+    fn close_all_windows(&self) -> Result<()> {
+        (**self).close_all_windows()
+    }
+
+    fn busy(&self) -> Result<bool> {
+        (**self).busy()
+    }
 }
 
 /// Trait representing SAP GUI session information
 pub trait SapSessionInfo {
     /// Get the current transaction
     fn transaction(&self) -> Result<String>;
+
+    /// Get the ABAP program of the current screen (e.g. "SAPMSYST")
+    fn program(&self) -> Result<String>;
+
+    /// Get the dynpro number of the current screen (e.g. 20)
+    fn screen_number(&self) -> Result<i32>;
+
+    /// Get the system ID (e.g. "PRD")
+    fn system_name(&self) -> Result<String>;
+
+    /// Get the logon client (e.g. "100")
+    fn client(&self) -> Result<String>;
+
+    /// Get the logged-on user
+    fn user(&self) -> Result<String>;
+
+    /// Get the logon language (e.g. "EN")
+    fn language(&self) -> Result<String>;
 }
 
 /// Trait for a SAP component factory
@@ -61,6 +189,41 @@ pub trait SapComponentFactory {
     fn create_session(&self, name: &str) -> Box<dyn SapSession>;
 }
 
+/// Normalize a component ID, dropping the "/app/con[0]/ses[0]/" prefix of full paths
+pub fn normalize_id(id: &str) -> &str {
+    let id = id.trim();
+    let id = match id.find("/ses[") {
+        Some(pos) if id.starts_with("/app/") => id[pos + 1..]
+            .split_once('/')
+            .map(|(_, rest)| rest)
+            .unwrap_or(""),
+        _ => id,
+    };
+    id.trim_start_matches('/')
+}
+
+/// Parse a row selection as used by `set_selected_rows` (e.g. "5", "1,3" or "0-4")
+pub fn parse_row_selection(rows: &str) -> Vec<i32> {
+    let mut result = Vec::new();
+
+    for part in rows.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+
+        if let Some((start, end)) = part.split_once('-') {
+            if let (Ok(start), Ok(end)) = (start.trim().parse::<i32>(), end.trim().parse::<i32>()) {
+                result.extend(start..=end);
+            }
+        } else if let Ok(row) = part.parse::<i32>() {
+            result.push(row);
+        }
+    }
+
+    result
+}
+
 /// Function to check if a control exists in the SAP GUI
 pub fn exist_ctrl(
     session: &dyn SapSession,
@@ -74,11 +237,14 @@ pub fn exist_ctrl(
         ctype: String::new(),
     };
 
-    // Try to find the control
-    let control_path = format!("wnd[{}]{}", n_wnd, control_id);
-    let ret_id = session.find_by_id(control_path.clone());
+    // Malformed IDs can never match a control
+    let Ok(control) = ControlId::window(n_wnd).join(control_id) else {
+        println!("Invalid control ID: wnd[{}]{}", n_wnd, control_id);
+        return Ok(err_chk);
+    };
 
-    if let Ok(component) = ret_id {
+    // Try to find the control
+    if let Ok(component) = session.find_control(&control) {
         err_chk.cband = true;
 
         if ret_msg {
@@ -103,9 +269,12 @@ pub fn hit_ctrl(
     event_id_value: &str,
 ) -> Result<String> {
     let mut aux_str = String::new();
-    let control_path = format!("wnd[{}]{}", n_wnd, control_id);
+    let Ok(control) = ControlId::window(n_wnd).join(control_id) else {
+        println!("Invalid control ID: wnd[{}]{}", n_wnd, control_id);
+        return Ok(aux_str);
+    };
 
-    if let Ok(component) = session.find_by_id(control_path) {
+    if let Ok(component) = session.find_control(&control) {
         match event_id {
             "Maximize" => {
                 component.maximize()?;
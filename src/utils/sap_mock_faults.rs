@@ -0,0 +1,183 @@
+use std::thread;
+use std::time::Duration;
+use windows::core::{Error, Result, HRESULT};
+
+use crate::utils::sap_interfaces::normalize_id;
+use crate::utils::sap_status::StatusMessage;
+
+pub use crate::utils::sap_constants::{
+    E_FAIL, RPC_E_CALL_REJECTED, RPC_E_DISCONNECTED, RPC_E_SERVERCALL_RETRYLATER,
+};
+
+/// A failure injected into `MockSapSession`
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockFault {
+    /// Fail the operation with a COM error
+    Error { code: i32, message: String },
+    /// Fail the operation as if the component did not exist
+    Missing,
+    /// Delay the operation
+    Latency(Duration),
+    /// Open a popup window after the operation
+    Popup {
+        window: i32,
+        title: String,
+        message: Option<String>,
+    },
+    /// Set the status bar text after the operation
+    Status(String),
+    /// Show a typed status bar message after the operation
+    Message(StatusMessage),
+}
+
+/// A fault together with the operations it applies to
+///
+/// Operations are named after the `SapSession`/`SapComponent` methods (e.g. "find_by_id",
+/// "press", "send_v_key"), and component IDs are compared after normalization.
+#[derive(Debug, Clone)]
+pub struct MockFaultRule {
+    pub fault: MockFault,
+    /// Operation the fault applies to (any operation if not set)
+    pub op: Option<String>,
+    /// Component the fault applies to (any component or the session if not set)
+    pub id: Option<String>,
+    /// Number of matching calls to let through before the fault fires
+    pub skip: u32,
+    /// Number of times the fault fires (every time if not set)
+    pub times: Option<u32>,
+    calls: u32,
+    fired: u32,
+}
+
+impl MockFaultRule {
+    pub fn new(fault: MockFault) -> Self {
+        Self {
+            fault,
+            op: None,
+            id: None,
+            skip: 0,
+            times: None,
+            calls: 0,
+            fired: 0,
+        }
+    }
+
+    /// Fail with a COM error (e.g. `RPC_E_CALL_REJECTED`)
+    pub fn error(code: i32, message: &str) -> Self {
+        Self::new(MockFault::Error {
+            code,
+            message: message.to_string(),
+        })
+    }
+
+    /// Make `find_by_id` fail for a component
+    pub fn missing(id: &str) -> Self {
+        Self::new(MockFault::Missing).on_op("find_by_id").on_id(id)
+    }
+
+    pub fn latency(latency: Duration) -> Self {
+        Self::new(MockFault::Latency(latency))
+    }
+
+    /// Open a popup on the given window, with an optional message text
+    pub fn popup(window: i32, title: &str, message: Option<&str>) -> Self {
+        Self::new(MockFault::Popup {
+            window,
+            title: title.to_string(),
+            message: message.map(|m| m.to_string()),
+        })
+    }
+
+    pub fn status(text: &str) -> Self {
+        Self::new(MockFault::Status(text.to_string()))
+    }
+
+    /// Show a typed status bar message (with message type, class and number)
+    pub fn message(message: StatusMessage) -> Self {
+        Self::new(MockFault::Message(message))
+    }
+
+    pub fn on_op(mut self, op: &str) -> Self {
+        self.op = Some(op.to_string());
+        self
+    }
+
+    pub fn on_id(mut self, id: &str) -> Self {
+        self.id = Some(normalize_id(id).to_string());
+        self
+    }
+
+    /// Let the first matching calls through before firing
+    pub fn after_calls(mut self, skip: u32) -> Self {
+        self.skip = skip;
+        self
+    }
+
+    pub fn times(mut self, times: u32) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    /// Get the number of times the fault has fired so far
+    pub fn fired(&self) -> u32 {
+        self.fired
+    }
+
+    fn matches(&self, op: &str, id: Option<&str>) -> bool {
+        let op_matches = self.op.as_deref().is_none_or(|o| o == op);
+        let id_matches = match &self.id {
+            Some(rule_id) => id.map(normalize_id) == Some(rule_id.as_str()),
+            None => true,
+        };
+        op_matches && id_matches
+    }
+}
+
+/// The faults injected into a mock session
+#[derive(Debug, Clone, Default)]
+pub struct MockFaults {
+    rules: Vec<MockFaultRule>,
+}
+
+impl MockFaults {
+    pub fn add(&mut self, rule: MockFaultRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    pub fn rules(&self) -> &[MockFaultRule] {
+        &self.rules
+    }
+
+    /// Fire the rules matching an operation
+    ///
+    /// Errors and latency take effect immediately. Popups and status messages are
+    /// returned so the session can apply them once the operation has completed.
+    pub fn fire(&mut self, op: &str, id: Option<&str>) -> Result<Vec<MockFault>> {
+        let mut effects = Vec::new();
+
+        for rule in self.rules.iter_mut().filter(|r| r.matches(op, id)) {
+            rule.calls += 1;
+            if rule.calls <= rule.skip || rule.times.is_some_and(|t| rule.fired >= t) {
+                continue;
+            }
+            rule.fired += 1;
+
+            match &rule.fault {
+                MockFault::Error { code, message } => {
+                    return Err(Error::new(HRESULT(*code), message.as_str().into()));
+                }
+                MockFault::Missing => {
+                    return Err(Error::new(HRESULT(E_FAIL), "Component not found".into()));
+                }
+                MockFault::Latency(latency) => thread::sleep(*latency),
+                effect => effects.push(effect.clone()),
+            }
+        }
+
+        Ok(effects)
+    }
+}
@@ -0,0 +1,305 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use crate::utils::sap_mock_impl::{MockComponent, MockGrid};
+use crate::utils::sap_status::StatusMessage;
+
+/// Screen-transition model for `MockSapSession`, loaded from a TOML fixture
+///
+/// A fixture lists screens (sets of components) and the transitions between them:
+///
+/// ```toml
+/// initial = "vt11_selection"
+///
+/// [[screen]]
+/// name = "vt11_selection"
+/// transaction = "VT11"
+/// components = [
+///     { id = "wnd[0]", type = "GuiMainWindow", text = "Shipment List: Planning" },
+///     { id = "wnd[0]/sbar", type = "GuiStatusbar" },
+///     { id = "wnd[0]/tbar[1]/btn[8]", type = "GuiButton" },
+/// ]
+///
+/// [[screen]]
+/// name = "no_shipments"
+/// base = "vt11_selection"
+/// components = [{ id = "wnd[1]", type = "GuiModalWindow", text = "Information" }]
+///
+/// [[transition]]
+/// from = "vt11_selection"
+/// action = "press"
+/// id = "wnd[0]/tbar[1]/btn[8]"
+/// to = "no_shipments"
+///
+/// [[transition]]
+/// action = "start_transaction"
+/// tcode = "VT11X"
+/// message = { kind = "E", message_class = "00", message_number = "343", params = ["VT11X"], text = "Transaction VT11X does not exist" }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockScreenFlow {
+    pub initial: String,
+    #[serde(default, rename = "screen")]
+    pub screens: Vec<MockScreen>,
+    #[serde(default, rename = "transition")]
+    pub transitions: Vec<MockTransition>,
+}
+
+/// A screen: the components visible at one point of a flow
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockScreen {
+    pub name: String,
+    /// Screen whose components are included before this screen's own (e.g. for popups)
+    pub base: Option<String>,
+    /// Transaction reported by the session while on this screen
+    pub transaction: Option<String>,
+    /// ABAP program reported by the session while on this screen (e.g. "SAPMSYST")
+    pub program: Option<String>,
+    /// Dynpro number reported by the session while on this screen
+    pub screen_number: Option<i32>,
+    /// Status bar text set when entering this screen (cleared if not set)
+    pub status: Option<String>,
+    /// Typed status bar message set when entering this screen (takes precedence over `status`)
+    pub message: Option<StatusMessage>,
+    #[serde(default)]
+    pub components: Vec<MockScreenComponent>,
+}
+
+/// A component definition within a screen
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockScreenComponent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub r_type: String,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+    /// Column names, if the component is a grid view or table control
+    pub columns: Option<Vec<String>>,
+    #[serde(default)]
+    pub rows: Vec<Vec<String>>,
+    pub visible_row_count: Option<i32>,
+}
+
+/// An action performed against the mock session that can trigger a transition
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum MockAction {
+    StartTransaction {
+        tcode: String,
+    },
+    EndTransaction,
+    Press {
+        id: String,
+    },
+    Select {
+        id: String,
+    },
+    #[serde(rename = "vkey")]
+    VKey {
+        window: i32,
+        key: i32,
+    },
+    Close {
+        window: i32,
+    },
+}
+
+/// A transition from one screen to another, triggered by an action
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockTransition {
+    /// Screen the transition applies to (any screen if not set)
+    pub from: Option<String>,
+    #[serde(flatten)]
+    pub action: MockAction,
+    /// Screen to switch to (stay on the current screen if not set)
+    pub to: Option<String>,
+    /// Status bar text set after the transition
+    pub status: Option<String>,
+    /// Typed status bar message set after the transition (takes precedence over `status`)
+    pub message: Option<StatusMessage>,
+}
+
+impl MockAction {
+    fn matches(&self, other: &MockAction) -> bool {
+        match (self, other) {
+            (
+                MockAction::StartTransaction { tcode: a },
+                MockAction::StartTransaction { tcode: b },
+            ) => a.eq_ignore_ascii_case(b),
+            _ => self == other,
+        }
+    }
+}
+
+impl MockScreenComponent {
+    /// Build a fresh mock component from this definition
+    pub fn build(&self) -> MockComponent {
+        let name = self
+            .name
+            .clone()
+            .unwrap_or_else(|| self.id.rsplit('/').next().unwrap_or(&self.id).to_string());
+        let mut component = MockComponent::new(&self.id, &name, &self.r_type);
+        component.text = self.text.clone();
+        component.properties = self.properties.clone();
+
+        if let Some(columns) = &self.columns {
+            let columns: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+            let visible = self.visible_row_count.unwrap_or(self.rows.len() as i32);
+            let mut grid = MockGrid::new(&columns, visible);
+            for row in &self.rows {
+                let values: Vec<&str> = row.iter().map(|v| v.as_str()).collect();
+                grid.add_row(&values);
+            }
+            component = component.with_grid(grid);
+        }
+
+        component
+    }
+}
+
+impl MockScreenFlow {
+    /// Parse a flow from TOML and check that every referenced screen exists
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let flow: MockScreenFlow =
+            toml::from_str(content).map_err(|e| anyhow!("Failed to parse screen flow: {}", e))?;
+        flow.validate()?;
+        Ok(flow)
+    }
+
+    /// Load a flow from a TOML fixture file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read screen flow {}: {}", path, e))?;
+        Self::from_toml_str(&content)
+    }
+
+    pub fn screen(&self, name: &str) -> Option<&MockScreen> {
+        self.screens.iter().find(|s| s.name == name)
+    }
+
+    /// Find the first transition from the given screen that matches an action
+    pub fn find_transition(
+        &self,
+        screen: Option<&str>,
+        action: &MockAction,
+    ) -> Option<&MockTransition> {
+        self.transitions.iter().find(|t| {
+            let from_matches = match &t.from {
+                Some(from) => screen == Some(from.as_str()),
+                None => true,
+            };
+            from_matches && t.action.matches(action)
+        })
+    }
+
+    /// Get the component definitions of a screen, including those of its base screens
+    pub fn screen_components(&self, name: &str) -> Vec<&MockScreenComponent> {
+        let mut chain = Vec::new();
+        let mut current = self.screen(name);
+        while let Some(screen) = current {
+            // validate() rejects cycles, but guard against them anyway
+            if chain.iter().any(|s: &&MockScreen| s.name == screen.name) {
+                break;
+            }
+            chain.push(screen);
+            current = screen.base.as_deref().and_then(|base| self.screen(base));
+        }
+
+        let mut components: Vec<&MockScreenComponent> = Vec::new();
+        for screen in chain.iter().rev() {
+            for component in &screen.components {
+                // Later screens override components with the same id
+                components.retain(|c| c.id != component.id);
+                components.push(component);
+            }
+        }
+        components
+    }
+
+    /// Get the transaction of a screen, falling back to its base screens
+    pub fn screen_transaction(&self, name: &str) -> Option<&str> {
+        self.inherited(name, |screen| screen.transaction.as_deref())
+    }
+
+    /// Get the program and dynpro number of a screen, falling back to its base screens
+    pub fn screen_id(&self, name: &str) -> Option<(&str, i32)> {
+        self.inherited(name, |screen| {
+            Some((screen.program.as_deref()?, screen.screen_number?))
+        })
+    }
+
+    /// Get the first value set on a screen or its base screens
+    fn inherited<'a, T>(
+        &'a self,
+        name: &str,
+        value: impl Fn(&'a MockScreen) -> Option<T>,
+    ) -> Option<T> {
+        let mut seen = HashSet::new();
+        let mut current = self.screen(name);
+        while let Some(screen) = current {
+            if !seen.insert(screen.name.as_str()) {
+                break;
+            }
+            if let Some(value) = value(screen) {
+                return Some(value);
+            }
+            current = screen.base.as_deref().and_then(|base| self.screen(base));
+        }
+        None
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for screen in &self.screens {
+            if !names.insert(screen.name.as_str()) {
+                return Err(anyhow!("Duplicate screen: {}", screen.name));
+            }
+        }
+
+        let check = |name: &str, context: &str| {
+            if names.contains(name) {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "Unknown screen ({}) referenced by {}",
+                    name,
+                    context
+                ))
+            }
+        };
+
+        check(&self.initial, "initial")?;
+
+        for screen in &self.screens {
+            if let Some(base) = &screen.base {
+                check(base, &format!("screen {}", screen.name))?;
+            }
+
+            // Follow the base chain to make sure it ends
+            let mut seen = HashSet::new();
+            let mut current = Some(screen);
+            while let Some(s) = current {
+                if !seen.insert(s.name.as_str()) {
+                    return Err(anyhow!("Screen {} has a cyclic base", screen.name));
+                }
+                current = s.base.as_deref().and_then(|base| self.screen(base));
+            }
+        }
+
+        for transition in &self.transitions {
+            if let Some(from) = &transition.from {
+                check(from, "transition")?;
+            }
+            if let Some(to) = &transition.to {
+                check(to, "transition")?;
+            }
+        }
+
+        Ok(())
+    }
+}
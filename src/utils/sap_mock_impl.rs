@@ -1,9 +1,61 @@
-use crate::utils::sap_interfaces::{SapComponent, SapComponentFactory, SapSession, SapSessionInfo};
+use crate::utils::sap_constants::MAX_MODAL_WINDOWS;
+use crate::utils::sap_interfaces::{
+    normalize_id, parse_row_selection, SapComponent, SapComponentFactory, SapSession,
+    SapSessionInfo,
+};
+use crate::utils::sap_mock_faults::{MockFault, MockFaultRule, MockFaults};
+use crate::utils::sap_mock_flow::{MockAction, MockScreenFlow};
+use crate::utils::sap_screen_dump::ScreenDump;
+use crate::utils::sap_status::{MessageKind, StatusMessage};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 use windows::core::{Error, Result, HRESULT};
 
+/// In-memory model of a grid view or table control
+#[derive(Debug, Clone, Default)]
+pub struct MockGrid {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub current_cell: (i32, String),
+    pub selected_rows: Vec<i32>,
+    pub selected_columns: Vec<String>,
+    pub first_visible_row: i32,
+    pub visible_row_count: i32,
+    pub double_clicks: Vec<(i32, String)>,
+}
+
+impl MockGrid {
+    pub fn new(columns: &[&str], visible_row_count: i32) -> Self {
+        Self {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            current_cell: (-1, String::new()),
+            visible_row_count,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_row(&mut self, values: &[&str]) {
+        self.rows
+            .push(values.iter().map(|v| v.to_string()).collect());
+    }
+
+    /// Find a column by name, falling back to a column index (as used by table controls)
+    fn column_index(&self, column: &str) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|c| c == column)
+            .or_else(|| column.parse::<usize>().ok())
+            .filter(|idx| *idx < self.columns.len())
+    }
+
+    pub fn cell_value(&self, row: i32, column: &str) -> Option<String> {
+        let col = self.column_index(column)?;
+        let row = usize::try_from(row).ok()?;
+        self.rows.get(row).and_then(|r| r.get(col)).cloned()
+    }
+}
+
 /// Mock component for testing
 #[derive(Debug, Clone)]
 pub struct MockComponent {
@@ -13,6 +65,7 @@ pub struct MockComponent {
     pub text: String,
     pub properties: HashMap<String, String>,
     pub children: Vec<Rc<RefCell<MockComponent>>>,
+    pub grid: Option<MockGrid>,
 }
 
 impl MockComponent {
@@ -24,94 +77,624 @@ impl MockComponent {
             text: String::new(),
             properties: HashMap::new(),
             children: Vec::new(),
+            grid: None,
         }
     }
 
     pub fn add_child(&mut self, child: Rc<RefCell<MockComponent>>) {
         self.children.push(child);
     }
+
+    /// Add a child component, identified by its ID relative to this one (e.g. "usr")
+    pub fn with_child(mut self, child: MockComponent) -> Self {
+        self.children.push(Rc::new(RefCell::new(child)));
+        self
+    }
+
+    pub fn with_grid(mut self, grid: MockGrid) -> Self {
+        self.grid = Some(grid);
+        self
+    }
+
+    /// Show a typed message, stored like the GuiStatusbar properties (if it's a status bar)
+    pub fn set_status_message(&mut self, message: &StatusMessage) {
+        self.clear_status_message();
+        self.text = message.text.clone();
+        let properties = [
+            ("MessageType", message.kind.code().to_string()),
+            ("MessageId", message.message_class.clone()),
+            ("MessageNumber", message.message_number.clone()),
+        ];
+        for (key, value) in properties {
+            self.properties.insert(key.to_string(), value);
+        }
+        for (i, param) in message.params.iter().enumerate() {
+            self.properties
+                .insert(format!("MessageParameter{}", i), param.clone());
+        }
+    }
+
+    /// Remove the message type, class, number and parameters, keeping the text
+    pub fn clear_status_message(&mut self) {
+        self.properties.retain(|key, _| !key.starts_with("Message"));
+    }
+
+    /// Get the typed message from the status bar properties
+    ///
+    /// Status text set without a message type is reported as Info.
+    fn status_message(&self) -> StatusMessage {
+        let property = |key: &str| self.properties.get(key).cloned().unwrap_or_default();
+        let params = (0..)
+            .map_while(|i| {
+                self.properties
+                    .get(&format!("MessageParameter{}", i))
+                    .cloned()
+            })
+            .collect();
+
+        StatusMessage {
+            kind: MessageKind::from_code(&property("MessageType")),
+            message_class: property("MessageId"),
+            message_number: property("MessageNumber"),
+            params,
+            text: self.text.clone(),
+        }
+    }
+}
+
+/// A window operation recorded by the mock session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockWindowEvent {
+    VKey { window: i32, key: i32 },
+    Close { window: i32 },
+}
+
+/// Get the window index from a component ID (e.g. 1 for "wnd[1]/usr/txtField")
+fn window_index(id: &str) -> i32 {
+    id.strip_prefix("wnd[")
+        .and_then(|rest| rest.split(']').next())
+        .and_then(|idx| idx.parse::<i32>().ok())
+        .unwrap_or(0)
+}
+
+/// Resolve an ID relative to a container (IDs starting with a window or "/" are absolute)
+fn resolve_id(container_id: &str, id: &str) -> String {
+    if id.starts_with("wnd[") || id.starts_with('/') || container_id.is_empty() {
+        normalize_id(id).to_string()
+    } else {
+        format!("{}/{}", normalize_id(container_id), id)
+    }
+}
+
+/// Get the ID of a component's parent (e.g. "wnd[0]/usr" for "wnd[0]/usr/txtField")
+fn parent_id(id: &str) -> Option<&str> {
+    id.rsplit_once('/').map(|(parent, _)| parent)
+}
+
+/// Add a child to a component's children unless it is already there
+fn link(parent: &Rc<RefCell<MockComponent>>, child: &Rc<RefCell<MockComponent>>) {
+    let mut parent = parent.borrow_mut();
+    if !parent.children.iter().any(|c| Rc::ptr_eq(c, child)) {
+        parent.children.push(child.clone());
+    }
+}
+
+/// Collect the descendants of a component matching a name and type, in tree order
+fn collect_by_name(
+    component: &Rc<RefCell<MockComponent>>,
+    name: &str,
+    r_type: &str,
+    found: &mut Vec<Rc<RefCell<MockComponent>>>,
+) {
+    for child in &component.borrow().children {
+        {
+            let c = child.borrow();
+            if c.name == name && c.r_type == r_type {
+                found.push(child.clone());
+            }
+        }
+        collect_by_name(child, name, r_type, found);
+    }
+}
+
+/// State shared between a mock session and the components it hands out
+struct MockSessionState {
+    components: HashMap<String, Rc<RefCell<MockComponent>>>,
+    current_transaction: String,
+    events: Vec<MockWindowEvent>,
+    flow: Option<MockScreenFlow>,
+    current_screen: Option<String>,
+    faults: MockFaults,
+    busy_polls: u32,
+    /// Screen and logon details reported by `info()` (the transaction is tracked above)
+    info: MockSapSessionInfo,
+}
+
+impl MockSessionState {
+    fn new() -> Self {
+        Self {
+            components: HashMap::new(),
+            current_transaction: "S000".to_string(), // Default to login screen
+            events: Vec::new(),
+            flow: None,
+            current_screen: None,
+            faults: MockFaults::default(),
+            busy_polls: 0,
+            info: MockSapSessionInfo::default(),
+        }
+    }
+
+    /// Add a component to the tree, along with any children it was built with
+    ///
+    /// Children with relative IDs get their full ID from their parent, and components
+    /// added before their parent are adopted by it once it arrives.
+    fn insert(&mut self, id: &str, component: Rc<RefCell<MockComponent>>) {
+        let id = normalize_id(id).to_string();
+        component.borrow_mut().id = id.clone();
+        let children = component.borrow().children.clone();
+
+        if let Some(existing) = self.components.get(&id) {
+            if !Rc::ptr_eq(existing, &component) {
+                if let Some(parent) = parent_id(&id).and_then(|p| self.components.get(p)) {
+                    parent
+                        .borrow_mut()
+                        .children
+                        .retain(|c| !Rc::ptr_eq(c, existing));
+                }
+            }
+        }
+
+        if let Some(parent) = parent_id(&id).and_then(|p| self.components.get(p)) {
+            link(parent, &component);
+        }
+
+        let mut orphans: Vec<_> = self
+            .components
+            .iter()
+            .filter(|(other, _)| parent_id(other) == Some(id.as_str()))
+            .map(|(other, c)| (other.clone(), c.clone()))
+            .collect();
+        orphans.sort_by(|a, b| a.0.cmp(&b.0));
+        for (_, orphan) in orphans {
+            link(&component, &orphan);
+        }
+
+        self.components.insert(id.clone(), component);
+
+        for child in children {
+            let child_id = resolve_id(&id, &child.borrow().id);
+            self.insert(&child_id, child);
+        }
+    }
+
+    /// Apply the transition matching an action, if there is one
+    fn perform(&mut self, action: MockAction) {
+        let transition = self.flow.as_ref().and_then(|flow| {
+            flow.find_transition(self.current_screen.as_deref(), &action)
+                .cloned()
+        });
+
+        match transition {
+            Some(transition) => {
+                if let Some(to) = &transition.to {
+                    self.enter_screen(to);
+                }
+                if let Some(message) = &transition.message {
+                    self.set_message(message);
+                } else if let Some(status) = &transition.status {
+                    self.set_status(status);
+                }
+            }
+            None => {
+                // Without a scripted transition, closing a popup still removes it
+                if let MockAction::Close { window } = action {
+                    if window > 0 {
+                        self.components.retain(|id, _| window_index(id) != window);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replace the components with those of a screen from the flow
+    fn enter_screen(&mut self, name: &str) {
+        let Some(flow) = &self.flow else {
+            return;
+        };
+
+        let mut components = Vec::new();
+        for definition in flow.screen_components(name) {
+            // Keep components that stay on screen so entered values survive the transition
+            let component = match self.components.get(normalize_id(&definition.id)) {
+                Some(existing) if existing.borrow().r_type == definition.r_type => {
+                    existing.borrow_mut().children.clear();
+                    existing.clone()
+                }
+                _ => Rc::new(RefCell::new(definition.build())),
+            };
+            components.push((definition.id.clone(), component));
+        }
+
+        if let Some(transaction) = flow.screen_transaction(name) {
+            self.current_transaction = transaction.to_string();
+        }
+        let (program, screen_number) = flow.screen_id(name).unwrap_or_default();
+        self.info.program = program.to_string();
+        self.info.screen_number = screen_number;
+        let screen = flow.screen(name);
+        let status = screen
+            .and_then(|screen| screen.status.clone())
+            .unwrap_or_default();
+        let message = screen.and_then(|screen| screen.message.clone());
+
+        self.components.clear();
+        for (id, component) in components {
+            self.insert(&id, component);
+        }
+        self.current_screen = Some(name.to_string());
+        match message {
+            Some(message) => self.set_message(&message),
+            None => self.set_status(&status),
+        }
+    }
+
+    /// Apply the popups and status messages injected for a completed operation
+    fn apply_effects(&mut self, effects: Vec<MockFault>) {
+        for effect in effects {
+            match effect {
+                MockFault::Popup {
+                    window,
+                    title,
+                    message,
+                } => {
+                    // A new popup replaces whatever was open on that window
+                    self.components.retain(|id, _| window_index(id) != window);
+
+                    let id = format!("wnd[{}]", window);
+                    let mut popup = MockComponent::new(&id, &id, "GuiModalWindow");
+                    popup.text = title;
+                    if let Some(message) = message {
+                        let mut text =
+                            MockComponent::new("txtMESSTXT1", "MESSTXT1", "GuiTextField");
+                        text.text = message;
+                        popup = popup.with_child(
+                            MockComponent::new("usr", "usr", "GuiUserArea").with_child(text),
+                        );
+                    }
+                    self.insert(&id, Rc::new(RefCell::new(popup)));
+                }
+                MockFault::Status(text) => self.set_status(&text),
+                MockFault::Message(message) => self.set_message(&message),
+                _ => {}
+            }
+        }
+    }
+
+    fn set_status(&mut self, text: &str) {
+        if let Some(statusbar) = self.components.get("wnd[0]/sbar") {
+            let mut statusbar = statusbar.borrow_mut();
+            statusbar.clear_status_message();
+            statusbar.text = text.to_string();
+        }
+    }
+
+    fn set_message(&mut self, message: &StatusMessage) {
+        if let Some(statusbar) = self.components.get("wnd[0]/sbar") {
+            statusbar.borrow_mut().set_status_message(message);
+        }
+    }
 }
 
 /// Implementation of SapComponent for mock components
 pub struct MockSapComponent {
     component: Rc<RefCell<MockComponent>>,
+    session: Rc<RefCell<MockSessionState>>,
 }
 
 impl MockSapComponent {
     pub fn new(component: Rc<RefCell<MockComponent>>) -> Self {
-        Self { component }
+        Self {
+            component,
+            session: Rc::new(RefCell::new(MockSessionState::new())),
+        }
+    }
+
+    /// Create a component whose actions are recorded by (and drive) a mock session
+    fn attached(
+        component: Rc<RefCell<MockComponent>>,
+        session: Rc<RefCell<MockSessionState>>,
+    ) -> Self {
+        Self { component, session }
+    }
+
+    fn id(&self) -> String {
+        self.component.borrow().id.clone()
+    }
+
+    fn record(&self, event: MockWindowEvent) {
+        self.session.borrow_mut().events.push(event);
+    }
+
+    fn perform(&self, action: MockAction) {
+        self.session.borrow_mut().perform(action);
+    }
+
+    /// Run an operation, applying the faults injected for it
+    fn faulted<T>(&self, op: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let id = self.id();
+        let effects = self.session.borrow_mut().faults.fire(op, Some(&id))?;
+        let result = f()?;
+        self.session.borrow_mut().apply_effects(effects);
+        Ok(result)
+    }
+
+    /// Run a closure against the grid model, failing if the component has none
+    fn with_grid<T>(&self, f: impl FnOnce(&mut MockGrid) -> Result<T>) -> Result<T> {
+        match self.component.borrow_mut().grid.as_mut() {
+            Some(grid) => f(grid),
+            None => Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view".into(),
+            )),
+        }
     }
 }
 
 impl SapComponent for MockSapComponent {
     fn r_type(&self) -> Result<String> {
-        Ok(self.component.borrow().r_type.clone())
+        self.faulted("r_type", || Ok(self.component.borrow().r_type.clone()))
     }
 
     fn name(&self) -> Result<String> {
-        Ok(self.component.borrow().name.clone())
+        self.faulted("name", || Ok(self.component.borrow().name.clone()))
     }
 
     fn get_text(&self) -> Result<String> {
-        Ok(self.component.borrow().text.clone())
+        self.faulted("get_text", || Ok(self.component.borrow().text.clone()))
     }
 
     fn set_text(&self, text: String) -> Result<()> {
-        self.component.borrow_mut().text = text;
-        Ok(())
+        self.faulted("set_text", || {
+            self.component.borrow_mut().text = text;
+            Ok(())
+        })
+    }
+
+    fn changeable(&self) -> Result<bool> {
+        self.faulted("changeable", || {
+            // Components are changeable unless marked otherwise in properties
+            let changeable = self
+                .component
+                .borrow()
+                .properties
+                .get("changeable")
+                .map(|s| s != "false")
+                .unwrap_or(true);
+            Ok(changeable)
+        })
     }
 
     fn set_focus(&self) -> Result<()> {
-        // In a mock, we don't need to do anything for set_focus
-        Ok(())
+        self.faulted("set_focus", || {
+            // In a mock, we don't need to do anything for set_focus
+            Ok(())
+        })
     }
 
     fn press(&self) -> Result<()> {
-        // In a mock, we don't need to do anything for press
-        Ok(())
+        self.faulted("press", || {
+            self.perform(MockAction::Press { id: self.id() });
+            Ok(())
+        })
     }
 
     fn select(&self) -> Result<()> {
-        // In a mock, we don't need to do anything for select
-        Ok(())
+        self.faulted("select", || {
+            self.perform(MockAction::Select { id: self.id() });
+            Ok(())
+        })
     }
 
     fn selected(&self) -> Result<bool> {
-        // Get the selected state from properties
-        let selected = self
-            .component
-            .borrow()
-            .properties
-            .get("selected")
-            .map(|s| s == "true")
-            .unwrap_or(false);
-        Ok(selected)
+        self.faulted("selected", || {
+            // Get the selected state from properties
+            let selected = self
+                .component
+                .borrow()
+                .properties
+                .get("selected")
+                .map(|s| s == "true")
+                .unwrap_or(false);
+            Ok(selected)
+        })
     }
 
     fn set_selected(&self, selected: bool) -> Result<()> {
-        // Set the selected state in properties
-        self.component
-            .borrow_mut()
-            .properties
-            .insert("selected".to_string(), selected.to_string());
-        Ok(())
+        self.faulted("set_selected", || {
+            // Set the selected state in properties
+            self.component
+                .borrow_mut()
+                .properties
+                .insert("selected".to_string(), selected.to_string());
+            Ok(())
+        })
     }
 
     fn maximize(&self) -> Result<()> {
-        // In a mock, we don't need to do anything for maximize
-        Ok(())
+        self.faulted("maximize", || {
+            // In a mock, we don't need to do anything for maximize
+            Ok(())
+        })
+    }
+
+    fn send_v_key(&self, key: i32) -> Result<()> {
+        self.faulted("send_v_key", || {
+            let window = window_index(&self.id());
+            self.record(MockWindowEvent::VKey { window, key });
+            self.perform(MockAction::VKey { window, key });
+            Ok(())
+        })
+    }
+
+    fn close(&self) -> Result<()> {
+        self.faulted("close", || {
+            let window = window_index(&self.id());
+            self.record(MockWindowEvent::Close { window });
+            self.perform(MockAction::Close { window });
+            Ok(())
+        })
+    }
+
+    fn set_selected_rows(&self, rows: String) -> Result<()> {
+        self.faulted("set_selected_rows", || {
+            self.with_grid(|grid| {
+                grid.selected_rows = parse_row_selection(&rows);
+                Ok(())
+            })
+        })
+    }
+
+    fn set_current_cell_row(&self, row: i32) -> Result<()> {
+        self.faulted("set_current_cell_row", || {
+            self.with_grid(|grid| {
+                grid.current_cell.0 = row;
+                Ok(())
+            })
+        })
+    }
+
+    fn context_menu(&self) -> Result<()> {
+        self.faulted("context_menu", || {
+            // In a mock, we don't need to do anything for context_menu
+            Ok(())
+        })
+    }
+
+    fn select_context_menu_item(&self, function_code: String) -> Result<()> {
+        self.faulted("select_context_menu_item", || {
+            // Store the selected context menu item in properties
+            self.component
+                .borrow_mut()
+                .properties
+                .insert("context_menu_item".to_string(), function_code);
+            Ok(())
+        })
+    }
+
+    fn set_current_cell(&self, row: i32, column: String) -> Result<()> {
+        self.faulted("set_current_cell", || {
+            self.with_grid(|grid| {
+                grid.current_cell = (row, column);
+                Ok(())
+            })
+        })
+    }
+
+    fn current_cell_row(&self) -> Result<i32> {
+        self.faulted("current_cell_row", || {
+            self.with_grid(|grid| Ok(grid.current_cell.0))
+        })
+    }
+
+    fn double_click_current_cell(&self) -> Result<()> {
+        self.faulted("double_click_current_cell", || {
+            self.with_grid(|grid| {
+                grid.double_clicks.push(grid.current_cell.clone());
+                Ok(())
+            })
+        })
+    }
+
+    fn row_count(&self) -> Result<i32> {
+        self.faulted("row_count", || {
+            self.with_grid(|grid| Ok(grid.rows.len() as i32))
+        })
+    }
+
+    fn visible_row_count(&self) -> Result<i32> {
+        self.faulted("visible_row_count", || {
+            self.with_grid(|grid| Ok(grid.visible_row_count))
+        })
+    }
+
+    fn first_visible_row(&self) -> Result<i32> {
+        self.faulted("first_visible_row", || {
+            self.with_grid(|grid| Ok(grid.first_visible_row))
+        })
+    }
+
+    fn set_first_visible_row(&self, row: i32) -> Result<()> {
+        self.faulted("set_first_visible_row", || {
+            self.with_grid(|grid| {
+                // Like SAP, stop scrolling once the last row is visible
+                let max_first_row = (grid.rows.len() as i32 - grid.visible_row_count).max(0);
+                grid.first_visible_row = row.clamp(0, max_first_row);
+                Ok(())
+            })
+        })
+    }
+
+    fn get_cell_value(&self, row: i32, column: String) -> Result<String> {
+        self.faulted("get_cell_value", || {
+            self.with_grid(|grid| {
+                grid.cell_value(row, &column)
+                    .ok_or_else(|| Error::new(HRESULT(-2147467259), "Cell not found".into()))
+            })
+        })
+    }
+
+    fn select_column(&self, column: String) -> Result<()> {
+        self.faulted("select_column", || {
+            self.with_grid(|grid| {
+                grid.selected_columns = vec![column];
+                Ok(())
+            })
+        })
+    }
+
+    fn press_column_header(&self, column: String) -> Result<()> {
+        self.faulted("press_column_header", || {
+            // Pressing a column header sorts the rows by that column
+            self.with_grid(|grid| {
+                if let Some(col) = grid.column_index(&column) {
+                    grid.rows.sort_by(|a, b| a.get(col).cmp(&b.get(col)));
+                }
+                Ok(())
+            })
+        })
+    }
+
+    fn status_message(&self) -> Result<StatusMessage> {
+        self.faulted("status_message", || {
+            let component = self.component.borrow();
+            if component.r_type != "GuiStatusbar" {
+                return Err(Error::new(
+                    HRESULT(-2147467259),
+                    "Component is not a status bar".into(),
+                ));
+            }
+            Ok(component.status_message())
+        })
     }
 }
 
 /// Implementation of SapSessionInfo for mock session info
+#[derive(Debug, Clone, Default)]
 pub struct MockSapSessionInfo {
-    transaction: String,
+    pub transaction: String,
+    pub program: String,
+    pub screen_number: i32,
+    pub system_name: String,
+    pub client: String,
+    pub user: String,
+    pub language: String,
 }
 
 impl MockSapSessionInfo {
     pub fn new(transaction: &str) -> Self {
         Self {
             transaction: transaction.to_string(),
+            ..Default::default()
         }
     }
 }
@@ -120,61 +703,317 @@ impl SapSessionInfo for MockSapSessionInfo {
     fn transaction(&self) -> Result<String> {
         Ok(self.transaction.clone())
     }
+
+    fn program(&self) -> Result<String> {
+        Ok(self.program.clone())
+    }
+
+    fn screen_number(&self) -> Result<i32> {
+        Ok(self.screen_number)
+    }
+
+    fn system_name(&self) -> Result<String> {
+        Ok(self.system_name.clone())
+    }
+
+    fn client(&self) -> Result<String> {
+        Ok(self.client.clone())
+    }
+
+    fn user(&self) -> Result<String> {
+        Ok(self.user.clone())
+    }
+
+    fn language(&self) -> Result<String> {
+        Ok(self.language.clone())
+    }
 }
 
 /// Implementation of SapSession for mock session
 pub struct MockSapSession {
     name: String,
-    components: HashMap<String, Rc<RefCell<MockComponent>>>,
-    current_transaction: String,
+    state: Rc<RefCell<MockSessionState>>,
 }
 
 impl MockSapSession {
     pub fn new(name: &str) -> Self {
         Self {
             name: name.to_string(),
-            components: HashMap::new(),
-            current_transaction: "S000".to_string(), // Default to login screen
+            state: Rc::new(RefCell::new(MockSessionState::new())),
+        }
+    }
+
+    /// Create a session driven by a screen-transition model, starting on its initial screen
+    pub fn with_flow(name: &str, flow: MockScreenFlow) -> Self {
+        let session = Self::new(name);
+        {
+            let mut state = session.state.borrow_mut();
+            let initial = flow.initial.clone();
+            state.flow = Some(flow);
+            state.enter_screen(&initial);
         }
+        session
+    }
+
+    /// Create a session from a TOML screen-flow fixture
+    pub fn from_flow_file(name: &str, path: &str) -> anyhow::Result<Self> {
+        Ok(Self::with_flow(name, MockScreenFlow::from_file(path)?))
     }
 
+    /// Create a session from a JSON screen dump captured with `dump_screen`
+    pub fn from_dump_file(name: &str, path: &str) -> anyhow::Result<Self> {
+        Ok(ScreenDump::from_file(path)?.to_mock_session(name))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Add a component to the tree, along with any children it was built with
     pub fn add_component(&mut self, id: &str, component: Rc<RefCell<MockComponent>>) {
-        self.components.insert(id.to_string(), component);
+        self.state.borrow_mut().insert(id, component);
     }
 
     pub fn set_transaction(&mut self, transaction: &str) {
-        self.current_transaction = transaction.to_string();
+        self.state.borrow_mut().current_transaction = transaction.to_string();
+    }
+
+    /// Set the program and dynpro number of the current screen
+    pub fn set_screen(&mut self, program: &str, screen_number: i32) {
+        let mut state = self.state.borrow_mut();
+        state.info.program = program.to_string();
+        state.info.screen_number = screen_number;
+    }
+
+    /// Set the system, client, user and language the session is logged on with
+    pub fn set_logon(&mut self, system_name: &str, client: &str, user: &str, language: &str) {
+        let mut state = self.state.borrow_mut();
+        state.info.system_name = system_name.to_string();
+        state.info.client = client.to_string();
+        state.info.user = user.to_string();
+        state.info.language = language.to_string();
+    }
+
+    /// Get a handle to a component currently on screen
+    pub fn component(&self, id: &str) -> Option<Rc<RefCell<MockComponent>>> {
+        self.state
+            .borrow()
+            .components
+            .get(normalize_id(id))
+            .cloned()
+    }
+
+    /// Get the direct children of a component, in the order they were added
+    pub fn children(&self, id: &str) -> Vec<Rc<RefCell<MockComponent>>> {
+        self.component(id)
+            .map(|component| component.borrow().children.clone())
+            .unwrap_or_default()
+    }
+
+    /// Find a component by an ID relative to a container (e.g. "usr/txtField" in "wnd[0]")
+    pub fn find_by_relative_id(
+        &self,
+        container_id: &str,
+        id: &str,
+    ) -> Result<Box<dyn SapComponent>> {
+        self.find_by_id(resolve_id(container_id, id))
+    }
+
+    /// Find the first component below a container with the given name and type
+    pub fn find_by_name(
+        &self,
+        container_id: &str,
+        name: &str,
+        r_type: &str,
+    ) -> Option<Rc<RefCell<MockComponent>>> {
+        self.find_all_by_name(container_id, name, r_type)
+            .into_iter()
+            .next()
+    }
+
+    /// Find every component below a container with the given name and type
+    pub fn find_all_by_name(
+        &self,
+        container_id: &str,
+        name: &str,
+        r_type: &str,
+    ) -> Vec<Rc<RefCell<MockComponent>>> {
+        let mut found = Vec::new();
+        if let Some(container) = self.component(container_id) {
+            collect_by_name(&container, name, r_type, &mut found);
+        }
+        found
+    }
+
+    /// Get the name of the current screen (if the session has a screen flow)
+    pub fn current_screen(&self) -> Option<String> {
+        self.state.borrow().current_screen.clone()
+    }
+
+    /// Get the current status bar text
+    pub fn status_text(&self) -> String {
+        self.component("wnd[0]/sbar")
+            .map(|statusbar| statusbar.borrow().text.clone())
+            .unwrap_or_default()
+    }
+
+    /// Show a typed message in the status bar of wnd[0]
+    pub fn set_status_message(&self, message: &StatusMessage) {
+        self.state.borrow_mut().set_message(message);
+    }
+
+    /// Get the current status bar message (None if the status bar is empty)
+    pub fn status_message(&self) -> Option<StatusMessage> {
+        self.component("wnd[0]/sbar")
+            .map(|statusbar| statusbar.borrow().status_message())
+            .filter(|message| !message.is_empty())
+    }
+
+    /// Get every window operation performed so far, in order
+    pub fn window_events(&self) -> Vec<MockWindowEvent> {
+        self.state.borrow().events.clone()
+    }
+
+    /// Get every virtual key sent so far as (window index, key) pairs
+    pub fn key_history(&self) -> Vec<(i32, i32)> {
+        self.window_events()
+            .iter()
+            .filter_map(|event| match event {
+                MockWindowEvent::VKey { window, key } => Some((*window, *key)),
+                MockWindowEvent::Close { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Get the virtual keys sent to one window
+    pub fn keys_sent_to(&self, wnd: i32) -> Vec<i32> {
+        self.key_history()
+            .into_iter()
+            .filter(|(window, _)| *window == wnd)
+            .map(|(_, key)| key)
+            .collect()
+    }
+
+    /// Get the indexes of the windows closed so far
+    pub fn closed_windows(&self) -> Vec<i32> {
+        self.window_events()
+            .iter()
+            .filter_map(|event| match event {
+                MockWindowEvent::Close { window } => Some(*window),
+                MockWindowEvent::VKey { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Inject a fault (COM error, missing component, latency, popup or status message)
+    ///
+    /// Faults on `send_v_key`, `close_window` and `close_all_windows` are injected
+    /// through the window component (operations "send_v_key" and "close").
+    pub fn inject_fault(&self, rule: MockFaultRule) {
+        self.state.borrow_mut().faults.add(rule);
+    }
+
+    /// Get the injected faults, with how often each one fired
+    pub fn fault_rules(&self) -> Vec<MockFaultRule> {
+        self.state.borrow().faults.rules().to_vec()
+    }
+
+    pub fn clear_faults(&self) {
+        self.state.borrow_mut().faults.clear();
+    }
+
+    /// Run a session operation, applying the faults injected for it
+    fn faulted<T>(&self, op: &str, id: Option<&str>, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let effects = self.state.borrow_mut().faults.fire(op, id)?;
+        let result = f()?;
+        self.state.borrow_mut().apply_effects(effects);
+        Ok(result)
+    }
+
+    /// Report the session as busy for the next `polls` calls to `busy()`
+    pub fn set_busy_for(&self, polls: u32) {
+        self.state.borrow_mut().busy_polls = polls;
+    }
+
+    /// Forget all recorded window operations
+    pub fn clear_window_events(&self) {
+        self.state.borrow_mut().events.clear();
     }
 }
 
 impl SapSession for MockSapSession {
     fn find_by_id(&self, id: String) -> Result<Box<dyn SapComponent>> {
-        if let Some(component) = self.components.get(&id) {
-            return Ok(Box::new(MockSapComponent::new(component.clone())));
-        }
+        self.faulted("find_by_id", Some(&id), || {
+            if let Some(component) = self.component(&id) {
+                return Ok(
+                    Box::new(MockSapComponent::attached(component, self.state.clone()))
+                        as Box<dyn SapComponent>,
+                );
+            }
 
-        // If not found, return an error
-        Err(Error::new(
-            HRESULT(-2147467259),
-            "Component not found".into(),
-        ))
+            // If not found, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component not found".into(),
+            ))
+        })
     }
 
     fn info(&self) -> Result<Box<dyn SapSessionInfo>> {
-        Ok(Box::new(MockSapSessionInfo::new(&self.current_transaction)))
+        self.faulted("info", None, || {
+            let state = self.state.borrow();
+            let info = MockSapSessionInfo {
+                transaction: state.current_transaction.clone(),
+                ..state.info.clone()
+            };
+            Ok(Box::new(info) as Box<dyn SapSessionInfo>)
+        })
     }
 
-    fn start_transaction(&self, _transaction: String) -> Result<()> {
-        // In a real implementation, this would update self.current_transaction
-        // But since self is not mutable here, we'll just return Ok
-        Ok(())
+    fn start_transaction(&self, transaction: String) -> Result<()> {
+        self.faulted("start_transaction", None, || {
+            let mut state = self.state.borrow_mut();
+            state.current_transaction = transaction.clone();
+            state.perform(MockAction::StartTransaction { tcode: transaction });
+            Ok(())
+        })
     }
 
     fn end_transaction(&self) -> Result<()> {
-        // In a real implementation, this would reset self.current_transaction to "S000"
-        // But since self is not mutable here, we'll just return Ok
+        self.faulted("end_transaction", None, || {
+            // Like SAP, ending a transaction returns to the session manager
+            let mut state = self.state.borrow_mut();
+            state.current_transaction = "SESSION_MANAGER".to_string();
+            state.perform(MockAction::EndTransaction);
+            Ok(())
+        })
+    }
+
+    fn send_v_key(&self, wnd: i32, key: i32) -> Result<()> {
+        self.find_by_id(format!("wnd[{}]", wnd))?.send_v_key(key)
+    }
+
+    fn close_window(&self, wnd: i32) -> Result<()> {
+        self.find_by_id(format!("wnd[{}]", wnd))?.close()
+    }
+
+    fn close_all_windows(&self) -> Result<()> {
+        for i in (1..=MAX_MODAL_WINDOWS).rev() {
+            if let Ok(window) = self.find_by_id(format!("wnd[{}]", i)) {
+                window.close()?;
+            }
+        }
         Ok(())
     }
+
+    fn busy(&self) -> Result<bool> {
+        self.faulted("busy", None, || {
+            let mut state = self.state.borrow_mut();
+            let busy = state.busy_polls > 0;
+            state.busy_polls = state.busy_polls.saturating_sub(1);
+            Ok(busy)
+        })
+    }
 }
 
 /// Factory for creating mock SAP components
@@ -212,6 +1051,7 @@ pub fn create_test_session() -> Box<dyn SapSession> {
         text: "Test Text".to_string(),
         children: Vec::new(),
         properties: Default::default(),
+        grid: None,
     }));
     session.add_component("wnd[0]/usr/txtField", text_field);
 
@@ -223,6 +1063,7 @@ pub fn create_test_session() -> Box<dyn SapSession> {
         text: "Press Me".to_string(),
         children: Vec::new(),
         properties: Default::default(),
+        grid: None,
     }));
     session.add_component("wnd[0]/tbar[0]/btn[0]", button);
 
@@ -236,6 +1077,7 @@ pub fn create_test_session() -> Box<dyn SapSession> {
         text: "Check Me".to_string(),
         children: Vec::new(),
         properties,
+        grid: None,
     }));
     session.add_component("wnd[0]/usr/chkBox", checkbox);
 
@@ -247,6 +1089,7 @@ pub fn create_test_session() -> Box<dyn SapSession> {
         text: "Status: OK".to_string(),
         children: Vec::new(),
         properties: Default::default(),
+        grid: None,
     }));
     session.add_component("wnd[0]/sbar", statusbar);
 
@@ -258,6 +1101,7 @@ pub fn create_test_session() -> Box<dyn SapSession> {
         text: "Popup Window".to_string(),
         children: Vec::new(),
         properties: Default::default(),
+        grid: None,
     }));
     session.add_component("wnd[1]", window);
 
@@ -0,0 +1,93 @@
+use windows::core::{Error, Result, HRESULT};
+
+use crate::utils::config_types::SapConfig;
+use crate::utils::sap_constants::E_PASSWORD_CHANGE_REQUIRED;
+use crate::utils::sap_control_id::ControlId;
+use crate::utils::sap_interfaces::SapSession;
+use crate::utils::sap_screens::active_window;
+use crate::utils::sap_session_state::{SessionState, NEW_PASSWORD_FIELD};
+use crate::utils::sap_status::read_status;
+use crate::utils::sap_wait::{wait_timeouts, wait_until, WaitCondition};
+
+/// Field for repeating the new password on the "change password" dialog
+pub const REPEAT_PASSWORD_FIELD: &str = "/usr/pwdRSYST-NCOD2";
+
+/// Check whether `unattended = true` is set in `[global]`
+///
+/// Unattended runs fail with [`E_PASSWORD_CHANGE_REQUIRED`] instead of asking for a
+/// new password.
+pub fn is_unattended(config: &SapConfig) -> bool {
+    config
+        .global
+        .as_ref()
+        .and_then(|g| g.run.unattended)
+        .unwrap_or(false)
+}
+
+/// Error returned when SAP asks for a new password and none can be entered
+pub fn password_change_required(user: &str) -> Error {
+    Error::new(
+        HRESULT(E_PASSWORD_CHANGE_REQUIRED),
+        format!(
+            "The password of {} has expired, log in once interactively to change it",
+            user
+        )
+        .into(),
+    )
+}
+
+/// Check whether an error means the password has to be changed by a person
+pub fn is_password_change_required(error: &Error) -> bool {
+    error.code().0 == E_PASSWORD_CHANGE_REQUIRED
+}
+
+/// Check a new password before it is sent to SAP
+pub fn check_new_password(old_password: &str, new_password: &str, repeated: &str) -> Result<()> {
+    let reason = if new_password.is_empty() {
+        "The new password is empty"
+    } else if new_password != repeated {
+        "The passwords do not match"
+    } else if new_password == old_password {
+        "The new password must differ from the old one"
+    } else {
+        return Ok(());
+    };
+    Err(Error::new(HRESULT(-2147467259), reason.into()))
+}
+
+/// Fill the "change password" dialog shown during logon and confirm it
+///
+/// Fails with the status bar text if SAP does not accept the password (e.g. because
+/// it was used before), leaving the dialog open.
+pub fn change_expired_password(session: &dyn SapSession, new_password: &str) -> Result<()> {
+    let wnd = active_window(session)?.unwrap_or(0);
+    let window = ControlId::window(wnd);
+    for field in [NEW_PASSWORD_FIELD, REPEAT_PASSWORD_FIELD] {
+        session
+            .find_control(&window.join(field)?)?
+            .set_text(new_password.to_string())?;
+    }
+    session.send_v_key(wnd, 0)?;
+
+    let timeouts = wait_timeouts();
+    wait_until(
+        session,
+        &WaitCondition::NotBusy,
+        timeouts.login,
+        timeouts.poll,
+    )?;
+
+    if SessionState::detect(session)? != SessionState::PasswordChange {
+        return Ok(());
+    }
+    let reason = match read_status(session, wnd)? {
+        Some(message) => Some(message),
+        None => read_status(session, 0)?,
+    }
+    .map(|message| message.text)
+    .unwrap_or_else(|| "no reason given".to_string());
+    Err(Error::new(
+        HRESULT(-2147467259),
+        format!("SAP did not accept the new password: {}", reason).into(),
+    ))
+}
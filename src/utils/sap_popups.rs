@@ -0,0 +1,608 @@
+use anyhow::{anyhow, Context};
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::OnceLock;
+use windows::core::{Error, Result, HRESULT};
+
+use crate::utils::config_types::SapConfig;
+use crate::utils::sap_constants::{CtrlCheck, MAX_MODAL_WINDOWS};
+use crate::utils::sap_control_id::ControlId;
+use crate::utils::sap_ctrl_utils::get_sap_text_errors;
+use crate::utils::sap_interfaces::{exist_ctrl, SapComponent, SapSession, SapSessionInfo};
+use crate::utils::sap_screens::{active_window, screen_by_name, SapScreen, ScreenId};
+use crate::utils::sap_status::StatusMessage;
+use crate::utils::sap_wait::{wait_timeouts, wait_until, WaitCondition};
+
+/// Name of the popup rules file, looked up next to config.toml
+pub const POPUP_RULES_FILE: &str = "popups.toml";
+
+/// Built-in popup rules, checked after the ones from popups.toml
+pub const DEFAULT_POPUP_RULES: &str = r#"
+# SAPLSPO1/0100 is SAP's generic yes/no popup, only the title tells the log off one apart
+[[rule]]
+name = "log_off"
+window_type = "GuiModalWindow"
+title = '(?i)^\s*(log ?off|abmelden)\s*$'
+screen = "log_off"
+action = { press = "/usr/btnSPOP-OPTION2" }
+
+[[rule]]
+name = "system_messages"
+window_type = "GuiModalWindow"
+screen = "system_messages"
+action = { press = "/tbar[0]/btn[0]" }
+
+[[rule]]
+name = "multiple_selection"
+window_type = "GuiModalWindow"
+title = "(?i)multiple selection"
+control = "/usr/btnSPOP-OPTION2"
+action = { press = "/usr/btnSPOP-OPTION2" }
+"#;
+
+/// Number of text lines captured from a popup before aborting (txtMESSTXT1 to 4)
+const ABORT_TEXT_LINES: i32 = 4;
+
+/// What to do with a popup a rule applies to
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PopupAction {
+    /// Press a button, ID relative to the window (e.g. "/usr/btnSPOP-OPTION1")
+    Press(String),
+    /// Send a virtual key to the window (e.g. 0 for Enter, 12 for Cancel)
+    #[serde(rename = "vkey")]
+    VKey(i32),
+    /// Capture the popup text (numbered text fields, e.g. "/usr/txtMESSTXT") and abort
+    Abort(String),
+    /// Log the popup and leave it open
+    Log,
+}
+
+/// A popup rule: the conditions a window must meet (all that are set) and the action
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PopupRule {
+    pub name: String,
+    /// Window type, e.g. "GuiModalWindow"
+    #[serde(default)]
+    pub window_type: Option<String>,
+    /// Regular expression the window title must match
+    #[serde(default, deserialize_with = "deserialize_title")]
+    pub title: Option<Regex>,
+    /// Known screen (see `SCREENS`), identified by program and dynpro or by title
+    #[serde(default, deserialize_with = "deserialize_screen")]
+    pub screen: Option<&'static SapScreen>,
+    /// Program of the window (only known for the active window)
+    #[serde(default)]
+    pub program: Option<String>,
+    /// Dynpro number of the window (only known for the active window)
+    #[serde(default)]
+    pub screen_number: Option<i32>,
+    /// Control that must exist in the window, ID relative to the window
+    #[serde(default)]
+    pub control: Option<String>,
+    pub action: PopupAction,
+}
+
+fn deserialize_title<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Regex>, D::Error> {
+    let Some(pattern) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    Regex::new(&pattern)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn deserialize_screen<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<&'static SapScreen>, D::Error> {
+    let Some(name) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    screen_by_name(&name)
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom(format!("unknown screen: {}", name)))
+}
+
+impl PopupRule {
+    /// Check whether window wnd[n] (as returned by `exist_ctrl`) meets the conditions
+    pub fn matches(&self, session: &dyn SapSession, wnd: i32, window: &CtrlCheck) -> Result<bool> {
+        if let Some(window_type) = &self.window_type {
+            if !window_type.eq_ignore_ascii_case(&window.ctype) {
+                return Ok(false);
+            }
+        }
+        if let Some(title) = &self.title {
+            if !title.is_match(&window.ctext) {
+                return Ok(false);
+            }
+        }
+        if let Some(screen) = self.screen {
+            if !screen.matches(session, wnd, &window.ctext)? {
+                return Ok(false);
+            }
+        }
+        if self.program.is_some() || self.screen_number.is_some() {
+            if active_window(session)? != Some(wnd) {
+                return Ok(false);
+            }
+            let current = ScreenId::current(session)?;
+            if let Some(program) = &self.program {
+                if !program.eq_ignore_ascii_case(&current.program) {
+                    return Ok(false);
+                }
+            }
+            if let Some(screen_number) = self.screen_number {
+                if screen_number != current.screen_number {
+                    return Ok(false);
+                }
+            }
+        }
+        if let Some(control) = &self.control {
+            if !exist_ctrl(session, wnd, control, false)?.cband {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Apply the action to window wnd[n]
+    ///
+    /// Abort actions return an error with the popup text.
+    pub fn apply(&self, session: &dyn SapSession, wnd: i32, window: &CtrlCheck) -> Result<()> {
+        match &self.action {
+            PopupAction::Press(button) => {
+                println!("Popup '{}': pressing {}", self.name, button);
+                match session.find_control(&ControlId::window(wnd).join(button)?) {
+                    Ok(component) => component.press(),
+                    Err(_) => {
+                        println!("Button not found: wnd[{}]{}", wnd, button);
+                        Ok(())
+                    }
+                }
+            }
+            PopupAction::VKey(key) => {
+                println!("Popup '{}': sending key {}", self.name, key);
+                session.send_v_key(wnd, *key)
+            }
+            PopupAction::Abort(text_id) => {
+                let text = get_sap_text_errors(session, wnd, text_id, ABORT_TEXT_LINES, None)?;
+                let text = if text.is_empty() {
+                    &window.ctext
+                } else {
+                    &text
+                };
+                println!("Popup '{}': aborting ({})", self.name, text);
+                Err(Error::new(
+                    HRESULT(-2147467259),
+                    format!("Popup '{}': {}", self.name, text).as_str().into(),
+                ))
+            }
+            PopupAction::Log => {
+                println!("Popup '{}' on wnd[{}]: {}", self.name, wnd, window.ctext);
+                Ok(())
+            }
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.window_type.is_none()
+            && self.title.is_none()
+            && self.screen.is_none()
+            && self.program.is_none()
+            && self.screen_number.is_none()
+            && self.control.is_none()
+        {
+            return Err(anyhow!("Popup rule '{}' has no conditions", self.name));
+        }
+        let window = ControlId::window(0);
+        for id in [self.control.as_ref(), self.action_control()]
+            .into_iter()
+            .flatten()
+        {
+            window
+                .join(id)
+                .map_err(|e| anyhow!("Popup rule '{}': {}", self.name, e))?;
+        }
+        Ok(())
+    }
+
+    fn action_control(&self) -> Option<&String> {
+        match &self.action {
+            PopupAction::Press(id) | PopupAction::Abort(id) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PopupRulesFile {
+    /// Use only the rules of the file, without the built-in ones
+    #[serde(default)]
+    replace_defaults: bool,
+    #[serde(default)]
+    rule: Vec<PopupRule>,
+}
+
+/// Ordered list of popup rules, the first rule that applies to a window is used
+///
+/// Rules from popups.toml are checked before the built-in ones, windows no rule
+/// applies to are left alone.
+#[derive(Debug, Clone)]
+pub struct PopupRules {
+    rules: Vec<PopupRule>,
+}
+
+impl Default for PopupRules {
+    fn default() -> Self {
+        Self {
+            rules: parse_rules(DEFAULT_POPUP_RULES)
+                .expect("built-in popup rules are valid")
+                .rule,
+        }
+    }
+}
+
+fn parse_rules(content: &str) -> anyhow::Result<PopupRulesFile> {
+    let file: PopupRulesFile = toml::from_str(content)?;
+    for rule in &file.rule {
+        rule.validate()?;
+    }
+    Ok(file)
+}
+
+impl PopupRules {
+    /// Create the built-in rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the rules file next to the configuration file (built-in rules if there is none)
+    pub fn load(config: &SapConfig) -> anyhow::Result<Self> {
+        Self::load_from_path(&Self::path_for(config))
+    }
+
+    /// Load a rules file
+    pub fn load_from_path(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read popup rules {}", path.display()))?;
+        Self::from_toml_str(&content)
+            .with_context(|| format!("Invalid popup rules {}", path.display()))
+    }
+
+    /// Parse rules from TOML, followed by the built-in rules unless `replace_defaults` is set
+    pub fn from_toml_str(content: &str) -> anyhow::Result<Self> {
+        let file = parse_rules(content)?;
+        let mut rules = file.rule;
+        if !file.replace_defaults {
+            rules.extend(Self::default().rules);
+        }
+        Ok(Self { rules })
+    }
+
+    /// Get the rules file path for a configuration (popups.toml next to config.toml)
+    pub fn path_for(config: &SapConfig) -> PathBuf {
+        Path::new(&config.config_path).with_file_name(POPUP_RULES_FILE)
+    }
+
+    pub fn rules(&self) -> &[PopupRule] {
+        &self.rules
+    }
+
+    /// Find the first rule that applies to window wnd[n] (None if it is not open)
+    pub fn find(
+        &self,
+        session: &dyn SapSession,
+        wnd: i32,
+    ) -> Result<Option<(&PopupRule, CtrlCheck)>> {
+        let window = exist_ctrl(session, wnd, "", true)?;
+        if !window.cband {
+            return Ok(None);
+        }
+        for rule in &self.rules {
+            if rule.matches(session, wnd, &window)? {
+                return Ok(Some((rule, window)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Apply the first rule that applies to window wnd[n], returns the rule
+    pub fn handle_window(&self, session: &dyn SapSession, wnd: i32) -> Result<Option<&PopupRule>> {
+        let Some((rule, window)) = self.find(session, wnd)? else {
+            return Ok(None);
+        };
+        rule.apply(session, wnd, &window)?;
+        Ok(Some(rule))
+    }
+
+    /// Apply the first rule that applies to window wnd[n] and wait for the window to close
+    ///
+    /// Returns false if no rule applies, the rule only logs or the window stays open.
+    pub fn close_window(&self, session: &dyn SapSession, wnd: i32) -> Result<bool> {
+        let Some(rule) = self.handle_window(session, wnd)? else {
+            return Ok(false);
+        };
+        if rule.action == PopupAction::Log {
+            return Ok(false);
+        }
+
+        let timeouts = wait_timeouts();
+        let closed = WaitCondition::WindowClosed(wnd);
+        if !wait_until(session, &closed, timeouts.popup, timeouts.poll)? {
+            println!("Popup wnd[{}] still open after rule '{}'", wnd, rule.name);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Close open popups, top-most first, until none is left that a rule closes
+    ///
+    /// Returns the number of popups closed.
+    pub fn handle_popups(&self, session: &dyn SapSession) -> Result<usize> {
+        let mut closed = 0;
+        for _ in 0..MAX_MODAL_WINDOWS * 2 {
+            let Some(wnd) = top_popup(session)? else {
+                break;
+            };
+            if !self.close_window(session, wnd)? {
+                break;
+            }
+            closed += 1;
+        }
+        Ok(closed)
+    }
+}
+
+/// Get the index of the top-most popup (None if only the main window is open)
+fn top_popup(session: &dyn SapSession) -> Result<Option<i32>> {
+    let mut top = None;
+    for i in 1..=MAX_MODAL_WINDOWS {
+        if !exist_ctrl(session, i, "", false)?.cband {
+            break;
+        }
+        top = Some(i);
+    }
+    Ok(top)
+}
+
+static RULES: OnceLock<PopupRules> = OnceLock::new();
+
+/// Get the rules loaded alongside config.toml (built-in rules if they cannot be loaded)
+pub fn popup_rules() -> &'static PopupRules {
+    RULES.get_or_init(|| {
+        let config = SapConfig::load().unwrap_or_default();
+        PopupRules::load(&config).unwrap_or_else(|e| {
+            eprintln!("Error loading popup rules, using defaults: {:#}", e);
+            PopupRules::default()
+        })
+    })
+}
+
+/// Session decorator that applies popup rules after every action
+///
+/// Session-level actions handle popups right away. Component actions (press,
+/// select, keys, ...) mark the session, and popups are handled before its next
+/// interaction, so that the flow never sees a popup a rule applies to.
+pub struct PopupGuardSapSession<'a, S: SapSession> {
+    inner: S,
+    rules: &'a PopupRules,
+    pending: Rc<Cell<bool>>,
+}
+
+impl<'a, S: SapSession> PopupGuardSapSession<'a, S> {
+    pub fn new(inner: S, rules: &'a PopupRules) -> Self {
+        Self {
+            inner,
+            rules,
+            pending: Rc::new(Cell::new(false)),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Handle popups if an action was performed since they were last handled
+    pub fn handle_pending(&self) -> Result<()> {
+        if self.pending.get() {
+            self.rules.handle_popups(&self.inner)?;
+            self.pending.set(false);
+        }
+        Ok(())
+    }
+
+    fn after_action(&self, result: Result<()>) -> Result<()> {
+        result?;
+        self.pending.set(true);
+        self.handle_pending()
+    }
+}
+
+impl<S: SapSession> SapSession for PopupGuardSapSession<'_, S> {
+    fn find_by_id(&self, id: String) -> Result<Box<dyn SapComponent>> {
+        self.handle_pending()?;
+        Ok(Box::new(PopupGuardComponent {
+            inner: self.inner.find_by_id(id)?,
+            pending: self.pending.clone(),
+        }))
+    }
+
+    fn info(&self) -> Result<Box<dyn SapSessionInfo>> {
+        self.handle_pending()?;
+        self.inner.info()
+    }
+
+    fn start_transaction(&self, transaction: String) -> Result<()> {
+        self.after_action(self.inner.start_transaction(transaction))
+    }
+
+    fn end_transaction(&self) -> Result<()> {
+        self.after_action(self.inner.end_transaction())
+    }
+
+    fn send_v_key(&self, wnd: i32, key: i32) -> Result<()> {
+        self.after_action(self.inner.send_v_key(wnd, key))
+    }
+
+    fn close_window(&self, wnd: i32) -> Result<()> {
+        self.inner.close_window(wnd)
+    }
+
+    fn close_all_windows(&self) -> Result<()> {
+        self.inner.close_all_windows()
+    }
+
+    fn busy(&self) -> Result<bool> {
+        self.inner.busy()
+    }
+}
+
+/// Component handed out by a `PopupGuardSapSession`
+struct PopupGuardComponent {
+    inner: Box<dyn SapComponent>,
+    pending: Rc<Cell<bool>>,
+}
+
+impl PopupGuardComponent {
+    /// Run an action that may open a popup
+    fn action(&self, result: Result<()>) -> Result<()> {
+        if result.is_ok() {
+            self.pending.set(true);
+        }
+        result
+    }
+}
+
+impl SapComponent for PopupGuardComponent {
+    fn r_type(&self) -> Result<String> {
+        self.inner.r_type()
+    }
+
+    fn name(&self) -> Result<String> {
+        self.inner.name()
+    }
+
+    fn get_text(&self) -> Result<String> {
+        self.inner.get_text()
+    }
+
+    fn set_text(&self, text: String) -> Result<()> {
+        self.inner.set_text(text)
+    }
+
+    fn set_focus(&self) -> Result<()> {
+        self.inner.set_focus()
+    }
+
+    fn changeable(&self) -> Result<bool> {
+        self.inner.changeable()
+    }
+
+    fn press(&self) -> Result<()> {
+        self.action(self.inner.press())
+    }
+
+    fn select(&self) -> Result<()> {
+        self.action(self.inner.select())
+    }
+
+    fn selected(&self) -> Result<bool> {
+        self.inner.selected()
+    }
+
+    fn set_selected(&self, selected: bool) -> Result<()> {
+        self.inner.set_selected(selected)
+    }
+
+    fn maximize(&self) -> Result<()> {
+        self.inner.maximize()
+    }
+
+    fn send_v_key(&self, key: i32) -> Result<()> {
+        self.action(self.inner.send_v_key(key))
+    }
+
+    fn close(&self) -> Result<()> {
+        self.inner.close()
+    }
+
+    fn set_selected_rows(&self, rows: String) -> Result<()> {
+        self.inner.set_selected_rows(rows)
+    }
+
+    fn set_current_cell_row(&self, row: i32) -> Result<()> {
+        self.inner.set_current_cell_row(row)
+    }
+
+    fn context_menu(&self) -> Result<()> {
+        self.inner.context_menu()
+    }
+
+    fn select_context_menu_item(&self, function_code: String) -> Result<()> {
+        self.action(self.inner.select_context_menu_item(function_code))
+    }
+
+    fn set_current_cell(&self, row: i32, column: String) -> Result<()> {
+        self.inner.set_current_cell(row, column)
+    }
+
+    fn current_cell_row(&self) -> Result<i32> {
+        self.inner.current_cell_row()
+    }
+
+    fn double_click_current_cell(&self) -> Result<()> {
+        self.action(self.inner.double_click_current_cell())
+    }
+
+    fn row_count(&self) -> Result<i32> {
+        self.inner.row_count()
+    }
+
+    fn visible_row_count(&self) -> Result<i32> {
+        self.inner.visible_row_count()
+    }
+
+    fn first_visible_row(&self) -> Result<i32> {
+        self.inner.first_visible_row()
+    }
+
+    fn set_first_visible_row(&self, row: i32) -> Result<()> {
+        self.inner.set_first_visible_row(row)
+    }
+
+    fn get_cell_value(&self, row: i32, column: String) -> Result<String> {
+        self.inner.get_cell_value(row, column)
+    }
+
+    fn select_column(&self, column: String) -> Result<()> {
+        self.inner.select_column(column)
+    }
+
+    fn press_column_header(&self, column: String) -> Result<()> {
+        self.action(self.inner.press_column_header(column))
+    }
+
+    fn status_message(&self) -> Result<StatusMessage> {
+        self.inner.status_message()
+    }
+}
+
+/// Run a flow against the session with the popup rules from popups.toml applied
+pub fn run_with_popup_rules<T>(
+    session: &dyn SapSession,
+    f: impl FnOnce(&dyn SapSession) -> T,
+) -> T {
+    f(&PopupGuardSapSession::new(session, popup_rules()))
+}
@@ -1,5 +1,10 @@
-use crate::utils::sap_interfaces::{SapComponent, SapComponentFactory, SapSession, SapSessionInfo};
+use crate::utils::sap_constants::MAX_MODAL_WINDOWS;
+use crate::utils::sap_interfaces::{
+    parse_row_selection, SapComponent, SapComponentFactory, SapSession, SapSessionInfo,
+};
+use crate::utils::sap_status::{MessageKind, StatusMessage};
 use sap_scripting::*;
+use std::borrow::Borrow;
 use windows::core::{Error, Result, HRESULT};
 
 /// Implementation of SapComponent for real SAP GUI components
@@ -26,12 +31,16 @@ impl SapComponent for RealSapComponent {
         // Try to get text based on component type
         if let Some(text_field) = self.component.downcast::<GuiTextField>() {
             text_field.text()
+        } else if let Some(text_field) = self.component.downcast::<GuiCTextField>() {
+            text_field.text()
         } else if let Some(button) = self.component.downcast::<GuiButton>() {
             button.text()
         } else if let Some(label) = self.component.downcast::<GuiLabel>() {
             label.text()
         } else if let Some(statusbar) = self.component.downcast::<GuiStatusbar>() {
             statusbar.text()
+        } else if let Some(window) = self.component.downcast::<GuiMainWindow>() {
+            window.text()
         } else if let Some(window) = self.component.downcast::<GuiFrameWindow>() {
             window.text()
         } else if let Some(modal_window) = self.component.downcast::<GuiModalWindow>() {
@@ -45,8 +54,12 @@ impl SapComponent for RealSapComponent {
     fn set_text(&self, text: String) -> Result<()> {
         if let Some(text_field) = self.component.downcast::<GuiTextField>() {
             text_field.set_text(text)
+        } else if let Some(text_field) = self.component.downcast::<GuiCTextField>() {
+            text_field.set_text(text)
         } else if let Some(password_field) = self.component.downcast::<GuiPasswordField>() {
             password_field.set_text(text)
+        } else if let Some(ok_code_field) = self.component.downcast::<GuiOkCodeField>() {
+            ok_code_field.set_text(text)
         } else {
             // If the component doesn't support setting text, return an error
             Err(Error::new(
@@ -59,10 +72,16 @@ impl SapComponent for RealSapComponent {
     fn set_focus(&self) -> Result<()> {
         if let Some(field) = self.component.downcast::<GuiTextField>() {
             field.set_focus()
+        } else if let Some(field) = self.component.downcast::<GuiCTextField>() {
+            field.set_focus()
         } else if let Some(button) = self.component.downcast::<GuiButton>() {
             button.set_focus()
         } else if let Some(radio) = self.component.downcast::<GuiRadioButton>() {
             radio.set_focus()
+        } else if let Some(label) = self.component.downcast::<GuiLabel>() {
+            label.set_focus()
+        } else if let Some(window) = self.component.downcast::<GuiMainWindow>() {
+            window.set_focus()
         } else {
             // If the component doesn't support setting focus, return an error
             Err(Error::new(
@@ -72,6 +91,21 @@ impl SapComponent for RealSapComponent {
         }
     }
 
+    fn changeable(&self) -> Result<bool> {
+        if let Some(field) = self.component.downcast::<GuiTextField>() {
+            field.changeable()
+        } else if let Some(field) = self.component.downcast::<GuiCTextField>() {
+            field.changeable()
+        } else if let Some(field) = self.component.downcast::<GuiPasswordField>() {
+            field.changeable()
+        } else {
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a text field".into(),
+            ))
+        }
+    }
+
     fn press(&self) -> Result<()> {
         if let Some(button) = self.component.downcast::<GuiButton>() {
             button.press()
@@ -87,11 +121,15 @@ impl SapComponent for RealSapComponent {
     fn select(&self) -> Result<()> {
         if let Some(radio_button) = self.component.downcast::<GuiRadioButton>() {
             radio_button.select()
+        } else if let Some(menu) = self.component.downcast::<GuiMenu>() {
+            menu.select()
+        } else if let Some(tab) = self.component.downcast::<GuiTab>() {
+            tab.select()
         } else {
-            // If the component is not a radio button, return an error
+            // If the component is not selectable, return an error
             Err(Error::new(
                 HRESULT(-2147467259),
-                "Component is not a radio button".into(),
+                "Component is not a radio button, menu or tab".into(),
             ))
         }
     }
@@ -131,6 +169,255 @@ impl SapComponent for RealSapComponent {
             ))
         }
     }
+
+    fn send_v_key(&self, key: i32) -> Result<()> {
+        if let Some(window) = self.component.downcast::<GuiMainWindow>() {
+            window.send_v_key(key)
+        } else if let Some(modal_window) = self.component.downcast::<GuiModalWindow>() {
+            modal_window.send_v_key(key)
+        } else if let Some(window) = self.component.downcast::<GuiFrameWindow>() {
+            window.send_v_key(key)
+        } else {
+            // If the component is not a window, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a window".into(),
+            ))
+        }
+    }
+
+    fn close(&self) -> Result<()> {
+        if let Some(modal_window) = self.component.downcast::<GuiModalWindow>() {
+            modal_window.close()
+        } else if let Some(window) = self.component.downcast::<GuiFrameWindow>() {
+            window.close()
+        } else {
+            // If the component is not a window, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a window".into(),
+            ))
+        }
+    }
+
+    fn set_selected_rows(&self, rows: String) -> Result<()> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.set_selected_rows(rows)
+        } else if let Some(table) = self.component.downcast::<GuiTableControl>() {
+            for row in parse_row_selection(&rows) {
+                table.get_absolute_row(row)?.set_selected(true)?;
+            }
+            Ok(())
+        } else {
+            // If the component is not a grid view or table control, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view or table control".into(),
+            ))
+        }
+    }
+
+    fn set_current_cell_row(&self, row: i32) -> Result<()> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.set_current_cell_row(row)
+        } else {
+            // If the component is not a grid view, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view".into(),
+            ))
+        }
+    }
+
+    fn context_menu(&self) -> Result<()> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.context_menu()
+        } else {
+            // If the component is not a grid view, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view".into(),
+            ))
+        }
+    }
+
+    fn select_context_menu_item(&self, function_code: String) -> Result<()> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.select_context_menu_item(function_code)
+        } else {
+            // If the component is not a grid view, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view".into(),
+            ))
+        }
+    }
+
+    fn set_current_cell(&self, row: i32, column: String) -> Result<()> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.set_current_cell(row, column)
+        } else {
+            // If the component is not a grid view, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view".into(),
+            ))
+        }
+    }
+
+    fn current_cell_row(&self) -> Result<i32> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.current_cell_row()
+        } else if let Some(table) = self.component.downcast::<GuiTableControl>() {
+            table.current_row()
+        } else {
+            // If the component is not a grid view or table control, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view or table control".into(),
+            ))
+        }
+    }
+
+    fn double_click_current_cell(&self) -> Result<()> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.double_click_current_cell()
+        } else {
+            // If the component is not a grid view, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view".into(),
+            ))
+        }
+    }
+
+    fn row_count(&self) -> Result<i32> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.row_count()
+        } else if let Some(table) = self.component.downcast::<GuiTableControl>() {
+            table.row_count()
+        } else {
+            // If the component is not a grid view or table control, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view or table control".into(),
+            ))
+        }
+    }
+
+    fn visible_row_count(&self) -> Result<i32> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.visible_row_count()
+        } else if let Some(table) = self.component.downcast::<GuiTableControl>() {
+            table.visible_row_count()
+        } else {
+            // If the component is not a grid view or table control, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view or table control".into(),
+            ))
+        }
+    }
+
+    fn first_visible_row(&self) -> Result<i32> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.first_visible_row()
+        } else if let Some(table) = self.component.downcast::<GuiTableControl>() {
+            table.vertical_scrollbar()?.position()
+        } else {
+            // If the component is not a grid view or table control, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view or table control".into(),
+            ))
+        }
+    }
+
+    fn set_first_visible_row(&self, row: i32) -> Result<()> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.set_first_visible_row(row)
+        } else if let Some(table) = self.component.downcast::<GuiTableControl>() {
+            table.vertical_scrollbar()?.set_position(row)
+        } else {
+            // If the component is not a grid view or table control, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view or table control".into(),
+            ))
+        }
+    }
+
+    fn get_cell_value(&self, row: i32, column: String) -> Result<String> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.get_cell_value(row, column)
+        } else if let Some(table) = self.component.downcast::<GuiTableControl>() {
+            // Table controls address cells by column index
+            let column = column.parse::<i32>().map_err(|_| {
+                Error::new(
+                    HRESULT(-2147467259),
+                    "Table control columns must be addressed by index".into(),
+                )
+            })?;
+            RealSapComponent::new(table.get_cell(row, column)?).get_text()
+        } else {
+            // If the component is not a grid view or table control, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view or table control".into(),
+            ))
+        }
+    }
+
+    fn select_column(&self, column: String) -> Result<()> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.select_column(column)
+        } else {
+            // If the component is not a grid view, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view".into(),
+            ))
+        }
+    }
+
+    fn press_column_header(&self, column: String) -> Result<()> {
+        if let Some(grid) = self.component.downcast::<GuiGridView>() {
+            grid.press_column_header(column)
+        } else {
+            // If the component is not a grid view, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a grid view".into(),
+            ))
+        }
+    }
+
+    fn status_message(&self) -> Result<StatusMessage> {
+        if let Some(statusbar) = self.component.downcast::<GuiStatusbar>() {
+            // Messages have up to four placeholders, unused ones are empty
+            let params = (0..4)
+                .map_while(|i| statusbar.message_parameter(i).ok())
+                .collect::<Vec<_>>();
+            let last_param = params
+                .iter()
+                .rposition(|p| !p.is_empty())
+                .map_or(0, |i| i + 1);
+
+            Ok(StatusMessage {
+                kind: MessageKind::from_code(&statusbar.message_type()?),
+                message_class: statusbar.message_id()?.trim().to_string(),
+                message_number: statusbar.message_number()?.trim().to_string(),
+                params: params[..last_param].to_vec(),
+                text: statusbar.text()?,
+            })
+        } else {
+            // If the component is not a status bar, return an error
+            Err(Error::new(
+                HRESULT(-2147467259),
+                "Component is not a status bar".into(),
+            ))
+        }
+    }
 }
 
 /// Implementation of SapSessionInfo for real SAP GUI session info
@@ -148,36 +435,90 @@ impl SapSessionInfo for RealSapSessionInfo {
     fn transaction(&self) -> Result<String> {
         self.info.transaction()
     }
+
+    fn program(&self) -> Result<String> {
+        self.info.program()
+    }
+
+    fn screen_number(&self) -> Result<i32> {
+        self.info.screen_number()
+    }
+
+    fn system_name(&self) -> Result<String> {
+        self.info.system_name()
+    }
+
+    fn client(&self) -> Result<String> {
+        self.info.client()
+    }
+
+    fn user(&self) -> Result<String> {
+        self.info.user()
+    }
+
+    fn language(&self) -> Result<String> {
+        self.info.language()
+    }
 }
 
 /// Implementation of SapSession for real SAP GUI session
-pub struct RealSapSession {
-    session: GuiSession,
+///
+/// The session can be owned (`RealSapSession<GuiSession>`) or borrowed
+/// (`RealSapSession<&GuiSession>`), so code that still holds a `&GuiSession`
+/// can hand it to functions that take a `&dyn SapSession`.
+pub struct RealSapSession<S: Borrow<GuiSession> = GuiSession> {
+    session: S,
 }
 
-impl RealSapSession {
-    pub fn new(session: GuiSession) -> Self {
+impl<S: Borrow<GuiSession>> RealSapSession<S> {
+    pub fn new(session: S) -> Self {
         Self { session }
     }
+
+    /// Get the underlying SAP GUI session
+    pub fn gui_session(&self) -> &GuiSession {
+        self.session.borrow()
+    }
 }
 
-impl SapSession for RealSapSession {
+impl<S: Borrow<GuiSession>> SapSession for RealSapSession<S> {
     fn find_by_id(&self, id: String) -> Result<Box<dyn SapComponent>> {
-        let component = self.session.find_by_id(id)?;
+        let component = self.gui_session().find_by_id(id)?;
         Ok(Box::new(RealSapComponent::new(component)))
     }
 
     fn info(&self) -> Result<Box<dyn SapSessionInfo>> {
-        let info = self.session.info()?;
+        let info = self.gui_session().info()?;
         Ok(Box::new(RealSapSessionInfo::new(info)))
     }
 
     fn start_transaction(&self, transaction: String) -> Result<()> {
-        self.session.start_transaction(transaction)
+        self.gui_session().start_transaction(transaction)
     }
 
     fn end_transaction(&self) -> Result<()> {
-        self.session.end_transaction()
+        self.gui_session().end_transaction()
+    }
+
+    fn send_v_key(&self, wnd: i32, key: i32) -> Result<()> {
+        self.find_by_id(format!("wnd[{}]", wnd))?.send_v_key(key)
+    }
+
+    fn close_window(&self, wnd: i32) -> Result<()> {
+        self.find_by_id(format!("wnd[{}]", wnd))?.close()
+    }
+
+    fn close_all_windows(&self) -> Result<()> {
+        for i in (1..=MAX_MODAL_WINDOWS).rev() {
+            if let Ok(window) = self.find_by_id(format!("wnd[{}]", i)) {
+                window.close()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn busy(&self) -> Result<bool> {
+        self.gui_session().busy()
     }
 }
 
@@ -0,0 +1,499 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cell::{Cell, RefCell};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Instant;
+use windows::core::Result;
+
+use crate::utils::config_types::SapConfig;
+use crate::utils::sap_interfaces::{SapComponent, SapSession, SapSessionInfo};
+use crate::utils::sap_status::StatusMessage;
+
+/// One recorded SAP GUI interaction (a line of the JSONL trace)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub run_id: String,
+    pub seq: u64,
+    pub timestamp: String,
+    pub duration_us: u64,
+    /// Operation name, matching the `SapSession`/`SapComponent` method
+    pub op: String,
+    /// Component ID (not set for session-level operations)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<Value>,
+    /// Returned value (null for operations without a result, not set on error)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<TraceError>,
+}
+
+/// An error returned by a recorded operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceError {
+    pub code: i32,
+    pub message: String,
+}
+
+/// Writes trace entries to a JSONL file
+pub struct TraceRecorder {
+    run_id: String,
+    path: PathBuf,
+    file: RefCell<File>,
+    seq: Cell<u64>,
+}
+
+impl TraceRecorder {
+    /// Create a trace file named `sap_trace_<run_id>.jsonl` in the given directory
+    pub fn create(dir: &Path, run_id: &str) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("sap_trace_{}.jsonl", run_id));
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(Self {
+            run_id: run_id.to_string(),
+            path,
+            file: RefCell::new(file),
+            seq: Cell::new(0),
+        })
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Run an operation and record its arguments, result and duration
+    pub fn record<T: Serialize>(
+        &self,
+        op: &str,
+        id: Option<&str>,
+        args: Vec<Value>,
+        f: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        self.record_with(op, id, args, f, |value| {
+            serde_json::to_value(value).unwrap_or(Value::Null)
+        })
+    }
+
+    /// Like `record`, for results that are described rather than serialized
+    pub fn record_with<T>(
+        &self,
+        op: &str,
+        id: Option<&str>,
+        args: Vec<Value>,
+        f: impl FnOnce() -> Result<T>,
+        describe: impl FnOnce(&T) -> Value,
+    ) -> Result<T> {
+        let timestamp = Local::now().to_rfc3339();
+        let started = Instant::now();
+        let result = f();
+        let duration_us = started.elapsed().as_micros() as u64;
+
+        let (value, error) = match &result {
+            Ok(value) => (Some(describe(value)), None),
+            Err(e) => (
+                None,
+                Some(TraceError {
+                    code: e.code().0,
+                    message: e.message().to_string(),
+                }),
+            ),
+        };
+
+        let seq = self.seq.get() + 1;
+        self.seq.set(seq);
+
+        self.write(&TraceEntry {
+            run_id: self.run_id.clone(),
+            seq,
+            timestamp,
+            duration_us,
+            op: op.to_string(),
+            id: id.map(|id| id.to_string()),
+            args,
+            result: value,
+            error,
+        });
+
+        result
+    }
+
+    fn write(&self, entry: &TraceEntry) {
+        // A broken trace must never stop the automation itself
+        let line = match serde_json::to_string(entry) {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Warning: Failed to serialize trace entry: {}", e);
+                return;
+            }
+        };
+
+        let mut file = self.file.borrow_mut();
+        if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            eprintln!(
+                "Warning: Failed to write trace {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Read all entries of a JSONL trace file
+pub fn read_trace(path: &Path) -> io::Result<Vec<TraceEntry>> {
+    let content = fs::read_to_string(path)?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        })
+        .collect()
+}
+
+/// Session decorator that records every interaction to a trace
+pub struct RecordingSapSession<S: SapSession> {
+    inner: S,
+    recorder: Rc<TraceRecorder>,
+}
+
+impl<S: SapSession> RecordingSapSession<S> {
+    pub fn new(inner: S, recorder: Rc<TraceRecorder>) -> Self {
+        Self { inner, recorder }
+    }
+
+    pub fn recorder(&self) -> &TraceRecorder {
+        &self.recorder
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: SapSession> SapSession for RecordingSapSession<S> {
+    fn find_by_id(&self, id: String) -> Result<Box<dyn SapComponent>> {
+        let component = self.recorder.record_with(
+            "find_by_id",
+            Some(&id),
+            Vec::new(),
+            || self.inner.find_by_id(id.clone()),
+            |_| Value::Null,
+        )?;
+
+        Ok(Box::new(RecordingSapComponent {
+            id,
+            inner: component,
+            recorder: self.recorder.clone(),
+        }))
+    }
+
+    fn info(&self) -> Result<Box<dyn SapSessionInfo>> {
+        let info = self.inner.info()?;
+        Ok(Box::new(RecordingSapSessionInfo {
+            inner: info,
+            recorder: self.recorder.clone(),
+        }))
+    }
+
+    fn start_transaction(&self, transaction: String) -> Result<()> {
+        self.recorder
+            .record("start_transaction", None, vec![json!(transaction)], || {
+                self.inner.start_transaction(transaction.clone())
+            })
+    }
+
+    fn end_transaction(&self) -> Result<()> {
+        self.recorder
+            .record("end_transaction", None, Vec::new(), || {
+                self.inner.end_transaction()
+            })
+    }
+
+    fn send_v_key(&self, wnd: i32, key: i32) -> Result<()> {
+        self.recorder
+            .record("send_v_key", None, vec![json!(wnd), json!(key)], || {
+                self.inner.send_v_key(wnd, key)
+            })
+    }
+
+    fn close_window(&self, wnd: i32) -> Result<()> {
+        self.recorder
+            .record("close_window", None, vec![json!(wnd)], || {
+                self.inner.close_window(wnd)
+            })
+    }
+
+    fn close_all_windows(&self) -> Result<()> {
+        self.recorder
+            .record("close_all_windows", None, Vec::new(), || {
+                self.inner.close_all_windows()
+            })
+    }
+
+    fn busy(&self) -> Result<bool> {
+        self.recorder
+            .record("busy", None, Vec::new(), || self.inner.busy())
+    }
+}
+
+/// Session info handed out by a `RecordingSapSession`
+struct RecordingSapSessionInfo {
+    inner: Box<dyn SapSessionInfo>,
+    recorder: Rc<TraceRecorder>,
+}
+
+impl SapSessionInfo for RecordingSapSessionInfo {
+    fn transaction(&self) -> Result<String> {
+        self.recorder
+            .record("transaction", None, Vec::new(), || self.inner.transaction())
+    }
+
+    fn program(&self) -> Result<String> {
+        self.recorder
+            .record("program", None, Vec::new(), || self.inner.program())
+    }
+
+    fn screen_number(&self) -> Result<i32> {
+        self.recorder.record("screen_number", None, Vec::new(), || {
+            self.inner.screen_number()
+        })
+    }
+
+    fn system_name(&self) -> Result<String> {
+        self.recorder
+            .record("system_name", None, Vec::new(), || self.inner.system_name())
+    }
+
+    fn client(&self) -> Result<String> {
+        self.recorder
+            .record("client", None, Vec::new(), || self.inner.client())
+    }
+
+    fn user(&self) -> Result<String> {
+        self.recorder
+            .record("user", None, Vec::new(), || self.inner.user())
+    }
+
+    fn language(&self) -> Result<String> {
+        self.recorder
+            .record("language", None, Vec::new(), || self.inner.language())
+    }
+}
+
+/// Component handed out by a `RecordingSapSession`
+struct RecordingSapComponent {
+    id: String,
+    inner: Box<dyn SapComponent>,
+    recorder: Rc<TraceRecorder>,
+}
+
+impl RecordingSapComponent {
+    fn record<T: Serialize>(
+        &self,
+        op: &str,
+        args: Vec<Value>,
+        f: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        self.recorder.record(op, Some(&self.id), args, f)
+    }
+}
+
+impl SapComponent for RecordingSapComponent {
+    fn r_type(&self) -> Result<String> {
+        self.record("r_type", Vec::new(), || self.inner.r_type())
+    }
+
+    fn name(&self) -> Result<String> {
+        self.record("name", Vec::new(), || self.inner.name())
+    }
+
+    fn get_text(&self) -> Result<String> {
+        self.record("get_text", Vec::new(), || self.inner.get_text())
+    }
+
+    fn set_text(&self, text: String) -> Result<()> {
+        self.record("set_text", vec![json!(text)], || {
+            self.inner.set_text(text.clone())
+        })
+    }
+
+    fn set_focus(&self) -> Result<()> {
+        self.record("set_focus", Vec::new(), || self.inner.set_focus())
+    }
+
+    fn changeable(&self) -> Result<bool> {
+        self.record("changeable", Vec::new(), || self.inner.changeable())
+    }
+
+    fn press(&self) -> Result<()> {
+        self.record("press", Vec::new(), || self.inner.press())
+    }
+
+    fn select(&self) -> Result<()> {
+        self.record("select", Vec::new(), || self.inner.select())
+    }
+
+    fn selected(&self) -> Result<bool> {
+        self.record("selected", Vec::new(), || self.inner.selected())
+    }
+
+    fn set_selected(&self, selected: bool) -> Result<()> {
+        self.record("set_selected", vec![json!(selected)], || {
+            self.inner.set_selected(selected)
+        })
+    }
+
+    fn maximize(&self) -> Result<()> {
+        self.record("maximize", Vec::new(), || self.inner.maximize())
+    }
+
+    fn send_v_key(&self, key: i32) -> Result<()> {
+        self.record("send_v_key", vec![json!(key)], || {
+            self.inner.send_v_key(key)
+        })
+    }
+
+    fn close(&self) -> Result<()> {
+        self.record("close", Vec::new(), || self.inner.close())
+    }
+
+    fn set_selected_rows(&self, rows: String) -> Result<()> {
+        self.record("set_selected_rows", vec![json!(rows)], || {
+            self.inner.set_selected_rows(rows.clone())
+        })
+    }
+
+    fn set_current_cell_row(&self, row: i32) -> Result<()> {
+        self.record("set_current_cell_row", vec![json!(row)], || {
+            self.inner.set_current_cell_row(row)
+        })
+    }
+
+    fn context_menu(&self) -> Result<()> {
+        self.record("context_menu", Vec::new(), || self.inner.context_menu())
+    }
+
+    fn select_context_menu_item(&self, function_code: String) -> Result<()> {
+        self.record(
+            "select_context_menu_item",
+            vec![json!(function_code)],
+            || self.inner.select_context_menu_item(function_code.clone()),
+        )
+    }
+
+    fn set_current_cell(&self, row: i32, column: String) -> Result<()> {
+        self.record("set_current_cell", vec![json!(row), json!(column)], || {
+            self.inner.set_current_cell(row, column.clone())
+        })
+    }
+
+    fn current_cell_row(&self) -> Result<i32> {
+        self.record("current_cell_row", Vec::new(), || {
+            self.inner.current_cell_row()
+        })
+    }
+
+    fn double_click_current_cell(&self) -> Result<()> {
+        self.record("double_click_current_cell", Vec::new(), || {
+            self.inner.double_click_current_cell()
+        })
+    }
+
+    fn row_count(&self) -> Result<i32> {
+        self.record("row_count", Vec::new(), || self.inner.row_count())
+    }
+
+    fn visible_row_count(&self) -> Result<i32> {
+        self.record("visible_row_count", Vec::new(), || {
+            self.inner.visible_row_count()
+        })
+    }
+
+    fn first_visible_row(&self) -> Result<i32> {
+        self.record("first_visible_row", Vec::new(), || {
+            self.inner.first_visible_row()
+        })
+    }
+
+    fn set_first_visible_row(&self, row: i32) -> Result<()> {
+        self.record("set_first_visible_row", vec![json!(row)], || {
+            self.inner.set_first_visible_row(row)
+        })
+    }
+
+    fn get_cell_value(&self, row: i32, column: String) -> Result<String> {
+        self.record("get_cell_value", vec![json!(row), json!(column)], || {
+            self.inner.get_cell_value(row, column.clone())
+        })
+    }
+
+    fn select_column(&self, column: String) -> Result<()> {
+        self.record("select_column", vec![json!(column)], || {
+            self.inner.select_column(column.clone())
+        })
+    }
+
+    fn press_column_header(&self, column: String) -> Result<()> {
+        self.record("press_column_header", vec![json!(column)], || {
+            self.inner.press_column_header(column.clone())
+        })
+    }
+
+    fn status_message(&self) -> Result<StatusMessage> {
+        self.record("status_message", Vec::new(), || self.inner.status_message())
+    }
+}
+
+/// Check whether SAP tracing is turned on (`trace_sap = "true"` in `[global]`)
+pub fn trace_enabled(config: &SapConfig) -> bool {
+    config
+        .global
+        .as_ref()
+        .and_then(|g| g.additional_params.get("trace_sap"))
+        .map(|value| value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Run a flow against the session, recording it under `<reports_dir>\\traces` if tracing is enabled
+///
+/// The run id combines the label with the start time, e.g. `vt11_20240301_061500`.
+pub fn run_with_trace<T>(
+    session: &dyn SapSession,
+    label: &str,
+    f: impl FnOnce(&dyn SapSession) -> T,
+) -> T {
+    let config = match SapConfig::load() {
+        Ok(config) if trace_enabled(&config) => config,
+        _ => return f(session),
+    };
+
+    let run_id = format!(
+        "{}_{}",
+        label.to_lowercase(),
+        Local::now().format("%Y%m%d_%H%M%S")
+    );
+    let trace_dir = format!("{}\\\\traces", config.get_reports_dir());
+
+    match TraceRecorder::create(Path::new(&trace_dir), &run_id) {
+        Ok(recorder) => {
+            println!("Recording SAP trace to {}", recorder.path().display());
+            let recording = RecordingSapSession::new(session, Rc::new(recorder));
+            f(&recording)
+        }
+        Err(e) => {
+            eprintln!("Warning: Failed to create SAP trace file: {}", e);
+            f(session)
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+use windows::core::{Error, Result, HRESULT};
+
+use crate::utils::sap_interfaces::{SapComponent, SapSession, SapSessionInfo};
+use crate::utils::sap_recording::{read_trace, TraceEntry};
+use crate::utils::sap_status::StatusMessage;
+
+/// Replay state shared between a session and the components it hands out
+struct ReplayState {
+    entries: Vec<TraceEntry>,
+    position: Cell<usize>,
+    divergence: RefCell<Option<String>>,
+}
+
+impl ReplayState {
+    /// Serve the next recorded response, failing if the call does not match the trace
+    fn replay<T: DeserializeOwned>(
+        &self,
+        op: &str,
+        id: Option<&str>,
+        args: Vec<Value>,
+    ) -> Result<T> {
+        if let Some(divergence) = self.divergence.borrow().as_ref() {
+            return Err(replay_error(divergence));
+        }
+
+        let position = self.position.get();
+        let Some(entry) = self.entries.get(position) else {
+            return Err(self.diverge(format!(
+                "Unexpected {} {} after the end of the trace",
+                op,
+                describe_call(id, &args)
+            )));
+        };
+
+        if entry.op != op || entry.id.as_deref() != id || entry.args != args {
+            return Err(self.diverge(format!(
+                "Replay diverged at entry {}: expected {} {}, got {} {}",
+                entry.seq,
+                entry.op,
+                describe_call(entry.id.as_deref(), &entry.args),
+                op,
+                describe_call(id, &args)
+            )));
+        }
+
+        self.position.set(position + 1);
+
+        if let Some(error) = &entry.error {
+            return Err(Error::new(
+                HRESULT(error.code),
+                error.message.as_str().into(),
+            ));
+        }
+
+        let value = entry.result.clone().unwrap_or(Value::Null);
+        serde_json::from_value(value).map_err(|e| {
+            self.diverge(format!(
+                "Recorded result of {} (entry {}) has an unexpected type: {}",
+                entry.op, entry.seq, e
+            ))
+        })
+    }
+
+    fn diverge(&self, message: String) -> Error {
+        eprintln!("{}", message);
+        let error = replay_error(&message);
+        *self.divergence.borrow_mut() = Some(message);
+        error
+    }
+}
+
+fn replay_error(message: &str) -> Error {
+    Error::new(HRESULT(-2147467259), message.into())
+}
+
+fn describe_call(id: Option<&str>, args: &[Value]) -> String {
+    format!(
+        "{} {}",
+        id.unwrap_or("(session)"),
+        Value::from(args.to_vec())
+    )
+}
+
+/// Session that serves the responses of a recorded trace back to the code under test
+///
+/// Every call must match the next trace entry (operation, component ID and arguments).
+/// The first mismatch fails that call and every call after it, and is reported by `finish`.
+pub struct ReplaySapSession {
+    state: Rc<ReplayState>,
+}
+
+impl ReplaySapSession {
+    pub fn new(entries: Vec<TraceEntry>) -> Self {
+        Self {
+            state: Rc::new(ReplayState {
+                entries,
+                position: Cell::new(0),
+                divergence: RefCell::new(None),
+            }),
+        }
+    }
+
+    /// Load a JSONL trace written by `RecordingSapSession`
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(read_trace(path)?))
+    }
+
+    /// Get the number of trace entries not replayed yet
+    pub fn remaining(&self) -> usize {
+        self.state.entries.len() - self.state.position.get()
+    }
+
+    /// Get the divergence message (if the code under test left the recorded path)
+    pub fn divergence(&self) -> Option<String> {
+        self.state.divergence.borrow().clone()
+    }
+
+    /// Check that the whole trace was replayed without diverging
+    pub fn finish(&self) -> Result<()> {
+        if let Some(divergence) = self.divergence() {
+            return Err(replay_error(&divergence));
+        }
+
+        if let Some(entry) = self.state.entries.get(self.state.position.get()) {
+            return Err(replay_error(&format!(
+                "Replay stopped early: {} entries left, next is {} {}",
+                self.remaining(),
+                entry.op,
+                describe_call(entry.id.as_deref(), &entry.args)
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl SapSession for ReplaySapSession {
+    fn find_by_id(&self, id: String) -> Result<Box<dyn SapComponent>> {
+        self.state
+            .replay::<Value>("find_by_id", Some(&id), Vec::new())?;
+        Ok(Box::new(ReplaySapComponent {
+            id,
+            state: self.state.clone(),
+        }))
+    }
+
+    fn info(&self) -> Result<Box<dyn SapSessionInfo>> {
+        Ok(Box::new(ReplaySapSessionInfo {
+            state: self.state.clone(),
+        }))
+    }
+
+    fn start_transaction(&self, transaction: String) -> Result<()> {
+        self.state
+            .replay("start_transaction", None, vec![json!(transaction)])
+    }
+
+    fn end_transaction(&self) -> Result<()> {
+        self.state.replay("end_transaction", None, Vec::new())
+    }
+
+    fn send_v_key(&self, wnd: i32, key: i32) -> Result<()> {
+        self.state
+            .replay("send_v_key", None, vec![json!(wnd), json!(key)])
+    }
+
+    fn close_window(&self, wnd: i32) -> Result<()> {
+        self.state.replay("close_window", None, vec![json!(wnd)])
+    }
+
+    fn close_all_windows(&self) -> Result<()> {
+        self.state.replay("close_all_windows", None, Vec::new())
+    }
+
+    fn busy(&self) -> Result<bool> {
+        self.state.replay("busy", None, Vec::new())
+    }
+}
+
+/// Session info handed out by a `ReplaySapSession`
+struct ReplaySapSessionInfo {
+    state: Rc<ReplayState>,
+}
+
+impl SapSessionInfo for ReplaySapSessionInfo {
+    fn transaction(&self) -> Result<String> {
+        self.state.replay("transaction", None, Vec::new())
+    }
+
+    fn program(&self) -> Result<String> {
+        self.state.replay("program", None, Vec::new())
+    }
+
+    fn screen_number(&self) -> Result<i32> {
+        self.state.replay("screen_number", None, Vec::new())
+    }
+
+    fn system_name(&self) -> Result<String> {
+        self.state.replay("system_name", None, Vec::new())
+    }
+
+    fn client(&self) -> Result<String> {
+        self.state.replay("client", None, Vec::new())
+    }
+
+    fn user(&self) -> Result<String> {
+        self.state.replay("user", None, Vec::new())
+    }
+
+    fn language(&self) -> Result<String> {
+        self.state.replay("language", None, Vec::new())
+    }
+}
+
+/// Component handed out by a `ReplaySapSession`
+struct ReplaySapComponent {
+    id: String,
+    state: Rc<ReplayState>,
+}
+
+impl ReplaySapComponent {
+    fn replay<T: DeserializeOwned>(&self, op: &str, args: Vec<Value>) -> Result<T> {
+        self.state.replay(op, Some(&self.id), args)
+    }
+}
+
+impl SapComponent for ReplaySapComponent {
+    fn r_type(&self) -> Result<String> {
+        self.replay("r_type", Vec::new())
+    }
+
+    fn name(&self) -> Result<String> {
+        self.replay("name", Vec::new())
+    }
+
+    fn get_text(&self) -> Result<String> {
+        self.replay("get_text", Vec::new())
+    }
+
+    fn set_text(&self, text: String) -> Result<()> {
+        self.replay("set_text", vec![json!(text)])
+    }
+
+    fn set_focus(&self) -> Result<()> {
+        self.replay("set_focus", Vec::new())
+    }
+
+    fn changeable(&self) -> Result<bool> {
+        self.replay("changeable", Vec::new())
+    }
+
+    fn press(&self) -> Result<()> {
+        self.replay("press", Vec::new())
+    }
+
+    fn select(&self) -> Result<()> {
+        self.replay("select", Vec::new())
+    }
+
+    fn selected(&self) -> Result<bool> {
+        self.replay("selected", Vec::new())
+    }
+
+    fn set_selected(&self, selected: bool) -> Result<()> {
+        self.replay("set_selected", vec![json!(selected)])
+    }
+
+    fn maximize(&self) -> Result<()> {
+        self.replay("maximize", Vec::new())
+    }
+
+    fn send_v_key(&self, key: i32) -> Result<()> {
+        self.replay("send_v_key", vec![json!(key)])
+    }
+
+    fn close(&self) -> Result<()> {
+        self.replay("close", Vec::new())
+    }
+
+    fn set_selected_rows(&self, rows: String) -> Result<()> {
+        self.replay("set_selected_rows", vec![json!(rows)])
+    }
+
+    fn set_current_cell_row(&self, row: i32) -> Result<()> {
+        self.replay("set_current_cell_row", vec![json!(row)])
+    }
+
+    fn context_menu(&self) -> Result<()> {
+        self.replay("context_menu", Vec::new())
+    }
+
+    fn select_context_menu_item(&self, function_code: String) -> Result<()> {
+        self.replay("select_context_menu_item", vec![json!(function_code)])
+    }
+
+    fn set_current_cell(&self, row: i32, column: String) -> Result<()> {
+        self.replay("set_current_cell", vec![json!(row), json!(column)])
+    }
+
+    fn current_cell_row(&self) -> Result<i32> {
+        self.replay("current_cell_row", Vec::new())
+    }
+
+    fn double_click_current_cell(&self) -> Result<()> {
+        self.replay("double_click_current_cell", Vec::new())
+    }
+
+    fn row_count(&self) -> Result<i32> {
+        self.replay("row_count", Vec::new())
+    }
+
+    fn visible_row_count(&self) -> Result<i32> {
+        self.replay("visible_row_count", Vec::new())
+    }
+
+    fn first_visible_row(&self) -> Result<i32> {
+        self.replay("first_visible_row", Vec::new())
+    }
+
+    fn set_first_visible_row(&self, row: i32) -> Result<()> {
+        self.replay("set_first_visible_row", vec![json!(row)])
+    }
+
+    fn get_cell_value(&self, row: i32, column: String) -> Result<String> {
+        self.replay("get_cell_value", vec![json!(row), json!(column)])
+    }
+
+    fn select_column(&self, column: String) -> Result<()> {
+        self.replay("select_column", vec![json!(column)])
+    }
+
+    fn press_column_header(&self, column: String) -> Result<()> {
+        self.replay("press_column_header", vec![json!(column)])
+    }
+
+    fn status_message(&self) -> Result<StatusMessage> {
+        self.replay("status_message", Vec::new())
+    }
+}
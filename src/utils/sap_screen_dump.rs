@@ -0,0 +1,223 @@
+use anyhow::anyhow;
+use chrono::Local;
+use sap_scripting::*;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use windows::core::Result;
+
+use crate::utils::sap_constants::MAX_MODAL_WINDOWS;
+use crate::utils::sap_interfaces::{normalize_id, SapComponent};
+use crate::utils::sap_mock_impl::{MockComponent, MockGrid, MockSapSession};
+use crate::utils::sap_real_impl::RealSapComponent;
+
+/// Snapshot of the windows of a SAP GUI session, stored as JSON
+///
+/// ```json
+/// {
+///   "transaction": "VT11",
+///   "captured_at": "2024-03-01 08:15:00",
+///   "windows": [
+///     {
+///       "id": "wnd[0]", "type": "GuiMainWindow", "name": "wnd[0]",
+///       "text": "Shipment List: Planning",
+///       "children": [
+///         { "id": "wnd[0]/usr", "type": "GuiUserArea", "name": "usr", "children": [
+///           { "id": "wnd[0]/usr/ctxtK_DATEN-LOW", "type": "GuiCTextField",
+///             "name": "K_DATEN-LOW", "changeable": true }
+///         ] }
+///       ]
+///     }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScreenDump {
+    #[serde(default)]
+    pub transaction: String,
+    /// Program and dynpro number of the active window
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub program: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub screen_number: i32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub captured_at: String,
+    #[serde(default)]
+    pub windows: Vec<DumpComponent>,
+}
+
+/// A component of a screen dump, with its children
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DumpComponent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub r_type: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    /// Whether the component accepts input (only set for text fields)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changeable: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<DumpComponent>,
+    /// Column names, if the component is a table control (addressed by index)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<String>>,
+    /// Visible cell values, if the component is a table control
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ScreenDump {
+    pub fn from_json_str(content: &str) -> anyhow::Result<Self> {
+        serde_json::from_str(content).map_err(|e| anyhow!("Failed to parse screen dump: {}", e))
+    }
+
+    /// Load a screen dump from a JSON fixture file
+    pub fn from_file(path: &str) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read screen dump {}: {}", path, e))?;
+        Self::from_json_str(&content)
+    }
+
+    /// Write the screen dump as pretty-printed JSON
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow!("Failed to write screen dump {}: {}", path.display(), e))
+    }
+
+    /// Build a mock session showing the dumped windows
+    pub fn to_mock_session(&self, name: &str) -> MockSapSession {
+        let mut session = MockSapSession::new(name);
+        if !self.transaction.is_empty() {
+            session.set_transaction(&self.transaction);
+        }
+        if !self.program.is_empty() {
+            session.set_screen(&self.program, self.screen_number);
+        }
+        for window in &self.windows {
+            session.add_component(&window.id, Rc::new(RefCell::new(window.build())));
+        }
+        session
+    }
+}
+
+impl DumpComponent {
+    /// Build a mock component tree from this dump
+    pub fn build(&self) -> MockComponent {
+        let mut component = MockComponent::new(normalize_id(&self.id), &self.name, &self.r_type);
+        component.text = self.text.clone();
+
+        let mut properties = HashMap::new();
+        if let Some(changeable) = self.changeable {
+            properties.insert("changeable".to_string(), changeable.to_string());
+        }
+        component.properties = properties;
+
+        if let Some(columns) = &self.columns {
+            let columns: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+            let mut grid = MockGrid::new(&columns, self.rows.len() as i32);
+            for row in &self.rows {
+                let values: Vec<&str> = row.iter().map(|v| v.as_str()).collect();
+                grid.add_row(&values);
+            }
+            component = component.with_grid(grid);
+        }
+
+        for child in &self.children {
+            component.add_child(Rc::new(RefCell::new(child.build())));
+        }
+
+        component
+    }
+}
+
+/// Get the children of a component (if it's a container)
+fn component_children(component: &GuiComponent) -> Result<Vec<GuiComponent>> {
+    let children = if let Some(container) = component.downcast::<GuiVContainer>() {
+        GuiVContainerExt::children(&container)?
+    } else if let Some(container) = component.downcast::<GuiContainer>() {
+        GuiContainerExt::children(&container)?
+    } else {
+        return Ok(Vec::new());
+    };
+
+    let mut result = Vec::new();
+    for i in 0..children.count()? {
+        result.push(children.element_at(i)?);
+    }
+    Ok(result)
+}
+
+/// Dump a real component and everything below it
+fn dump_component(component: GuiComponent) -> Result<DumpComponent> {
+    let children = component_children(&component)?;
+    let table = component.downcast::<GuiTableControl>();
+    let id = normalize_id(&component.id()?).to_string();
+
+    let mut dump = DumpComponent {
+        id,
+        r_type: component.r_type()?,
+        name: component.name()?,
+        ..Default::default()
+    };
+
+    let wrapped = RealSapComponent::new(component);
+    dump.text = wrapped.get_text().unwrap_or_default();
+    dump.changeable = wrapped.changeable().ok();
+
+    // Table controls only hold the visible rows, so dump those cells
+    if let Some(table) = table {
+        let column_count = table.columns()?.count()?;
+        let first_row = wrapped.first_visible_row().unwrap_or(0);
+        let visible_rows = wrapped.visible_row_count()?;
+        dump.columns = Some((0..column_count).map(|c| c.to_string()).collect());
+        for row in first_row..first_row + visible_rows {
+            let mut values = Vec::new();
+            for column in 0..column_count {
+                values.push(
+                    wrapped
+                        .get_cell_value(row, column.to_string())
+                        .unwrap_or_default(),
+                );
+            }
+            dump.rows.push(values);
+        }
+    }
+
+    for child in children {
+        dump.children.push(dump_component(child)?);
+    }
+
+    Ok(dump)
+}
+
+fn is_zero(value: &i32) -> bool {
+    *value == 0
+}
+
+/// Capture every open window of a real SAP GUI session as a screen dump
+pub fn dump_screen(session: &GuiSession) -> Result<ScreenDump> {
+    let info = session.info()?;
+    let mut dump = ScreenDump {
+        transaction: info.transaction()?,
+        program: info.program()?,
+        screen_number: info.screen_number()?,
+        captured_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        windows: Vec::new(),
+    };
+
+    for i in 0..=MAX_MODAL_WINDOWS {
+        if let Ok(window) = session.find_by_id(format!("wnd[{}]", i)) {
+            dump.windows.push(dump_component(window)?);
+        }
+    }
+
+    Ok(dump)
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+use windows::core::Result;
+
+use crate::utils::sap_constants::MAX_MODAL_WINDOWS;
+use crate::utils::sap_interfaces::{exist_ctrl, SapSession};
+
+/// Program and dynpro number of a screen, as shown under System > Status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenId {
+    pub program: String,
+    pub screen_number: i32,
+}
+
+impl ScreenId {
+    pub fn new(program: &str, screen_number: i32) -> Self {
+        Self {
+            program: program.to_string(),
+            screen_number,
+        }
+    }
+
+    /// Get the screen the session is showing (that of its active window)
+    pub fn current(session: &dyn SapSession) -> Result<Self> {
+        let info = session.info()?;
+        Ok(Self {
+            program: info.program()?,
+            screen_number: info.screen_number()?,
+        })
+    }
+}
+
+impl fmt::Display for ScreenId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{:04}", self.program, self.screen_number)
+    }
+}
+
+/// How window titles are compared when a screen is not identified by its program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TitleMatch {
+    /// The title equals one of the titles (ignoring case)
+    Exact,
+    /// The title contains one of the titles (ignoring case)
+    Contains,
+}
+
+/// A screen known to the automation, identified by program and dynpro number
+///
+/// Titles depend on the logon language, so they are only compared when the
+/// program and dynpro cannot be read (e.g. for windows other than the active one).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SapScreen {
+    pub name: &'static str,
+    pub program: &'static str,
+    pub screen_number: i32,
+    /// Transactions showing the screen, for programs shared by several screens
+    /// (e.g. the generic ALV list), empty for any transaction
+    pub transactions: &'static [&'static str],
+    /// English window titles, used as fallback
+    pub titles: &'static [&'static str],
+    pub title_match: TitleMatch,
+}
+
+/// Logon screen
+pub const LOGIN: SapScreen = SapScreen {
+    name: "login",
+    program: "SAPMSYST",
+    screen_number: 20,
+    transactions: &[],
+    titles: &["SAP R/3", "SAP"],
+    title_match: TitleMatch::Exact,
+};
+
+/// SAP Easy Access menu
+pub const EASY_ACCESS: SapScreen = SapScreen {
+    name: "easy_access",
+    program: "SAPLSMTR_NAVIGATION",
+    screen_number: 100,
+    transactions: &[],
+    titles: &["SAP Easy Access"],
+    title_match: TitleMatch::Exact,
+};
+
+/// "Log Off" confirmation popup
+pub const LOG_OFF: SapScreen = SapScreen {
+    name: "log_off",
+    program: "SAPLSPO1",
+    screen_number: 100,
+    transactions: &[],
+    titles: &["Log Off"],
+    title_match: TitleMatch::Exact,
+};
+
+/// System messages shown after logon
+pub const SYSTEM_MESSAGES: SapScreen = SapScreen {
+    name: "system_messages",
+    program: "SAPLSM02",
+    screen_number: 110,
+    transactions: &[],
+    titles: &["System Messages"],
+    title_match: TitleMatch::Exact,
+};
+
+/// "Save list in file..." format selection popup
+pub const SAVE_LIST_IN_FILE: SapScreen = SapScreen {
+    name: "save_list_in_file",
+    program: "SAPLSPO5",
+    screen_number: 110,
+    transactions: &[],
+    titles: &["SAVE LIST IN FILE..."],
+    title_match: TitleMatch::Contains,
+};
+
+/// LX02/LX03 bin status report (ALV list)
+pub const BIN_STATUS_LIST: SapScreen = SapScreen {
+    name: "bin_status_list",
+    program: "SAPLKKBL",
+    screen_number: 500,
+    transactions: &["LX02", "LX03"],
+    titles: &["BIN STATUS REPORT: OVERVIEW"],
+    title_match: TitleMatch::Contains,
+};
+
+/// VL06O delivery list (ALV grid)
+pub const VL06O_DELIVERY_LIST: SapScreen = SapScreen {
+    name: "vl06o_delivery_list",
+    program: "SAPLSLVC_FULLSCREEN",
+    screen_number: 500,
+    transactions: &["VL06O"],
+    titles: &["LIST OF OUTBOUND DELIVERIES"],
+    title_match: TitleMatch::Contains,
+};
+
+/// VT11 shipment list (ALV list)
+pub const VT11_SHIPMENT_LIST: SapScreen = SapScreen {
+    name: "vt11_shipment_list",
+    program: "SAPLKKBL",
+    screen_number: 500,
+    transactions: &["VT11"],
+    titles: &["SHIPMENT LIST: PLANNING"],
+    title_match: TitleMatch::Contains,
+};
+
+/// ZMDESNR serial number history list
+pub const ZMDESNR_SERIAL_NUMBER_LIST: SapScreen = SapScreen {
+    name: "zmdesnr_serial_number_list",
+    program: "ZMDE_SERIALNUMBER_HISTORY",
+    screen_number: 9100,
+    transactions: &[],
+    titles: &["ZMDEMAIN SERIAL NUMBER HISTORY CONTENTS"],
+    title_match: TitleMatch::Contains,
+};
+
+impl SapScreen {
+    /// Check whether a program and dynpro number identify this screen
+    pub fn is(&self, id: &ScreenId) -> bool {
+        id.program.eq_ignore_ascii_case(self.program) && id.screen_number == self.screen_number
+    }
+
+    /// Check whether the screen is shown by a transaction (any if none are listed)
+    pub fn in_transaction(&self, transaction: &str) -> bool {
+        self.transactions.is_empty()
+            || self
+                .transactions
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(transaction.trim()))
+    }
+
+    /// Check whether a window title is one of this screen's (English) titles
+    pub fn title_matches(&self, title: &str) -> bool {
+        let title = title.trim().to_uppercase();
+        self.titles.iter().any(|t| {
+            let t = t.to_uppercase();
+            match self.title_match {
+                TitleMatch::Exact => title == t,
+                TitleMatch::Contains => title.contains(&t),
+            }
+        })
+    }
+
+    /// Check whether window wnd[n], with the given title, shows this screen
+    ///
+    /// The session only reports the program and dynpro of its active window, so
+    /// the title is only compared for other windows or when they cannot be read.
+    pub fn matches(&self, session: &dyn SapSession, wnd: i32, title: &str) -> Result<bool> {
+        if active_window(session)? == Some(wnd) {
+            if let Ok(info) = session.info() {
+                if let (Ok(program), Ok(screen_number)) = (info.program(), info.screen_number()) {
+                    if !program.is_empty() {
+                        let id = ScreenId {
+                            program,
+                            screen_number,
+                        };
+                        return Ok(self.is(&id) && self.in_transaction(&info.transaction()?));
+                    }
+                }
+            }
+        }
+        Ok(self.title_matches(title))
+    }
+}
+
+/// Get the index of the active window (the open window with the highest index)
+pub fn active_window(session: &dyn SapSession) -> Result<Option<i32>> {
+    for i in (0..=MAX_MODAL_WINDOWS).rev() {
+        if exist_ctrl(session, i, "", false)?.cband {
+            return Ok(Some(i));
+        }
+    }
+    Ok(None)
+}
+
+/// All known screens, looked up by name from configuration files
+pub const SCREENS: &[SapScreen] = &[
+    LOGIN,
+    EASY_ACCESS,
+    LOG_OFF,
+    SYSTEM_MESSAGES,
+    SAVE_LIST_IN_FILE,
+    BIN_STATUS_LIST,
+    VL06O_DELIVERY_LIST,
+    VT11_SHIPMENT_LIST,
+    ZMDESNR_SERIAL_NUMBER_LIST,
+];
+
+/// Find a known screen by name (e.g. "log_off")
+pub fn screen_by_name(name: &str) -> Option<&'static SapScreen> {
+    SCREENS.iter().find(|screen| screen.name == name)
+}
@@ -0,0 +1,345 @@
+use sap_scripting::*;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use windows::core::{Error, Result, HRESULT};
+
+use crate::utils::config_types::SapConfig;
+use crate::utils::sap_interfaces::SapSession;
+use crate::utils::sap_real_impl::RealSapSession;
+use crate::utils::sap_status::read_status;
+use crate::utils::sap_wait::{poll_until, wait_timeouts};
+
+/// Sessions per connection allowed by SAP unless configured otherwise (rdisp/max_alt_modes)
+pub const DEFAULT_MAX_SESSIONS: usize = 6;
+
+/// Command field of the main window
+const OK_CODE_FIELD: &str = "wnd[0]/tbar[0]/okcd";
+
+/// OK code opening a new session on SAP Easy Access
+const NEW_SESSION_COMMAND: &str = "/oSESSION_MANAGER";
+
+/// OK code ending the current session
+const END_SESSION_COMMAND: &str = "/i";
+
+/// Get the session limit from `max_sessions` in `[global]`
+pub fn max_sessions(config: &SapConfig) -> usize {
+    match config.global.as_ref().and_then(|g| g.run.max_sessions) {
+        Some(0) => {
+            eprintln!("Invalid max_sessions (0), using {}", DEFAULT_MAX_SESSIONS);
+            DEFAULT_MAX_SESSIONS
+        }
+        Some(max) => max,
+        None => DEFAULT_MAX_SESSIONS,
+    }
+}
+
+/// Set of SAP sessions (by id) shared by jobs, each session driven by one job at a time
+pub struct SessionPool {
+    ids: Vec<String>,
+    opened: Vec<String>,
+    in_use: Mutex<Vec<bool>>,
+    released: Condvar,
+}
+
+impl SessionPool {
+    /// Create a pool over the given session ids, e.g. `/app/con[0]/ses[1]`
+    pub fn new(ids: Vec<String>) -> Result<Self> {
+        if ids.is_empty() {
+            return Err(Error::new(
+                HRESULT(-2147467259),
+                "Session pool needs at least one session".into(),
+            ));
+        }
+        let in_use = Mutex::new(vec![false; ids.len()]);
+        Ok(Self {
+            ids,
+            opened: Vec::new(),
+            in_use,
+            released: Condvar::new(),
+        })
+    }
+
+    /// Record the sessions opened for the pool, closed by `close_opened_sessions`
+    pub fn with_opened(mut self, opened: Vec<String>) -> Self {
+        self.opened = opened;
+        self
+    }
+
+    /// Get the ids of all sessions in the pool
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    /// Get the ids of the sessions opened for the pool
+    pub fn opened(&self) -> &[String] {
+        &self.opened
+    }
+
+    /// Get the number of sessions in the pool
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Check whether the pool has no sessions (never true for a pool from `new`)
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Get the number of sessions currently leased
+    pub fn in_use(&self) -> usize {
+        self.slots().iter().filter(|used| **used).count()
+    }
+
+    /// Lease a free session, waiting until one is released if all are in use
+    pub fn acquire(&self) -> SessionLease<'_> {
+        let mut slots = self.slots();
+        loop {
+            if let Some(index) = slots.iter().position(|used| !used) {
+                slots[index] = true;
+                return SessionLease { pool: self, index };
+            }
+            slots = self
+                .released
+                .wait(slots)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Lease a free session without waiting
+    pub fn try_acquire(&self) -> Option<SessionLease<'_>> {
+        let mut slots = self.slots();
+        let index = slots.iter().position(|used| !used)?;
+        slots[index] = true;
+        Some(SessionLease { pool: self, index })
+    }
+
+    fn slots(&self) -> std::sync::MutexGuard<'_, Vec<bool>> {
+        // A job that panicked while holding the lock leaves the slots consistent
+        self.in_use
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn release(&self, index: usize) {
+        self.slots()[index] = false;
+        self.released.notify_one();
+    }
+}
+
+/// Exclusive use of one pool session, returned to the pool when dropped
+pub struct SessionLease<'a> {
+    pool: &'a SessionPool,
+    index: usize,
+}
+
+impl SessionLease<'_> {
+    /// Get the id of the leased session
+    pub fn id(&self) -> &str {
+        &self.pool.ids[self.index]
+    }
+}
+
+impl Drop for SessionLease<'_> {
+    fn drop(&mut self) {
+        self.pool.release(self.index);
+    }
+}
+
+/// Run every job on its own thread, each on a session leased from the pool
+///
+/// At most `pool.len()` jobs run at once. Results are returned in the order of the jobs.
+pub fn run_parallel<J, T, F>(pool: &SessionPool, jobs: &[J], run: F) -> Vec<T>
+where
+    J: Sync,
+    T: Send,
+    F: Fn(&str, &J) -> T + Sync,
+{
+    let run = &run;
+    thread::scope(|scope| {
+        let handles: Vec<_> = jobs
+            .iter()
+            .map(|job| {
+                scope.spawn(move || {
+                    let lease = pool.acquire();
+                    run(lease.id(), job)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| match handle.join() {
+                Ok(result) => result,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    })
+}
+
+/// Ask SAP to open another session of the same connection (`/o`)
+///
+/// Fails with the status bar message if SAP refuses, e.g. when the session limit is reached.
+pub fn open_session(session: &dyn SapSession) -> Result<()> {
+    session
+        .find_by_id(OK_CODE_FIELD.to_string())?
+        .set_text(NEW_SESSION_COMMAND.to_string())?;
+    session.send_v_key(0, 0)?;
+
+    if let Some(message) = read_status(session, 0)? {
+        if message.is_error() {
+            return Err(Error::new(
+                HRESULT(-2147467259),
+                format!("Could not open a new session: {}", message.text).into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// End a session (`/i`)
+pub fn close_session(session: &dyn SapSession) -> Result<()> {
+    session
+        .find_by_id(OK_CODE_FIELD.to_string())?
+        .set_text(END_SESSION_COMMAND.to_string())?;
+    session.send_v_key(0, 0)
+}
+
+/// Get the ids of all sessions of a connection
+pub fn connection_session_ids(connection: &GuiConnection) -> Result<Vec<String>> {
+    let children = GuiConnectionExt::children(connection)?;
+    let mut ids = Vec::new();
+    for i in 0..children.count()? {
+        if let Some(session) = children.element_at(i)?.downcast::<GuiSession>() {
+            ids.push(session.id()?);
+        }
+    }
+    Ok(ids)
+}
+
+/// Get the ids of all sessions of all open connections
+pub fn session_ids(engine: &GuiApplication) -> Result<Vec<String>> {
+    let connections = GuiApplicationExt::children(engine)?;
+    let mut ids = Vec::new();
+    for i in 0..connections.count()? {
+        if let Some(connection) = connections.element_at(i)?.downcast::<GuiConnection>() {
+            ids.extend(connection_session_ids(&connection)?);
+        }
+    }
+    Ok(ids)
+}
+
+/// Open sessions on a connection until it has `count` of them (capped at SAP's limit)
+///
+/// Returns the ids of all sessions of the connection. Stops early, keeping the sessions
+/// already open, if SAP refuses to open another one.
+pub fn open_sessions(
+    connection: &GuiConnection,
+    count: usize,
+    limit: usize,
+) -> Result<Vec<String>> {
+    let target = count.min(limit);
+    let mut ids = connection_session_ids(connection)?;
+    let timeouts = wait_timeouts();
+
+    while ids.len() < target {
+        let children = GuiConnectionExt::children(connection)?;
+        let Some(first) = children.element_at(0)?.downcast::<GuiSession>() else {
+            break;
+        };
+        let before = ids.len();
+        println!("Opening SAP session {} of {}", before + 1, target);
+        if let Err(e) = open_session(&RealSapSession::new(&first)) {
+            eprintln!("{}", e.message());
+            break;
+        }
+
+        let opened = poll_until(
+            || Ok(GuiConnectionExt::children(connection)?.count()? as usize > before),
+            timeouts.default,
+            timeouts.poll,
+        )?;
+        ids = connection_session_ids(connection)?;
+        if !opened {
+            println!(
+                "SAP did not open a new session, continuing with {}",
+                ids.len()
+            );
+            break;
+        }
+    }
+    Ok(ids)
+}
+
+/// Run `f` on a session found by id, from any thread
+///
+/// SAP GUI scripting objects cannot be shared between threads, so each call attaches
+/// to the scripting engine on its own and looks the session up again.
+pub fn with_session<T>(id: &str, f: impl FnOnce(&GuiSession) -> T) -> Result<T> {
+    let com_instance = SAPComInstance::new()?;
+    let wrapper = com_instance.sap_wrapper()?;
+    let engine = wrapper.scripting_engine()?;
+    let component = engine.find_by_id(id.to_string())?;
+    let Some(session) = component.downcast::<GuiSession>() else {
+        return Err(Error::new(
+            HRESULT(-2147467259),
+            format!("{} is not a SAP session", id).into(),
+        ));
+    };
+    Ok(f(&session))
+}
+
+/// Get the sessions of a pool: `session_id` first, then the sessions opened for it, up to `size`
+pub fn pool_ids(session_id: &str, opened: &[String], size: usize) -> Vec<String> {
+    let mut ids = vec![session_id.to_string()];
+    ids.extend(opened.iter().filter(|id| *id != session_id).cloned());
+    ids.truncate(size.max(1));
+    ids
+}
+
+/// Close the sessions opened for a pool, logging the ones that could not be closed
+pub fn close_opened_sessions(pool: &SessionPool) {
+    for id in pool.opened() {
+        let closed = with_session(id, |session| close_session(&RealSapSession::new(session)));
+        if let Err(e) = closed.and_then(|result| result) {
+            eprintln!("Could not close SAP session {}: {}", id, e.message());
+        }
+    }
+}
+
+/// Build a pool of up to `size` sessions on the connection of `session`, opening sessions as needed
+///
+/// The pool holds `session` and the sessions opened for it, other sessions the user has
+/// open are left alone. Close the opened ones with `close_opened_sessions` when done.
+pub fn pool_for_session(session: &GuiSession, size: usize) -> Result<SessionPool> {
+    let session_id = session.id()?;
+    let connection_id = match session_id.find("/ses[") {
+        Some(end) => &session_id[..end],
+        None => session_id.as_str(),
+    };
+
+    let com_instance = SAPComInstance::new()?;
+    let wrapper = com_instance.sap_wrapper()?;
+    let engine = wrapper.scripting_engine()?;
+    let Some(connection) = engine
+        .find_by_id(connection_id.to_string())?
+        .downcast::<GuiConnection>()
+    else {
+        return Err(Error::new(
+            HRESULT(-2147467259),
+            format!("{} is not a SAP connection", connection_id).into(),
+        ));
+    };
+
+    let limit = SapConfig::load()
+        .map(|config| max_sessions(&config))
+        .unwrap_or(DEFAULT_MAX_SESSIONS);
+    let existing = connection_session_ids(&connection)?;
+    let wanted = existing.len() + size.saturating_sub(1);
+    let opened: Vec<String> = open_sessions(&connection, wanted, limit)?
+        .into_iter()
+        .filter(|id| !existing.contains(id))
+        .collect();
+
+    let ids = pool_ids(&session_id, &opened, size);
+    Ok(SessionPool::new(ids)?.with_opened(opened))
+}
@@ -0,0 +1,166 @@
+use std::fmt;
+use windows::core::Result;
+
+use crate::utils::sap_interfaces::{exist_ctrl, SapSession};
+use crate::utils::sap_screens::{active_window, ScreenId, EASY_ACCESS, LOGIN};
+use crate::utils::sap_wait::is_busy_error;
+use crate::utils::sap_wnd_utils::close_popups;
+
+/// Transaction reported while the logon screen is shown
+pub const LOGIN_TRANSACTION: &str = "S000";
+
+/// Transaction of the SAP Easy Access menu
+pub const EASY_ACCESS_TRANSACTION: &str = "SESSION_MANAGER";
+
+/// New password field of the "change password" dialog shown during logon
+pub const NEW_PASSWORD_FIELD: &str = "/usr/pwdRSYST-NCODE";
+
+/// Attempts made to get back to SAP Easy Access
+const MAX_RESET_ATTEMPTS: usize = 3;
+
+/// What a SAP session is showing, and so what can be done with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionState {
+    /// No session, or the session no longer responds
+    Disconnected,
+    /// Logon screen (transaction S000)
+    LoginScreen,
+    /// Logon asks for a new password
+    PasswordChange,
+    /// SAP Easy Access menu (transaction SESSION_MANAGER)
+    EasyAccess,
+    /// A transaction is running
+    InTransaction {
+        tcode: String,
+        program: String,
+        dynpro: i32,
+    },
+    /// A popup is open on top of a logged in session
+    ModalPopup { wnd: i32, title: String },
+}
+
+impl SessionState {
+    /// Derive the state from what the session is showing
+    ///
+    /// Errors of a session that no longer responds are reported as `Disconnected`,
+    /// calls rejected because SAP is busy are returned.
+    pub fn detect(session: &dyn SapSession) -> Result<Self> {
+        let (transaction, screen) = match session.info().and_then(|info| {
+            Ok((
+                info.transaction()?,
+                ScreenId::new(&info.program()?, info.screen_number()?),
+            ))
+        }) {
+            Ok(info) => info,
+            Err(e) if is_busy_error(&e) => return Err(e),
+            Err(e) => {
+                println!("SAP session not available: {}", e.message());
+                return Ok(SessionState::Disconnected);
+            }
+        };
+
+        let wnd = active_window(session)?.unwrap_or(0);
+        if exist_ctrl(session, wnd, NEW_PASSWORD_FIELD, false)?.cband {
+            return Ok(SessionState::PasswordChange);
+        }
+        if transaction.eq_ignore_ascii_case(LOGIN_TRANSACTION) || LOGIN.is(&screen) {
+            return Ok(SessionState::LoginScreen);
+        }
+        if wnd > 0 {
+            let title = exist_ctrl(session, wnd, "", true)?.ctext;
+            return Ok(SessionState::ModalPopup { wnd, title });
+        }
+        if transaction.eq_ignore_ascii_case(EASY_ACCESS_TRANSACTION) || EASY_ACCESS.is(&screen) {
+            return Ok(SessionState::EasyAccess);
+        }
+
+        Ok(SessionState::InTransaction {
+            tcode: transaction,
+            program: screen.program,
+            dynpro: screen.screen_number,
+        })
+    }
+
+    /// Check whether the session is connected (whether or not it is logged in)
+    pub fn is_connected(&self) -> bool {
+        *self != SessionState::Disconnected
+    }
+
+    /// Check whether a user is logged in
+    pub fn is_logged_in(&self) -> bool {
+        matches!(
+            self,
+            SessionState::EasyAccess
+                | SessionState::InTransaction { .. }
+                | SessionState::ModalPopup { .. }
+        )
+    }
+
+    /// Check whether a transaction can be started without closing anything first
+    pub fn can_start_transaction(&self) -> bool {
+        matches!(
+            self,
+            SessionState::EasyAccess | SessionState::InTransaction { .. }
+        )
+    }
+
+    /// Get the running transaction, if any
+    pub fn tcode(&self) -> Option<&str> {
+        match self {
+            SessionState::InTransaction { tcode, .. } => Some(tcode),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SessionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionState::Disconnected => write!(f, "disconnected"),
+            SessionState::LoginScreen => write!(f, "logon screen"),
+            SessionState::PasswordChange => write!(f, "password change required"),
+            SessionState::EasyAccess => write!(f, "SAP Easy Access"),
+            SessionState::InTransaction {
+                tcode,
+                program,
+                dynpro,
+            } => {
+                if program.is_empty() {
+                    write!(f, "transaction {}", tcode)
+                } else {
+                    write!(f, "transaction {} ({}/{:04})", tcode, program, dynpro)
+                }
+            }
+            SessionState::ModalPopup { wnd, title } => write!(f, "popup wnd[{}] '{}'", wnd, title),
+        }
+    }
+}
+
+/// Close popups and leave the running transaction to get back to SAP Easy Access
+///
+/// Returns the state reached, sessions that are not logged in are left as they are.
+pub fn return_to_easy_access(session: &dyn SapSession) -> Result<SessionState> {
+    let mut state = SessionState::detect(session)?;
+
+    for _ in 0..MAX_RESET_ATTEMPTS {
+        match &state {
+            SessionState::ModalPopup { .. } => {
+                close_popups(session, None, None)?;
+            }
+            SessionState::InTransaction { tcode, .. } => {
+                println!("Leaving transaction {}", tcode);
+                session.start_transaction(EASY_ACCESS_TRANSACTION.to_string())?;
+            }
+            _ => break,
+        }
+        state = SessionState::detect(session)?;
+    }
+
+    if state != SessionState::EasyAccess {
+        println!(
+            "Could not return to SAP Easy Access, session is at {}",
+            state
+        );
+    }
+    Ok(state)
+}
@@ -0,0 +1,128 @@
+use windows::core::{Error, Result, HRESULT};
+
+use crate::utils::config_types::SapConfig;
+use crate::utils::sap_interfaces::SapSession;
+use crate::utils::sap_session_state::{return_to_easy_access, SessionState};
+use crate::utils::sap_wnd_utils::close_popups;
+
+/// Re-logins allowed per step unless configured otherwise
+pub const DEFAULT_MAX_RELOGINS: usize = 3;
+
+/// Get the re-login cap from `max_relogin_attempts` in `[global]`
+pub fn max_relogins(config: &SapConfig) -> usize {
+    config
+        .global
+        .as_ref()
+        .and_then(|g| g.run.max_relogin_attempts)
+        .unwrap_or(DEFAULT_MAX_RELOGINS)
+}
+
+/// Logs a session back in when it was logged off, so long runs can resume where they were
+///
+/// Popups such as "System Messages" are closed by the popup rules on the way back to
+/// SAP Easy Access. A session that dropped to the logon screen is logged in again with
+/// `relogin`, at most `max_relogins` times in a row without a step completing.
+pub struct SessionWatchdog<'a> {
+    relogin: Box<dyn FnMut(&dyn SapSession) -> Result<()> + 'a>,
+    max_relogins: usize,
+    relogins: usize,
+    total_relogins: usize,
+}
+
+impl<'a> SessionWatchdog<'a> {
+    /// Create a watchdog that logs in again with `relogin`
+    pub fn new(
+        max_relogins: usize,
+        relogin: impl FnMut(&dyn SapSession) -> Result<()> + 'a,
+    ) -> Self {
+        Self {
+            relogin: Box::new(relogin),
+            max_relogins,
+            relogins: 0,
+            total_relogins: 0,
+        }
+    }
+
+    /// Create a watchdog with the re-login cap from config.toml
+    pub fn from_config(relogin: impl FnMut(&dyn SapSession) -> Result<()> + 'a) -> Self {
+        let max_relogins = SapConfig::load()
+            .map(|config| max_relogins(&config))
+            .unwrap_or(DEFAULT_MAX_RELOGINS);
+        Self::new(max_relogins, relogin)
+    }
+
+    /// Get the number of re-logins done so far
+    pub fn relogins(&self) -> usize {
+        self.total_relogins
+    }
+
+    /// Make sure the session can start a transaction, logging in again if it was logged off
+    ///
+    /// A session in SAP Easy Access or a transaction is left where it is, others are
+    /// taken back to SAP Easy Access first. Fails if the session cannot start a
+    /// transaction afterwards, e.g. when it is disconnected, asks for a new password
+    /// or the re-login cap is reached.
+    pub fn ensure_ready(&mut self, session: &dyn SapSession) -> Result<SessionState> {
+        let mut state = SessionState::detect(session)?;
+        if !state.can_start_transaction() {
+            state = return_to_easy_access(session)?;
+        }
+
+        while state == SessionState::LoginScreen {
+            if self.relogins >= self.max_relogins {
+                return Err(Error::new(
+                    HRESULT(-2147467259),
+                    format!(
+                        "SAP session was logged off, giving up after {} re-logins",
+                        self.relogins
+                    )
+                    .into(),
+                ));
+            }
+            self.relogins += 1;
+            self.total_relogins += 1;
+            println!(
+                "SAP session was logged off, logging in again (attempt {} of {})",
+                self.relogins, self.max_relogins
+            );
+
+            // Timeout notices can be left open on top of the logon screen
+            close_popups(session, None, None)?;
+            (self.relogin)(session)?;
+            state = return_to_easy_access(session)?;
+        }
+
+        if !state.can_start_transaction() {
+            return Err(Error::new(
+                HRESULT(-2147467259),
+                format!("SAP session is not usable, it is at {}", state).into(),
+            ));
+        }
+        Ok(state)
+    }
+
+    /// Run a step, logging in again and repeating it if the session was logged off meanwhile
+    ///
+    /// Errors of steps that fail on a session that is still logged in are returned as is.
+    pub fn run_step<T, E: From<Error>>(
+        &mut self,
+        session: &dyn SapSession,
+        mut step: impl FnMut() -> std::result::Result<T, E>,
+    ) -> std::result::Result<T, E> {
+        loop {
+            self.ensure_ready(session)?;
+            match step() {
+                Ok(value) => {
+                    self.relogins = 0;
+                    return Ok(value);
+                }
+                Err(e) => {
+                    if SessionState::detect(session)? != SessionState::LoginScreen {
+                        return Err(e);
+                    }
+                    println!("SAP session was logged off during the step, resuming after re-login");
+                }
+            }
+        }
+    }
+}
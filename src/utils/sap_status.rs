@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use windows::core::Result;
+
+use crate::utils::sap_control_id::ControlId;
+use crate::utils::sap_interfaces::SapSession;
+
+/// Message "Transaction &1 does not exist"
+pub const TRANSACTION_NOT_FOUND: (&str, &str) = ("00", "343");
+
+/// Message "Function code cannot be selected" (menu entry or button not valid on this screen)
+pub const FUNCTION_NOT_AVAILABLE: (&str, &str) = ("00", "038");
+
+/// Message "Name or password is incorrect (repeat logon)"
+pub const LOGON_INCORRECT: (&str, &str) = ("00", "152");
+
+/// Message "Client &1 is not available in this system"
+pub const CLIENT_NOT_AVAILABLE: (&str, &str) = ("00", "179");
+
+/// Message "Enter a new password" (the password expired or was reset)
+pub const NEW_PASSWORD_REQUIRED: (&str, &str) = ("00", "180");
+
+/// Message "Enter date in the format &1"
+pub const INVALID_DATE_FORMAT: (&str, &str) = ("00", "065");
+
+/// ALV message "No layouts found"
+pub const NO_LAYOUTS_FOUND: (&str, &str) = ("0K", "018");
+
+/// ALV message "Layout &1 applied"
+pub const LAYOUT_APPLIED: (&str, &str) = ("0K", "034");
+
+/// Type of a status bar message (GuiStatusbar.MessageType)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageKind {
+    #[serde(rename = "S")]
+    Success,
+    #[serde(rename = "E")]
+    Error,
+    #[serde(rename = "W")]
+    Warning,
+    #[serde(rename = "I")]
+    Info,
+    #[serde(rename = "A")]
+    Abort,
+}
+
+impl MessageKind {
+    /// Get the kind for a SAP message type ("S", "E", "W", "I", "A" or "X")
+    ///
+    /// Unknown or empty types are reported as Info.
+    pub fn from_code(code: &str) -> Self {
+        match code.trim().to_ascii_uppercase().as_str() {
+            "S" => MessageKind::Success,
+            "E" => MessageKind::Error,
+            "W" => MessageKind::Warning,
+            "A" | "X" => MessageKind::Abort,
+            _ => MessageKind::Info,
+        }
+    }
+
+    /// Get the SAP message type
+    pub fn code(&self) -> &'static str {
+        match self {
+            MessageKind::Success => "S",
+            MessageKind::Error => "E",
+            MessageKind::Warning => "W",
+            MessageKind::Info => "I",
+            MessageKind::Abort => "A",
+        }
+    }
+}
+
+/// A message shown in the status bar, with its message class and number
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusMessage {
+    pub kind: MessageKind,
+    /// Message class (GuiStatusbar.MessageId, e.g. "00")
+    #[serde(default)]
+    pub message_class: String,
+    /// Message number within the class (e.g. "343")
+    #[serde(default)]
+    pub message_number: String,
+    /// Values of the message placeholders (&1 to &4)
+    #[serde(default)]
+    pub params: Vec<String>,
+    #[serde(default)]
+    pub text: String,
+}
+
+impl StatusMessage {
+    pub fn new(kind: MessageKind, message_class: &str, message_number: &str, text: &str) -> Self {
+        Self {
+            kind,
+            message_class: message_class.to_string(),
+            message_number: message_number.to_string(),
+            params: Vec::new(),
+            text: text.to_string(),
+        }
+    }
+
+    pub fn with_params(mut self, params: &[&str]) -> Self {
+        self.params = params.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// Check whether this is the given message, e.g. `is(TRANSACTION_NOT_FOUND)`
+    pub fn is(&self, (message_class, message_number): (&str, &str)) -> bool {
+        self.message_class.eq_ignore_ascii_case(message_class)
+            && self.message_number.trim_start_matches('0') == message_number.trim_start_matches('0')
+    }
+
+    /// Check whether the message is an error or abort message
+    pub fn is_error(&self) -> bool {
+        matches!(self.kind, MessageKind::Error | MessageKind::Abort)
+    }
+
+    /// Check whether the status bar is empty
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && self.message_class.is_empty()
+    }
+}
+
+impl fmt::Display for StatusMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.message_class.is_empty() {
+            write!(f, "{}: {}", self.kind.code(), self.text)
+        } else {
+            write!(
+                f,
+                "{}({}/{}): {}",
+                self.kind.code(),
+                self.message_class,
+                self.message_number,
+                self.text
+            )
+        }
+    }
+}
+
+/// Read the status bar of a window (None if it is empty or the window has none)
+pub fn read_status(session: &dyn SapSession, wnd: i32) -> Result<Option<StatusMessage>> {
+    let Ok(statusbar) = session.find_control(&ControlId::window(wnd).join("/sbar")?) else {
+        return Ok(None);
+    };
+
+    let message = statusbar.status_message()?;
+    Ok((!message.is_empty()).then_some(message))
+}
@@ -1,37 +1,49 @@
 use crate::utils::close_popups;
 use crate::utils::sap_constants::STR_FORM;
-use crate::utils::sap_ctrl_utils::hit_ctrl;
-use sap_scripting::*;
-use std::thread;
-use std::time::Duration;
+use crate::utils::sap_interfaces::SapSession;
+use crate::utils::sap_session_state::SessionState;
+use crate::utils::sap_status::{read_status, TRANSACTION_NOT_FOUND};
+use crate::utils::sap_wait::{wait_timeouts, wait_until, WaitCondition};
 use windows::core::Result;
 
-pub fn assert_tcode(session: &GuiSession, tcode: &str, wnd: Option<i32>) -> Result<bool> {
+pub fn assert_tcode(session: &dyn SapSession, tcode: &str, wnd: Option<i32>) -> Result<bool> {
     let wnd_num = wnd.unwrap_or(0);
 
     // Start the transaction
     session.start_transaction(tcode.to_string())?;
 
+    // Wait for the transaction to load before reading the status bar
+    let timeouts = wait_timeouts();
+    wait_until(
+        session,
+        &WaitCondition::NotBusy,
+        timeouts.default,
+        timeouts.poll,
+    )?;
+
     // Check for errors in status bar
-    let err_msg = hit_ctrl(session, wnd_num, "/sbar", "Text", "Get", "")?;
+    let Some(message) = read_status(session, wnd_num)? else {
+        return Ok(true);
+    };
 
-    if err_msg.contains("exist") || err_msg.contains("autho") {
-        println!("Error: {}", err_msg);
+    if message.is(TRANSACTION_NOT_FOUND) {
+        println!("Error: transaction {} does not exist", tcode);
         return Ok(false);
     }
 
-    if err_msg.is_empty() {
-        return Ok(true);
+    if message.is_error() {
+        println!("Error: {}", message);
+        return Ok(false);
     }
 
-    // Log error message
-    println!("{}{}{}", STR_FORM, err_msg, STR_FORM);
+    // Log message
+    println!("{}{}{}", STR_FORM, message, STR_FORM);
 
     Ok(false)
 }
 
 pub fn check_tcode(
-    session: &GuiSession,
+    session: &dyn SapSession,
     tcode: &str,
     run: Option<bool>,
     _kill_popups: Option<bool>,
@@ -45,6 +57,15 @@ pub fn check_tcode(
         close_popups(session, None, None)?;
     }
 
+    let state = SessionState::detect(session)?;
+    if !state.is_logged_in() {
+        println!(
+            "Cannot check tCode ({}), SAP session is at {}",
+            tcode, state
+        );
+        return Ok(false);
+    }
+
     // Get current transaction
     let current = session.info()?.transaction()?;
 
@@ -55,8 +76,15 @@ pub fn check_tcode(
     } else if run_val {
         // Run if requested
         println!("tCode mismatch, attempting to run tCode ({})", tcode);
-        let _ = assert_tcode(session, tcode, None)?;
-        thread::sleep(Duration::from_millis(500)); // Time_Event equivalent
+        if !state.can_start_transaction() {
+            println!("Closing {} before running tCode ({})", state, tcode);
+            close_popups(session, None, None)?;
+        }
+        if assert_tcode(session, tcode, None)? {
+            let timeouts = wait_timeouts();
+            let condition = WaitCondition::Transaction(tcode.to_string());
+            wait_until(session, &condition, timeouts.default, timeouts.poll)?;
+        }
 
         // Recursive call to check again
         return check_tcode(session, tcode, Some(false), Some(false));
@@ -69,15 +97,13 @@ pub fn check_tcode(
     }
 }
 
-pub fn variant_select(session: &GuiSession, tcode: &str, variant_name: &str) -> Result<bool> {
+pub fn variant_select(session: &dyn SapSession, tcode: &str, variant_name: &str) -> Result<bool> {
     println!("Selecting variant '{}' for tCode '{}'", variant_name, tcode);
 
     // Choose variant
     if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[17]".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        } else {
-            println!("Failed to downcast variant button to GuiButton");
+        if btn.press().is_err() {
+            println!("Failed to press variant button");
             return Ok(false);
         }
     } else {
@@ -86,18 +112,15 @@ pub fn variant_select(session: &GuiSession, tcode: &str, variant_name: &str) ->
     }
 
     // Check if variant selection window opened
-    let err_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-    if err_msg.contains("error") || err_msg.contains("not found") {
-        println!("Error opening variant selection: {}", err_msg);
+    if let Some(message) = read_status(session, 0)?.filter(|m| m.is_error()) {
+        println!("Error opening variant selection: {}", message);
         return Ok(false);
     }
 
     // Enter variant name
     if let Ok(txt) = session.find_by_id("wnd[1]/usr/txtV-LOW".to_string()) {
-        if let Some(text_field) = txt.downcast::<GuiTextField>() {
-            text_field.set_text(variant_name.to_string())?;
-        } else {
-            println!("Failed to downcast variant name field to GuiTextField");
+        if txt.set_text(variant_name.to_string()).is_err() {
+            println!("Failed to set variant name field");
             return Ok(false);
         }
     } else {
@@ -107,9 +130,7 @@ pub fn variant_select(session: &GuiSession, tcode: &str, variant_name: &str) ->
 
     // Blank username
     if let Ok(txt) = session.find_by_id("wnd[1]/usr/txtENAME-LOW".to_string()) {
-        if let Some(text_field) = txt.downcast::<GuiTextField>() {
-            text_field.set_text("".to_string())?;
-        } else {
+        if txt.set_text("".to_string()).is_err() {
             // If we can't find or use the username field, just continue
             println!("Warning: Could not clear username field, continuing anyway");
         }
@@ -117,10 +138,8 @@ pub fn variant_select(session: &GuiSession, tcode: &str, variant_name: &str) ->
 
     // Close variant select window
     if let Ok(btn) = session.find_by_id("wnd[1]/tbar[0]/btn[8]".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        } else {
-            println!("Failed to downcast confirm button to GuiButton");
+        if btn.press().is_err() {
+            println!("Failed to press confirm button");
             return Ok(false);
         }
     } else {
@@ -129,9 +148,8 @@ pub fn variant_select(session: &GuiSession, tcode: &str, variant_name: &str) ->
     }
 
     // Check for errors in status bar after variant selection
-    let err_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-    if !err_msg.is_empty() && (err_msg.contains("error") || err_msg.contains("not found")) {
-        println!("Error selecting variant: {}", err_msg);
+    if let Some(message) = read_status(session, 0)?.filter(|m| m.is_error()) {
+        println!("Error selecting variant: {}", message);
         return Ok(false);
     }
 
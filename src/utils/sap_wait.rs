@@ -0,0 +1,211 @@
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+use windows::core::{Error, Result};
+
+use crate::utils::config_types::SapConfig;
+use crate::utils::sap_constants::{RPC_E_CALL_REJECTED, RPC_E_SERVERCALL_RETRYLATER};
+use crate::utils::sap_control_id::ControlId;
+use crate::utils::sap_interfaces::SapSession;
+
+/// A state of the SAP GUI (or the file system) to wait for
+#[derive(Debug, Clone, PartialEq)]
+pub enum WaitCondition {
+    /// The session has finished processing the last request
+    NotBusy,
+    /// Window wnd[n] is open
+    WindowExists(i32),
+    /// Window wnd[n] is closed
+    WindowClosed(i32),
+    /// The text of a control contains the given text
+    TextContains { id: String, text: String },
+    /// The given transaction is active
+    Transaction(String),
+    /// A file exists and its size has not changed for `stable_for`
+    FileStable { path: PathBuf, stable_for: Duration },
+}
+
+impl fmt::Display for WaitCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitCondition::NotBusy => write!(f, "session not busy"),
+            WaitCondition::WindowExists(wnd) => write!(f, "window wnd[{}] to open", wnd),
+            WaitCondition::WindowClosed(wnd) => write!(f, "window wnd[{}] to close", wnd),
+            WaitCondition::TextContains { id, text } => {
+                write!(f, "text of {} to contain '{}'", id, text)
+            }
+            WaitCondition::Transaction(tcode) => write!(f, "transaction {}", tcode),
+            WaitCondition::FileStable { path, .. } => {
+                write!(f, "file {} to be written", path.display())
+            }
+        }
+    }
+}
+
+/// Size of a file and when it was first seen at that size
+type FileProgress = Option<(u64, Instant)>;
+
+impl WaitCondition {
+    /// Check the condition once
+    fn check(&self, session: &dyn SapSession, progress: &mut FileProgress) -> Result<bool> {
+        match self {
+            WaitCondition::NotBusy => Ok(!session.busy()?),
+            WaitCondition::WindowExists(wnd) => window_open(session, *wnd),
+            WaitCondition::WindowClosed(wnd) => Ok(!window_open(session, *wnd)?),
+            WaitCondition::TextContains { id, text } => match session.find_by_id(id.clone()) {
+                Ok(component) => Ok(component.get_text()?.contains(text.as_str())),
+                Err(e) if is_busy_error(&e) => Err(e),
+                Err(_) => Ok(false),
+            },
+            WaitCondition::Transaction(tcode) => {
+                let current = session.info()?.transaction()?;
+                Ok(current.eq_ignore_ascii_case(tcode))
+            }
+            WaitCondition::FileStable { path, stable_for } => {
+                let Ok(metadata) = fs::metadata(path) else {
+                    return Ok(false);
+                };
+                let size = metadata.len();
+                match progress {
+                    Some((last_size, since)) if *last_size == size => {
+                        Ok(size > 0 && since.elapsed() >= *stable_for)
+                    }
+                    _ => {
+                        *progress = Some((size, Instant::now()));
+                        Ok(false)
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn window_open(session: &dyn SapSession, wnd: i32) -> Result<bool> {
+    match session.find_control(&ControlId::window(wnd)) {
+        Ok(_) => Ok(true),
+        Err(e) if is_busy_error(&e) => Err(e),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Check whether an error means the SAP GUI is busy and the call can be retried
+pub fn is_busy_error(error: &Error) -> bool {
+    [RPC_E_CALL_REJECTED, RPC_E_SERVERCALL_RETRYLATER].contains(&error.code().0)
+}
+
+/// Poll a check until it succeeds or the timeout expires
+///
+/// Calls rejected because the SAP GUI is busy count as "not yet", any other error
+/// is returned. Returns false on timeout.
+pub fn poll_until(
+    mut check: impl FnMut() -> Result<bool>,
+    timeout: Duration,
+    poll: Duration,
+) -> Result<bool> {
+    let start = Instant::now();
+    loop {
+        match check() {
+            Ok(true) => return Ok(true),
+            Ok(false) => {}
+            Err(e) if is_busy_error(&e) => {}
+            Err(e) => return Err(e),
+        }
+
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            return Ok(false);
+        }
+        thread::sleep(poll.min(timeout - elapsed));
+    }
+}
+
+/// Wait until a condition holds, polling every `poll`
+///
+/// Returns false (after logging) if the condition did not hold within `timeout`.
+pub fn wait_until(
+    session: &dyn SapSession,
+    condition: &WaitCondition,
+    timeout: Duration,
+    poll: Duration,
+) -> Result<bool> {
+    let mut progress = None;
+    let met = poll_until(|| condition.check(session, &mut progress), timeout, poll)?;
+    if !met {
+        println!(
+            "Timed out after {} ms waiting for {}",
+            timeout.as_millis(),
+            condition
+        );
+    }
+    Ok(met)
+}
+
+/// Timeouts used when waiting for SAP, configurable in `[global]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaitTimeouts {
+    /// Default timeout (`wait_timeout_ms`)
+    pub default: Duration,
+    /// Interval between checks (`wait_poll_ms`)
+    pub poll: Duration,
+    /// Timeout for popups to close (`popup_timeout_ms`)
+    pub popup: Duration,
+    /// Timeout for saving an export (`save_timeout_ms`)
+    pub save: Duration,
+    /// Timeout for the logon to complete (`login_timeout_ms`)
+    pub login: Duration,
+    /// How long a saved file must keep its size to count as written (`file_stable_ms`)
+    pub file_stable: Duration,
+}
+
+impl Default for WaitTimeouts {
+    fn default() -> Self {
+        Self {
+            default: Duration::from_millis(10000),
+            poll: Duration::from_millis(200),
+            popup: Duration::from_millis(2000),
+            save: Duration::from_millis(30000),
+            login: Duration::from_millis(15000),
+            file_stable: Duration::from_millis(1000),
+        }
+    }
+}
+
+impl WaitTimeouts {
+    /// Read the timeouts from `[global]`, keeping the defaults for missing values
+    ///
+    /// Invalid values are left out when the config is loaded (see `dropped_keys`).
+    pub fn from_config(config: &SapConfig) -> Self {
+        let defaults = Self::default();
+        let run = config
+            .global
+            .as_ref()
+            .map(|g| g.run.clone())
+            .unwrap_or_default();
+        let millis = |value: Option<u64>, default: Duration| {
+            value.map(Duration::from_millis).unwrap_or(default)
+        };
+
+        Self {
+            default: millis(run.wait_timeout_ms, defaults.default),
+            poll: millis(run.wait_poll_ms, defaults.poll),
+            popup: millis(run.popup_timeout_ms, defaults.popup),
+            save: millis(run.save_timeout_ms, defaults.save),
+            login: millis(run.login_timeout_ms, defaults.login),
+            file_stable: millis(run.file_stable_ms, defaults.file_stable),
+        }
+    }
+}
+
+static TIMEOUTS: OnceLock<WaitTimeouts> = OnceLock::new();
+
+/// Get the timeouts from config.toml (defaults if it cannot be loaded)
+pub fn wait_timeouts() -> &'static WaitTimeouts {
+    TIMEOUTS.get_or_init(|| {
+        SapConfig::load()
+            .map(|config| WaitTimeouts::from_config(&config))
+            .unwrap_or_default()
+    })
+}
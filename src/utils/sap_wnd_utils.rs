@@ -1,12 +1,14 @@
-use crate::utils::sap_constants::{ErrorCheck, ParamsStruct, TIME_FORMAT};
-use crate::utils::sap_ctrl_utils::{exist_ctrl, hit_ctrl};
+use crate::utils::sap_constants::{ErrorCheck, ParamsStruct, MAX_MODAL_WINDOWS, TIME_FORMAT};
+use crate::utils::sap_interfaces::{exist_ctrl, hit_ctrl, SapSession};
+use crate::utils::sap_popups::popup_rules;
+use crate::utils::sap_screens::{SapScreen, EASY_ACCESS, LOGIN, SAVE_LIST_IN_FILE};
 use crate::utils::sap_tcode_utils::check_tcode;
+use crate::utils::sap_wait::{wait_timeouts, wait_until, WaitCondition};
 use chrono;
-use sap_scripting::*;
 use windows::core::Result;
 
 pub fn close_popups(
-    session: &GuiSession,
+    session: &dyn SapSession,
     wnd_idx: Option<i32>,
     repeat: Option<i32>,
 ) -> Result<bool> {
@@ -20,7 +22,7 @@ pub fn close_popups(
 
                 // Find the highest index popup
                 let mut highest_idx = 0;
-                for i in 1..=5 {
+                for i in 1..=MAX_MODAL_WINDOWS {
                     let err_wnd = exist_ctrl(session, i, "", true)?;
                     if err_wnd.cband {
                         highest_idx = i;
@@ -42,7 +44,7 @@ pub fn close_popups(
                 // Original behavior: close all popups from 5 down to 1
                 println!("Closing all popups");
 
-                for i in (1..=5).rev() {
+                for i in (1..=MAX_MODAL_WINDOWS).rev() {
                     close_specific_popup(session, i)?;
                 }
             }
@@ -52,8 +54,10 @@ pub fn close_popups(
     Ok(true)
 }
 
-fn close_specific_popup(session: &GuiSession, i: i32) -> Result<bool> {
+fn close_specific_popup(session: &dyn SapSession, i: i32) -> Result<bool> {
     let max_tries = 5;
+    let timeouts = wait_timeouts();
+    let closed = WaitCondition::WindowClosed(i);
     let mut j = 0;
 
     while j < max_tries {
@@ -61,36 +65,27 @@ fn close_specific_popup(session: &GuiSession, i: i32) -> Result<bool> {
         if err_wnd.cband {
             println!("Closing window ({})", i);
 
-            // First attempt: try to close the window using close()
-            if let Ok(component) = session.find_by_id(format!("wnd[{}]", i)) {
-                if let Some(window) = component.downcast::<GuiModalWindow>() {
-                    window.close()?;
+            // Let a popup rule handle the window, close it blindly if none applies
+            if !popup_rules().close_window(session, i)? {
+                // First attempt: try to close the window using close()
+                if let Ok(component) = session.find_by_id(format!("wnd[{}]", i)) {
+                    component.close()?;
                 }
             }
 
             // Check if window is still open after first attempt
-            let still_open = exist_ctrl(session, i, "", true)?;
-            if still_open.cband {
+            if !wait_until(session, &closed, timeouts.popup, timeouts.poll)? {
                 // Second attempt: try to close the window using F12
                 if let Ok(component) = session.find_by_id(format!("wnd[{}]", i)) {
-                    if let Some(window) = component.downcast::<GuiModalWindow>() {
-                        window.send_v_key(0)?; // Send Enter key
-                    } else if let Some(modal_window) = component.downcast::<GuiModalWindow>() {
-                        modal_window.send_v_key(12)?; // Send F12 (Cancel)
-                    }
+                    component.send_v_key(0)?; // Send Enter key
                 }
 
                 // Check if window is still open after second attempt
-                let still_open_after_second = exist_ctrl(session, i, "", true)?;
-                if still_open_after_second.cband {
+                if !wait_until(session, &closed, timeouts.popup, timeouts.poll)? {
                     println!("Window {} still open, trying vkey0 (Enter)", i);
                     // Third attempt: try to close using vkey0 (Enter key)
                     if let Ok(component) = session.find_by_id(format!("wnd[{}]", i)) {
-                        if let Some(window) = component.downcast::<GuiModalWindow>() {
-                            window.send_v_key(0)?; // Send Enter key
-                        } else if let Some(modal_window) = component.downcast::<GuiModalWindow>() {
-                            modal_window.send_v_key(0)?; // Send Enter key
-                        }
+                        component.send_v_key(0)?; // Send Enter key
                     }
                 }
             }
@@ -103,27 +98,10 @@ fn close_specific_popup(session: &GuiSession, i: i32) -> Result<bool> {
                     i + 1,
                     next_err_wnd.ctext
                 );
-                if next_err_wnd.ctext.contains("multiple selection") {
-                    // Press no
-                    let btn_err_wnd = exist_ctrl(session, i + 1, "/usr/btnSPOP-OPTION2", true)?;
-                    if btn_err_wnd.cband {
-                        if let Ok(component) =
-                            session.find_by_id(format!("wnd[{}]/usr/btnSPOP-OPTION2", i + 1))
-                        {
-                            if let Some(button) = component.downcast::<GuiButton>() {
-                                button.press()?;
-                            }
-                        }
-                        let _ = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-                    }
-                } else {
+                if !popup_rules().close_window(session, i + 1)? {
                     // Try to close this new popup
                     if let Ok(component) = session.find_by_id(format!("wnd[{}]", i + 1)) {
-                        if let Some(window) = component.downcast::<GuiModalWindow>() {
-                            window.close()?;
-                        } else if let Some(modal_window) = component.downcast::<GuiModalWindow>() {
-                            modal_window.send_v_key(0)?; // Send Enter key
-                        }
+                        component.close()?;
                     }
                     continue; // Go back to the top of the loop
                 }
@@ -149,48 +127,49 @@ fn close_specific_popup(session: &GuiSession, i: i32) -> Result<bool> {
     Ok(true)
 }
 
-pub fn check_export_window(session: &GuiSession, tcode: &str, correct_title: &str) -> Result<bool> {
+pub fn check_export_window(
+    session: &dyn SapSession,
+    tcode: &str,
+    export_screen: &SapScreen,
+) -> Result<bool> {
     // Check if tcode is active
     if !check_tcode(session, tcode, Some(false), Some(false))? {
         println!("tCode ({}) not active, exiting....", tcode);
         return Ok(false);
     }
 
+    let timeouts = wait_timeouts();
+    let export_window = WaitCondition::WindowExists(1);
+
     loop {
         // Check for window
         let err_wnd = exist_ctrl(session, 1, "", true)?;
 
         if err_wnd.cband {
-            println!("Looking for: {}", correct_title);
+            println!("Looking for: {}", export_screen.name);
 
             if err_wnd.ctext.contains("Select Spreadsheet") {
                 // Press Excel button
                 if let Ok(component) = session.find_by_id("wnd[1]/tbar[0]/btn[0]".to_string()) {
-                    if let Some(button) = component.downcast::<GuiButton>() {
-                        button.press()?;
-                    }
+                    component.press()?;
                 }
                 return Ok(true);
-            } else if err_wnd.ctext.contains("SAVE LIST IN FILE...") {
+            } else if SAVE_LIST_IN_FILE.matches(session, 1, &err_wnd.ctext)? {
                 println!("Window Title {}", err_wnd.ctext);
                 println!("Saving as 'local file'");
 
                 // Select local file radio button
                 if let Ok(component) = session.find_by_id("wnd[1]/usr/subSUBSCREEN_STEPLOOP:SAPLSPO5:0150/sub:SAPLSPO5:0150/radSPOPLI-SELFLAG[1,0]".to_string()) {
-                    if let Some(radio) = component.downcast::<GuiRadioButton>() {
-                        radio.select()?;
-                    }
+                    component.select()?;
                 }
 
                 // Press button
                 if let Ok(component) = session.find_by_id("wnd[1]/tbar[0]/btn[0]".to_string()) {
-                    if let Some(button) = component.downcast::<GuiButton>() {
-                        button.press()?;
-                    }
+                    component.press()?;
                 }
 
                 return Ok(true);
-            } else if err_wnd.ctext.contains(correct_title) {
+            } else if export_screen.matches(session, 1, &err_wnd.ctext)? {
                 return Ok(true);
             } else {
                 println!("Error with Excel Export, trying Local File, trying to correct...");
@@ -208,11 +187,11 @@ pub fn check_export_window(session: &GuiSession, tcode: &str, correct_title: &st
 
                     // Send F12 (key 44)
                     if let Ok(component) = session.find_by_id("wnd[0]".to_string()) {
-                        if let Some(window) = component.downcast::<GuiFrameWindow>() {
-                            window.send_v_key(44)?;
-                        }
+                        component.send_v_key(44)?;
+                    }
+                    if wait_until(session, &export_window, timeouts.default, timeouts.poll)? {
+                        continue; // Go back to the top of the loop
                     }
-                    continue; // Go back to the top of the loop
                 }
                 "MB51" => {
                     base_obj_id = "/usr/cntlGRID1/shellcont/shell";
@@ -220,21 +199,21 @@ pub fn check_export_window(session: &GuiSession, tcode: &str, correct_title: &st
                     let err_wnd = exist_ctrl(session, 0, base_obj_id, true)?;
 
                     if err_wnd.cband {
-                        if let Ok(component) = session.find_by_id(obj_id.clone()) {
-                            if let Some(grid) = component.downcast::<GuiGridView>() {
-                                grid.set_selected_rows("0".to_string())?;
-                                grid.set_current_cell_row(-1)?;
-                                grid.context_menu()?;
-                                grid.select_context_menu_item("&XXL".to_string())?;
-                            }
+                        if let Ok(grid) = session.find_by_id(obj_id.clone()) {
+                            grid.set_selected_rows("0".to_string())?;
+                            grid.set_current_cell_row(-1)?;
+                            grid.context_menu()?;
+                            grid.select_context_menu_item("&XXL".to_string())?;
+                        }
+
+                        if !wait_until(session, &export_window, timeouts.default, timeouts.poll)? {
+                            break;
                         }
 
-                        if let Ok(component) =
+                        if let Ok(checkbox) =
                             session.find_by_id("wnd[1]/usr/chkCB_ALWAYS".to_string())
                         {
-                            if let Some(checkbox) = component.downcast::<GuiCheckBox>() {
-                                checkbox.set_selected(false)?;
-                            }
+                            checkbox.set_selected(false)?;
                         }
 
                         continue; // Go back to the top of the loop
@@ -247,21 +226,21 @@ pub fn check_export_window(session: &GuiSession, tcode: &str, correct_title: &st
 
                     if err_wnd.cband {
                         // Similar to MB51 handling
-                        if let Ok(component) = session.find_by_id(obj_id.clone()) {
-                            if let Some(grid) = component.downcast::<GuiGridView>() {
-                                grid.set_selected_rows("0".to_string())?;
-                                grid.set_current_cell_row(-1)?;
-                                grid.context_menu()?;
-                                grid.select_context_menu_item("&XXL".to_string())?;
-                            }
+                        if let Ok(grid) = session.find_by_id(obj_id.clone()) {
+                            grid.set_selected_rows("0".to_string())?;
+                            grid.set_current_cell_row(-1)?;
+                            grid.context_menu()?;
+                            grid.select_context_menu_item("&XXL".to_string())?;
+                        }
+
+                        if !wait_until(session, &export_window, timeouts.default, timeouts.poll)? {
+                            break;
                         }
 
-                        if let Ok(component) =
+                        if let Ok(checkbox) =
                             session.find_by_id("wnd[1]/usr/chkCB_ALWAYS".to_string())
                         {
-                            if let Some(checkbox) = component.downcast::<GuiCheckBox>() {
-                                checkbox.set_selected(false)?;
-                            }
+                            checkbox.set_selected(false)?;
                         }
 
                         continue; // Go back to the top of the loop
@@ -274,21 +253,21 @@ pub fn check_export_window(session: &GuiSession, tcode: &str, correct_title: &st
 
                     if err_wnd.cband {
                         // Similar to MB51 handling
-                        if let Ok(component) = session.find_by_id(obj_id.clone()) {
-                            if let Some(grid) = component.downcast::<GuiGridView>() {
-                                grid.set_selected_rows("0".to_string())?;
-                                grid.set_current_cell_row(-1)?;
-                                grid.context_menu()?;
-                                grid.select_context_menu_item("&XXL".to_string())?;
-                            }
+                        if let Ok(grid) = session.find_by_id(obj_id.clone()) {
+                            grid.set_selected_rows("0".to_string())?;
+                            grid.set_current_cell_row(-1)?;
+                            grid.context_menu()?;
+                            grid.select_context_menu_item("&XXL".to_string())?;
                         }
 
-                        if let Ok(component) =
+                        if !wait_until(session, &export_window, timeouts.default, timeouts.poll)? {
+                            break;
+                        }
+
+                        if let Ok(checkbox) =
                             session.find_by_id("wnd[1]/usr/chkCB_ALWAYS".to_string())
                         {
-                            if let Some(checkbox) = component.downcast::<GuiCheckBox>() {
-                                checkbox.set_selected(false)?;
-                            }
+                            checkbox.set_selected(false)?;
                         }
 
                         continue; // Go back to the top of the loop
@@ -309,7 +288,11 @@ pub fn check_export_window(session: &GuiSession, tcode: &str, correct_title: &st
     Ok(false)
 }
 
-pub fn check_wnd(session: &GuiSession, n_wnd: i32, params_in: &ParamsStruct) -> Result<ErrorCheck> {
+pub fn check_wnd(
+    session: &dyn SapSession,
+    n_wnd: i32,
+    params_in: &ParamsStruct,
+) -> Result<ErrorCheck> {
     let get_time = chrono::Local::now().format(TIME_FORMAT).to_string();
 
     let mut err_chk = ErrorCheck {
@@ -326,97 +309,60 @@ pub fn check_wnd(session: &GuiSession, n_wnd: i32, params_in: &ParamsStruct) ->
         match wnd_type.as_str() {
             "GuiFrameWindow" => {}
             "GuiMainWindow" => {
-                match wnd_title.as_str() {
-                    "SAP Easy Access" => {
-                        err_chk.bchgb = true;
-                        err_chk.msg = String::new();
-
-                        // Maximize SAP Easy Access Window
-                        let err_ctl = exist_ctrl(session, n_wnd, "", true)?;
-                        if err_ctl.cband {
-                            let _ = hit_ctrl(session, n_wnd, "", "Focus", "", "")?;
-                            let _ = hit_ctrl(session, n_wnd, "", "Maximize", "", "")?;
-                        }
+                if EASY_ACCESS.matches(session, n_wnd, &wnd_title)? {
+                    err_chk.bchgb = true;
+                    err_chk.msg = String::new();
+
+                    // Maximize SAP Easy Access Window
+                    let err_ctl = exist_ctrl(session, n_wnd, "", true)?;
+                    if err_ctl.cband {
+                        let _ = hit_ctrl(session, n_wnd, "", "Focus", "", "")?;
+                        let _ = hit_ctrl(session, n_wnd, "", "Maximize", "", "")?;
+                    }
+                } else if LOGIN.matches(session, n_wnd, &wnd_title)? {
+                    // Login Initial Screen
+                    let ctrl_id = "/usr/txtRSYST-MANDT"; // Client
+                    let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
+                    if err_ctl.cband {
+                        let _ =
+                            hit_ctrl(session, n_wnd, ctrl_id, "Text", "Set", &params_in.client_id)?;
                     }
-                    "SAP R/3" | "SAP" => {
-                        // Login Initial Screen
-                        let ctrl_id = "/usr/txtRSYST-MANDT"; // Client
-                        let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
-                        if err_ctl.cband {
-                            let _ = hit_ctrl(
-                                session,
-                                n_wnd,
-                                ctrl_id,
-                                "Text",
-                                "Set",
-                                &params_in.client_id,
-                            )?;
-                        }
-
-                        let ctrl_id = "/usr/txtRSYST-BNAME"; // User
-                        let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
-                        if err_ctl.cband {
-                            let _ =
-                                hit_ctrl(session, n_wnd, ctrl_id, "Text", "Set", &params_in.user)?;
-                        }
 
-                        let ctrl_id = "/usr/pwdRSYST-BCODE"; // Password
-                        let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
-                        if err_ctl.cband {
-                            let _ =
-                                hit_ctrl(session, n_wnd, ctrl_id, "Text", "Set", &params_in.pass)?;
-                        }
+                    let ctrl_id = "/usr/txtRSYST-BNAME"; // User
+                    let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
+                    if err_ctl.cband {
+                        let _ = hit_ctrl(session, n_wnd, ctrl_id, "Text", "Set", &params_in.user)?;
+                    }
 
-                        let ctrl_id = "/usr/txtRSYST-LANGU"; // Language
-                        let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
-                        if err_ctl.cband {
-                            let _ = hit_ctrl(
-                                session,
-                                n_wnd,
-                                ctrl_id,
-                                "Text",
-                                "Set",
-                                &params_in.language,
-                            )?;
-                        }
+                    let ctrl_id = "/usr/pwdRSYST-BCODE"; // Password
+                    let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
+                    if err_ctl.cband {
+                        let _ = hit_ctrl(session, n_wnd, ctrl_id, "Text", "Set", &params_in.pass)?;
+                    }
 
-                        let ctrl_id = "/tbar[0]/btn[0]"; // Enter
-                        let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
-                        if err_ctl.cband {
-                            let _ = hit_ctrl(session, n_wnd, ctrl_id, "Press", "", "")?;
-                        }
+                    let ctrl_id = "/usr/txtRSYST-LANGU"; // Language
+                    let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
+                    if err_ctl.cband {
+                        let _ =
+                            hit_ctrl(session, n_wnd, ctrl_id, "Text", "Set", &params_in.language)?;
+                    }
 
-                        err_chk.bchgb = true;
-                        err_chk.msg = get_time;
+                    let ctrl_id = "/tbar[0]/btn[0]"; // Enter
+                    let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
+                    if err_ctl.cband {
+                        let _ = hit_ctrl(session, n_wnd, ctrl_id, "Press", "", "")?;
                     }
-                    // Add other window title cases as needed
-                    _ => {}
+
+                    err_chk.bchgb = true;
+                    err_chk.msg = get_time;
                 }
+                // Add other screens as needed
             }
             "GuiModalWindow" => {
-                match wnd_title.as_str() {
-                    "Log Off" => {
-                        let ctrl_id = "/usr/btnSPOP-OPTION2";
-                        let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
-                        if err_ctl.cband {
-                            let _ = hit_ctrl(session, n_wnd, ctrl_id, "Press", "", "")?;
-                        }
-
-                        err_chk.bchgb = true;
-                        err_chk.msg = get_time;
-                    }
-                    "System Messages" => {
-                        let ctrl_id = "/tbar[0]/btn[0]";
-                        let err_ctl = exist_ctrl(session, n_wnd, ctrl_id, true)?;
-                        if err_ctl.cband {
-                            let _ = hit_ctrl(session, n_wnd, ctrl_id, "Press", "", "")?;
-                        }
-
-                        err_chk.bchgb = true;
-                        err_chk.msg = get_time;
-                    }
-                    // Add other modal window title cases as needed
-                    _ => {}
+                // Popups are handled by the popup rules (built-in ones and popups.toml)
+                if popup_rules().handle_window(session, n_wnd)?.is_some() {
+                    err_chk.bchgb = true;
+                    err_chk.msg = get_time;
                 }
             }
             _ => {}
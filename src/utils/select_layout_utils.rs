@@ -1,10 +1,13 @@
 use dialoguer::{Input, Select};
-use sap_scripting::*;
 use std::collections::HashMap;
 use std::thread;
 use std::time::Duration; // Added for interactive user input
 
-use crate::utils::sap_ctrl_utils::*;
+use crate::utils::sap_interfaces::{self, exist_ctrl, hit_ctrl, SapComponent, SapSession};
+use crate::utils::sap_screens::{
+    SapScreen, BIN_STATUS_LIST, VL06O_DELIVERY_LIST, VT11_SHIPMENT_LIST, ZMDESNR_SERIAL_NUMBER_LIST,
+};
+use crate::utils::sap_status::{read_status, FUNCTION_NOT_AVAILABLE, LAYOUT_APPLIED, NO_LAYOUTS_FOUND};
 use crate::utils::sap_wnd_utils::*;
 use crate::utils::utils::*;
 
@@ -33,7 +36,7 @@ pub struct Params {
 /// * `Ok(true)` if the layout was successfully selected
 /// * `Ok(false)` if the layout was not found or could not be selected and user chose to exit
 pub fn select_layout(
-    session: &GuiSession,
+    session: &dyn SapSession,
     n_wnd: i32,
     object_name: &str,
     layout_name: &str,
@@ -46,7 +49,7 @@ pub fn select_layout(
     // Loop until a valid layout is found or user chooses to exit
     loop {
         // Check if window exists
-        let err_wnd = exist_ctrl(session, n_wnd, "", true)?;
+        let err_wnd = sap_interfaces::exist_ctrl(session, n_wnd, "", true)?;
         if !err_wnd.cband {
             println!("Window not open, exiting...");
             return Ok(false);
@@ -56,7 +59,7 @@ pub fn select_layout(
         println!("Checking if layout exists...");
 
         // Check if object exists
-        let err_wnd = exist_ctrl(session, n_wnd, object_name, true)?;
+        let err_wnd = sap_interfaces::exist_ctrl(session, n_wnd, object_name, true)?;
         if !err_wnd.cband {
             println!("Object ({}) not found.", object_name);
             return Ok(false);
@@ -64,12 +67,10 @@ pub fn select_layout(
 
         // Get the object
         let obj_path = format!("wnd[{}]{}", n_wnd, object_name);
-        let obj = session.find_by_id(obj_path)?;
+        let grid = session.find_by_id(obj_path)?;
 
-        // Try to downcast to GuiGridView
-        if let Some(grid) = obj.downcast::<GuiGridView>() {
-            // Get row count
-            let row_count = grid.row_count()?;
+        // Make sure the object is a grid
+        if let Ok(row_count) = grid.row_count() {
             println!("Object has {} rows", row_count);
 
             // Scroll down to end (in case long)
@@ -125,11 +126,9 @@ pub fn select_layout(
                         println!("Layout selection cancelled");
 
                         // Close the window
-                        if let Ok(window) = session.find_by_id(format!("wnd[{}]", n_wnd)) {
-                            if let Some(wnd) = window.downcast::<GuiModalWindow>() {
-                                println!("Closing window since layout selection was cancelled.");
-                                wnd.close()?;
-                            }
+                        if let Ok(wnd) = session.find_by_id(format!("wnd[{}]", n_wnd)) {
+                            println!("Closing window since layout selection was cancelled.");
+                            wnd.close()?;
                         }
 
                         return Ok(false);
@@ -146,52 +145,52 @@ pub fn select_layout(
                     println!("Layout selection cancelled");
 
                     // Close the window
-                    if let Ok(window) = session.find_by_id(format!("wnd[{}]", n_wnd)) {
-                        if let Some(wnd) = window.downcast::<GuiModalWindow>() {
-                            println!("Closing window since layout selection was cancelled.");
-                            wnd.close()?;
-                        }
+                    if let Ok(wnd) = session.find_by_id(format!("wnd[{}]", n_wnd)) {
+                        println!("Closing window since layout selection was cancelled.");
+                        wnd.close()?;
                     }
 
                     return Ok(false);
                 }
             }
         } else {
-            println!("Object is not a GuiGridView.");
+            println!("Object is not a grid view.");
             return Ok(false);
         }
     }
 }
 
+/// Press a component if it is a button (other components, e.g. menu entries, are left alone)
+fn press_if_button(component: &dyn SapComponent) -> windows::core::Result<()> {
+    if component.r_type()? == "GuiButton" {
+        component.press()?;
+    }
+    Ok(())
+}
+
 /// Trigger layout popup based on transaction code
 ///
 /// This function is a port of the VBA function layout_popup
-pub fn layout_popup(session: &GuiSession, tcode: &str) -> windows::core::Result<bool> {
+pub fn layout_popup(session: &dyn SapSession, tcode: &str) -> windows::core::Result<bool> {
     match tcode.to_lowercase().as_str() {
         "lx03" | "lx02" => {
             // Select Layout
             if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
-                if let Some(btn) = button.downcast::<GuiButton>() {
-                    btn.press()?;
-                }
+                press_if_button(button.as_ref())?;
             }
         }
         "vt11" => {
             // Choose Layout Button
-            if let Ok(menu) = session.find_by_id("wnd[0]/mbar/menu[3]/menu[0]/menu[1]".to_string())
+            if let Ok(menu_item) = session.find_by_id("wnd[0]/mbar/menu[3]/menu[0]/menu[1]".to_string())
             {
-                if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-                    menu_item.select()?;
-                }
+                menu_item.select()?;
             }
         }
         "vl06o" => {
             // Choose Layout Button for VL06O
             if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string())
             {
-                if let Some(btn) = button.downcast::<GuiButton>() {
-                    btn.press()?;
-                }
+                press_if_button(button.as_ref())?;
             }
         }
         "zmdesnr" => {
@@ -200,17 +199,13 @@ pub fn layout_popup(session: &GuiSession, tcode: &str) -> windows::core::Result<
             if let Ok(button) =
                 session.find_by_id("wnd[0]/mbar/menu[4]/menu[0]/menu[1]".to_string())
             {
-                if let Some(btn) = button.downcast::<GuiButton>() {
-                    btn.press()?;
-                }
+                press_if_button(button.as_ref())?;
             } else {
                 // Check if button exists in toolbar
                 let err_ctl = exist_ctrl(session, 0, "/tbar[1]/btn[33]", true)?;
                 if err_ctl.cband {
                     if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
-                        if let Some(btn) = button.downcast::<GuiButton>() {
-                            btn.press()?;
-                        }
+                        press_if_button(button.as_ref())?;
                     }
                 }
             }
@@ -218,17 +213,13 @@ pub fn layout_popup(session: &GuiSession, tcode: &str) -> windows::core::Result<
         "mb52" => {
             // Check if button exists
             if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
-                if let Some(btn) = button.downcast::<GuiButton>() {
-                    btn.press()?;
-                }
+                press_if_button(button.as_ref())?;
             }
         }
         _ => {
             // Try common layout buttons
             if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
-                if let Some(btn) = button.downcast::<GuiButton>() {
-                    btn.press()?;
-                }
+                press_if_button(button.as_ref())?;
             }
         }
     }
@@ -285,7 +276,7 @@ pub fn get_layout_object_name(tcode: &str) -> String {
 /// * `Ok(true)` if a layout was successfully selected
 /// * `Ok(false)` if the user cancelled or no layout could be selected
 pub fn select_layout_interactive(
-    session: &GuiSession,
+    session: &dyn SapSession,
     tcode: &str,
     layout_name: &str,
 ) -> windows::core::Result<bool> {
@@ -320,7 +311,7 @@ pub fn select_layout_interactive(
 ///
 /// This function is a port of the VBA function check_select_layout
 pub fn check_select_layout(
-    session: &GuiSession,
+    session: &dyn SapSession,
     tcode: &str,
     layout_row: &str,
     args: Option<HashMap<String, String>>,
@@ -350,9 +341,7 @@ pub fn check_select_layout(
             "lx03" | "lx02" | "lt23" | "vt22" => {
                 // Select Layout
                 if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
-                    if let Some(btn) = button.downcast::<GuiButton>() {
-                        btn.press()?;
-                    }
+                    press_if_button(button.as_ref())?;
                 }
             }
             "vt11" => {
@@ -360,19 +349,13 @@ pub fn check_select_layout(
                 if let Ok(menu) =
                     session.find_by_id("wnd[0]/mbar/menu[3]/menu[0]/menu[1]".to_string())
                 {
-                    if let Some(menu_item) = menu.downcast::<GuiComponent>() {
-                        if let Some(btn) = menu_item.downcast::<GuiButton>() {
-                            btn.press()?;
-                        }
-                    }
+                    press_if_button(menu.as_ref())?;
                 }
             }
             "mb52" | "vl06o" => {
                 // Check if button exists
                 if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
-                    if let Some(btn) = button.downcast::<GuiButton>() {
-                        btn.press()?;
-                    }
+                    press_if_button(button.as_ref())?;
                 }
             }
             "zmdesnr" => {
@@ -381,18 +364,17 @@ pub fn check_select_layout(
                 if let Ok(button) =
                     session.find_by_id("wnd[0]/mbar/menu[4]/menu[0]/menu[1]".to_string())
                 {
-                    if let Some(btn) = button.downcast::<GuiButton>() {
-                        btn.press()?;
-                    }
+                    press_if_button(button.as_ref())?;
                 }
             }
             _ => {}
         }
 
         // Check for error in status bar
-        let bar_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-        if contains(&bar_msg, "valid function", Some(false)) {
-            local_r_val.err = bar_msg;
+        if let Some(message) =
+            read_status(session, 0)?.filter(|m| m.is(FUNCTION_NOT_AVAILABLE))
+        {
+            local_r_val.err = message.text;
             return Ok(local_r_val);
         }
 
@@ -430,28 +412,20 @@ pub fn check_select_layout(
                             let ctrl_msg = if let Ok(label) =
                                 session.find_by_id(format!("wnd[1]/usr/lbl[1,{}]", i))
                             {
-                                if let Some(lbl) = label.downcast::<GuiLabel>() {
-                                    lbl.text()?
-                                } else {
-                                    String::new()
-                                }
+                                label.get_text()?
                             } else {
                                 String::new()
                             };
 
                             if ctrl_msg.to_uppercase() == layout_row.to_uppercase() {
-                                if let Ok(label) =
+                                if let Ok(lbl) =
                                     session.find_by_id(format!("wnd[1]/usr/lbl[1,{}]", i))
                                 {
-                                    if let Some(lbl) = label.downcast::<GuiLabel>() {
-                                        lbl.set_focus()?;
-                                    }
+                                    lbl.set_focus()?;
                                 }
 
-                                if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                                    if let Some(wnd) = window.downcast::<GuiModalWindow>() {
-                                        wnd.send_v_key(2)?;
-                                    }
+                                if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                                    wnd.send_v_key(2)?;
                                 }
 
                                 println!("Layout number ({}), ({}) selected.", i, layout_row);
@@ -462,8 +436,8 @@ pub fn check_select_layout(
                     }
 
                     // Check status bar message
-                    let bar_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-                    if !contains(&bar_msg, "layout applied", Some(false)) {
+                    let applied = read_status(session, 0)?.is_some_and(|m| m.is(LAYOUT_APPLIED));
+                    if !applied {
                         // If layout not found, close any popups and setup layout
                         close_popups(session, None, None)?;
 
@@ -475,9 +449,7 @@ pub fn check_select_layout(
                                 if let Ok(button) =
                                     session.find_by_id("wnd[0]/tbar[1]/btn[32]".to_string())
                                 {
-                                    if let Some(btn) = button.downcast::<GuiButton>() {
-                                        btn.press()?;
-                                    }
+                                    press_if_button(button.as_ref())?;
                                 }
 
                                 // Setup layout
@@ -490,9 +462,7 @@ pub fn check_select_layout(
                                 if let Ok(menu) = session
                                     .find_by_id("wnd[0]/mbar/menu[3]/menu[0]/menu[0]".to_string())
                                 {
-                                    if let Some(btn) = menu.downcast::<GuiButton>() {
-                                        btn.press()?;
-                                    }
+                                    press_if_button(menu.as_ref())?;
                                 }
 
                                 // Setup layout_li
@@ -501,13 +471,8 @@ pub fn check_select_layout(
                             }
                             _ => {
                                 if let Ok(menu) = session
-                                    .find_by_id("wnd[0]/mbar/menu[3]/menu[2]/menu[0]".to_string())
-                                {
-                                    if let Some(menu_item) = menu.downcast::<GuiComponent>() {
-                                        if let Some(btn) = menu_item.downcast::<GuiButton>() {
-                                            btn.press()?;
-                                        }
-                                    }
+                                    .find_by_id("wnd[0]/mbar/menu[3]/menu[2]/menu[0]".to_string()) {
+                                    press_if_button(menu.as_ref())?;
                                 }
 
                                 // Setup layout_li
@@ -523,27 +488,24 @@ pub fn check_select_layout(
         }
 
         // Check status bar message
-        let bar_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-        if contains(&bar_msg, "Layout", Some(false)) {
-            println!("Status bar message: ({})", bar_msg);
+        if let Some(message) = read_status(session, 0)?
+            .filter(|m| m.is(LAYOUT_APPLIED) || m.is(NO_LAYOUTS_FOUND))
+        {
+            println!("Status bar message: ({})", message);
         }
 
         // Make sure all windows are closed
         close_popups(session, None, None)?;
 
         // Export based on tcode
-        let export_wnd_name = match tcode.to_lowercase().as_str() {
+        let export_screen = match tcode.to_lowercase().as_str() {
             "lx03" | "lx02" => {
                 if let Ok(menu) =
                     session.find_by_id("wnd[0]/mbar/menu[0]/menu[1]/menu[1]".to_string())
                 {
-                    if let Some(menu_item) = menu.downcast::<GuiComponent>() {
-                        if let Some(btn) = menu_item.downcast::<GuiButton>() {
-                            btn.press()?;
-                        }
-                    }
+                    press_if_button(menu.as_ref())?;
                 }
-                "BIN STATUS REPORT: OVERVIEW"
+                Some(&BIN_STATUS_LIST)
             }
             "vt11" => {
                 // Check for no results
@@ -560,44 +522,32 @@ pub fn check_select_layout(
                 if let Ok(menu) =
                     session.find_by_id("wnd[0]/mbar/menu[0]/menu[10]/menu[0]".to_string())
                 {
-                    if let Some(menu_item) = menu.downcast::<GuiComponent>() {
-                        if let Some(btn) = menu_item.downcast::<GuiButton>() {
-                            btn.press()?;
-                        }
-                    }
+                    press_if_button(menu.as_ref())?;
                 }
-                "SHIPMENT LIST: PLANNING"
+                Some(&VT11_SHIPMENT_LIST)
             }
             "zmdesnr" | "zvt11" => {
                 if let Ok(menu) =
                     session.find_by_id("wnd[0]/mbar/menu[0]/menu[3]/menu[1]".to_string())
                 {
-                    if let Some(menu_item) = menu.downcast::<GuiComponent>() {
-                        if let Some(btn) = menu_item.downcast::<GuiButton>() {
-                            btn.press()?;
-                        }
-                    }
+                    press_if_button(menu.as_ref())?;
                 }
-                "ZMDEMAIN SERIAL NUMBER HISTORY CONTENTS"
+                Some(&ZMDESNR_SERIAL_NUMBER_LIST)
             }
             "vl06o" => {
                 if let Ok(menu) =
                     session.find_by_id("wnd[0]/mbar/menu[0]/menu[5]/menu[1]".to_string())
                 {
-                    if let Some(menu_item) = menu.downcast::<GuiComponent>() {
-                        if let Some(btn) = menu_item.downcast::<GuiButton>() {
-                            btn.press()?;
-                        }
-                    }
+                    press_if_button(menu.as_ref())?;
                 }
-                "LIST OF OUTBOUND DELIVERIES"
+                Some(&VL06O_DELIVERY_LIST)
             }
-            _ => "",
+            _ => None,
         };
 
         // Check export window
-        if !export_wnd_name.is_empty() {
-            local_r_val.run_check = check_export_window(session, tcode, export_wnd_name)?;
+        if let Some(export_screen) = export_screen {
+            local_r_val.run_check = check_export_window(session, tcode, export_screen)?;
             if !local_r_val.run_check {
                 local_r_val.err = format!("Failed to check export window for {}", tcode);
                 return Ok(local_r_val);
@@ -611,7 +561,7 @@ pub fn check_select_layout(
 }
 
 /// Helper function for goto_setup
-fn goto_setup(session: &GuiSession, tcode: &str, layout_row: &str) -> windows::core::Result<()> {
+fn goto_setup(session: &dyn SapSession, tcode: &str, layout_row: &str) -> windows::core::Result<()> {
     // Implementation would go here
     println!(
         "Going to setup for tcode: {}, layout: {}",
@@ -621,7 +571,7 @@ fn goto_setup(session: &GuiSession, tcode: &str, layout_row: &str) -> windows::c
 }
 
 /// Helper function for goto_choose
-fn goto_choose(session: &GuiSession, tcode: &str, layout_row: &str) -> windows::core::Result<()> {
+fn goto_choose(session: &dyn SapSession, tcode: &str, layout_row: &str) -> windows::core::Result<()> {
     // Implementation would go here
     println!(
         "Going to choose for tcode: {}, layout: {}",
@@ -632,11 +582,11 @@ fn goto_choose(session: &GuiSession, tcode: &str, layout_row: &str) -> windows::
 
 /// Check export window
 ///
-/// This function checks if the export window is present and has the expected title
+/// This function checks if the export window is present and shows the expected screen
 fn check_export_window(
-    session: &GuiSession,
+    session: &dyn SapSession,
     tcode: &str,
-    expected_title: &str,
+    expected: &SapScreen,
 ) -> windows::core::Result<bool> {
     // Check if window exists
     let err_wnd = exist_ctrl(session, 0, "", true)?;
@@ -645,18 +595,18 @@ fn check_export_window(
         return Ok(false);
     }
 
-    // Check if window title matches expected title
-    if !contains(&err_wnd.ctext, expected_title, Some(false)) {
+    // Check if window shows the expected screen
+    if !expected.matches(session, 0, &err_wnd.ctext)? {
         println!(
-            "Export window title ({}) does not match expected title ({}).",
-            err_wnd.ctext, expected_title
+            "Export window ({}) does not show the expected screen ({}).",
+            err_wnd.ctext, expected.name
         );
         return Ok(false);
     }
 
     println!(
-        "Export window found with expected title: {}",
-        expected_title
+        "Export window found with expected screen: {}",
+        expected.name
     );
     Ok(true)
 }
@@ -6,9 +6,17 @@ use std::io;
 use std::thread;
 use std::time::Duration;
 
+use crate::app::session_watchdog;
 use crate::utils::config_types::SapConfig;
+use crate::utils::sap_interfaces::SapSession;
+use crate::utils::sap_popups::run_with_popup_rules;
+use crate::utils::sap_real_impl::RealSapSession;
+use crate::utils::sap_recording::run_with_trace;
+use crate::utils::sap_session_pool::{close_opened_sessions, pool_for_session, run_parallel, with_session};
+use crate::utils::sap_session_watchdog::SessionWatchdog;
 use crate::utils::config_types::{SequenceConfig as ConfigSequenceConfig, default_iterations, default_delay_seconds, default_interval_seconds};
 use crate::vl06o_delivery_module::run_vl06o_delivery_packages_auto;
+use crate::vt11_module::run_vt11_auto;
 use crate::zmdesnr_module::run_zmdesnr_auto;
 
 /// Structure to map menu options to their names and functions
@@ -21,6 +29,10 @@ pub struct MenuOption {
 /// Get available menu options for sequences
 pub fn get_available_menu_options() -> Vec<MenuOption> {
     vec![
+        MenuOption {
+            id: "2".to_string(),
+            name: "VT11 - Auto Run".to_string(),
+        },
         MenuOption {
             id: "9".to_string(),
             name: "ZMDESNR - Auto Run".to_string(),
@@ -32,6 +44,25 @@ pub fn get_available_menu_options() -> Vec<MenuOption> {
     ]
 }
 
+/// Options that only read and export data, so they can run side by side on separate sessions
+pub const READ_ONLY_OPTIONS: &[&str] = &["2", "7", "9"];
+
+/// Group the options of one iteration into steps, keeping the configured order
+///
+/// With parallel execution on, each run of consecutive read-only options is one step
+/// running them side by side.
+pub fn sequence_steps(options: &[String], parallel: bool) -> Vec<Vec<String>> {
+    let read_only = |option: &String| parallel && READ_ONLY_OPTIONS.contains(&option.as_str());
+    let mut steps: Vec<Vec<String>> = Vec::new();
+    for option in options {
+        match steps.last_mut() {
+            Some(step) if read_only(option) && step.iter().all(read_only) => step.push(option.clone()),
+            _ => steps.push(vec![option.clone()]),
+        }
+    }
+    steps
+}
+
 /// Get menu option name by ID
 pub fn get_menu_option_name(id: &str) -> String {
     for option in get_available_menu_options() {
@@ -43,15 +74,19 @@ pub fn get_menu_option_name(id: &str) -> String {
 }
 
 /// Execute a menu option by ID
-pub fn execute_menu_option(session: &GuiSession, id: &str) -> Result<()> {
+pub fn execute_menu_option(session: &dyn SapSession, id: &str) -> Result<()> {
     match id {
+        "2" => {
+            println!("Running VT11 Auto...");
+            run_with_trace(session, "VT11", |s| run_with_popup_rules(s, run_vt11_auto))?;
+        },
         "9" => {
             println!("Running ZMDESNR Auto...");
-            run_zmdesnr_auto(session)?;
+            run_with_trace(session, "ZMDESNR", |s| run_with_popup_rules(s, run_zmdesnr_auto))?;
         },
         "7" => {
             println!("Running VL06O Delivery Packages Auto...");
-            run_vl06o_delivery_packages_auto(session)?;
+            run_with_trace(session, "VL06O", |s| run_with_popup_rules(s, run_vl06o_delivery_packages_auto))?;
         },
         _ => {
             println!("Unknown option: {}", id);
@@ -67,6 +102,8 @@ pub struct SequenceConfig {
     pub iterations: usize,
     pub delay_seconds: u64,
     pub interval_seconds: u64,
+    /// Run consecutive read-only exports side by side (`param_parallel`)
+    pub parallel: bool,
     pub params: HashMap<String, String>,
 }
 
@@ -77,6 +114,7 @@ impl Default for SequenceConfig {
             iterations: 1,
             delay_seconds: 60,
             interval_seconds: 10,
+            parallel: false,
             params: HashMap::new(),
         }
     }
@@ -99,20 +137,11 @@ impl SequenceConfig {
                 // Get options
                 config.options = sequence_config.options.clone();
                 
-                // Get iterations
-                if let Ok(iter_val) = sequence_config.iterations.parse::<usize>() {
-                    config.iterations = iter_val;
-                }
-                
-                // Get delay seconds
-                if let Ok(delay_val) = sequence_config.delay_seconds.parse::<u64>() {
-                    config.delay_seconds = delay_val;
-                }
-                
-                // Get interval seconds
-                if let Ok(interval_val) = sequence_config.interval_seconds.parse::<u64>() {
-                    config.interval_seconds = interval_val;
-                }
+                // Get iterations, delay and interval seconds
+                config.iterations = sequence_config.iterations;
+                config.delay_seconds = sequence_config.delay_seconds;
+                config.interval_seconds = sequence_config.interval_seconds;
+                config.parallel = sequence_config.parallel.unwrap_or(false);
                 
                 // Get parameters
                 for (key, value) in &sequence_config.params {
@@ -145,9 +174,10 @@ impl SequenceConfig {
         
         let sequence_config = ConfigSequenceConfig {
             options: self.options.clone(),
-            iterations: self.iterations.to_string(),
-            delay_seconds: self.delay_seconds.to_string(),
-            interval_seconds: self.interval_seconds.to_string(),
+            iterations: self.iterations,
+            delay_seconds: self.delay_seconds,
+            interval_seconds: self.interval_seconds,
+            parallel: Some(self.parallel),
             params: sequence_params,
         };
         
@@ -174,6 +204,7 @@ pub fn handle_configure_sequence() -> Result<()> {
         "Configure Iterations",
         "Configure Delay (seconds)",
         "Configure Interval (seconds)",
+        "Toggle Parallel Exports",
         "Add/Edit Parameter",
         "Remove Parameter",
         "Show Current Configuration",
@@ -300,6 +331,15 @@ pub fn handle_configure_sequence() -> Result<()> {
                 }
             },
             4 => {
+                // Toggle Parallel Exports
+                config.parallel = !config.parallel;
+                if config.parallel {
+                    println!("Read-only exports now run in parallel on separate SAP sessions");
+                } else {
+                    println!("All options now run one by one");
+                }
+            },
+            5 => {
                 // Add/Edit Parameter
                 let param_name: String = Input::new()
                     .with_prompt("Enter Parameter Name")
@@ -323,7 +363,7 @@ pub fn handle_configure_sequence() -> Result<()> {
                     println!("Parameter '{}' set to: {}", param_name, param_value);
                 }
             },
-            5 => {
+            6 => {
                 // Remove Parameter
                 let mut param_names: Vec<String> = Vec::new();
                 
@@ -356,7 +396,7 @@ pub fn handle_configure_sequence() -> Result<()> {
                 config.params.remove(param_name);
                 println!("Parameter '{}' removed.", param_name);
             },
-            6 => {
+            7 => {
                 // Show Current Configuration
                 println!("\nCurrent Sequence Configuration:");
                 println!("-------------------------------");
@@ -376,6 +416,7 @@ pub fn handle_configure_sequence() -> Result<()> {
                 }
                 println!("Delay: {} seconds", config.delay_seconds);
                 println!("Interval: {} seconds", config.interval_seconds);
+                println!("Parallel exports: {}", if config.parallel { "on" } else { "off" });
                 
                 if !config.params.is_empty() {
                     println!("\nParameters:");
@@ -408,6 +449,49 @@ pub fn handle_configure_sequence() -> Result<()> {
     Ok(())
 }
 
+/// Run one sequence step on the pool session with the given id
+fn run_step_on(session_id: &str, option: &str) -> Result<()> {
+    with_session(session_id, |session| {
+        println!("Running: {} on {}", get_menu_option_name(option), session_id);
+        let sap_session = RealSapSession::new(session);
+        session_watchdog(session).run_step(&sap_session, || execute_menu_option(&sap_session, option))
+    })?
+}
+
+/// Run one sequence step on the main session, fails if the session cannot be made ready
+fn run_step_here(watchdog: &mut SessionWatchdog, session: &dyn SapSession, option: &str) -> Result<()> {
+    // Get back to SAP Easy Access (logging in again if needed) before each step
+    watchdog.ensure_ready(session)?;
+    
+    // Execute the selected option, repeating it if SAP logged the session off meanwhile
+    println!("Running: {}", get_menu_option_name(option));
+    if let Err(e) = watchdog.run_step(session, || execute_menu_option(session, option)) {
+        eprintln!("Error executing option: {}", e);
+    }
+    Ok(())
+}
+
+/// Run read-only options side by side on pool sessions, returns false if no pool could be built
+fn run_parallel_step(session: &GuiSession, options: &[String]) -> bool {
+    let pool = match pool_for_session(session, options.len()) {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("Could not open parallel SAP sessions, running the steps one by one: {}", e);
+            return false;
+        }
+    };
+    
+    println!("Running {} steps on {} SAP sessions", options.len(), pool.len());
+    let results = run_parallel(&pool, options, |session_id, option| run_step_on(session_id, option));
+    for (option, result) in options.iter().zip(results) {
+        if let Err(e) = result {
+            eprintln!("Error executing {}: {}", get_menu_option_name(option), e);
+        }
+    }
+    close_opened_sessions(&pool);
+    true
+}
+
 /// Run a sequence of operations with the specified configuration
 pub fn run_sequence(session: &GuiSession) -> Result<()> {
     println!("Run Sequence from Configuration");
@@ -452,6 +536,9 @@ pub fn run_sequence(session: &GuiSession) -> Result<()> {
     }
     println!("Delay between iterations: {} seconds", config.delay_seconds);
     println!("Interval between steps: {} seconds", config.interval_seconds);
+    if config.parallel {
+        println!("Read-only exports run in parallel on separate SAP sessions");
+    }
     
     if !config.params.is_empty() {
         println!("\nParameters:");
@@ -465,8 +552,11 @@ pub fn run_sequence(session: &GuiSession) -> Result<()> {
     io::stdin().read_line(&mut input).unwrap();
     
     // Run the sequence in a loop
+    let sap_session = RealSapSession::new(session);
+    let mut watchdog = session_watchdog(session);
+    let steps = sequence_steps(&config.options, config.parallel);
     let mut iteration = 1;
-    loop {
+    'sequence: loop {
         // Display iteration information
         if config.iterations == 0 {
             println!("\nIteration {} (infinite loop, press Ctrl+C to stop)", iteration);
@@ -474,18 +564,22 @@ pub fn run_sequence(session: &GuiSession) -> Result<()> {
             println!("\nIteration {}/{}", iteration, config.iterations);
         }
         
-        // Run each step in the sequence
-        for (step_index, option) in config.options.iter().enumerate() {
-            println!("\nRunning step {} of {}: Option {}", step_index + 1, config.options.len(), option);
+        // Run the steps in order, consecutive read-only exports side by side if enabled
+        for (step_index, step) in steps.iter().enumerate() {
+            println!("\nRunning step {} of {}: Option {}", step_index + 1, steps.len(), step.join(", "));
             
-            // Execute the selected option
-            println!("Running: {}", get_menu_option_name(option));
-            if let Err(e) = execute_menu_option(session, option) {
-                eprintln!("Error executing option: {}", e);
+            let ran_in_parallel = step.len() > 1 && run_parallel_step(session, step);
+            if !ran_in_parallel {
+                for option in step {
+                    if let Err(e) = run_step_here(&mut watchdog, &sap_session, option) {
+                        println!("Stopping sequence: {}", e);
+                        break 'sequence;
+                    }
+                }
             }
             
             // If this is not the last step, wait for the interval
-            if step_index < config.options.len() - 1 {
+            if step_index < steps.len() - 1 {
                 println!("Waiting {} seconds before next step...", config.interval_seconds);
                 thread::sleep(Duration::from_secs(config.interval_seconds));
             }
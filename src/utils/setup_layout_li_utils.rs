@@ -1,8 +1,8 @@
-use sap_scripting::*;
 use std::thread;
 use std::time::Duration;
 
-use crate::utils::sap_ctrl_utils::*;
+use crate::utils::sap_interfaces::{exist_ctrl, SapSession};
+use crate::utils::sap_status::read_status;
 use crate::utils::utils::*;
 
 /// Struct to hold layout parameters
@@ -18,7 +18,7 @@ pub struct LayoutParams {
 ///
 /// This function is a port of the VBA function SetupLayout_li
 pub fn setup_layout_li(
-    session: &GuiSession,
+    session: &dyn SapSession,
     tcode: &str,
     n_wnd: i32,
     base_obj_name: &str,
@@ -40,12 +40,10 @@ pub fn setup_layout_li(
     let _nw_obj_list_right = "/usr/tblSAPLSKBHTC_FIELD_LIST";
 
     // Get the left grid
-    let left_grid = session.find_by_id(obj_list_left.clone())?;
+    let grid_left = session.find_by_id(obj_list_left.clone())?;
 
-    // Try to downcast to GuiGridView
-    if let Some(grid_left) = left_grid.downcast::<GuiGridView>() {
-        // Get row count
-        let rc = grid_left.row_count()?;
+    // Get row count (also makes sure the object is a grid or table)
+    if let Ok(rc) = grid_left.row_count() {
         let vrc = grid_left.visible_row_count()?;
         let curr_row = grid_left.current_cell_row()?;
         let scrl_pos = (curr_row as f64 / rc as f64 * rc as f64).floor() as i32;
@@ -86,27 +84,21 @@ pub fn setup_layout_li(
         }
 
         // Move to right (clear)
-        if let Ok(button) = session.find_by_id(obj_button_to_right.clone()) {
-            if let Some(btn) = button.downcast::<GuiButton>() {
-                btn.press()?;
-            }
+        if let Ok(btn) = session.find_by_id(obj_button_to_right.clone()) {
+            btn.press()?;
         }
 
         // Start working with right list
         // Order alphabetical
-        if let Ok(button) = session.find_by_id("wnd[1]/usr/btn%#AUTOTEXT002".to_string()) {
-            if let Some(btn) = button.downcast::<GuiButton>() {
-                btn.press()?;
-            }
+        if let Ok(btn) = session.find_by_id("wnd[1]/usr/btn%#AUTOTEXT002".to_string()) {
+            btn.press()?;
         }
 
         // Get the right grid
-        let right_grid = session.find_by_id(obj_list_right.clone())?;
+        let grid_right = session.find_by_id(obj_list_right.clone())?;
 
-        // Try to downcast to GuiGridView
-        if let Some(grid_right) = right_grid.downcast::<GuiGridView>() {
-            // Get row count
-            let rc = grid_right.row_count()?;
+        // Get row count (also makes sure the object is a grid or table)
+        if let Ok(rc) = grid_right.row_count() {
             let _vrc = grid_right.visible_row_count()?;
             let _scrl_pos = (curr_row as f64 / rc as f64 * rc as f64).floor() as i32;
 
@@ -137,12 +129,10 @@ pub fn setup_layout_li(
                                         thread::sleep(Duration::from_millis(100));
 
                                         // Move to left - create a fresh clone of the button path for each use
-                                        if let Ok(button) =
+                                        if let Ok(btn) =
                                             session.find_by_id(obj_button_to_left.clone())
                                         {
-                                            if let Some(btn) = button.downcast::<GuiButton>() {
-                                                btn.press()?;
-                                            }
+                                            btn.press()?;
                                         }
 
                                         println!("Item ({}) moved to left", name);
@@ -202,19 +192,13 @@ pub fn setup_layout_li(
                 match tcode.to_uppercase().as_str() {
                     "MB52" => {
                         // Enter
-                        if let Ok(button) = session.find_by_id("wnd[1]/tbar[0]/btn[0]".to_string())
-                        {
-                            if let Some(btn) = button.downcast::<GuiButton>() {
-                                btn.press()?;
-                            }
+                        if let Ok(btn) = session.find_by_id("wnd[1]/tbar[0]/btn[0]".to_string()) {
+                            btn.press()?;
                         }
 
                         // Save
-                        if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[34]".to_string())
-                        {
-                            if let Some(btn) = button.downcast::<GuiButton>() {
-                                btn.press()?;
-                            }
+                        if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[34]".to_string()) {
+                            btn.press()?;
                         }
 
                         // Check layout name length
@@ -224,64 +208,48 @@ pub fn setup_layout_li(
                             // Close window if exists
                             let err_ctrl = exist_ctrl(session, 1, "", true)?;
                             if err_ctrl.cband {
-                                if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                                    if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                        wnd.close()?;
-                                    }
+                                if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                                    wnd.close()?;
                                 }
                             }
                         } else {
                             // Set layout name
-                            if let Ok(textfield) =
+                            if let Ok(txt) =
                                 session.find_by_id("wnd[1]/usr/ctxtLTDX-VARIANT".to_string())
                             {
-                                if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                                    txt.set_text(layout_name.to_string())?;
-                                }
+                                txt.set_text(layout_name.to_string())?;
                             }
 
                             // Set layout description
-                            if let Ok(textfield) =
+                            if let Ok(txt) =
                                 session.find_by_id("wnd[1]/usr/txtLTDXT-TEXT".to_string())
                             {
-                                if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                                    txt.set_text(layout_name.to_string())?;
-                                }
+                                txt.set_text(layout_name.to_string())?;
                             }
 
                             // Enter
-                            if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                                if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                    wnd.send_v_key(0)?;
-                                }
+                            if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                                wnd.send_v_key(0)?;
                             }
 
                             // LayoutExists Overwrite Y/N
                             let err_ctrl = exist_ctrl(session, 2, "", true)?;
                             if err_ctrl.cband {
-                                if let Ok(window) = session.find_by_id("wnd[2]".to_string()) {
-                                    if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                        wnd.send_v_key(0)?;
-                                    }
+                                if let Ok(wnd) = session.find_by_id("wnd[2]".to_string()) {
+                                    wnd.send_v_key(0)?;
                                 }
                             }
                         }
                     }
                     "LX03" | "LX02" => {
                         // Enter
-                        if let Ok(button) = session.find_by_id("wnd[1]/tbar[0]/btn[0]".to_string())
-                        {
-                            if let Some(btn) = button.downcast::<GuiButton>() {
-                                btn.press()?;
-                            }
+                        if let Ok(btn) = session.find_by_id("wnd[1]/tbar[0]/btn[0]".to_string()) {
+                            btn.press()?;
                         }
 
                         // Save
-                        if let Ok(button) = session.find_by_id("wnd[0]/tbar[1]/btn[36]".to_string())
-                        {
-                            if let Some(btn) = button.downcast::<GuiButton>() {
-                                btn.press()?;
-                            }
+                        if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[36]".to_string()) {
+                            btn.press()?;
                         }
 
                         // Check layout name length
@@ -291,229 +259,171 @@ pub fn setup_layout_li(
                             // Close window if exists
                             let err_ctrl = exist_ctrl(session, 1, "", true)?;
                             if err_ctrl.cband {
-                                if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                                    if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                        wnd.close()?;
-                                    }
+                                if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                                    wnd.close()?;
                                 }
                             }
                         } else {
                             // Set layout name
-                            if let Ok(textfield) =
+                            if let Ok(txt) =
                                 session.find_by_id("wnd[1]/usr/ctxtLTDX-VARIANT".to_string())
                             {
-                                if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                                    txt.set_text(layout_name.to_string())?;
-                                }
+                                txt.set_text(layout_name.to_string())?;
                             }
 
                             // Set layout description
-                            if let Ok(textfield) =
+                            if let Ok(txt) =
                                 session.find_by_id("wnd[1]/usr/txtLTDXT-TEXT".to_string())
                             {
-                                if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                                    txt.set_text(layout_name.to_string())?;
-                                }
+                                txt.set_text(layout_name.to_string())?;
                             }
 
                             // Enter
-                            if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                                if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                    wnd.send_v_key(0)?;
-                                }
+                            if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                                wnd.send_v_key(0)?;
                             }
 
                             // LayoutExists Overwrite Y/N
                             let err_ctrl = exist_ctrl(session, 2, "", true)?;
                             if err_ctrl.cband {
-                                if let Ok(window) = session.find_by_id("wnd[2]".to_string()) {
-                                    if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                        wnd.send_v_key(0)?;
-                                    }
+                                if let Ok(wnd) = session.find_by_id("wnd[2]".to_string()) {
+                                    wnd.send_v_key(0)?;
                                 }
                             }
                         }
                     }
                     "LT23" => {
                         // Enter (Close window)
-                        if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                            if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                wnd.send_v_key(0)?;
-                            }
+                        if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                            wnd.send_v_key(0)?;
                         }
 
                         // Save layout button
-                        if let Ok(menu) =
+                        if let Ok(menu_item) =
                             session.find_by_id("wnd[0]/mbar/menu[3]/menu[2]/menu[3]".to_string())
                         {
-                            if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-                                menu_item.select()?;
-                            }
+                            menu_item.select()?;
                         }
 
                         // User Specific
-                        if let Ok(checkbox) =
-                            session.find_by_id("wnd[1]/usr/chkG_FOR_USER".to_string())
+                        if let Ok(chk) = session.find_by_id("wnd[1]/usr/chkG_FOR_USER".to_string())
                         {
-                            if let Some(chk) = checkbox.downcast::<GuiCheckBox>() {
-                                chk.set_selected(true)?;
-                            }
+                            chk.set_selected(true)?;
                         }
 
                         // LayoutName
-                        if let Ok(textfield) =
+                        if let Ok(txt) =
                             session.find_by_id("wnd[1]/usr/ctxtLTDX-VARIANT".to_string())
                         {
-                            if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                                txt.set_text(layout_name.to_string())?;
-                            }
+                            txt.set_text(layout_name.to_string())?;
                         }
 
                         // Layout Description
-                        if let Ok(textfield) =
-                            session.find_by_id("wnd[1]/usr/txtLTDXT-TEXT".to_string())
+                        if let Ok(txt) = session.find_by_id("wnd[1]/usr/txtLTDXT-TEXT".to_string())
                         {
-                            if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                                txt.set_text(layout_name.to_string())?;
-                            }
+                            txt.set_text(layout_name.to_string())?;
                         }
 
                         // Enter (Save)
-                        if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                            if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                wnd.send_v_key(0)?;
-                            }
+                        if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                            wnd.send_v_key(0)?;
                         }
 
                         // LayoutExists Overwrite Y/N
                         let err_ctrl = exist_ctrl(session, 2, "", true)?;
                         if err_ctrl.cband {
-                            if let Ok(window) = session.find_by_id("wnd[2]".to_string()) {
-                                if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                    wnd.send_v_key(0)?;
-                                }
+                            if let Ok(wnd) = session.find_by_id("wnd[2]".to_string()) {
+                                wnd.send_v_key(0)?;
                             }
                         }
                     }
                     "VT11" => {
                         // Enter (Close window)
-                        if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                            if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                wnd.send_v_key(0)?;
-                            }
+                        if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                            wnd.send_v_key(0)?;
                         }
 
                         // Save layout button
-                        if let Ok(menu) =
+                        if let Ok(menu_item) =
                             session.find_by_id("wnd[0]/mbar/menu[3]/menu[0]/menu[3]".to_string())
                         {
-                            if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-                                menu_item.select()?;
-                            }
+                            menu_item.select()?;
                         }
 
                         // User Specific
-                        if let Ok(checkbox) =
-                            session.find_by_id("wnd[1]/usr/chkG_FOR_USER".to_string())
+                        if let Ok(chk) = session.find_by_id("wnd[1]/usr/chkG_FOR_USER".to_string())
                         {
-                            if let Some(chk) = checkbox.downcast::<GuiCheckBox>() {
-                                chk.set_selected(true)?;
-                            }
+                            chk.set_selected(true)?;
                         }
 
                         // Save as name
-                        if let Ok(textfield) =
+                        if let Ok(txt) =
                             session.find_by_id("wnd[1]/usr/ctxtLTDX-VARIANT".to_string())
                         {
-                            if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                                txt.set_text(layout_name.to_string())?;
-                            }
+                            txt.set_text(layout_name.to_string())?;
                         }
 
                         // Save as Description
-                        if let Ok(textfield) =
-                            session.find_by_id("wnd[1]/usr/txtLTDXT-TEXT".to_string())
+                        if let Ok(txt) = session.find_by_id("wnd[1]/usr/txtLTDXT-TEXT".to_string())
                         {
-                            if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                                txt.set_text(layout_name.to_string())?;
-                            }
+                            txt.set_text(layout_name.to_string())?;
                         }
 
                         // Enter (Save)
-                        if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                            if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                wnd.send_v_key(0)?;
-                            }
+                        if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                            wnd.send_v_key(0)?;
                         }
 
                         // LayoutExists Overwrite Y/N
                         let err_ctrl = exist_ctrl(session, 2, "", true)?;
                         if err_ctrl.cband {
-                            if let Ok(window) = session.find_by_id("wnd[2]".to_string()) {
-                                if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                    wnd.send_v_key(0)?;
-                                }
+                            if let Ok(wnd) = session.find_by_id("wnd[2]".to_string()) {
+                                wnd.send_v_key(0)?;
                             }
                         }
                     }
                     "VL06O" => {
                         // Enter (Close window)
-                        if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                            if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                wnd.send_v_key(0)?;
-                            }
+                        if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                            wnd.send_v_key(0)?;
                         }
 
                         // Save Layout button
-                        if let Ok(menu) =
+                        if let Ok(menu_item) =
                             session.find_by_id("wnd[0]/mbar/menu[3]/menu[2]/menu[3]".to_string())
                         {
-                            if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-                                menu_item.select()?;
-                            }
+                            menu_item.select()?;
                         }
 
                         // User Specific
-                        if let Ok(checkbox) =
-                            session.find_by_id("wnd[1]/usr/chkG_FOR_USER".to_string())
+                        if let Ok(chk) = session.find_by_id("wnd[1]/usr/chkG_FOR_USER".to_string())
                         {
-                            if let Some(chk) = checkbox.downcast::<GuiCheckBox>() {
-                                chk.set_selected(true)?;
-                            }
+                            chk.set_selected(true)?;
                         }
 
                         // Save layout name
-                        if let Ok(textfield) =
+                        if let Ok(txt) =
                             session.find_by_id("wnd[1]/usr/ctxtLTDX-VARIANT".to_string())
                         {
-                            if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                                txt.set_text(layout_name.to_string())?;
-                            }
+                            txt.set_text(layout_name.to_string())?;
                         }
 
                         // Save layout description
-                        if let Ok(textfield) =
-                            session.find_by_id("wnd[1]/usr/txtLTDXT-TEXT".to_string())
+                        if let Ok(txt) = session.find_by_id("wnd[1]/usr/txtLTDXT-TEXT".to_string())
                         {
-                            if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                                txt.set_text(layout_name.to_string())?;
-                            }
+                            txt.set_text(layout_name.to_string())?;
                         }
 
                         // Enter (Save)
-                        if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                            if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                wnd.send_v_key(0)?;
-                            }
+                        if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+                            wnd.send_v_key(0)?;
                         }
 
                         // LayoutExists Overwrite Y/N
                         let err_ctrl = exist_ctrl(session, 2, "", true)?;
                         if err_ctrl.cband {
-                            if let Ok(window) = session.find_by_id("wnd[2]".to_string()) {
-                                if let Some(wnd) = window.downcast::<GuiFrameWindow>() {
-                                    wnd.send_v_key(0)?;
-                                }
+                            if let Ok(wnd) = session.find_by_id("wnd[2]".to_string()) {
+                                wnd.send_v_key(0)?;
                             }
                         }
                     }
@@ -524,7 +434,9 @@ pub fn setup_layout_li(
             }
 
             // Get status bar message
-            let status_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
+            let status_msg = read_status(session, 0)?
+                .map(|message| message.to_string())
+                .unwrap_or_default();
             println!(
                 "Layout ({}) Successfully Setup: {}",
                 layout_name, status_msg
@@ -1,8 +1,8 @@
-use sap_scripting::*;
 use std::thread;
 use std::time::Duration;
 
-use crate::utils::sap_ctrl_utils::*;
+use crate::utils::sap_interfaces::{exist_ctrl, SapSession};
+use crate::utils::sap_status::read_status;
 
 /// Struct to hold layout parameters
 #[derive(Debug, Clone, Default)]
@@ -17,7 +17,7 @@ pub struct LayoutParams {
 ///
 /// This function is a port of the VBA function SetupLayout
 pub fn setup_layout(
-    session: &GuiSession,
+    session: &dyn SapSession,
     n_wnd: i32,
     base_obj_name: &str,
     layout_name: &str,
@@ -33,16 +33,13 @@ pub fn setup_layout(
     let obj_button_to_right = format!("{}/btnAPP_FL_SING", obj_name);
 
     // Get the left grid
-    let left_grid = session.find_by_id(obj_list_left.clone())?;
+    let grid_left = session.find_by_id(obj_list_left.clone())?;
 
     // Get the right grid
-    let right_grid = session.find_by_id(obj_list_right.clone())?;
+    let grid_right = session.find_by_id(obj_list_right.clone())?;
 
-    // Try to downcast to GuiGridView
-    if let (Some(grid_left), Some(grid_right)) = (
-        left_grid.downcast::<GuiGridView>(),
-        right_grid.downcast::<GuiGridView>(),
-    ) {
+    // Make sure both objects are grids
+    if grid_left.row_count().is_ok() && grid_right.row_count().is_ok() {
         // Set current cell row to 0
         grid_left.set_current_cell_row(0)?;
 
@@ -50,10 +47,8 @@ pub fn setup_layout(
         grid_left.set_selected_rows(format!("0-{}", grid_left.row_count()? - 1))?;
 
         // Press the button to move to right
-        if let Ok(button) = session.find_by_id(obj_button_to_right) {
-            if let Some(btn) = button.downcast::<GuiButton>() {
-                btn.press()?;
-            }
+        if let Ok(btn) = session.find_by_id(obj_button_to_right) {
+            btn.press()?;
         }
 
         // Reset selection
@@ -90,67 +85,53 @@ pub fn setup_layout(
         // Save the layout if not no_save
         if !no_save {
             // Save layout button
-            if let Ok(button) = session.find_by_id("wnd[1]/tbar[0]/btn[5]".to_string()) {
-                if let Some(btn) = button.downcast::<GuiButton>() {
-                    btn.press()?;
-                }
+            if let Ok(btn) = session.find_by_id("wnd[1]/tbar[0]/btn[5]".to_string()) {
+                btn.press()?;
             }
 
             // User-Specific
-            if let Ok(checkbox) = session.find_by_id("wnd[2]/usr/tabsG50_TABSTRIP/tabpTAB_D0501/ssubD0505_SUBSCREEN:SAPLSLVC_DIALOG:0501/chkG51_USPEC".to_string()) {
-                if let Some(chk) = checkbox.downcast::<GuiCheckBox>() {
-                    chk.set_selected(true)?;
-                }
+            if let Ok(chk) = session.find_by_id("wnd[2]/usr/tabsG50_TABSTRIP/tabpTAB_D0501/ssubD0505_SUBSCREEN:SAPLSLVC_DIALOG:0501/chkG51_USPEC".to_string()) {
+                chk.set_selected(true)?;
             }
 
             // Default Layout Yes/No
-            if let Ok(checkbox) = session.find_by_id("wnd[2]/usr/tabsG50_TABSTRIP/tabpTAB_D0501/ssubD0505_SUBSCREEN:SAPLSLVC_DIALOG:0501/chkLTVARIANT-DEFAULTVAR".to_string()) {
-                if let Some(chk) = checkbox.downcast::<GuiCheckBox>() {
-                    chk.set_selected(false)?;
-                }
+            if let Ok(chk) = session.find_by_id("wnd[2]/usr/tabsG50_TABSTRIP/tabpTAB_D0501/ssubD0505_SUBSCREEN:SAPLSLVC_DIALOG:0501/chkLTVARIANT-DEFAULTVAR".to_string()) {
+                chk.set_selected(false)?;
             }
 
             // Save as name
-            if let Ok(textfield) = session.find_by_id("wnd[2]/usr/tabsG50_TABSTRIP/tabpTAB_D0501/ssubD0505_SUBSCREEN:SAPLSLVC_DIALOG:0501/txtLTDX-VARIANT".to_string()) {
-                if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                    txt.set_text(layout_name.to_string())?;
-                }
+            if let Ok(txt) = session.find_by_id("wnd[2]/usr/tabsG50_TABSTRIP/tabpTAB_D0501/ssubD0505_SUBSCREEN:SAPLSLVC_DIALOG:0501/txtLTDX-VARIANT".to_string()) {
+                txt.set_text(layout_name.to_string())?;
             }
 
             // Save as Description
-            if let Ok(textfield) = session.find_by_id("wnd[2]/usr/tabsG50_TABSTRIP/tabpTAB_D0501/ssubD0505_SUBSCREEN:SAPLSLVC_DIALOG:0501/txtLTDXT-TEXT".to_string()) {
-                if let Some(txt) = textfield.downcast::<GuiTextField>() {
-                    txt.set_text(layout_name.to_string())?;
-                }
+            if let Ok(txt) = session.find_by_id("wnd[2]/usr/tabsG50_TABSTRIP/tabpTAB_D0501/ssubD0505_SUBSCREEN:SAPLSLVC_DIALOG:0501/txtLTDXT-TEXT".to_string()) {
+                txt.set_text(layout_name.to_string())?;
             }
 
             // Green Checkmark wnd2
-            if let Ok(button) = session.find_by_id("wnd[2]/tbar[0]/btn[0]".to_string()) {
-                if let Some(btn) = button.downcast::<GuiButton>() {
-                    btn.press()?;
-                }
+            if let Ok(btn) = session.find_by_id("wnd[2]/tbar[0]/btn[0]".to_string()) {
+                btn.press()?;
             }
 
             // LayoutExists Overwrite Y/N
             let err_ctrl = exist_ctrl(session, 3, "", true)?;
             if err_ctrl.cband {
-                if let Ok(button) = session.find_by_id("wnd[3]/usr/btnSPOP-OPTION1".to_string()) {
-                    if let Some(btn) = button.downcast::<GuiButton>() {
-                        btn.press()?;
-                    }
+                if let Ok(btn) = session.find_by_id("wnd[3]/usr/btnSPOP-OPTION1".to_string()) {
+                    btn.press()?;
                 }
             }
         }
 
         // Green Checkmark wnd1
-        if let Ok(button) = session.find_by_id("wnd[1]/tbar[0]/btn[0]".to_string()) {
-            if let Some(btn) = button.downcast::<GuiButton>() {
-                btn.press()?;
-            }
+        if let Ok(btn) = session.find_by_id("wnd[1]/tbar[0]/btn[0]".to_string()) {
+            btn.press()?;
         }
 
         // Get status bar message
-        let status_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
+        let status_msg = read_status(session, 0)?
+            .map(|message| message.to_string())
+            .unwrap_or_default();
         println!(
             "Layout ({}) Successfully Setup: {}",
             layout_name, status_msg
@@ -1,7 +1,11 @@
-use sap_scripting::*;
 use windows::core::Result;
 
 use crate::utils::config_types::TcodeConfig;
+use crate::utils::sap_control_catalog::{control, control_id};
+use crate::utils::sap_interfaces::{self, SapSession};
+use crate::utils::sap_screens::VL06O_DELIVERY_LIST;
+use crate::utils::sap_status::{read_status, INVALID_DATE_FORMAT};
+use crate::utils::sap_wait::{wait_timeouts, wait_until, WaitCondition};
 use crate::utils::select_layout_utils::{check_select_layout, select_layout};
 use crate::utils::{choose_layout, sap_file_utils::*};
 // Import specific functions to avoid ambiguity
@@ -11,6 +15,9 @@ use crate::utils::sap_wnd_utils::*;
 
 use chrono::NaiveDate;
 
+/// Delivery message "Goods issue date &1 lies in the past"
+const GOODS_ISSUE_DATE_IN_PAST: (&str, &str) = ("VL", "416");
+
 /// Struct to hold VL06O export parameters
 #[derive(Debug)]
 pub struct VL06OParams {
@@ -238,7 +245,7 @@ impl Default for VL06ODateUpdateParams {
 /// Run VL06O export with the given parameters
 ///
 /// This function is a port of the VBA function VL06O_DeliveryList_Run_Export
-pub fn run_export(session: &GuiSession, params: &VL06OParams) -> Result<bool> {
+pub fn run_export(session: &dyn SapSession, params: &VL06OParams) -> Result<bool> {
     println!("Running VL06O export...");
 
     // Check if tCode is active
@@ -248,10 +255,8 @@ pub fn run_export(session: &GuiSession, params: &VL06OParams) -> Result<bool> {
     }
 
     // Press "List Outbound Deliveries" button
-    if let Ok(btn) = session.find_by_id("wnd[0]/usr/btnBUTTON6".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("vl06o.initial.list_deliveries_button")?) {
+        button.press()?;
     }
 
     // Apply variant if provided
@@ -266,31 +271,23 @@ pub fn run_export(session: &GuiSession, params: &VL06OParams) -> Result<bool> {
     }
 
     // Clear date fields
-    if let Ok(txt) = session.find_by_id("wnd[0]/usr/ctxtIT_WADAT-LOW".to_string()) {
-        if let Some(text_field) = txt.downcast::<GuiCTextField>() {
-            text_field.set_text("".to_string())?;
-        }
+    if let Ok(text_field) = session.find_by_id(control_id("vl06o.selection.goods_issue_date_low")?) {
+        text_field.set_text("".to_string())?;
     }
 
-    if let Ok(txt) = session.find_by_id("wnd[0]/usr/ctxtIT_WADAT-HIGH".to_string()) {
-        if let Some(text_field) = txt.downcast::<GuiCTextField>() {
-            text_field.set_text("".to_string())?;
-        }
+    if let Ok(text_field) = session.find_by_id(control_id("vl06o.selection.goods_issue_date_high")?) {
+        text_field.set_text("".to_string())?;
     }
 
     // Press Multi Shipment Number button
-    if let Ok(btn) = session.find_by_id("wnd[0]/usr/btn%_IT_TKNUM_%_APP_%-VALU_PUSH".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("vl06o.selection.shipment_multi_button")?) {
+        button.press()?;
     }
 
     // Clear previous entries
     println!("DEBUG:Clearing Entries");
-    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-        if let Some(modal_window) = window.downcast::<GuiModalWindow>() {
-            modal_window.send_v_key(16)?; // Clear Previous entries
-        }
+    if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+        modal_window.send_v_key(16)?; // Clear Previous entries
     }
 
     // Paste shipment numbers using the scrollable paste function
@@ -321,38 +318,35 @@ pub fn run_export(session: &GuiSession, params: &VL06OParams) -> Result<bool> {
     }
 
     // Close Multi-Window
-    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-        if let Some(modal_window) = window.downcast::<GuiModalWindow>() {
-            modal_window.send_v_key(8)?; // Close Multi-Window
-        }
+    if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+        modal_window.send_v_key(8)?; // Close Multi-Window
     }
 
     // Execute
-    if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-        if let Some(gui) = wnd.downcast::<GuiMainWindow>() {
-            gui.send_v_key(8)?;
-        }
+    if let Ok(gui) = session.find_by_id("wnd[0]".to_string()) {
+        gui.send_v_key(8)?;
     }
 
+    // Wait for the delivery list to load
+    let timeouts = wait_timeouts();
+    wait_until(session, &WaitCondition::NotBusy, timeouts.default, timeouts.poll)?;
+
     // check for popup
-    let sbar = hit_ctrl(session, 0, "/sbar", "Text", "Get", "");
-    match sbar {
-        Ok(s) => {
-            if !s.is_empty() {
-                eprintln!("status bar message: {}", s);
-            }
+    match read_status(session, 0) {
+        Ok(Some(message)) => {
+            eprintln!("status bar message: {}", message);
         }
+        Ok(None) => {}
         Err(e) => {
             eprintln!("ERror getting sbar message: {}", e);
         }
     }
 
     // Press Item View Button
-    if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[18]".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("vl06o.list.item_view_button")?) {
+        button.press()?;
     }
+    wait_until(session, &WaitCondition::NotBusy, timeouts.default, timeouts.poll)?;
 
     // Check if layout provided and select it using the abstracted function
     if let Some(layout_row) = &params.layout_row {
@@ -363,18 +357,17 @@ pub fn run_export(session: &GuiSession, params: &VL06OParams) -> Result<bool> {
     }
 
     // Get statusbar message
-    let err_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-    println!("Statusbar message: ({})", err_msg);
+    if let Some(message) = read_status(session, 0)? {
+        println!("Statusbar message: ({})", message);
+    }
 
     // Export as Excel
-    if let Ok(menu) = session.find_by_id("wnd[0]/mbar/menu[0]/menu[5]/menu[1]".to_string()) {
-        if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-            menu_item.select()?;
-        }
+    if let Ok(menu_item) = session.find_by_id(control_id("vl06o.list.export_menu")?) {
+        menu_item.select()?;
     }
 
     // Check export window
-    let run_check = check_export_window(session, "VL06O", "LIST OF OUTBOUND DELIVERIES")?;
+    let run_check = check_export_window(session, "VL06O", &VL06O_DELIVERY_LIST)?;
     if !run_check {
         println!("Error checking export window");
         return Ok(false);
@@ -392,7 +385,7 @@ pub fn run_export(session: &GuiSession, params: &VL06OParams) -> Result<bool> {
 /// Run VL06O export with delivery numbers to get package counts
 ///
 /// This function is a port of the VBA code in deliv_packages.md
-pub fn run_export_delivery_packages(session: &GuiSession, params: &VL06ODeliveryParams) -> Result<bool> {
+pub fn run_export_delivery_packages(session: &dyn SapSession, params: &VL06ODeliveryParams) -> Result<bool> {
     println!("Running VL06O export for delivery packages...");
 
     // Check if tCode is active
@@ -402,10 +395,8 @@ pub fn run_export_delivery_packages(session: &GuiSession, params: &VL06ODelivery
     }
 
     // Press "List Outbound Deliveries" button
-    if let Ok(btn) = session.find_by_id("wnd[0]/usr/btnBUTTON6".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("vl06o.initial.list_deliveries_button")?) {
+        button.press()?;
     }
 
     // Apply variant if provided
@@ -423,17 +414,13 @@ pub fn run_export_delivery_packages(session: &GuiSession, params: &VL06ODelivery
     let delivery_numbers: Vec<String> = params.delivery_numbers.iter().cloned().collect::<std::collections::HashSet<_>>().into_iter().collect();
 
     // Press Multi Delivery button
-    if let Ok(btn) = session.find_by_id("wnd[0]/usr/btn%_IT_VBELN_%_APP_%-VALU_PUSH".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("vl06o.selection.delivery_multi_button")?) {
+        button.press()?;
     }
 
     // Clear previous entries
-    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-        if let Some(modal_window) = window.downcast::<GuiModalWindow>() {
-            modal_window.send_v_key(24)?; // Shift+F8 to clear entries
-        }
+    if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+        modal_window.send_v_key(24)?; // Shift+F8 to clear entries
     }
 
       // Enter delivery numbers using the scrollable paste function
@@ -455,33 +442,31 @@ pub fn run_export_delivery_packages(session: &GuiSession, params: &VL06ODelivery
       }
 
     // Close Multi-Window
-    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-        if let Some(modal_window) = window.downcast::<GuiModalWindow>() {
-            modal_window.send_v_key(8)?; // F8 key to close
-        }
+    if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+        modal_window.send_v_key(8)?; // F8 key to close
     }
 
     // Execute
-    if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-        if let Some(main_window) = wnd.downcast::<GuiMainWindow>() {
-            main_window.send_v_key(8)?; // F8 key to execute
-        }
+    if let Ok(main_window) = session.find_by_id("wnd[0]".to_string()) {
+        main_window.send_v_key(8)?; // F8 key to execute
     }
 
+    // Wait for the delivery list to load
+    let timeouts = wait_timeouts();
+    wait_until(session, &WaitCondition::NotBusy, timeouts.default, timeouts.poll)?;
+
     // select layout
     if let Some(layout_row) = &params.layout_row {
         choose_layout(session, &params.t_code, layout_row.as_str())?;
     }
 
     // Export as Excel
-    if let Ok(menu) = session.find_by_id("wnd[0]/mbar/menu[0]/menu[5]/menu[1]".to_string()) {
-        if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-            menu_item.select()?;
-        }
+    if let Ok(menu_item) = session.find_by_id(control_id("vl06o.list.export_menu")?) {
+        menu_item.select()?;
     }
 
     // Check export window
-    let run_check = check_export_window(session, "VL06O", "LIST OF OUTBOUND DELIVERIES")?;
+    let run_check = check_export_window(session, "VL06O", &VL06O_DELIVERY_LIST)?;
     if !run_check {
         println!("Error checking export window");
         return Ok(false);
@@ -499,7 +484,7 @@ pub fn run_export_delivery_packages(session: &GuiSession, params: &VL06ODelivery
 /// Run VL06O date update with the given parameters
 ///
 /// This function is a port of the VBA function vl06o_date_update
-pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) -> Result<(i32, Vec<(String, String)>)> {
+pub fn run_date_update(session: &dyn SapSession, params: &VL06ODateUpdateParams) -> Result<(i32, Vec<(String, String)>)> {
     println!("Running VL06O date update...");
     
     // Get the configured date format
@@ -523,64 +508,48 @@ pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) ->
     }
     
     // Press "List Outbound Deliveries" button
-    if let Ok(btn) = session.find_by_id("wnd[0]/usr/btnBUTTON6".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("vl06o.initial.list_deliveries_button")?) {
+        button.press()?;
     }
     
     // Apply variant if provided
     if let Some(variant_name) = &params.sap_variant_name {
         if !variant_name.is_empty() {
             // Variant select window
-            if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-                if let Some(main_window) = wnd.downcast::<GuiMainWindow>() {
-                    main_window.send_v_key(17)?; // F4 key for variant selection
-                }
+            if let Ok(main_window) = session.find_by_id("wnd[0]".to_string()) {
+                main_window.send_v_key(17)?; // F4 key for variant selection
             }
             
             // Traditional variant select
-            if let Ok(txt) = session.find_by_id("wnd[1]/usr/txtV-LOW".to_string()) {
-                if let Some(text_field) = txt.downcast::<GuiTextField>() {
-                    text_field.set_text(variant_name.clone())?;
-                }
+            if let Ok(text_field) = session.find_by_id(control_id("vl06o.variant.name")?) {
+                text_field.set_text(variant_name.clone())?;
             }
             
             // Clear name
-            if let Ok(txt) = session.find_by_id("wnd[1]/usr/txtENAME-LOW".to_string()) {
-                if let Some(text_field) = txt.downcast::<GuiTextField>() {
-                    text_field.set_text("".to_string())?;
-                }
+            if let Ok(text_field) = session.find_by_id(control_id("vl06o.variant.created_by")?) {
+                text_field.set_text("".to_string())?;
             }
             
             // Enter
-            if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
-                if let Some(modal_window) = wnd.downcast::<GuiModalWindow>() {
-                    modal_window.send_v_key(0)?; // Enter key
-                }
+            if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+                modal_window.send_v_key(0)?; // Enter key
             }
             
             // Close
-            if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
-                if let Some(modal_window) = wnd.downcast::<GuiModalWindow>() {
-                    modal_window.send_v_key(8)?; // F8 key to close
-                }
+            if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+                modal_window.send_v_key(8)?; // F8 key to close
             }
         }
     }
     
     // Press Multi Delivery button
-    if let Ok(btn) = session.find_by_id("wnd[0]/usr/btn%_IT_VBELN_%_APP_%-VALU_PUSH".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("vl06o.selection.delivery_multi_button")?) {
+        button.press()?;
     }
     
     // Clear previous entries
-    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-        if let Some(modal_window) = window.downcast::<GuiModalWindow>() {
-            modal_window.send_v_key(24)?; // Shift+F8 to clear entries
-        }
+    if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+        modal_window.send_v_key(24)?; // Shift+F8 to clear entries
     }
     
     // Enter delivery numbers using the scrollable paste function
@@ -602,24 +571,18 @@ pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) ->
     }
     
     // Close Multi-Window
-    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-        if let Some(modal_window) = window.downcast::<GuiModalWindow>() {
-            modal_window.send_v_key(8)?; // F8 key to close
-        }
+    if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+        modal_window.send_v_key(8)?; // F8 key to close
     }
     
     // Execute
-    if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-        if let Some(main_window) = wnd.downcast::<GuiMainWindow>() {
-            main_window.send_v_key(8)?; // F8 key to execute
-        }
+    if let Ok(main_window) = session.find_by_id("wnd[0]".to_string()) {
+        main_window.send_v_key(8)?; // F8 key to execute
     }
     
     // Press F5 (Select All)
-    if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-        if let Some(main_window) = wnd.downcast::<GuiMainWindow>() {
-            main_window.send_v_key(5)?; // F5 key to refresh
-        }
+    if let Ok(main_window) = session.find_by_id("wnd[0]".to_string()) {
+        main_window.send_v_key(5)?; // F5 key to refresh
     }
     
     println!("Starting VL06O date update for {} deliveries", params.delivery_numbers.len());
@@ -629,21 +592,17 @@ pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) ->
     let mut changes = Vec::new();
     
     // Press F13 (Shift+F1) to begin processing - this is the key step that starts the update process
-    if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-        if let Some(main_window) = wnd.downcast::<GuiMainWindow>() {
-            main_window.send_v_key(13)?; // F13 key (Shift+F1) to process
-            println!("Pressed Shift+F1 to begin processing");
-        }
+    if let Ok(main_window) = session.find_by_id("wnd[0]".to_string()) {
+        main_window.send_v_key(13)?; // F13 key (Shift+F1) to process
+        println!("Pressed Shift+F1 to begin processing");
     }
     
     // Check for popup message after starting processing
-    let err_ctrl = exist_ctrl(session, 1, "", true)?;
+    let err_ctrl = sap_interfaces::exist_ctrl(session, 1, "", true)?;
     if err_ctrl.cband {
-        if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
-            if let Some(p_window) = wnd.downcast::<GuiModalWindow>() {
-                p_window.send_v_key(0)?; // Enter key to close
-                println!("Closed loading message popup");
-            }
+        if let Ok(p_window) = session.find_by_id("wnd[1]".to_string()) {
+            p_window.send_v_key(0)?; // Enter key to close
+            println!("Closed loading message popup");
         }
     }
     
@@ -651,7 +610,7 @@ pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) ->
     loop {
 
         // Check if date field exists
-        let date_field = exist_ctrl(session, 0, r"/usr/tabsTAXI_TABSTRIP_OVERVIEW/tabpT\01/ssubSUBSCREEN_BODY:SAPMV50A:1102/ctxtLIKP-WADAT", true)?;
+        let date_field = sap_interfaces::exist_ctrl(session, 0, r"/usr/tabsTAXI_TABSTRIP_OVERVIEW/tabpT\01/ssubSUBSCREEN_BODY:SAPMV50A:1102/ctxtLIKP-WADAT", true)?;
 
         match date_field.cband {
             true => { /* no-op */ }
@@ -659,12 +618,8 @@ pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) ->
         }
 
         // Get delivery number
-        let delivery_number = if let Ok(txt) = session.find_by_id("wnd[0]/usr/subSUBSCREEN_HEADER:SAPMV50A:1502/ctxtLIKP-VBELN".to_string()) {
-            if let Some(text_field) = txt.downcast::<GuiCTextField>() {
-                text_field.text()?
-            } else {
-                "Unknown".to_string()
-            }
+        let delivery_number = if let Ok(text_field) = session.find_by_id(control_id("vl06o.delivery.number")?) {
+            text_field.get_text()?
         } else {
             "Unknown".to_string()
         };
@@ -672,20 +627,14 @@ pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) ->
         println!("Working with delivery ({})", delivery_number);
 
         // Select item overview tab (1st)
-        if let Ok(tab) = session.find_by_id(r"wnd[0]/usr/tabsTAXI_TABSTRIP_OVERVIEW/tabpT\01".to_string()) {
-            if let Some(tab_strip) = tab.downcast::<GuiTab>() {
-                tab_strip.select()?;
-                println!("Selected item overview tab");
-            }
+        if let Ok(tab_strip) = session.find_by_id(control_id("vl06o.delivery.overview_tab")?) {
+            tab_strip.select()?;
+            println!("Selected item overview tab");
         }
         
         // Check if date is changeable
-        let date_changeable = if let Ok(txt) = session.find_by_id(r"wnd[0]/usr/tabsTAXI_TABSTRIP_OVERVIEW/tabpT\01/ssubSUBSCREEN_BODY:SAPMV50A:1102/ctxtLIKP-WADAT".to_string()) {
-            if let Some(text_field) = txt.downcast::<GuiCTextField>() {
-                text_field.changeable()?
-            } else {
-                false
-            }
+        let date_changeable = if let Ok(text_field) = session.find_by_id(control_id("vl06o.delivery.goods_issue_date")?) {
+            text_field.changeable()?
         } else {
             false
         };
@@ -694,66 +643,61 @@ pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) ->
             println!("Delivery date not changeable for delivery {}", delivery_number);
             
             // F3 back
-            if let Ok(window) = session.find_by_id("wnd[0]".to_string()) {
-                if let Some(wnd) = window.downcast::<GuiMainWindow>() {
-                    wnd.send_v_key(3)?;
-                    println!("Pressed back button to skip non-changeable delivery");
-                }
+            if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
+                wnd.send_v_key(3)?;
+                println!("Pressed back button to skip non-changeable delivery");
             }
         } else {
             // Get original date
-            let original_date = if let Ok(txt) = session.find_by_id(r"wnd[0]/usr/tabsTAXI_TABSTRIP_OVERVIEW/tabpT\01/ssubSUBSCREEN_BODY:SAPMV50A:1102/ctxtLIKP-WADAT".to_string()) {
-                if let Some(text_field) = txt.downcast::<GuiCTextField>() {
-                    text_field.text()?
-                } else {
-                    "Unknown".to_string()
-                }
+            let original_date = if let Ok(text_field) = session.find_by_id(control_id("vl06o.delivery.goods_issue_date")?) {
+                text_field.get_text()?
             } else {
                 "Unknown".to_string()
             };
             
             // Change date
-            if let Ok(txt) = session.find_by_id(r"wnd[0]/usr/tabsTAXI_TABSTRIP_OVERVIEW/tabpT\01/ssubSUBSCREEN_BODY:SAPMV50A:1102/ctxtLIKP-WADAT".to_string()) {
-                if let Some(text_field) = txt.downcast::<GuiCTextField>() {
-                    text_field.set_text(target_date_str.clone())?;
-                }
+            if let Ok(text_field) = session.find_by_id(control_id("vl06o.delivery.goods_issue_date")?) {
+                text_field.set_text(target_date_str.clone())?;
             }
             
             println!("Changing date from ({}) to ({})", original_date, target_date_str);
             
             // Enter loop to handle any messages
+            let mut last_message = None;
             loop {
 
                 // Send enter key (vkey0)
-                if let Ok(window) = session.find_by_id("wnd[0]".to_string()) {
-                    if let Some(wnd) = window.downcast::<GuiMainWindow>() {
-                        wnd.send_v_key(0)?;
-                        println!("Sent (Enter) key");
-                    }
+                if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
+                    wnd.send_v_key(0)?;
+                    println!("Sent (Enter) key");
                 }
 
                 // Get status bar message
-                let status_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-                if !status_msg.is_empty() {
-                    println!("Status bar: {}", status_msg);
+                if let Some(message) = read_status(session, 0)? {
+                    println!("Status bar: {}", message);
                 }
 
                 // Send enter key (vkey0)
-                if let Ok(window) = session.find_by_id("wnd[0]".to_string()) {
-                    if let Some(wnd) = window.downcast::<GuiMainWindow>() {
-                        wnd.send_v_key(0)?;
-                        println!("Sent (Enter) key");
-                    }
+                if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
+                    wnd.send_v_key(0)?;
+                    println!("Sent (Enter) key");
                 }
                 
-                // Check if status bar is empty or has short message
-                let new_status = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-                if new_status.len() <= 1 {
-                    break;
-                } else if new_status.contains("date in the format") {
-                    // try different date format
-                } else if new_status.contains("Goods issue") {
-                    break;
+                // Stop once the status bar is empty or shows the goods issue date message,
+                // other messages (e.g. warnings) are confirmed with Enter again
+                match read_status(session, 0)? {
+                    None => break,
+                    Some(message) if message.is(GOODS_ISSUE_DATE_IN_PAST) => break,
+                    Some(message) => {
+                        if message.is(INVALID_DATE_FORMAT) {
+                            println!("Date ({}) not accepted: {}", target_date_str, message);
+                        }
+                        // Stop if Enter did not change the message
+                        if last_message.as_ref() == Some(&message) {
+                            break;
+                        }
+                        last_message = Some(message);
+                    }
                 }
             }
             
@@ -763,51 +707,43 @@ pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) ->
             }
             
             // Save
-            if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-                if let Some(main_window) = wnd.downcast::<GuiMainWindow>() {
-                    main_window.send_v_key(11)?; // Ctrl+S to save
-                    println!("Saved changes for delivery {}", delivery_number);
-                }
+            if let Ok(main_window) = session.find_by_id("wnd[0]".to_string()) {
+                main_window.send_v_key(11)?; // Ctrl+S to save
+                println!("Saved changes for delivery {}", delivery_number);
             }
             
                 
             // Handle confirmation popup - "Continue with next delivery?" - Always click Yes
-            let popup_ctrl = exist_ctrl(session, 1, "/usr/btnSPOP-OPTION1", true)?;
+            let option1 = control("vl06o.popup.option1_button")?;
+            let popup_ctrl = sap_interfaces::exist_ctrl(session, option1.window_index(), &option1.relative_path(), true)?;
             if popup_ctrl.cband {
-                if let Ok(btn) = session.find_by_id("wnd[1]/usr/btnSPOP-OPTION1".to_string()) {
-                    if let Some(button) = btn.downcast::<GuiButton>() {
-                        button.press()?;
-                        println!("Clicked 'Yes' on popup to continue with next delivery");
-                    }
+                if let Ok(button) = session.find_by_id(option1.to_string()) {
+                    button.press()?;
+                    println!("Clicked 'Yes' on popup to continue with next delivery");
                 }
             }
                 
             // Handle any other popups (like loading messages)
-            let err_popup = exist_ctrl(session, 1, "", true)?;
+            let err_popup = sap_interfaces::exist_ctrl(session, 1, "", true)?;
             if err_popup.cband {
                 let msg = get_sap_text_errors(session, 1, "/usr/txtMESSTXT1", 10, None)?;
                 println!("Popup message: {}", msg);
                 if msg.contains("loading") {
-                    if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-                        if let Some(main_window) = wnd.downcast::<GuiMainWindow>() {
-                            main_window.send_v_key(0)?; // Enter key to close
-                            println!("Closed loading message popup");
-                        }
+                    if let Ok(main_window) = session.find_by_id("wnd[0]".to_string()) {
+                        main_window.send_v_key(0)?; // Enter key to close
+                        println!("Closed loading message popup");
                     }
                 }
             }
                 
-            // Check for "currently being" message in status bar
-            let bar_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-            if bar_msg.contains("currently being") {
-                println!("Error: ({})", bar_msg);
+            // Check for an error in the status bar (e.g. the delivery is locked by another user)
+            if let Some(message) = read_status(session, 0)?.filter(|m| m.is_error()) {
+                println!("Error: ({})", message);
                     
                 // F3 to exit
-                if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-                    if let Some(main_window) = wnd.downcast::<GuiMainWindow>() {
-                        main_window.send_v_key(3)?; // F3 key to exit
-                        println!("Pressed F3 to exit due to error");
-                    }
+                if let Ok(main_window) = session.find_by_id("wnd[0]".to_string()) {
+                    main_window.send_v_key(3)?; // F3 key to exit
+                    println!("Pressed F3 to exit due to error");
                 }
             }
         }
@@ -816,25 +752,20 @@ pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) ->
         counter += 1;
         
         // Check for popup message for next deliv
-        if let Ok(button) = session.find_by_id("wnd[1]/usr/btnSPOP-OPTION1".to_string()) {
-            if let Some(btn) = button.downcast::<GuiButton>() {
-                eprintln!("pressing 'yes' button on popup");
-                btn.press()?
-            }
+        if let Ok(btn) = session.find_by_id(control_id("vl06o.popup.option1_button")?) {
+            eprintln!("pressing 'yes' button on popup");
+            btn.press()?
         }
     }
     
-    // Check for any final status bar message
-    let bar_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-    if bar_msg.contains("restricted") {
-        println!("Error: ({})", bar_msg);
+    // Check for any final status bar error
+    if let Some(message) = read_status(session, 0)?.filter(|m| m.is_error()) {
+        println!("Error: ({})", message);
         
         // F3 to exit
-        if let Ok(wnd) = session.find_by_id("wnd[0]".to_string()) {
-            if let Some(main_window) = wnd.downcast::<GuiMainWindow>() {
-                main_window.send_v_key(3)?; // F3 key to exit
-                println!("Pressed F3 to exit due to error");
-            }
+        if let Ok(main_window) = session.find_by_id("wnd[0]".to_string()) {
+            main_window.send_v_key(3)?; // F3 key to exit
+            println!("Pressed F3 to exit due to error");
         }
     }
     
@@ -847,7 +778,7 @@ pub fn run_date_update(session: &GuiSession, params: &VL06ODateUpdateParams) ->
 ///
 /// This is a helper function for run_export
 fn check_multi_paste(
-    session: &GuiSession,
+    session: &dyn SapSession,
     tcode: &str,
     wnd_idx: i32,
     row_idx: i32,
@@ -855,12 +786,10 @@ fn check_multi_paste(
     // Check if the first row has a value
     let input_field_id = format!("wnd[{}]/usr/tabsTAB_STRIP/tabpSIVA/ssubSCREEN_HEADER:SAPLALDB:3010/tblSAPLALDBSINGLE/ctxtRSCSEL_255-SLOW_I[1,{}]", wnd_idx, row_idx);
 
-    if let Ok(txt) = session.find_by_id(input_field_id) {
-        if let Some(text_field) = txt.downcast::<GuiCTextField>() {
-            let value = text_field.text()?;
-            if !value.is_empty() {
-                return Ok(true);
-            }
+    if let Ok(text_field) = session.find_by_id(input_field_id) {
+        let value = text_field.get_text()?;
+        if !value.is_empty() {
+            return Ok(true);
         }
     }
 
@@ -4,7 +4,6 @@ use crossterm::{
     terminal::{Clear, ClearType},
 };
 use dialoguer::{Input, Select};
-use sap_scripting::*;
 use std::fs;
 use std::io::{self};
 use std::path::Path;
@@ -14,10 +13,11 @@ use crate::utils::{config_ops::get_reports_dir, excel_path_utils::resolve_path};
 use crate::utils::config_types::SapConfig;
 use crate::utils::excel_file_ops::read_excel_column;
 use crate::utils::excel_path_utils::{get_excel_file_path, get_newest_file};
+use crate::utils::sap_interfaces::SapSession;
 use crate::vl06o::{run_export_delivery_packages, VL06ODeliveryParams};
 
 /// Run VL06O export with delivery numbers to get package counts
-pub fn run_vl06o_delivery_packages_module(session: &GuiSession) -> Result<()> {
+pub fn run_vl06o_delivery_packages_module(session: &dyn SapSession) -> Result<()> {
     clear_screen();
     println!("VL06O - List of Delivery Packages");
     println!("================================");
@@ -49,7 +49,7 @@ pub fn run_vl06o_delivery_packages_module(session: &GuiSession) -> Result<()> {
 /// Run VL06O delivery packages auto using default configs
 /// This function automatically gets deliveries from the "Delivery" column
 /// in the latest Excel file in the zmdesnr subdirectory
-pub fn run_vl06o_delivery_packages_auto(session: &GuiSession) -> Result<()> {
+pub fn run_vl06o_delivery_packages_auto(session: &dyn SapSession) -> Result<()> {
     clear_screen();
     println!("VL06O - Auto Run Delivery Packages");
     println!("=================================");
@@ -4,7 +4,6 @@ use crossterm::{
     terminal::{Clear, ClearType},
 };
 use dialoguer::{Input, Select};
-use sap_scripting::*;
 use std::collections::HashMap;
 use std::fs;
 use std::io::{self};
@@ -15,10 +14,11 @@ use crate::utils::{config_ops::get_reports_dir, excel_path_utils::resolve_path};
 use crate::utils::config_types::SapConfig;
 use crate::utils::excel_file_ops::read_excel_column;
 use crate::utils::excel_path_utils::{get_excel_file_path, get_newest_file};
+use crate::utils::sap_interfaces::SapSession;
 use crate::vl06o::{run_date_update, run_export, VL06ODateUpdateParams, VL06OParams};
 use crate::vl06o_delivery_module::run_vl06o_delivery_packages_module;
 
-pub fn run_vl06o_module(session: &GuiSession) -> Result<()> {
+pub fn run_vl06o_module(session: &dyn SapSession) -> Result<()> {
     clear_screen();
     println!("VL06O - List of Outbound Deliveries");
     println!("==================================");
@@ -47,7 +47,7 @@ pub fn run_vl06o_module(session: &GuiSession) -> Result<()> {
     Ok(())
 }
 
-pub fn run_vl06o_auto(session: &GuiSession) -> Result<()> {
+pub fn run_vl06o_auto(session: &dyn SapSession) -> Result<()> {
     clear_screen();
     println!("VL06O - Auto Run from Configuration");
     println!("==================================");
@@ -175,7 +175,7 @@ pub fn run_vl06o_auto(session: &GuiSession) -> Result<()> {
     Ok(())
 }
 
-pub fn run_vl06o_date_update_module(session: &GuiSession) -> Result<()> {
+pub fn run_vl06o_date_update_module(session: &dyn SapSession) -> Result<()> {
     clear_screen();
     println!("VL06O - Change Delivery Date");
     println!("===========================");
@@ -1,11 +1,13 @@
 use chrono::NaiveDate;
-use sap_scripting::*;
 use windows::core::Result;
 
+use crate::utils::sap_control_catalog::control;
 use crate::utils::{choose_layout, sap_file_utils::*};
 // Import specific functions to avoid ambiguity
-use crate::utils::sap_ctrl_utils::exist_ctrl;
+use crate::utils::sap_interfaces::{exist_ctrl, SapSession};
+use crate::utils::sap_screens::VT11_SHIPMENT_LIST;
 use crate::utils::sap_tcode_utils::*;
+use crate::utils::sap_wait::{wait_timeouts, wait_until, WaitCondition};
 use crate::utils::sap_wnd_utils::*;
 
 /// Struct to hold VT11 export parameters
@@ -37,7 +39,7 @@ impl Default for VT11Params {
 /// Run VT11 export with the given parameters
 ///
 /// This function is a port of the VBA function VT11_Run_Export
-pub fn run_export(session: &GuiSession, params: &VT11Params) -> Result<bool> {
+pub fn run_export(session: &dyn SapSession, params: &VT11Params) -> Result<bool> {
     println!("Running VT11 export...");
 
     // Check if tCode is active
@@ -64,20 +66,16 @@ pub fn run_export(session: &GuiSession, params: &VT11Params) -> Result<bool> {
     // Set date fields based on by_date parameter
     if params.by_date {
         // Set start date
-        if let Ok(txt) = session.find_by_id("wnd[0]/usr/ctxtK_DATEN-LOW".to_string()) {
-            if let Some(text_field) = txt.downcast::<GuiTextField>() {
-                text_field.set_text(start_date_str.clone())?;
-            }
+        if let Ok(text_field) = session.find_control(&control("vt11.selection.date_low")?) {
+            text_field.set_text(start_date_str.clone())?;
         }
 
         // Set end date (leave blank if same as start date)
-        if let Ok(txt) = session.find_by_id("wnd[0]/usr/ctxtK_DATEN-HIGH".to_string()) {
-            if let Some(text_field) = txt.downcast::<GuiTextField>() {
-                if params.start_date == params.end_date {
-                    text_field.set_text("".to_string())?;
-                } else {
-                    text_field.set_text(end_date_str.clone())?;
-                }
+        if let Ok(text_field) = session.find_control(&control("vt11.selection.date_high")?) {
+            if params.start_date == params.end_date {
+                text_field.set_text("".to_string())?;
+            } else {
+                text_field.set_text(end_date_str.clone())?;
             }
         }
     }
@@ -99,10 +97,10 @@ pub fn run_export(session: &GuiSession, params: &VT11Params) -> Result<bool> {
                 }
                 "date_range" => {
                     // Blank 2nd description to prevent issues
-                    if let Ok(txt) = session.find_by_id("wnd[0]/usr/txtK_TPBEZ-HIGH".to_string()) {
-                        if let Some(text_field) = txt.downcast::<GuiTextField>() {
-                            text_field.set_text("".to_string())?;
-                        }
+                    if let Ok(text_field) =
+                        session.find_control(&control("vt11.selection.description_high")?)
+                    {
+                        text_field.set_text("".to_string())?;
                     }
 
                     // This would also require clipboard functionality
@@ -116,33 +114,42 @@ pub fn run_export(session: &GuiSession, params: &VT11Params) -> Result<bool> {
     }
 
     // Execute the transaction
-    if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[8]".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_control(&control("vt11.selection.execute_button")?) {
+        button.press()?;
     }
 
+    // Wait for the shipment list (or the "no shipments" popup)
+    let timeouts = wait_timeouts();
+    wait_until(
+        session,
+        &WaitCondition::NotBusy,
+        timeouts.default,
+        timeouts.poll,
+    )?;
+
     // Check for error (No Shipments Found)
-    let err_ctl = exist_ctrl(session, 1, "/usr/txtMESSTXT1", false)?;
+    let message_id = control("vt11.popup.message")?;
+    let err_ctl = exist_ctrl(
+        session,
+        message_id.window_index(),
+        &message_id.relative_path(),
+        false,
+    )?;
     if err_ctl.cband {
-        if let Ok(txt) = session.find_by_id("wnd[1]/usr/txtMESSTXT1".to_string()) {
-            if let Some(text_field) = txt.downcast::<GuiTextField>() {
-                let error_text = text_field.text()?;
-                if error_text.contains("No shipments were found for the selection criteria") {
-                    println!(
-                        "No shipments found from dates ({} to {})",
-                        start_date_str, end_date_str
-                    );
-
-                    // Close window
-                    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                        if let Some(modal_window) = window.downcast::<GuiFrameWindow>() {
-                            modal_window.close()?;
-                        }
-                    }
-
-                    return Ok(false);
+        if let Ok(text_field) = session.find_control(&message_id) {
+            let error_text = text_field.get_text()?;
+            if error_text.contains("No shipments were found for the selection criteria") {
+                println!(
+                    "No shipments found from dates ({} to {})",
+                    start_date_str, end_date_str
+                );
+
+                // Close window
+                if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
+                    window.close()?;
                 }
+
+                return Ok(false);
             }
         }
     }
@@ -151,11 +158,8 @@ pub fn run_export(session: &GuiSession, params: &VT11Params) -> Result<bool> {
     if let Some(layout_row) = &params.layout_row {
         if !layout_row.is_empty() {
             // Choose Layout - only open layout selection if a layout is provided
-            if let Ok(menu) = session.find_by_id("wnd[0]/mbar/menu[3]/menu[0]/menu[1]".to_string())
-            {
-                if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-                    menu_item.select()?;
-                }
+            if let Ok(menu_item) = session.find_control(&control("vt11.list.choose_layout_menu")?) {
+                menu_item.select()?;
             }
 
             // Check if window exists
@@ -177,11 +181,7 @@ pub fn run_export(session: &GuiSession, params: &VT11Params) -> Result<bool> {
                 // If we get here and the layout window is still open, the layout wasn't found
                 let err_ctl = exist_ctrl(session, 1, "", true)?;
                 if err_ctl.cband {
-                    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                        if let Some(modal_window) = window.downcast::<GuiFrameWindow>() {
-                            modal_window.close()?;
-                        }
-                    }
+                    session.close_window(1)?;
 
                     println!("Layout ({}) not found. Setting up layout...", layout_row);
                     // Setup layout functionality would be implemented here
@@ -191,11 +191,7 @@ pub fn run_export(session: &GuiSession, params: &VT11Params) -> Result<bool> {
             // If layout is empty or zero-length, close popup window and export as-is
             let err_ctl = exist_ctrl(session, 1, "", true)?;
             if err_ctl.cband {
-                if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                    if let Some(modal_window) = window.downcast::<GuiFrameWindow>() {
-                        modal_window.close()?;
-                    }
-                }
+                session.close_window(1)?;
             }
 
             println!("Layout is empty or zero-length. Exporting as-is.");
@@ -203,16 +199,14 @@ pub fn run_export(session: &GuiSession, params: &VT11Params) -> Result<bool> {
     }
 
     // Export to Excel
-    if let Ok(menu) = session.find_by_id("wnd[0]/mbar/menu[0]/menu[10]/menu[0]".to_string()) {
-        if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-            menu_item.select()?;
-        }
+    if let Ok(menu_item) = session.find_control(&control("vt11.list.export_menu")?) {
+        menu_item.select()?;
     }
 
     // debug
     eprintln!("DEBUG: Exporting to Excel");
     // Check export window
-    let run_check = check_export_window(session, "VT11", "SHIPMENT LIST: PLANNING")?;
+    let run_check = check_export_window(session, "VT11", &VT11_SHIPMENT_LIST)?;
     match run_check {
         true => {
             println!("Export window opened successfully.");
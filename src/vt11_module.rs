@@ -4,15 +4,15 @@ use crossterm::{
     terminal::{Clear, ClearType},
 };
 use dialoguer::{Input, Select};
-use sap_scripting::*;
 use std::collections::HashMap;
 use std::io::{self};
 use windows::core::Result;
 
 use crate::utils::config_types::SapConfig;
+use crate::utils::sap_interfaces::SapSession;
 use crate::vt11::{run_export, VT11Params};
 
-pub fn run_vt11_module(session: &GuiSession) -> Result<()> {
+pub fn run_vt11_module(session: &dyn SapSession) -> Result<()> {
 
     clear_screen();
     println!("VT11 - Shipment List Planning");
@@ -42,7 +42,7 @@ pub fn run_vt11_module(session: &GuiSession) -> Result<()> {
     Ok(())
 }
 
-pub fn run_vt11_auto(session: &GuiSession) -> Result<()> {
+pub fn run_vt11_auto(session: &dyn SapSession) -> Result<()> {
 
     clear_screen();
     println!("VT11 - Auto Run from Configuration");
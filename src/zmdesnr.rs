@@ -1,9 +1,13 @@
-use sap_scripting::*;
 use windows::core::Result;
 use crate::utils::sap_file_utils::*;
+use crate::utils::sap_control_catalog::{control, control_id};
+use crate::utils::sap_control_id::ControlSegment;
+use crate::utils::sap_interfaces::SapSession;
+use crate::utils::sap_screens::ZMDESNR_SERIAL_NUMBER_LIST;
+use crate::utils::sap_status::{read_status, NO_LAYOUTS_FOUND};
+use crate::utils::sap_wait::{wait_timeouts, wait_until, WaitCondition};
 use crate::utils::select_layout_utils::check_select_layout;
 // Import specific functions to avoid ambiguity
-use crate::utils::sap_ctrl_utils::hit_ctrl;
 use crate::utils::sap_tcode_utils::*;
 use crate::utils::sap_wnd_utils::*;
 
@@ -48,7 +52,7 @@ impl Default for ZMDESNRParams {
 ///
 /// This function is a port of the VBA code in docs/zmdesnr_layout.md
 /// It adds columns to the layout based on the add_layout_columns configuration
-fn add_layout_columns(session: &GuiSession, params: &ZMDESNRParams) -> Result<bool> {
+fn add_layout_columns(session: &dyn SapSession, params: &ZMDESNRParams) -> Result<bool> {
     println!("Adding layout columns...");
     
     // Get columns from params if available, otherwise use default
@@ -71,18 +75,14 @@ fn add_layout_columns(session: &GuiSession, params: &ZMDESNRParams) -> Result<bo
     // Implement the VBA code from docs/zmdesnr_layout.md
     
     // Select row 5 and column "STATUS"
-    if let Ok(shell) = session.find_by_id("wnd[0]/usr/cntlGRID1/shellcont/shell".to_string()) {
-        if let Some(grid) = shell.downcast::<GuiGridView>() {
-            grid.set_current_cell(5, "STATUS".to_string())?;
-            grid.set_selected_rows("5".to_string())?;
-        }
+    if let Ok(grid) = session.find_by_id(control_id("zmdesnr.list.grid")?) {
+        grid.set_current_cell(5, "STATUS".to_string())?;
+        grid.set_selected_rows("5".to_string())?;
     }
     
     // Select menu option 4/0/0 (Change Layout)
-    if let Ok(menu) = session.find_by_id("wnd[0]/mbar/menu[4]/menu[0]/menu[0]".to_string()) {
-        if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-            menu_item.select()?;
-        }
+    if let Ok(menu_item) = session.find_by_id(control_id("zmdesnr.list.change_layout_menu")?) {
+        menu_item.select()?;
     }
 
     for col in add_layout_columns {
@@ -91,20 +91,16 @@ fn add_layout_columns(session: &GuiSession, params: &ZMDESNRParams) -> Result<bo
     
     for r in  &["3", "2", "2"] {
         // Select row in the layout container
-        if let Ok(shell) = session.find_by_id("wnd[1]/usr/tabsG_TS_ALV/tabpALV_M_R1/ssubSUB_DYN0510:SAPLSKBH:0620/cntlCONTAINER1_LAYO/shellcont/shell".to_string()) {
-            if let Some(grid) = shell.downcast::<GuiGridView>() {
-                grid.set_current_cell_row(r.parse::<i32>().unwrap())?;
-                grid.set_selected_rows(r.to_string())?;
-                grid.double_click_current_cell()?;
-            }
+        if let Ok(grid) = session.find_by_id(control_id("zmdesnr.layout.columns_grid")?) {
+            grid.set_current_cell_row(r.parse::<i32>().unwrap())?;
+            grid.set_selected_rows(r.to_string())?;
+            grid.double_click_current_cell()?;
         }
     }
         
     // Send VKey 0 (Enter)
-    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-        if let Some(wnd) = window.downcast::<GuiModalWindow>() {
-            wnd.send_v_key(0)?;
-        }
+    if let Ok(wnd) = session.find_by_id("wnd[1]".to_string()) {
+        wnd.send_v_key(0)?;
     }
     
     println!("Layout columns added successfully.");
@@ -114,7 +110,7 @@ fn add_layout_columns(session: &GuiSession, params: &ZMDESNRParams) -> Result<bo
 /// Run ZMDESNR export with the given parameters
 ///
 /// This function is a port of the VBA function ZMDESNR_With_Exclude_Export
-pub fn run_export(session: &GuiSession, params: &ZMDESNRParams) -> Result<bool> {
+pub fn run_export(session: &dyn SapSession, params: &ZMDESNRParams) -> Result<bool> {
     println!("Running ZMDESNR export...");
 
     // Check if tCode is active
@@ -138,11 +134,10 @@ pub fn run_export(session: &GuiSession, params: &ZMDESNRParams) -> Result<bool>
     let tab_number = params.tab_number.unwrap_or(2);
 
     // Select the specified tab based on tab_number
-    let tab_id = format!("wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM{}", tab_number);
-    if let Ok(tab) = session.find_by_id(tab_id) {
-        if let Some(tab_strip) = tab.downcast::<GuiTab>() {
-            tab_strip.select()?;
-        }
+    let tab_id = control("zmdesnr.selection.tab_strip")?
+        .child(ControlSegment::new("tabp", &format!("UCOMM{}", tab_number)));
+    if let Ok(tab_strip) = session.find_by_id(tab_id.to_string()) {
+        tab_strip.select()?;
     }
 
     // Handle tab-specific operations
@@ -153,20 +148,20 @@ pub fn run_export(session: &GuiSession, params: &ZMDESNRParams) -> Result<bool>
         // Default operations for unspecified tabs
         println!("Tab number {} not specifically handled", tab_number);
         // For now, we'll just execute the query
-        if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[8]".to_string()) {
-            if let Some(button) = btn.downcast::<GuiButton>() {
-                button.press()?;
-            }
+        if let Ok(button) = session.find_by_id(control_id("zmdesnr.selection.execute_button")?) {
+            button.press()?;
         }
         true // Assume success for unhandled tabs
     };            
     
     // Execute
-    if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[8]".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("zmdesnr.selection.execute_button")?) {
+        button.press()?;
     }
+
+    // Wait for the serial number list to load
+    let timeouts = wait_timeouts();
+    wait_until(session, &WaitCondition::NotBusy, timeouts.default, timeouts.poll)?;
             
 
     // If tab operations failed, return early
@@ -179,10 +174,8 @@ pub fn run_export(session: &GuiSession, params: &ZMDESNRParams) -> Result<bool>
         if pre_export_back == "true" {
             println!("Sending vkey 3 (back) after export before layout selection");
             // Send vkey 3 (back)
-            if let Ok(window) = session.find_by_id("wnd[0]".to_string()) {
-                if let Some(main_window) = window.downcast::<GuiMainWindow>() {
-                    main_window.send_v_key(3)?; // Send vkey 3 (back)
-                }
+            if let Ok(main_window) = session.find_by_id("wnd[0]".to_string()) {
+                main_window.send_v_key(3)?; // Send vkey 3 (back)
             }
         }
     }
@@ -195,18 +188,17 @@ pub fn run_export(session: &GuiSession, params: &ZMDESNRParams) -> Result<bool>
     }
 
     // Get statusbar message
-    let bar_msg = hit_ctrl(session, 0, "/sbar", "Text", "Get", "")?;
-    match bar_msg.as_str() {
-        "" => {}
-        "No layouts found" => {
+    match read_status(session, 0)? {
+        None => {}
+        Some(message) if message.is(NO_LAYOUTS_FOUND) => {
             println!(
                 "Statusbar message: No layouts found for layout {}",
                 params.layout_row.as_deref().unwrap_or("")
             );
             return Ok(false);
         }
-        _ => {
-            println!("Statusbar message: {}", bar_msg);
+        Some(message) => {
+            println!("Statusbar message: {}", message);
         }
     }
     
@@ -217,18 +209,12 @@ pub fn run_export(session: &GuiSession, params: &ZMDESNRParams) -> Result<bool>
     }
 
     // Export as Excel (common for all tabs)
-    if let Ok(menu) = session.find_by_id("wnd[0]/mbar/menu[0]/menu[3]/menu[1]".to_string()) {
-        if let Some(menu_item) = menu.downcast::<GuiMenu>() {
-            menu_item.select()?;
-        }
+    if let Ok(menu_item) = session.find_by_id(control_id("zmdesnr.list.export_menu")?) {
+        menu_item.select()?;
     }
 
     // Check export window
-    let run_check = check_export_window(
-        session,
-        "ZMDESNR",
-        "ZMDEMAIN SERIAL NUMBER HISTORY CONTENTS",
-    )?;
+    let run_check = check_export_window(session, "ZMDESNR", &ZMDESNR_SERIAL_NUMBER_LIST)?;
     if !run_check {
         println!("Error checking export window");
         return Ok(false);
@@ -244,15 +230,13 @@ pub fn run_export(session: &GuiSession, params: &ZMDESNRParams) -> Result<bool>
 }
 
 /// Handle operations specific to tab 2
-fn handle_tab2_operations(session: &GuiSession, params: &ZMDESNRParams) -> Result<bool> {
+fn handle_tab2_operations(session: &dyn SapSession, params: &ZMDESNRParams) -> Result<bool> {
     // Check if we're using serial number or delivery numbers
     if let Some(serial) = &params.serial_number {
         if !serial.is_empty() {
             // Set the serial number field
-            if let Ok(txt) = session.find_by_id("wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/txtS_PARENT-LOW".to_string()) {
-                if let Some(text_field) = txt.downcast::<GuiTextField>() {
-                    text_field.set_text(serial.clone())?;
-                }
+            if let Ok(text_field) = session.find_by_id(control_id("zmdesnr.selection.parent_low")?) {
+                text_field.set_text(serial.clone())?;
             }
             
 
@@ -263,49 +247,37 @@ fn handle_tab2_operations(session: &GuiSession, params: &ZMDESNRParams) -> Resul
     // If no serial number provided, continue with delivery numbers
     
     // Clear the Low Delivery Number field
-    if let Ok(txt) = session.find_by_id("wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/txtS_VBELN-LOW".to_string()) {
-        if let Some(text_field) = txt.downcast::<GuiTextField>() {
-            text_field.set_text("".to_string())?;
-        }
+    if let Ok(text_field) = session.find_by_id(control_id("zmdesnr.selection.delivery_low")?) {
+        text_field.set_text("".to_string())?;
     }
 
     // Clear the High Delivery Number field
-    if let Ok(txt) = session.find_by_id("wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/txtS_VBELN-HIGH".to_string()) {
-        if let Some(text_field) = txt.downcast::<GuiTextField>() {
-            text_field.set_text("".to_string())?;
-        }
+    if let Ok(text_field) = session.find_by_id(control_id("zmdesnr.selection.delivery_high")?) {
+        text_field.set_text("".to_string())?;
     }
 
     // Clear the Palletized field
-    if let Ok(txt) = session.find_by_id("wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/ctxtS_PALLTD-LOW".to_string()) {
-        if let Some(text_field) = txt.downcast::<GuiCTextField>() {
-            text_field.set_text("".to_string())?;
-        }
+    if let Ok(text_field) = session.find_by_id(control_id("zmdesnr.selection.palletized_low")?) {
+        text_field.set_text("".to_string())?;
     }
 
     // Press Multi Delivery Entry button
-    if let Ok(btn) = session.find_by_id("wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/btn%_S_VBELN_%_APP_%-VALU_PUSH".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("zmdesnr.selection.delivery_multi_button")?) {
+        button.press()?;
     }
 
     // Clear previous entries
-    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-        if let Some(modal_window) = window.downcast::<GuiModalWindow>() {
-            modal_window.send_v_key(16)?; // Clear Previous entries
-        }
+    if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+        modal_window.send_v_key(16)?; // Clear Previous entries
     }
 
     // Paste delivery numbers
     let mut j = 0;
     for delivery_number in &params.delivery_numbers {
         let input_field_id = format!("wnd[1]/usr/tabsTAB_STRIP/tabpSIVA/ssubSCREEN_HEADER:SAPLALDB:3010/tblSAPLALDBSINGLE/txtRSCSEL_255-SLOW_I[1,{}]", j);
-        if let Ok(txt) = session.find_by_id(input_field_id) {
-            if let Some(text_field) = txt.downcast::<GuiTextField>() {
-                text_field.set_text(delivery_number.clone())?;
-                j += 1;
-            }
+        if let Ok(text_field) = session.find_by_id(input_field_id) {
+            text_field.set_text(delivery_number.clone())?;
+            j += 1;
         }
     }
 
@@ -319,45 +291,35 @@ fn handle_tab2_operations(session: &GuiSession, params: &ZMDESNRParams) -> Resul
     }
 
     // Close Multi-Window
-    if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-        if let Some(modal_window) = window.downcast::<GuiModalWindow>() {
-            modal_window.send_v_key(8)?; // Close Multi-Window
-        }
+    if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+        modal_window.send_v_key(8)?; // Close Multi-Window
     }
 
     // Handle exclude serials if provided
     if let Some(exclude_serials) = &params.exclude_serials {
         if !exclude_serials.is_empty() {
             // Press Multi Parent SN Popup button
-            if let Ok(btn) = session.find_by_id("wnd[0]/usr/tabsTABSTRIP_TABB1/tabpUCOMM2/ssub%_SUBSCREEN_TABB1:ZMDE_SERIALNUMBER_HISTORY:9002/btn%_S_PARENT_%_APP_%-VALU_PUSH".to_string()) {
-                if let Some(button) = btn.downcast::<GuiButton>() {
-                    button.press()?;
-                }
+            if let Ok(button) = session.find_by_id(control_id("zmdesnr.selection.parent_multi_button")?) {
+                button.press()?;
             }
 
             // Select Exclude Tab
-            if let Ok(tab) = session.find_by_id("wnd[1]/usr/tabsTAB_STRIP/tabpNOSV".to_string()) {
-                if let Some(tab_strip) = tab.downcast::<GuiTab>() {
-                    tab_strip.select()?;
-                }
+            if let Ok(tab_strip) = session.find_by_id(control_id("zmdesnr.popup.exclude_tab")?) {
+                tab_strip.select()?;
             }
 
             // Clear previous entries
-            if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                if let Some(modal_window) = window.downcast::<GuiModalWindow>() {
-                    modal_window.send_v_key(16)?; // Clear Previous entries
-                }
+            if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+                modal_window.send_v_key(16)?; // Clear Previous entries
             }
 
             // Paste exclude serials
             let mut j = 0;
             for serial in exclude_serials {
                 let input_field_id = format!("wnd[1]/usr/tabsTAB_STRIP/tabpNOSV/ssubSCREEN_HEADER:SAPLALDB:3030/tblSAPLALDBSINGLE_E/txtRSCSEL_255-SLOW_E[1,{}]", j);
-                if let Ok(txt) = session.find_by_id(input_field_id) {
-                    if let Some(text_field) = txt.downcast::<GuiTextField>() {
-                        text_field.set_text(serial.clone())?;
-                        j += 1;
-                    }
+                if let Ok(text_field) = session.find_by_id(input_field_id) {
+                    text_field.set_text(serial.clone())?;
+                    j += 1;
                 }
             }
 
@@ -369,31 +331,25 @@ fn handle_tab2_operations(session: &GuiSession, params: &ZMDESNRParams) -> Resul
             }
 
             // Close Multi-Window
-            if let Ok(window) = session.find_by_id("wnd[1]".to_string()) {
-                if let Some(modal_window) = window.downcast::<GuiModalWindow>() {
-                    modal_window.send_v_key(8)?; // Close Multi-Window
-                }
+            if let Ok(modal_window) = session.find_by_id("wnd[1]".to_string()) {
+                modal_window.send_v_key(8)?; // Close Multi-Window
             }
         }
     }
 
     // Execute
-    if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[8]".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("zmdesnr.selection.execute_button")?) {
+        button.press()?;
     }
 
     Ok(true)
 }
 
 /// Apply layout to the current view
-fn apply_layout(session: &GuiSession, layout_row: &str) -> Result<bool> {
+fn apply_layout(session: &dyn SapSession, layout_row: &str) -> Result<bool> {
     // Choose Layout
-    if let Ok(btn) = session.find_by_id("wnd[0]/tbar[1]/btn[33]".to_string()) {
-        if let Some(button) = btn.downcast::<GuiButton>() {
-            button.press()?;
-        }
+    if let Ok(button) = session.find_by_id(control_id("zmdesnr.list.choose_layout_button")?) {
+        button.press()?;
     }
 
     // Use the existing layout selection utility
@@ -417,16 +373,14 @@ fn apply_layout(session: &GuiSession, layout_row: &str) -> Result<bool> {
 /// Check if items were pasted successfully in the multi-selection window
 ///
 /// This is a helper function for run_export
-fn check_sn_paste(session: &GuiSession, tcode: &str, wnd_idx: i32, row_idx: i32) -> Result<bool> {
+fn check_sn_paste(session: &dyn SapSession, tcode: &str, wnd_idx: i32, row_idx: i32) -> Result<bool> {
     // Check if the first row has a value
     let input_field_id = format!("wnd[{}]/usr/tabsTAB_STRIP/tabpNOSV/ssubSCREEN_HEADER:SAPLALDB:3030/tblSAPLALDBSINGLE_E/txtRSCSEL_255-SLOW_E[1,{}]", wnd_idx, row_idx);
 
-    if let Ok(txt) = session.find_by_id(input_field_id) {
-        if let Some(text_field) = txt.downcast::<GuiTextField>() {
-            let value = text_field.text()?;
-            if !value.is_empty() {
-                return Ok(true);
-            }
+    if let Ok(text_field) = session.find_by_id(input_field_id) {
+        let value = text_field.get_text()?;
+        if !value.is_empty() {
+            return Ok(true);
         }
     }
 
@@ -441,7 +395,7 @@ fn check_sn_paste(session: &GuiSession, tcode: &str, wnd_idx: i32, row_idx: i32)
 ///
 /// This is a helper function for run_export
 fn check_delivery_paste(
-    session: &GuiSession,
+    session: &dyn SapSession,
     tcode: &str,
     wnd_idx: i32,
     row_idx: i32,
@@ -449,12 +403,10 @@ fn check_delivery_paste(
     // Check if the first row has a value
     let input_field_id = format!("wnd[{}]/usr/tabsTAB_STRIP/tabpSIVA/ssubSCREEN_HEADER:SAPLALDB:3010/tblSAPLALDBSINGLE/txtRSCSEL_255-SLOW_I[1,{}]", wnd_idx, row_idx);
 
-    if let Ok(txt) = session.find_by_id(input_field_id) {
-        if let Some(text_field) = txt.downcast::<GuiTextField>() {
-            let value = text_field.text()?;
-            if !value.is_empty() {
-                return Ok(true);
-            }
+    if let Ok(text_field) = session.find_by_id(input_field_id) {
+        let value = text_field.get_text()?;
+        if !value.is_empty() {
+            return Ok(true);
         }
     }
 
@@ -3,7 +3,6 @@ use crossterm::{
     terminal::{Clear, ClearType},
 };
 use dialoguer::Input;
-use sap_scripting::*;
 use std::collections::HashMap;
 use std::fs;
 use std::io::{self};
@@ -12,11 +11,13 @@ use windows::core::Result;
 
 use crate::utils::config_ops::get_reports_dir;
 use crate::utils::config_types::SapConfig;
+use crate::utils::config_values::parse_list;
 use crate::utils::excel_file_ops::read_excel_column;
 use crate::utils::excel_path_utils::get_newest_file;
+use crate::utils::sap_interfaces::SapSession;
 use crate::zmdesnr::{run_export, ZMDESNRParams};
 
-pub fn run_zmdesnr_module(session: &GuiSession) -> Result<()> {
+pub fn run_zmdesnr_module(session: &dyn SapSession) -> Result<()> {
     clear_screen();
     println!("ZMDESNR - Serial Number History");
     println!("==============================");
@@ -45,7 +46,7 @@ pub fn run_zmdesnr_module(session: &GuiSession) -> Result<()> {
     Ok(())
 }
 
-pub fn run_zmdesnr_auto(session: &GuiSession) -> Result<()> {
+pub fn run_zmdesnr_auto(session: &dyn SapSession) -> Result<()> {
     clear_screen();
     println!("ZMDESNR - Auto Run from Configuration");
     println!("===================================");
@@ -200,13 +201,13 @@ fn create_zmdesnr_params_from_config(config: &HashMap<String, String>) -> ZMDESN
 
     // Set add_layout_columns if available
     if let Some(add_layout_columns) = config.get("add_layout_columns") {
-        // Parse the string value as a TOML array
-        match toml::from_str::<Vec<String>>(add_layout_columns) {
-            Ok(columns) => {
+        // Parse the value as a TOML array (or a comma separated list)
+        match parse_list(add_layout_columns) {
+            Some(columns) => {
                 params.additional_params.add_layout_columns = Some(columns);
             }
-            Err(e) => {
-                println!("Error parsing add_layout_columns: {}", e);
+            None => {
+                println!("Error parsing add_layout_columns: {}", add_layout_columns);
                 // Use default values from the task
                 params.additional_params.add_layout_columns = Some(vec![
                     "Created By".to_string(),
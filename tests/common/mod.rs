@@ -0,0 +1,42 @@
+// Mock session helpers shared by the integration tests
+#![allow(dead_code)]
+
+use sap_automation::utils::sap_mock_impl::{MockComponent, MockGrid, MockSapSession};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Add a component to the mock session and return a handle for later assertions
+pub fn add(
+    session: &mut MockSapSession,
+    id: &str,
+    r_type: &str,
+    text: &str,
+) -> Rc<RefCell<MockComponent>> {
+    let name = id.rsplit('/').next().unwrap_or(id);
+    let mut component = MockComponent::new(id, name, r_type);
+    component.text = text.to_string();
+    let component = Rc::new(RefCell::new(component));
+    session.add_component(id, component.clone());
+    component
+}
+
+// Add a component showing `grid` to the mock session and return a handle for later assertions
+pub fn add_grid(
+    session: &mut MockSapSession,
+    id: &str,
+    r_type: &str,
+    grid: MockGrid,
+) -> Rc<RefCell<MockComponent>> {
+    let component = add(session, id, r_type, "");
+    component.borrow_mut().grid = Some(grid);
+    component
+}
+
+// Create a mock session in `transaction` with a main window titled `title` and a status bar
+pub fn create_session(name: &str, transaction: &str, title: &str) -> MockSapSession {
+    let mut session = MockSapSession::new(name);
+    session.set_transaction(transaction);
+    add(&mut session, "wnd[0]", "GuiMainWindow", title);
+    add(&mut session, "wnd[0]/sbar", "GuiStatusbar", "");
+    session
+}
@@ -0,0 +1,199 @@
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_impl::{MockComponent, MockSapSession};
+use std::cell::RefCell;
+use std::rc::Rc;
+use windows::core::Result;
+
+// Build the VL06O multi-selection popup as a tree instead of flat ids
+fn create_tree_session() -> MockSapSession {
+    let mut first = MockComponent::new(
+        "ctxtRSCSEL_255-SLOW_I[1,0]",
+        "RSCSEL_255-SLOW_I",
+        "GuiCTextField",
+    );
+    first.text = "80001234".to_string();
+    let second = MockComponent::new(
+        "ctxtRSCSEL_255-SLOW_I[1,1]",
+        "RSCSEL_255-SLOW_I",
+        "GuiCTextField",
+    );
+
+    let popup =
+        MockComponent::new("wnd[1]", "wnd[1]", "GuiModalWindow")
+            .with_child(
+                MockComponent::new("usr", "usr", "GuiUserArea").with_child(
+                    MockComponent::new("tabsTAB_STRIP", "TAB_STRIP", "GuiTabStrip").with_child(
+                        MockComponent::new("tabpSIVA", "SIVA", "GuiTab").with_child(
+                            MockComponent::new(
+                                "ssubSCREEN_HEADER:SAPLALDB:3010",
+                                "SCREEN_HEADER",
+                                "GuiSimpleContainer",
+                            )
+                            .with_child(
+                                MockComponent::new(
+                                    "tblSAPLALDBSINGLE",
+                                    "SAPLALDBSINGLE",
+                                    "GuiTableControl",
+                                )
+                                .with_child(first)
+                                .with_child(second),
+                            ),
+                        ),
+                    ),
+                ),
+            )
+            .with_child(
+                MockComponent::new("tbar[0]", "tbar[0]", "GuiToolbar")
+                    .with_child(MockComponent::new("btn[8]", "btn[8]", "GuiButton")),
+            );
+
+    let mut session = MockSapSession::new("Tree Session");
+    session.add_component("wnd[1]", Rc::new(RefCell::new(popup)));
+    session
+}
+
+const TABLE_ID: &str =
+    "wnd[1]/usr/tabsTAB_STRIP/tabpSIVA/ssubSCREEN_HEADER:SAPLALDB:3010/tblSAPLALDBSINGLE";
+
+#[test]
+fn test_tree_children_get_full_ids() -> Result<()> {
+    let session = create_tree_session();
+
+    let field = session.find_by_id(format!("{}/ctxtRSCSEL_255-SLOW_I[1,0]", TABLE_ID))?;
+    assert_eq!(field.get_text()?, "80001234");
+    assert_eq!(field.r_type()?, "GuiCTextField");
+
+    let handle = session
+        .component(&format!("{}/ctxtRSCSEL_255-SLOW_I[1,1]", TABLE_ID))
+        .expect("nested component should be registered");
+    assert_eq!(
+        handle.borrow().id,
+        format!("{}/ctxtRSCSEL_255-SLOW_I[1,1]", TABLE_ID)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_find_by_full_path_and_relative_id() -> Result<()> {
+    let session = create_tree_session();
+
+    let button = session.find_by_id("/app/con[0]/ses[0]/wnd[1]/tbar[0]/btn[8]".to_string())?;
+    assert_eq!(button.r_type()?, "GuiButton");
+
+    let table = session.find_by_relative_id("wnd[1]", "usr/tabsTAB_STRIP/tabpSIVA")?;
+    assert_eq!(table.name()?, "SIVA");
+
+    // Absolute IDs ignore the container
+    let window = session.find_by_relative_id("wnd[1]/usr", "wnd[1]")?;
+    assert_eq!(window.r_type()?, "GuiModalWindow");
+
+    assert!(session
+        .find_by_relative_id("wnd[1]", "usr/txtMissing")
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_children_are_enumerated_in_order() {
+    let session = create_tree_session();
+
+    let ids: Vec<String> = session
+        .children("wnd[1]")
+        .iter()
+        .map(|c| c.borrow().id.clone())
+        .collect();
+    assert_eq!(ids, vec!["wnd[1]/usr", "wnd[1]/tbar[0]"]);
+
+    let cells = session.children(TABLE_ID);
+    assert_eq!(cells.len(), 2);
+    assert!(session.children("wnd[1]/tbar[0]/btn[8]").is_empty());
+    assert!(session.children("wnd[5]").is_empty());
+}
+
+#[test]
+fn test_find_by_name_and_type() {
+    let session = create_tree_session();
+
+    let first = session
+        .find_by_name("wnd[1]", "RSCSEL_255-SLOW_I", "GuiCTextField")
+        .expect("field should be found by name");
+    assert_eq!(first.borrow().text, "80001234");
+
+    let all = session.find_all_by_name("wnd[1]", "RSCSEL_255-SLOW_I", "GuiCTextField");
+    assert_eq!(all.len(), 2);
+    assert!(all[1].borrow().id.ends_with("[1,1]"));
+
+    // The type has to match as well
+    assert!(session
+        .find_by_name("wnd[1]", "RSCSEL_255-SLOW_I", "GuiTextField")
+        .is_none());
+    // Only components below the container are searched
+    assert!(session
+        .find_by_name("wnd[1]/tbar[0]", "RSCSEL_255-SLOW_I", "GuiCTextField")
+        .is_none());
+}
+
+#[test]
+fn test_flat_components_join_the_tree() {
+    let mut session = MockSapSession::new("Flat Session");
+    // Children added before their parent are adopted once it arrives
+    session.add_component(
+        "wnd[0]/usr/txtField",
+        Rc::new(RefCell::new(MockComponent::new(
+            "wnd[0]/usr/txtField",
+            "Field",
+            "GuiTextField",
+        ))),
+    );
+    session.add_component(
+        "wnd[0]/usr",
+        Rc::new(RefCell::new(MockComponent::new(
+            "wnd[0]/usr",
+            "usr",
+            "GuiUserArea",
+        ))),
+    );
+    session.add_component(
+        "wnd[0]",
+        Rc::new(RefCell::new(MockComponent::new(
+            "wnd[0]",
+            "wnd[0]",
+            "GuiMainWindow",
+        ))),
+    );
+
+    assert_eq!(session.children("wnd[0]").len(), 1);
+    assert_eq!(session.children("wnd[0]/usr").len(), 1);
+    assert!(session
+        .find_by_name("wnd[0]", "Field", "GuiTextField")
+        .is_some());
+
+    // Replacing a component swaps it in its parent's children
+    session.add_component(
+        "wnd[0]/usr/txtField",
+        Rc::new(RefCell::new(MockComponent::new(
+            "wnd[0]/usr/txtField",
+            "Replaced",
+            "GuiTextField",
+        ))),
+    );
+    let children = session.children("wnd[0]/usr");
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].borrow().name, "Replaced");
+}
+
+#[test]
+fn test_closing_popup_removes_its_tree() -> Result<()> {
+    let session = create_tree_session();
+
+    session.close_window(1)?;
+
+    assert!(session.find_by_id(TABLE_ID.to_string()).is_err());
+    assert!(session
+        .find_all_by_name("wnd[1]", "RSCSEL_255-SLOW_I", "GuiCTextField")
+        .is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use chrono::NaiveDate;
+use sap_automation::utils::config_types::SapConfig;
+use sap_automation::utils::config_values::{format_list, parse_list};
+use std::fs;
+
+const NATIVE_CONFIG: &str = r#"
+[global]
+instance_id = "rs"
+reports_dir = "C:\\Reports"
+unattended = true
+wait_timeout_ms = 5000
+max_relogin_attempts = 3
+retries = 3
+
+[tcode.ZMDESNR]
+variant = "DAILY"
+date_range_start = 2025-04-01
+date_range_end = 2025-04-15
+by_date = true
+tab_number = 2
+add_layout_columns = ["Created By", "Shipment Number"]
+
+[loop]
+tcode = "ZMDESNR"
+iterations = 4
+delay_seconds = 15
+
+[sequence]
+options = ["VT11", "ZMDESNR"]
+iterations = 2
+delay_seconds = 30
+interval_seconds = 5
+param_parallel = true
+param_batch = 25
+"#;
+
+const LEGACY_CONFIG: &str = r#"
+[global]
+instance_id = "rs"
+reports_dir = "C:\\Reports"
+unattended = "true"
+wait_timeout_ms = "5000"
+max_relogin_attempts = "3"
+retries = "3"
+
+[tcode.ZMDESNR]
+variant = "DAILY"
+date_range_start = "04/01/2025"
+date_range_end = "04/15/2025"
+by_date = "true"
+tab_number = "2"
+add_layout_columns = '["Created By", "Shipment Number"]'
+
+[loop]
+tcode = "ZMDESNR"
+iterations = "4"
+delay_seconds = "15"
+
+[sequence]
+options = ["VT11", "ZMDESNR"]
+iterations = "2"
+delay_seconds = "30"
+interval_seconds = "5"
+param_parallel = "true"
+param_batch = "25"
+"#;
+
+fn load_config(name: &str, content: &str) -> SapConfig {
+    let path = std::env::temp_dir().join(format!("sap_config_types_{}.toml", name));
+    fs::write(&path, content).unwrap();
+    SapConfig::load_from_path(path.to_str().unwrap()).unwrap()
+}
+
+fn assert_typed_values(config: &SapConfig) {
+    let zmdesnr = &config.tcode.as_ref().unwrap()["ZMDESNR"];
+    assert_eq!(
+        zmdesnr.date_range_start,
+        NaiveDate::from_ymd_opt(2025, 4, 1)
+    );
+    assert_eq!(zmdesnr.date_range_end, NaiveDate::from_ymd_opt(2025, 4, 15));
+    assert_eq!(zmdesnr.by_date, Some(true));
+    assert_eq!(zmdesnr.tab_number, Some(2));
+    assert_eq!(
+        zmdesnr.add_layout_columns,
+        Some(vec![
+            "Created By".to_string(),
+            "Shipment Number".to_string()
+        ])
+    );
+    assert!(zmdesnr.additional_params.is_empty());
+
+    let loop_config = config.loop_config.as_ref().unwrap();
+    assert_eq!(loop_config.iterations, 4);
+    assert_eq!(loop_config.delay_seconds, 15);
+
+    let sequence = config.sequence.as_ref().unwrap();
+    assert_eq!(sequence.options, vec!["VT11", "ZMDESNR"]);
+    assert_eq!(sequence.iterations, 2);
+    assert_eq!(sequence.delay_seconds, 30);
+    assert_eq!(sequence.interval_seconds, 5);
+    assert_eq!(sequence.parallel, Some(true));
+    assert_eq!(sequence.params["param_batch"], "25");
+    assert!(!sequence.params.contains_key("param_parallel"));
+
+    let global = config.global.as_ref().unwrap();
+    assert_eq!(global.run.unattended, Some(true));
+    assert_eq!(global.run.wait_timeout_ms, Some(5000));
+    assert_eq!(global.run.max_relogin_attempts, Some(3));
+    assert!(!global.additional_params.contains_key("unattended"));
+    assert_eq!(global.additional_params["retries"], "3");
+
+    assert!(config.dropped_keys.is_empty(), "{:?}", config.dropped_keys);
+}
+
+#[test]
+fn test_native_values() {
+    assert_typed_values(&load_config("native", NATIVE_CONFIG));
+}
+
+#[test]
+fn test_legacy_strings_are_still_accepted() {
+    assert_typed_values(&load_config("legacy", LEGACY_CONFIG));
+}
+
+#[test]
+fn test_unusable_keys_are_reported() {
+    let content = NATIVE_CONFIG
+        .replace("by_date = true", "by_date = \"maybe\"")
+        .replace("iterations = 4", "iterations = \"many\"")
+        .replace(
+            "date_range_end = 2025-04-15",
+            "date_range_end = \"15.04.2025\"",
+        )
+        .replace("retries = 3", "retries = [1, 2]")
+        .replace("unattended = true", "unattended = \"maybe\"")
+        .replace("wait_timeout_ms = 5000", "wait_timeout_ms = -1");
+    let config = load_config("dropped", &content);
+
+    let zmdesnr = &config.tcode.as_ref().unwrap()["ZMDESNR"];
+    assert_eq!(zmdesnr.by_date, None);
+    assert_eq!(zmdesnr.date_range_end, None);
+    assert_eq!(zmdesnr.tab_number, Some(2), "Other keys are still read");
+    assert_eq!(
+        config.loop_config.as_ref().unwrap().iterations,
+        1,
+        "Falls back to the default"
+    );
+    let global = config.global.as_ref().unwrap();
+    assert_eq!(global.run.unattended, None);
+    assert_eq!(global.run.wait_timeout_ms, None);
+    assert!(!global.additional_params.contains_key("unattended"));
+
+    let dropped = config.dropped_keys.join("\n");
+    for key in [
+        "tcode.ZMDESNR.by_date",
+        "tcode.ZMDESNR.date_range_end",
+        "loop.iterations",
+        "global.retries",
+        "global.unattended",
+        "global.wait_timeout_ms",
+    ] {
+        assert!(dropped.contains(key), "{} missing in {}", key, dropped);
+    }
+    assert_eq!(config.dropped_keys.len(), 6, "{}", dropped);
+}
+
+#[test]
+fn test_tcode_config_uses_date_format() {
+    let mut config = load_config("tcode_config", NATIVE_CONFIG);
+
+    let zmdesnr = config.get_tcode_config("ZMDESNR", None).unwrap();
+    assert_eq!(zmdesnr["date_range_start"], "04/01/2025");
+    assert_eq!(zmdesnr["by_date"], "true");
+    assert_eq!(zmdesnr["tab_number"], "2");
+    assert_eq!(
+        parse_list(&zmdesnr["add_layout_columns"]),
+        Some(vec![
+            "Created By".to_string(),
+            "Shipment Number".to_string()
+        ])
+    );
+
+    config.global.as_mut().unwrap().date_format = "yyyy-mm-dd".to_string();
+    let zmdesnr = config.get_tcode_config("ZMDESNR", None).unwrap();
+    assert_eq!(zmdesnr["date_range_start"], "2025-04-01");
+}
+
+#[test]
+fn test_save_writes_native_values() {
+    let config = load_config("save", LEGACY_CONFIG);
+    let path = std::env::temp_dir().join("sap_config_types_saved.toml");
+    config.save_to_path(path.to_str().unwrap()).unwrap();
+
+    let content = fs::read_to_string(&path).unwrap();
+    for line in [
+        "date_range_start = 2025-04-01",
+        "by_date = true",
+        "tab_number = 2",
+        "add_layout_columns = [\"Created By\", \"Shipment Number\"]",
+        "iterations = 4",
+        "interval_seconds = 5",
+    ] {
+        assert!(content.contains(line), "{} missing in\n{}", line, content);
+    }
+
+    assert_typed_values(&SapConfig::load_from_path(path.to_str().unwrap()).unwrap());
+}
+
+#[test]
+fn test_save_keeps_the_type_of_every_value() {
+    let config = load_config("save_native", NATIVE_CONFIG);
+    let path = std::env::temp_dir().join("sap_config_types_saved_native.toml");
+    config.save_to_path(path.to_str().unwrap()).unwrap();
+
+    let content = fs::read_to_string(&path).unwrap();
+    for line in [
+        "unattended = true",
+        "wait_timeout_ms = 5000",
+        "max_relogin_attempts = 3",
+        "retries = 3",
+        "param_parallel = true",
+        "param_batch = 25",
+    ] {
+        assert!(content.contains(line), "{} missing in\n{}", line, content);
+    }
+    assert!(!content.contains("param_param_"), "{}", content);
+
+    let saved = SapConfig::load_from_path(path.to_str().unwrap()).unwrap();
+    assert_typed_values(&saved);
+    let global = saved.global.as_ref().unwrap();
+    assert_eq!(
+        saved.raw_config.as_ref().unwrap()["global"]["retries"].as_integer(),
+        Some(3)
+    );
+    assert_eq!(global.additional_params["retries"], "3");
+}
+
+#[test]
+fn test_parse_list() {
+    let columns = Some(vec![
+        "Created By".to_string(),
+        "Shipment Number".to_string(),
+    ]);
+
+    assert_eq!(parse_list("[\"Created By\", \"Shipment Number\"]"), columns);
+    assert_eq!(parse_list("Created By, Shipment Number"), columns);
+    assert_eq!(parse_list(&format_list(columns.as_ref().unwrap())), columns);
+    assert_eq!(parse_list("[1, 2]"), None);
+}
@@ -0,0 +1,19 @@
+use sap_automation::app::is_connection_string;
+
+#[test]
+fn test_connection_strings() {
+    assert!(is_connection_string("/H/sapes1.example.com/S/3200"));
+    assert!(is_connection_string(
+        " /M/msges1.example.com/S/3601/G/PUBLIC "
+    ));
+    assert!(is_connection_string(
+        "conn=/H/sapes1.example.com/S/3200&clnt=025"
+    ));
+}
+
+#[test]
+fn test_logon_entries() {
+    assert!(!is_connection_string("ES1 - Production"));
+    assert!(!is_connection_string("Production Instance"));
+    assert!(!is_connection_string(""));
+}
@@ -0,0 +1,122 @@
+use sap_automation::utils::config_types::SapConfig;
+use sap_automation::utils::sap_control_catalog::{ControlCatalog, DEFAULT_CONTROLS};
+use sap_automation::utils::sap_control_id::ControlId;
+use std::fs;
+use std::path::Path;
+
+const OVERRIDES: &str = r#"
+[vl06o.selection]
+delivery_multi_button = "wnd[0]/usr/btn%_IT_VBELN_%_APP_%-VALU_PUSH2"
+
+[instance.rs.vl06o.selection]
+goods_issue_date_low = "wnd[0]/usr/ctxtIT_WADAT2-LOW"
+
+[instance.QA1]
+"vl06o.selection.goods_issue_date_low" = "wnd[0]/usr/ctxtZZ_WADAT-LOW"
+"#;
+
+#[test]
+fn test_defaults_are_valid_control_ids() {
+    for (name, id) in DEFAULT_CONTROLS {
+        let control = ControlId::parse(id).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        assert_eq!(control.to_string(), *id, "{} should round trip", name);
+    }
+
+    let catalog = ControlCatalog::new();
+    assert_eq!(catalog.names().len(), DEFAULT_CONTROLS.len());
+    assert_eq!(
+        catalog.id("vl06o.selection.delivery_multi_button").unwrap(),
+        "wnd[0]/usr/btn%_IT_VBELN_%_APP_%-VALU_PUSH"
+    );
+}
+
+#[test]
+fn test_unknown_name_is_an_error() {
+    let catalog = ControlCatalog::new();
+    assert!(catalog.id("vl06o.selection.no_such_button").is_err());
+    assert!(catalog.control("vl06o").is_err());
+}
+
+#[test]
+fn test_overrides_apply_to_every_instance() {
+    let catalog = ControlCatalog::from_toml_str(OVERRIDES, "PRD").unwrap();
+
+    assert_eq!(
+        catalog.id("vl06o.selection.delivery_multi_button").unwrap(),
+        "wnd[0]/usr/btn%_IT_VBELN_%_APP_%-VALU_PUSH2"
+    );
+    assert_eq!(
+        catalog.id("vl06o.selection.goods_issue_date_low").unwrap(),
+        "wnd[0]/usr/ctxtIT_WADAT-LOW",
+        "Instance overrides for other instances are ignored"
+    );
+    assert_eq!(
+        catalog.id("vt11.selection.date_low").unwrap(),
+        "wnd[0]/usr/ctxtK_DATEN-LOW",
+        "Controls without overrides keep their defaults"
+    );
+}
+
+#[test]
+fn test_instance_overrides() {
+    let rs = ControlCatalog::from_toml_str(OVERRIDES, "rs").unwrap();
+    assert_eq!(
+        rs.id("vl06o.selection.goods_issue_date_low").unwrap(),
+        "wnd[0]/usr/ctxtIT_WADAT2-LOW"
+    );
+
+    let qa = ControlCatalog::from_toml_str(OVERRIDES, "QA1").unwrap();
+    let control = qa.control("vl06o.selection.goods_issue_date_low").unwrap();
+    assert_eq!(control.last().unwrap().name, "ZZ_WADAT-LOW");
+    assert_eq!(
+        qa.id("vl06o.selection.delivery_multi_button").unwrap(),
+        "wnd[0]/usr/btn%_IT_VBELN_%_APP_%-VALU_PUSH2",
+        "Instance overrides are applied on top of the shared ones"
+    );
+}
+
+#[test]
+fn test_invalid_overrides_are_rejected() {
+    for content in [
+        "[vl06o.selection]\ndelivery_multi_buton = \"wnd[0]/usr/btnX\"",
+        "[vl06o.selection]\ndelivery_multi_button = \"usr/btnX\"",
+        "[vl06o.selection]\ndelivery_multi_button = 5",
+        "instance = \"rs\"",
+        "[vl06o",
+    ] {
+        assert!(
+            ControlCatalog::from_toml_str(content, "rs").is_err(),
+            "{} should be rejected",
+            content
+        );
+    }
+}
+
+#[test]
+fn test_load_next_to_config_file() {
+    let dir = std::env::temp_dir().join("sap_control_catalog_tests");
+    fs::create_dir_all(&dir).unwrap();
+    let mut config = SapConfig::new();
+    config.config_path = dir.join("config.toml").to_string_lossy().to_string();
+    config.set_instance_id("QA1");
+
+    let path = ControlCatalog::path_for(&config);
+    assert_eq!(path, dir.join("controls.toml"));
+
+    let _ = fs::remove_file(&path);
+    assert_eq!(
+        ControlCatalog::load(&config).unwrap(),
+        ControlCatalog::new()
+    );
+
+    fs::write(&path, OVERRIDES).unwrap();
+    let catalog = ControlCatalog::load(&config).unwrap();
+    assert_eq!(
+        catalog.id("vl06o.selection.goods_issue_date_low").unwrap(),
+        "wnd[0]/usr/ctxtZZ_WADAT-LOW"
+    );
+
+    fs::write(&path, "[vt11.selection]\ndate_low = \"\"").unwrap();
+    assert!(ControlCatalog::load_from_path(Path::new(&path), "QA1").is_err());
+    fs::remove_file(&path).unwrap();
+}
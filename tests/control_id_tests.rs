@@ -0,0 +1,136 @@
+use sap_automation::utils::sap_control_id::{ControlId, ControlIndex, ControlSegment};
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_impl::{MockComponent, MockSapSession};
+use std::cell::RefCell;
+use std::rc::Rc;
+use windows::core::Result;
+
+const MULTI_SELECT_CELL: &str = "wnd[1]/usr/tabsTAB_STRIP/tabpSIVA/ssubSCREEN_HEADER:SAPLALDB:3010/tblSAPLALDBSINGLE/ctxtRSCSEL_255-SLOW_I[1,0]";
+
+#[test]
+fn test_parse_round_trips() {
+    for id in [
+        "wnd[0]",
+        "wnd[0]/sbar",
+        "wnd[0]/tbar[1]/btn[8]",
+        "wnd[1]/usr/lbl[2,4]",
+        "wnd[0]/usr/btn%_IT_VBELN_%_APP_%-VALU_PUSH",
+        "wnd[0]/usr/cntlGRID1/shellcont/shell/shellcont[1]/shell",
+        r"wnd[0]/usr/tabsTAXI_TABSTRIP_OVERVIEW/tabpT\01/ssubSUBSCREEN_BODY:SAPMV50A:1102/ctxtLIKP-WADAT",
+        MULTI_SELECT_CELL,
+    ] {
+        let control = ControlId::parse(id).unwrap();
+        assert_eq!(control.to_string(), id);
+    }
+}
+
+#[test]
+fn test_parse_segments() {
+    let control = ControlId::parse(MULTI_SELECT_CELL).unwrap();
+
+    assert_eq!(control.window_index(), 1);
+    assert_eq!(control.segments().len(), 6);
+    assert_eq!(control.segments()[1].prefix, "tabs");
+    assert_eq!(control.segments()[1].name, "TAB_STRIP");
+    let last = control.last().unwrap();
+    assert_eq!(last.prefix, "ctxt");
+    assert_eq!(last.name, "RSCSEL_255-SLOW_I");
+    assert_eq!(last.index, Some(ControlIndex::Cell { column: 1, row: 0 }));
+    assert_eq!(control.cell(), Some((1, 0)));
+
+    let button = ControlId::parse("wnd[0]/tbar[1]/btn[8]").unwrap();
+    assert_eq!(
+        button.last(),
+        Some(&ControlSegment::indexed("btn", 8)),
+        "Indexed segments have no name"
+    );
+    assert_eq!(button.cell(), None);
+}
+
+#[test]
+fn test_parse_full_path() {
+    let control = ControlId::parse("/app/con[0]/ses[0]/wnd[0]/usr/ctxtLIKP-VBELN").unwrap();
+    assert_eq!(control.to_string(), "wnd[0]/usr/ctxtLIKP-VBELN");
+}
+
+#[test]
+fn test_parse_rejects_malformed_ids() {
+    for id in [
+        "",
+        "usr/ctxtLIKP-VBELN",
+        "wnd[x]/usr",
+        "wnd[-1]",
+        "wnd[0]//usr",
+        "wnd[0]/usr/",
+        "wnd[0]/usr/ctxtLIKP-VBELN[1",
+        "wnd[0]/usr/ctxtLIKP-VBELN]",
+        "wnd[0]/usr/ctxtLIKP[1,]",
+        "wnd[0]/usr/LIKP-VBELN",
+        "wnd[0]/usr/fooLIKP-VBELN",
+        "wnd[0]/usr/ctxt LIKP",
+    ] {
+        assert!(ControlId::parse(id).is_err(), "{} should be rejected", id);
+    }
+}
+
+#[test]
+fn test_builder_matches_hand_written_id() {
+    let control = ControlId::window(1)
+        .usr()
+        .child(ControlSegment::new("tabs", "TAB_STRIP"))
+        .child(ControlSegment::new("tabp", "SIVA"))
+        .child(ControlSegment::new("ssub", "SCREEN_HEADER:SAPLALDB:3010"))
+        .child(ControlSegment::new("tbl", "SAPLALDBSINGLE"))
+        .child(ControlSegment::cell("ctxt", "RSCSEL_255-SLOW_I", 1, 0));
+
+    assert_eq!(control.to_string(), MULTI_SELECT_CELL);
+    assert_eq!(control, ControlId::parse(MULTI_SELECT_CELL).unwrap());
+    assert_eq!(
+        control.clone().with_cell(1, 6).to_string(),
+        MULTI_SELECT_CELL.replace("[1,0]", "[1,6]")
+    );
+}
+
+#[test]
+fn test_join_relative_paths() -> Result<()> {
+    let window = ControlId::window(0);
+
+    assert_eq!(window.join("")?.to_string(), "wnd[0]");
+    assert_eq!(window.join("/sbar")?.to_string(), "wnd[0]/sbar");
+    assert_eq!(window.join("sbar")?.to_string(), "wnd[0]/sbar");
+    assert_eq!(
+        window.join("/usr/txtMESSTXT1")?.relative_path(),
+        "/usr/txtMESSTXT1"
+    );
+    assert!(window.join("/usr/??").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_rebase_and_parent() {
+    let control = ControlId::parse("wnd[1]/usr/btnSPOP-OPTION1").unwrap();
+
+    let rebased = control.rebase(2);
+    assert_eq!(rebased.to_string(), "wnd[2]/usr/btnSPOP-OPTION1");
+    assert_eq!(control.window_index(), 1, "Rebasing returns a new ID");
+
+    let parent = control.parent().unwrap();
+    assert_eq!(parent.to_string(), "wnd[1]/usr");
+    assert_eq!(parent.parent().unwrap(), ControlId::window(1));
+    assert!(ControlId::window(1).parent().is_none());
+}
+
+#[test]
+fn test_find_control_on_mock_session() -> Result<()> {
+    let mut session = MockSapSession::new("Control Session");
+    let mut field = MockComponent::new(MULTI_SELECT_CELL, "RSCSEL_255-SLOW_I", "GuiCTextField");
+    field.text = "80001234".to_string();
+    session.add_component(MULTI_SELECT_CELL, Rc::new(RefCell::new(field)));
+
+    let control: ControlId = MULTI_SELECT_CELL.parse()?;
+    assert_eq!(session.find_control(&control)?.get_text()?, "80001234");
+    assert!(session.find_control(&control.with_cell(1, 1)).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,236 @@
+use sap_automation::utils::credential_envelope::{
+    CredentialEnvelope, CredentialError, EnvelopeKdf, KdfParams, ENVELOPE_VERSION,
+};
+use sap_automation::utils::credential_store::{CredentialStore, Credentials, FileCredentialStore};
+use sap_automation::utils::utils::encrypt_data;
+use std::fs;
+use std::path::PathBuf;
+
+const KEY: [u8; 32] = [7u8; 32];
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sap_envelope_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn sealed() -> CredentialEnvelope {
+    CredentialEnvelope::seal("JDOE\nsecret", &KEY, EnvelopeKdf::None, "ES1", "025").unwrap()
+}
+
+#[test]
+fn test_round_trip() -> Result<(), CredentialError> {
+    let envelope = CredentialEnvelope::parse(&sealed().to_file_string()?)?;
+
+    assert_eq!(envelope.version, ENVELOPE_VERSION);
+    assert_eq!(envelope.created, envelope.rotated);
+    assert_eq!(envelope.open(&KEY, "ES1", "025")?, "JDOE\nsecret");
+
+    Ok(())
+}
+
+#[test]
+fn test_wrong_key() {
+    let error = sealed().open(&[8u8; 32], "ES1", "025").unwrap_err();
+
+    assert_eq!(error, CredentialError::WrongKey);
+}
+
+#[test]
+fn test_tampered_ciphertext() -> Result<(), CredentialError> {
+    let mut envelope = sealed();
+    let mut ciphertext = envelope.ciphertext.into_bytes();
+    ciphertext[0] = if ciphertext[0] == b'A' { b'B' } else { b'A' };
+    envelope.ciphertext = String::from_utf8(ciphertext).unwrap();
+
+    assert_eq!(
+        envelope.open(&KEY, "ES1", "025").unwrap_err(),
+        CredentialError::Tampered
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_tampered_header() {
+    let mut envelope = sealed();
+    envelope.created = "2001-01-01T00:00:00+00:00".to_string();
+
+    assert_eq!(
+        envelope.open(&KEY, "ES1", "025").unwrap_err(),
+        CredentialError::Tampered
+    );
+}
+
+#[test]
+fn test_moved_to_another_instance() {
+    let mut envelope = sealed();
+
+    assert!(matches!(
+        envelope.open(&KEY, "QS1", "025"),
+        Err(CredentialError::WrongScope { .. })
+    ));
+    assert!(matches!(
+        envelope.open(&KEY, "ES1", "100"),
+        Err(CredentialError::WrongScope { .. })
+    ));
+
+    // Editing the header to match does not help either
+    envelope.instance_id = "QS1".to_string();
+    assert_eq!(
+        envelope.open(&KEY, "QS1", "025").unwrap_err(),
+        CredentialError::Tampered
+    );
+}
+
+#[test]
+fn test_instance_binding_ignores_case_and_spacing() -> Result<(), CredentialError> {
+    let envelope =
+        CredentialEnvelope::seal("JDOE\nsecret", &KEY, EnvelopeKdf::None, " es1", "025")?;
+
+    assert_eq!(envelope.instance_id, "ES1");
+    assert_eq!(envelope.open(&KEY, "ES1", "025")?, "JDOE\nsecret");
+    assert_eq!(envelope.open(&KEY, "es1 ", "025")?, "JDOE\nsecret");
+
+    Ok(())
+}
+
+#[test]
+fn test_client_binding_ignores_case_and_spacing() -> Result<(), CredentialError> {
+    let envelope =
+        CredentialEnvelope::seal("JDOE\nsecret", &KEY, EnvelopeKdf::None, "ES1", "025 ")?;
+
+    assert_eq!(envelope.client, "025");
+    assert_eq!(envelope.open(&KEY, "ES1", "025")?, "JDOE\nsecret");
+    assert_eq!(envelope.open(&KEY, "ES1", " 025")?, "JDOE\nsecret");
+    assert!(matches!(
+        envelope.open(&KEY, "ES1", "0025"),
+        Err(CredentialError::WrongScope { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_reseal_keeps_created() -> Result<(), CredentialError> {
+    let mut envelope = sealed();
+    envelope.created = "2001-01-01T00:00:00+00:00".to_string();
+
+    let resealed = envelope.reseal("JDOE\nnew", &KEY)?;
+
+    assert_eq!(resealed.created, "2001-01-01T00:00:00+00:00");
+    assert_ne!(resealed.rotated, resealed.created);
+    assert_eq!(resealed.open(&KEY, "ES1", "025")?, "JDOE\nnew");
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_errors() {
+    let legacy = encrypt_data("JDOE\nsecret", &KEY).unwrap();
+    assert_eq!(
+        CredentialEnvelope::parse(&legacy).unwrap_err(),
+        CredentialError::OldFormat
+    );
+
+    let with_version = |version: &str| {
+        sealed().to_file_string().unwrap().replace(
+            &format!("\"version\": {}", ENVELOPE_VERSION),
+            &format!("\"version\": {}", version),
+        )
+    };
+    assert_eq!(
+        CredentialEnvelope::parse(&with_version("9")).unwrap_err(),
+        CredentialError::UnsupportedVersion(9)
+    );
+    for version in ["0", "1", "4294967298"] {
+        assert!(
+            matches!(
+                CredentialEnvelope::parse(&with_version(version)),
+                Err(CredentialError::Malformed(_))
+            ),
+            "version {}",
+            version
+        );
+    }
+
+    assert!(matches!(
+        CredentialEnvelope::parse("not a credential file"),
+        Err(CredentialError::Malformed(_))
+    ));
+}
+
+#[test]
+fn test_password_kdf() -> Result<(), CredentialError> {
+    let kdf = EnvelopeKdf::argon2id(KdfParams {
+        memory_kib: 1024,
+        iterations: 1,
+        parallelism: 1,
+    });
+    let key = kdf.derive_key("master")?;
+    let envelope = CredentialEnvelope::seal("{}", &key, kdf, "*", "*")?;
+
+    let parsed = CredentialEnvelope::parse(&envelope.to_file_string()?)?;
+    let key = parsed.kdf.derive_key("master")?;
+    assert_eq!(parsed.open(&key, "*", "*")?, "{}");
+
+    let guess = parsed.kdf.derive_key("guess")?;
+    assert_eq!(
+        parsed.open(&guess, "*", "*").unwrap_err(),
+        CredentialError::WrongKey
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_file_store_upgrades_old_files() -> anyhow::Result<()> {
+    let dir = test_dir("upgrade");
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join("sap_auto_ES1_key.bin"), KEY)?;
+    fs::write(
+        dir.join("sap_auto_ES1.txt"),
+        encrypt_data("JDOE\nsecret", &KEY)?,
+    )?;
+    let mut store = FileCredentialStore::new(&dir, "100");
+    store.set("QS1", &Credentials::new("JDOE", "other"))?;
+
+    // Reading leaves the file as it is
+    let legacy = fs::read_to_string(store.auth_file("ES1"))?;
+    assert_eq!(store.get("ES1")?, Some(Credentials::new("JDOE", "secret")));
+    assert_eq!(fs::read_to_string(store.auth_file("ES1"))?, legacy);
+
+    assert_eq!(store.upgrade_entries()?, vec!["ES1"]);
+
+    let envelope = CredentialEnvelope::parse(&fs::read_to_string(store.auth_file("ES1"))?)?;
+    assert_eq!(envelope.instance_id, "ES1");
+    assert_eq!(envelope.client, "100");
+    assert_eq!(store.get("ES1")?, Some(Credentials::new("JDOE", "secret")));
+    assert!(store.upgrade_entries()?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_file_store_reports_why_reading_failed() -> anyhow::Result<()> {
+    let dir = test_dir("errors");
+    let mut store = FileCredentialStore::new(&dir, "025");
+    store.set("ES1", &Credentials::new("JDOE", "secret"))?;
+
+    let error = FileCredentialStore::new(&dir, "100")
+        .get("ES1")
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<CredentialError>(),
+        Some(CredentialError::WrongScope { .. })
+    ));
+
+    fs::write(store.key_file("ES1"), [8u8; 32])?;
+    let error = store.get("ES1").unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<CredentialError>(),
+        Some(&CredentialError::WrongKey)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,321 @@
+use sap_automation::utils::config_types::SapConfig;
+use sap_automation::utils::credential_envelope::CredentialEnvelope;
+use sap_automation::utils::credential_store::{
+    open_credential_store, CredentialStore, Credentials, EnvCredentialStore, FileCredentialStore,
+    KdfParams, VaultCredentialStore,
+};
+use sap_automation::utils::utils::encrypt_data;
+use std::fs;
+use std::path::PathBuf;
+
+/// Cheap key derivation settings so the tests stay fast
+const TEST_KDF: KdfParams = KdfParams {
+    memory_kib: 1024,
+    iterations: 1,
+    parallelism: 1,
+};
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sap_credentials_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_file_store_round_trip() -> anyhow::Result<()> {
+    let dir = test_dir("file_round_trip");
+    let mut store = FileCredentialStore::new(&dir, "025");
+
+    assert_eq!(store.get("ES1")?, None);
+    store.set("ES1", &Credentials::new("JDOE", "secret"))?;
+    store.set("QS1", &Credentials::new("JDOE", "other"))?;
+
+    assert_eq!(store.get("ES1")?, Some(Credentials::new("JDOE", "secret")));
+    assert_eq!(store.list()?, vec!["ES1", "QS1"]);
+
+    assert!(store.remove("QS1")?);
+    assert!(!store.remove("QS1")?);
+    assert!(!store.key_file("QS1").exists());
+    assert_eq!(store.list()?, vec!["ES1"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_file_store_reads_existing_files() -> anyhow::Result<()> {
+    let dir = test_dir("file_existing");
+    fs::create_dir_all(&dir)?;
+    let key = [7u8; 32];
+    // Files as written by earlier versions of the tool
+    fs::write(dir.join("sap_auto_rs_key.bin"), key)?;
+    fs::write(
+        dir.join("sap_auto_rs.txt"),
+        encrypt_data("JDOE\nsecret", &key)?,
+    )?;
+
+    let store = FileCredentialStore::new(&dir, "025");
+
+    assert_eq!(store.get("rs")?, Some(Credentials::new("JDOE", "secret")));
+
+    Ok(())
+}
+
+#[test]
+fn test_rotate_password_keeps_user() -> anyhow::Result<()> {
+    let dir = test_dir("file_rotate");
+    let mut store = FileCredentialStore::new(&dir, "025");
+    store.set("ES1", &Credentials::new("JDOE", "old"))?;
+
+    store.rotate_password("ES1", "new")?;
+
+    assert_eq!(store.get("ES1")?, Some(Credentials::new("JDOE", "new")));
+    assert!(store.rotate_password("QS1", "new").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_rotate_keys() -> anyhow::Result<()> {
+    let dir = test_dir("file_rotate_keys");
+    let mut store = FileCredentialStore::new(&dir, "025");
+    store.set("ES1", &Credentials::new("JDOE", "secret"))?;
+    store.set("QS1", &Credentials::new("JDOE", "other"))?;
+    let old_key = fs::read(store.key_file("ES1"))?;
+    let created = CredentialEnvelope::parse(&fs::read_to_string(store.auth_file("ES1"))?)?.created;
+
+    assert_eq!(store.rotate_keys()?, vec!["ES1", "QS1"]);
+
+    assert_ne!(fs::read(store.key_file("ES1"))?, old_key);
+    assert_eq!(store.get("ES1")?, Some(Credentials::new("JDOE", "secret")));
+    assert_eq!(store.get("QS1")?, Some(Credentials::new("JDOE", "other")));
+    let envelope = CredentialEnvelope::parse(&fs::read_to_string(store.auth_file("ES1"))?)?;
+    assert_eq!(envelope.created, created);
+    for id in ["ES1", "QS1"] {
+        assert!(!FileCredentialStore::backup_file(&store.key_file(id)).exists());
+        assert!(!FileCredentialStore::backup_file(&store.auth_file(id)).exists());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_rotate_keys_covers_entries_of_other_clients() -> anyhow::Result<()> {
+    let dir = test_dir("file_rotate_keys_clients");
+    let mut store = FileCredentialStore::new(&dir, "025");
+    store.set("ES1", &Credentials::new("JDOE", "secret"))?;
+    let mut other_client = FileCredentialStore::new(&dir, "100");
+    other_client.set("QS1", &Credentials::new("JSMITH", "other"))?;
+    let old_key = fs::read(store.key_file("QS1"))?;
+
+    assert_eq!(store.rotate_keys()?, vec!["ES1", "QS1"]);
+
+    assert_ne!(fs::read(store.key_file("QS1"))?, old_key);
+    assert_eq!(store.get("ES1")?, Some(Credentials::new("JDOE", "secret")));
+    assert_eq!(
+        other_client.get("QS1")?,
+        Some(Credentials::new("JSMITH", "other"))
+    );
+    let envelope = CredentialEnvelope::parse(&fs::read_to_string(store.auth_file("QS1"))?)?;
+    assert_eq!(envelope.client, "100");
+
+    Ok(())
+}
+
+#[test]
+fn test_rotate_keys_changes_nothing_if_an_entry_is_unreadable() -> anyhow::Result<()> {
+    let dir = test_dir("file_rotate_keys_unreadable");
+    let mut store = FileCredentialStore::new(&dir, "025");
+    store.set("ES1", &Credentials::new("JDOE", "secret"))?;
+    store.set("QS1", &Credentials::new("JDOE", "other"))?;
+    fs::write(store.key_file("QS1"), [9u8; 32])?;
+    let old_key = fs::read(store.key_file("ES1"))?;
+
+    let error = store.rotate_keys().unwrap_err();
+
+    assert!(error.to_string().contains("no key was changed"));
+    assert_eq!(fs::read(store.key_file("ES1"))?, old_key);
+    assert_eq!(store.get("ES1")?, Some(Credentials::new("JDOE", "secret")));
+
+    Ok(())
+}
+
+#[test]
+fn test_rotate_keys_does_not_upgrade_files_when_cancelled() -> anyhow::Result<()> {
+    let dir = test_dir("file_rotate_keys_no_upgrade");
+    let mut store = FileCredentialStore::new(&dir, "025");
+    store.set("QS1", &Credentials::new("JDOE", "other"))?;
+    fs::write(store.key_file("QS1"), [9u8; 32])?;
+    let key = [7u8; 32];
+    let legacy = encrypt_data("JDOE\nsecret", &key)?;
+    fs::write(store.key_file("ES1"), key)?;
+    fs::write(store.auth_file("ES1"), &legacy)?;
+
+    assert!(store.rotate_keys().is_err());
+
+    assert_eq!(fs::read_to_string(store.auth_file("ES1"))?, legacy);
+
+    Ok(())
+}
+
+#[test]
+fn test_rotate_keys_puts_every_entry_back_if_one_fails() -> anyhow::Result<()> {
+    let dir = test_dir("file_rotate_keys_swap");
+    let mut store = FileCredentialStore::new(&dir, "025");
+    store.set("ES1", &Credentials::new("JDOE", "secret"))?;
+    store.set("QS1", &Credentials::new("JDOE", "other"))?;
+    let old_keys = [
+        fs::read(store.key_file("ES1"))?,
+        fs::read(store.key_file("QS1"))?,
+    ];
+    // A directory in place of the temporary key file makes writing QS1 fail after ES1
+    let blocked = PathBuf::from(format!("{}.tmp", store.key_file("QS1").display()));
+    fs::create_dir_all(&blocked)?;
+
+    let error = store.rotate_keys().unwrap_err();
+
+    assert!(error.to_string().contains("old keys were kept"));
+    assert_eq!(fs::read(store.key_file("ES1"))?, old_keys[0]);
+    assert_eq!(fs::read(store.key_file("QS1"))?, old_keys[1]);
+    assert_eq!(store.get("ES1")?, Some(Credentials::new("JDOE", "secret")));
+    assert_eq!(store.get("QS1")?, Some(Credentials::new("JDOE", "other")));
+    for id in ["ES1", "QS1"] {
+        assert!(!FileCredentialStore::backup_file(&store.key_file(id)).exists());
+        assert!(!FileCredentialStore::backup_file(&store.auth_file(id)).exists());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_env_store() -> anyhow::Result<()> {
+    std::env::set_var("SAP_USER_ENV_TEST_1", "SVC_USER");
+    std::env::set_var("SAP_PASSWORD_ENV_TEST_1", "svc-secret");
+    let mut store = EnvCredentialStore;
+
+    assert_eq!(EnvCredentialStore::var_suffix("env-test.1"), "ENV_TEST_1");
+    assert_eq!(
+        store.get("env-test.1")?,
+        Some(Credentials::new("SVC_USER", "svc-secret"))
+    );
+    assert!(store.list()?.contains(&"ENV_TEST_1".to_string()));
+
+    assert!(store.is_read_only());
+    assert!(store
+        .set("env-test.1", &Credentials::new("OTHER", "x"))
+        .is_err());
+    assert!(store.rotate_keys().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_vault_round_trip() -> anyhow::Result<()> {
+    let path = test_dir("vault_round_trip").join("vault.json");
+    let mut vault = VaultCredentialStore::open_with(&path, "master", TEST_KDF)?;
+    vault.set("ES1", &Credentials::new("JDOE", "secret"))?;
+    vault.set("QS1", &Credentials::new("JDOE", "other"))?;
+    vault.remove("QS1")?;
+
+    let vault = VaultCredentialStore::open(&path, "master")?;
+
+    assert_eq!(vault.get("ES1")?, Some(Credentials::new("JDOE", "secret")));
+    assert_eq!(vault.list()?, vec!["ES1"]);
+
+    let content = fs::read_to_string(&path)?;
+    assert!(!content.contains("JDOE"), "Entries are encrypted");
+    assert!(
+        content.contains("memory_kib"),
+        "The KDF settings are stored"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_vault_wrong_master_password() -> anyhow::Result<()> {
+    let path = test_dir("vault_wrong_password").join("vault.json");
+    let mut vault = VaultCredentialStore::open_with(&path, "master", TEST_KDF)?;
+    vault.set("ES1", &Credentials::new("JDOE", "secret"))?;
+
+    let error = VaultCredentialStore::open(&path, "guess").err().unwrap();
+
+    assert!(error.to_string().contains("Wrong master password"));
+
+    Ok(())
+}
+
+#[test]
+fn test_vault_rejects_excessive_kdf_settings() -> anyhow::Result<()> {
+    let path = test_dir("vault_kdf_limits").join("vault.json");
+    let mut vault = VaultCredentialStore::open_with(&path, "master", TEST_KDF)?;
+    vault.set("ES1", &Credentials::new("JDOE", "secret"))?;
+
+    let content = fs::read_to_string(&path)?;
+    for (setting, value) in [
+        ("memory_kib", 64 * 1024 * 1024),
+        ("iterations", 1_000_000),
+        ("parallelism", 0),
+    ] {
+        let mut envelope: serde_json::Value = serde_json::from_str(&content)?;
+        envelope["kdf"][setting] = value.into();
+        fs::write(&path, envelope.to_string())?;
+
+        let error = VaultCredentialStore::open(&path, "master").err().unwrap();
+        assert!(
+            format!("{:#}", error).contains("outside"),
+            "{} = {} should be rejected: {:#}",
+            setting,
+            value,
+            error
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_vault_save_leaves_no_temporary_file() -> anyhow::Result<()> {
+    let dir = test_dir("vault_atomic");
+    let path = dir.join("vault.json");
+    let mut vault = VaultCredentialStore::open_with(&path, "master", TEST_KDF)?;
+    vault.set("ES1", &Credentials::new("JDOE", "secret"))?;
+    vault.set("ES1", &Credentials::new("JDOE", "changed"))?;
+
+    let files: Vec<_> = fs::read_dir(&dir)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(files, ["vault.json"]);
+    let vault = VaultCredentialStore::open(&path, "master")?;
+    assert_eq!(vault.get("ES1")?, Some(Credentials::new("JDOE", "changed")));
+
+    Ok(())
+}
+
+#[test]
+fn test_debug_hides_password() {
+    let credentials = Credentials::new("JDOE", "secret");
+    let debug = format!("{:?}", credentials);
+
+    assert!(debug.contains("JDOE"));
+    assert!(!debug.contains("secret"));
+}
+
+#[test]
+fn test_store_selection() -> anyhow::Result<()> {
+    let mut config = SapConfig::default();
+    assert_eq!(open_credential_store(&config, "025")?.name(), "file");
+
+    let global = config.global.as_mut().unwrap();
+    global
+        .additional_params
+        .insert("credential_store".to_string(), "env".to_string());
+    assert_eq!(open_credential_store(&config, "025")?.name(), "env");
+
+    let global = config.global.as_mut().unwrap();
+    global
+        .additional_params
+        .insert("credential_store".to_string(), "keychain".to_string());
+    assert!(open_credential_store(&config, "025").is_err());
+
+    Ok(())
+}
@@ -0,0 +1,243 @@
+mod common;
+
+use common::{add, create_session};
+use sap_automation::utils::sap_file_utils::save_sap_file;
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_faults::{MockFaultRule, E_FAIL, RPC_E_CALL_REJECTED};
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_screens::VT11_SHIPMENT_LIST;
+use sap_automation::utils::sap_wnd_utils::{check_export_window, close_popups};
+use std::time::{Duration, Instant};
+use windows::core::{Result, HRESULT};
+
+// Create a mock session with a main window and one popup
+fn create_popup_session() -> MockSapSession {
+    let mut session = create_session("Fault Session", "S000", "SAP Easy Access");
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Information");
+    session
+}
+
+// Create a mock session showing the "Save As" dialog of a list export
+fn create_save_session() -> MockSapSession {
+    let mut session = create_session("Save Session", "S000", "Shipment List");
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Save As");
+    add(&mut session, "wnd[1]/usr/ctxtDY_PATH", "GuiCTextField", "");
+    add(
+        &mut session,
+        "wnd[1]/usr/ctxtDY_FILENAME",
+        "GuiCTextField",
+        "",
+    );
+    add(
+        &mut session,
+        "wnd[1]/tbar[0]/btn[0]",
+        "GuiButton",
+        "Generate",
+    );
+    session
+}
+
+fn text_of(session: &MockSapSession, id: &str) -> String {
+    session.component(id).unwrap().borrow().text.clone()
+}
+
+#[test]
+fn test_injected_com_error_is_returned() {
+    let session = create_popup_session();
+    session.inject_fault(
+        MockFaultRule::error(RPC_E_CALL_REJECTED, "Call was rejected by callee")
+            .on_op("close")
+            .on_id("wnd[1]"),
+    );
+
+    let err = close_popups(&session, Some(1), None).unwrap_err();
+
+    assert_eq!(err.code(), HRESULT(RPC_E_CALL_REJECTED));
+    assert!(
+        session.component("wnd[1]").is_some(),
+        "A rejected close should leave the popup open"
+    );
+}
+
+#[test]
+fn test_fault_fires_limited_times() -> Result<()> {
+    let session = create_popup_session();
+    session.inject_fault(
+        MockFaultRule::error(RPC_E_CALL_REJECTED, "Call was rejected by callee")
+            .on_op("get_text")
+            .after_calls(1)
+            .times(1),
+    );
+    let window = session.find_by_id("wnd[0]".to_string())?;
+
+    assert!(window.get_text().is_ok(), "The first call is let through");
+    assert!(window.get_text().is_err(), "The second call fails");
+    assert_eq!(window.get_text()?, "SAP Easy Access");
+    assert_eq!(session.fault_rules()[0].fired(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_component_is_skipped() -> Result<()> {
+    let session = create_popup_session();
+    session.inject_fault(MockFaultRule::missing("/app/con[0]/ses[0]/wnd[1]"));
+
+    assert!(session.find_by_id("wnd[1]".to_string()).is_err());
+    close_popups(&session, Some(1), None)?;
+
+    assert!(
+        session.closed_windows().is_empty(),
+        "A popup that cannot be found is not closed"
+    );
+
+    session.clear_faults();
+    close_popups(&session, Some(1), None)?;
+    assert_eq!(session.closed_windows(), vec![1]);
+
+    Ok(())
+}
+
+#[test]
+fn test_close_popups_handles_unexpected_second_popup() -> Result<()> {
+    let session = create_popup_session();
+    session.inject_fault(
+        MockFaultRule::popup(2, "Warning", Some("Data was not saved"))
+            .on_op("close")
+            .on_id("wnd[1]")
+            .times(1),
+    );
+
+    close_popups(&session, Some(1), None)?;
+
+    assert_eq!(session.closed_windows(), vec![1, 2]);
+    assert!(session.component("wnd[2]").is_none());
+    assert!(session.component("wnd[2]/usr/txtMESSTXT1").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_injected_latency_delays_operation() -> Result<()> {
+    let session = create_popup_session();
+    session.inject_fault(MockFaultRule::latency(Duration::from_millis(50)).on_op("send_v_key"));
+
+    let start = Instant::now();
+    session.send_v_key(0, 0)?;
+
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert_eq!(session.keys_sent_to(0), vec![0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_status_message_after_action() -> Result<()> {
+    let session = create_popup_session();
+    session.inject_fault(
+        MockFaultRule::status("Shipment 1234 is currently being processed")
+            .on_op("send_v_key")
+            .on_id("wnd[0]"),
+    );
+
+    session.send_v_key(0, 11)?;
+
+    assert_eq!(
+        session.status_text(),
+        "Shipment 1234 is currently being processed"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_check_export_window_waits_for_spreadsheet_popup() -> Result<()> {
+    let mut session = create_session("Export Session", "VT11", "Shipment List");
+    // The export popup only opens once the list is exported (F12 on VT11)
+    session.inject_fault(
+        MockFaultRule::popup(1, "Select Spreadsheet", None)
+            .on_op("send_v_key")
+            .on_id("wnd[0]")
+            .times(1),
+    );
+
+    let result = check_export_window(&session, "VT11", &VT11_SHIPMENT_LIST)?;
+
+    assert!(result);
+    assert_eq!(session.keys_sent_to(0), vec![44]);
+    assert!(session.component("wnd[1]").is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_check_export_window_rejected_call() {
+    let mut session = MockSapSession::new("Export Session");
+    session.set_transaction("VT11");
+    add(&mut session, "wnd[0]", "GuiMainWindow", "Shipment List");
+    session.inject_fault(
+        MockFaultRule::error(RPC_E_CALL_REJECTED, "Call was rejected by callee")
+            .on_op("send_v_key"),
+    );
+
+    let err = check_export_window(&session, "VT11", &VT11_SHIPMENT_LIST).unwrap_err();
+
+    assert_eq!(err.code(), HRESULT(RPC_E_CALL_REJECTED));
+}
+
+#[test]
+fn test_save_sap_file_reports_error_popup() -> Result<()> {
+    let mut session = create_save_session();
+    add(
+        &mut session,
+        "wnd[1]/usr/txtMESSTXT1",
+        "GuiTextField",
+        "Directory does not exist",
+    );
+
+    let saved = save_sap_file(&session, "C:\\\\reports", "export.xlsx", None)?;
+
+    assert!(!saved);
+    assert_eq!(text_of(&session, "wnd[1]/usr/ctxtDY_PATH"), "");
+
+    Ok(())
+}
+
+#[test]
+fn test_save_sap_file_skips_missing_path_field() -> Result<()> {
+    let session = create_save_session();
+    session.inject_fault(MockFaultRule::missing("wnd[1]/usr/ctxtDY_PATH"));
+    session.inject_fault(
+        MockFaultRule::status("Data saved")
+            .on_op("press")
+            .on_id("wnd[1]/tbar[0]/btn[0]"),
+    );
+
+    let saved = save_sap_file(&session, "C:\\\\reports", "export.xlsx", None)?;
+
+    // The missing field is silently skipped, so the save still reports success
+    assert!(saved);
+    assert_eq!(text_of(&session, "wnd[1]/usr/ctxtDY_PATH"), "");
+    assert_eq!(
+        text_of(&session, "wnd[1]/usr/ctxtDY_FILENAME"),
+        "export.xlsx"
+    );
+    assert_eq!(session.status_text(), "Data saved");
+
+    Ok(())
+}
+
+#[test]
+fn test_save_sap_file_set_text_failure() {
+    let session = create_save_session();
+    session.inject_fault(
+        MockFaultRule::error(E_FAIL, "The control could not be changed")
+            .on_op("set_text")
+            .on_id("wnd[1]/usr/ctxtDY_FILENAME"),
+    );
+
+    let err = save_sap_file(&session, "C:\\\\reports", "export.xlsx", None).unwrap_err();
+
+    assert_eq!(err.code(), HRESULT(E_FAIL));
+    assert_eq!(text_of(&session, "wnd[1]/usr/ctxtDY_PATH"), "C:\\\\reports");
+}
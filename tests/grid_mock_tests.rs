@@ -0,0 +1,188 @@
+mod common;
+
+use common::{add, add_grid};
+use sap_automation::utils::sap_interfaces::{parse_row_selection, SapSession};
+use sap_automation::utils::sap_mock_impl::{MockGrid, MockSapSession};
+use sap_automation::utils::select_layout_utils::select_layout;
+use sap_automation::utils::setup_layout_utils::setup_layout;
+use windows::core::Result;
+
+const LAYOUT_GRID: &str =
+    "/usr/ssubD0500_SUBSCREEN:SAPLSLVC_DIALOG:0501/cntlG51_CONTAINER/shellcont/shell";
+const SETUP_BASE: &str = "/usr/tabsG_TS_ALV/tabpALV_M_R1/ssubSUB_DYN0510:SAPLSKBH:0620";
+
+// Create a layout selection grid with the given layout names
+fn layout_grid(layouts: &[&str]) -> MockGrid {
+    let mut grid = MockGrid::new(&["VARIANT", "TEXT"], 2);
+    for layout in layouts {
+        grid.add_row(&[layout, ""]);
+    }
+    grid
+}
+
+#[test]
+fn test_parse_row_selection() {
+    assert_eq!(parse_row_selection("5"), vec![5]);
+    assert_eq!(parse_row_selection("1,3"), vec![1, 3]);
+    assert_eq!(parse_row_selection("0-4"), vec![0, 1, 2, 3, 4]);
+    assert_eq!(parse_row_selection(""), Vec::<i32>::new());
+}
+
+#[test]
+fn test_grid_cell_access() -> Result<()> {
+    let mut session = MockSapSession::new("Grid Session");
+    add_grid(
+        &mut session,
+        "wnd[0]/usr/cntlGRID1/shellcont/shell",
+        "GuiShell",
+        layout_grid(&["DEFAULT", "DAILY"]),
+    );
+
+    let grid = session.find_by_id("wnd[0]/usr/cntlGRID1/shellcont/shell".to_string())?;
+
+    assert_eq!(grid.row_count()?, 2);
+    assert_eq!(grid.visible_row_count()?, 2);
+    assert_eq!(grid.get_cell_value(1, "VARIANT".to_string())?, "DAILY");
+    assert_eq!(
+        grid.get_cell_value(0, "0".to_string())?,
+        "DEFAULT",
+        "Columns should also be addressable by index"
+    );
+    assert!(grid.get_cell_value(5, "VARIANT".to_string()).is_err());
+    assert!(grid.get_cell_value(0, "MISSING".to_string()).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_grid_operations_fail_on_non_grid() -> Result<()> {
+    let mut session = MockSapSession::new("Grid Session");
+    add(&mut session, "wnd[0]/usr/txtFIELD", "GuiTextField", "");
+
+    let field = session.find_by_id("wnd[0]/usr/txtFIELD".to_string())?;
+
+    assert!(field.row_count().is_err());
+    assert!(field.set_current_cell_row(0).is_err());
+    assert!(field.get_cell_value(0, "VARIANT".to_string()).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_set_first_visible_row_clamps() -> Result<()> {
+    let mut session = MockSapSession::new("Grid Session");
+    let component = add_grid(
+        &mut session,
+        "wnd[0]/usr/cntlGRID1/shellcont/shell",
+        "GuiShell",
+        layout_grid(&["A", "B", "C", "D", "E"]),
+    );
+
+    let grid = session.find_by_id("wnd[0]/usr/cntlGRID1/shellcont/shell".to_string())?;
+
+    grid.set_first_visible_row(10)?;
+    assert_eq!(
+        grid.first_visible_row()?,
+        3,
+        "Scrolling should stop at the last page"
+    );
+
+    grid.set_first_visible_row(-1)?;
+    assert_eq!(
+        grid.first_visible_row()?,
+        0,
+        "Scrolling should stop at the top"
+    );
+
+    grid.set_selected_rows("1-2".to_string())?;
+    assert_eq!(
+        component.borrow().grid.as_ref().unwrap().selected_rows,
+        vec![1, 2]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_select_layout_double_clicks_match() -> Result<()> {
+    let mut session = MockSapSession::new("Layout Session");
+    add(&mut session, "wnd[1]", "GuiModalWindow", "");
+    let component = add_grid(
+        &mut session,
+        &format!("wnd[1]{}", LAYOUT_GRID),
+        "GuiShell",
+        layout_grid(&["DEFAULT", "daily_ship", "WEEKLY"]),
+    );
+
+    let result = select_layout(&session, 1, LAYOUT_GRID, "DAILY_SHIP")?;
+
+    assert!(result, "Layout should be found case-insensitively");
+    let component = component.borrow();
+    let grid = component.grid.as_ref().unwrap();
+    assert_eq!(grid.current_cell, (1, "VARIANT".to_string()));
+    assert_eq!(grid.selected_rows, vec![1]);
+    assert_eq!(grid.double_clicks, vec![(1, "VARIANT".to_string())]);
+
+    Ok(())
+}
+
+#[test]
+fn test_select_layout_without_window() -> Result<()> {
+    let session = MockSapSession::new("Layout Session");
+
+    let result = select_layout(&session, 1, LAYOUT_GRID, "DAILY_SHIP")?;
+
+    assert!(
+        !result,
+        "Layout selection should stop when no window is open"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_setup_layout_adds_listed_columns() -> Result<()> {
+    let mut session = MockSapSession::new("Layout Session");
+    add(&mut session, "wnd[0]/sbar", "GuiStatusbar", "");
+
+    let mut left = MockGrid::new(&["SELTEXT"], 10);
+    left.add_row(&["Delivery"]);
+    add_grid(
+        &mut session,
+        &format!("wnd[1]{}/cntlCONTAINER2_LAYO/shellcont/shell", SETUP_BASE),
+        "GuiShell",
+        left,
+    );
+
+    let mut right = MockGrid::new(&["SELTEXT"], 10);
+    for column in ["Shipment Number", "Created By", "Batch"] {
+        right.add_row(&[column]);
+    }
+    let right = add_grid(
+        &mut session,
+        &format!("wnd[1]{}/cntlCONTAINER1_LAYO/shellcont/shell", SETUP_BASE),
+        "GuiShell",
+        right,
+    );
+
+    let list = vec!["created by".to_string(), "Missing".to_string()];
+    let result = setup_layout(&session, 1, SETUP_BASE, "MYLAYOUT", &list, 3, true)?;
+
+    assert!(result, "Layout setup should succeed");
+    let right = right.borrow();
+    let grid = right.grid.as_ref().unwrap();
+    assert_eq!(
+        grid.cell_value(0, "SELTEXT").as_deref(),
+        Some("Batch"),
+        "Pressing the column header should sort the rows"
+    );
+    assert_eq!(grid.selected_columns, vec!["SELTEXT".to_string()]);
+    assert_eq!(
+        grid.double_clicks.len(),
+        1,
+        "Only listed columns should be added"
+    );
+    assert_eq!(grid.double_clicks[0].0, 1);
+
+    Ok(())
+}
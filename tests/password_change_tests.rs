@@ -0,0 +1,135 @@
+use sap_automation::utils::config_types::SapConfig;
+use sap_automation::utils::sap_constants::E_PASSWORD_CHANGE_REQUIRED;
+use sap_automation::utils::sap_mock_flow::MockScreenFlow;
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_password_change::{
+    change_expired_password, check_new_password, is_password_change_required, is_unattended,
+    password_change_required,
+};
+use sap_automation::utils::sap_session_state::SessionState;
+use windows::core::Result;
+
+const PASSWORD_FLOW: &str = r#"
+initial = "password_change"
+
+[[screen]]
+name = "login"
+transaction = "S000"
+program = "SAPMSYST"
+screen_number = 20
+components = [
+    { id = "wnd[0]", type = "GuiMainWindow", text = "SAP" },
+    { id = "wnd[0]/sbar", type = "GuiStatusbar" },
+]
+
+[[screen]]
+name = "password_change"
+base = "login"
+components = [
+    { id = "wnd[1]", type = "GuiModalWindow", text = "SAP: Change Password" },
+    { id = "wnd[1]/usr/pwdRSYST-NCODE", type = "GuiPasswordField" },
+    { id = "wnd[1]/usr/pwdRSYST-NCOD2", type = "GuiPasswordField" },
+]
+
+[[screen]]
+name = "easy_access"
+transaction = "SESSION_MANAGER"
+components = [
+    { id = "wnd[0]", type = "GuiMainWindow", text = "SAP Easy Access" },
+    { id = "wnd[0]/sbar", type = "GuiStatusbar" },
+]
+
+[[transition]]
+from = "password_change"
+action = "vkey"
+window = 1
+key = 0
+to = "easy_access"
+"#;
+
+fn create_session(flow: &str) -> MockSapSession {
+    MockSapSession::with_flow(
+        "Password Session",
+        MockScreenFlow::from_toml_str(flow).unwrap(),
+    )
+}
+
+#[test]
+fn test_password_change_dialog_is_detected() -> Result<()> {
+    let session = create_session(PASSWORD_FLOW);
+
+    assert_eq!(
+        SessionState::detect(&session)?,
+        SessionState::PasswordChange
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_change_expired_password() -> Result<()> {
+    let session = create_session(PASSWORD_FLOW);
+
+    change_expired_password(&session, "N3w-secret")?;
+
+    assert_eq!(session.keys_sent_to(1), vec![0]);
+    assert_eq!(session.current_screen().as_deref(), Some("easy_access"));
+    assert_eq!(SessionState::detect(&session)?, SessionState::EasyAccess);
+
+    Ok(())
+}
+
+#[test]
+fn test_fills_both_password_fields() -> Result<()> {
+    // Without the transition the dialog stays open, so the fields can be checked
+    let flow = PASSWORD_FLOW.split("[[transition]]").next().unwrap();
+    let session = create_session(flow);
+
+    let _ = change_expired_password(&session, "N3w-secret");
+
+    for id in ["wnd[1]/usr/pwdRSYST-NCODE", "wnd[1]/usr/pwdRSYST-NCOD2"] {
+        assert_eq!(session.component(id).unwrap().borrow().text, "N3w-secret");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_rejected_password_reports_the_reason() {
+    let flow = PASSWORD_FLOW.replace(
+        "to = \"easy_access\"",
+        "message = { kind = \"E\", message_class = \"00\", message_number = \"190\", params = [], \
+         text = \"Choose a password that is different from your last 5 passwords\" }",
+    );
+    let session = create_session(&flow);
+
+    let error = change_expired_password(&session, "0ld-secret").unwrap_err();
+
+    assert!(error
+        .message()
+        .to_string()
+        .contains("different from your last 5 passwords"));
+    assert_eq!(session.current_screen().as_deref(), Some("password_change"));
+}
+
+#[test]
+fn test_check_new_password() {
+    assert!(check_new_password("old", "new", "new").is_ok());
+    assert!(check_new_password("old", "", "").is_err());
+    assert!(check_new_password("old", "new", "neww").is_err());
+    assert!(check_new_password("old", "old", "old").is_err());
+}
+
+#[test]
+fn test_unattended_runs_get_a_distinct_error() {
+    let mut config = SapConfig::default();
+    assert!(!is_unattended(&config));
+
+    config.global.as_mut().unwrap().run.unattended = Some(true);
+    assert!(is_unattended(&config));
+
+    let error = password_change_required("JDOE");
+    assert_eq!(error.code().0, E_PASSWORD_CHANGE_REQUIRED);
+    assert!(is_password_change_required(&error));
+    assert!(error.message().to_string().contains("JDOE"));
+}
@@ -0,0 +1,297 @@
+mod common;
+
+use common::add;
+use sap_automation::utils::sap_constants::ParamsStruct;
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_faults::MockFaultRule;
+use sap_automation::utils::sap_mock_flow::MockScreenFlow;
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_popups::{PopupAction, PopupGuardSapSession, PopupRules};
+use sap_automation::utils::sap_wnd_utils::check_wnd;
+use windows::core::Result;
+
+const RULES: &str = r#"
+[[rule]]
+name = "save_changes"
+window_type = "GuiModalWindow"
+title = "(?i)^save changes"
+control = "/usr/btnSPOP-OPTION1"
+action = { press = "/usr/btnSPOP-OPTION1" }
+
+[[rule]]
+name = "delivery_locked"
+title = "(?i)error"
+action = { abort = "/usr/txtMESSTXT" }
+
+[[rule]]
+name = "information"
+program = "SAPLSPO1"
+screen_number = 300
+action = "log"
+"#;
+
+// Selection screen whose execute button asks to save changes first
+const FLOW: &str = r#"
+initial = "selection"
+
+[[screen]]
+name = "selection"
+transaction = "VL02N"
+components = [
+    { id = "wnd[0]", type = "GuiMainWindow", text = "Change Outbound Delivery" },
+    { id = "wnd[0]/sbar", type = "GuiStatusbar" },
+    { id = "wnd[0]/tbar[1]/btn[8]", type = "GuiButton", text = "Execute" },
+]
+
+[[screen]]
+name = "save_changes"
+base = "selection"
+components = [
+    { id = "wnd[1]", type = "GuiModalWindow", text = "Save Changes" },
+    { id = "wnd[1]/usr/btnSPOP-OPTION1", type = "GuiButton", text = "Yes" },
+    { id = "wnd[1]/usr/btnSPOP-OPTION2", type = "GuiButton", text = "No" },
+]
+
+[[transition]]
+from = "selection"
+action = "press"
+id = "wnd[0]/tbar[1]/btn[8]"
+to = "save_changes"
+
+[[transition]]
+from = "save_changes"
+action = "press"
+id = "wnd[1]/usr/btnSPOP-OPTION1"
+to = "selection"
+status = "Changes saved"
+"#;
+
+fn create_session() -> MockSapSession {
+    common::create_session("Popup Session", "S000", "SAP Easy Access")
+}
+
+fn flow_session() -> MockSapSession {
+    MockSapSession::with_flow("Flow Session", MockScreenFlow::from_toml_str(FLOW).unwrap())
+}
+
+#[test]
+fn test_builtin_rules() {
+    let rules = PopupRules::new();
+    let names: Vec<&str> = rules.rules().iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["log_off", "system_messages", "multiple_selection"]);
+}
+
+#[test]
+fn test_configured_rules_come_before_builtin_ones() {
+    let rules = PopupRules::from_toml_str(RULES).unwrap();
+    assert_eq!(rules.rules().len(), 6);
+    assert_eq!(rules.rules()[0].name, "save_changes");
+    assert_eq!(
+        rules.rules()[0].action,
+        PopupAction::Press("/usr/btnSPOP-OPTION1".to_string())
+    );
+    assert_eq!(rules.rules()[2].action, PopupAction::Log);
+    assert_eq!(rules.rules()[3].name, "log_off");
+
+    let only = PopupRules::from_toml_str(&format!("replace_defaults = true\n{}", RULES)).unwrap();
+    assert_eq!(only.rules().len(), 3);
+}
+
+#[test]
+fn test_invalid_rules_are_rejected() {
+    for content in [
+        "[[rule]]\nname = \"x\"\ntitle = \"(unclosed\"\naction = \"log\"",
+        "[[rule]]\nname = \"x\"\nscreen = \"no_such_screen\"\naction = \"log\"",
+        "[[rule]]\nname = \"x\"\naction = \"log\"",
+        "[[rule]]\nname = \"x\"\ntitle = \"x\"\naction = { press = \"/usr/btn[\" }",
+        "[[rule]]\nname = \"x\"\ntitle = \"x\"\naction = { reboot = 1 }",
+        "[[rule]]\nname = \"x\"\ntitel = \"x\"\naction = \"log\"",
+    ] {
+        assert!(
+            PopupRules::from_toml_str(content).is_err(),
+            "Should reject: {}",
+            content
+        );
+    }
+}
+
+#[test]
+fn test_rule_conditions() -> Result<()> {
+    let rules = PopupRules::from_toml_str(RULES).unwrap();
+    let mut session = create_session();
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Save changes?");
+
+    assert!(
+        rules.find(&session, 1)?.is_none(),
+        "The button required by the rule is missing"
+    );
+
+    add(
+        &mut session,
+        "wnd[1]/usr/btnSPOP-OPTION1",
+        "GuiButton",
+        "Yes",
+    );
+    let (rule, window) = rules.find(&session, 1)?.unwrap();
+    assert_eq!(rule.name, "save_changes");
+    assert_eq!(window.ctext, "Save changes?");
+
+    assert!(rules.find(&session, 2)?.is_none(), "Window is not open");
+
+    Ok(())
+}
+
+#[test]
+fn test_rule_matches_program_and_dynpro() -> Result<()> {
+    let rules = PopupRules::from_toml_str(RULES).unwrap();
+    let mut session = create_session();
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Informationen");
+    session.set_screen("SAPLSPO1", 300);
+
+    let (rule, _) = rules.find(&session, 1)?.unwrap();
+    assert_eq!(rule.name, "information");
+
+    // A log rule leaves the window open
+    assert!(!rules.close_window(&session, 1)?);
+    assert!(session.component("wnd[1]").is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_log_off_rule_leaves_other_confirmation_popups_open() -> Result<()> {
+    let rules = PopupRules::new();
+    let mut session = create_session();
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Delete Entry");
+    add(
+        &mut session,
+        "wnd[1]/usr/btnSPOP-OPTION2",
+        "GuiButton",
+        "No",
+    );
+    session.set_screen("SAPLSPO1", 100);
+
+    assert!(rules.handle_window(&session, 1)?.is_none());
+    assert!(session.component("wnd[1]").is_some());
+
+    // The same popup titled "Log Off" is answered with "No"
+    session.component("wnd[1]").unwrap().borrow_mut().text = "Log Off".to_string();
+    let rule = rules.handle_window(&session, 1)?.unwrap();
+    assert_eq!(rule.name, "log_off");
+
+    Ok(())
+}
+
+#[test]
+fn test_vkey_action() -> Result<()> {
+    let rules = PopupRules::from_toml_str(
+        "replace_defaults = true\n[[rule]]\nname = \"info\"\ntitle = \"Information\"\naction = { vkey = 0 }",
+    )
+    .unwrap();
+    let mut session = create_session();
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Information");
+
+    let rule = rules.handle_window(&session, 1)?.unwrap();
+
+    assert_eq!(rule.name, "info");
+    assert_eq!(session.keys_sent_to(1), vec![0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_abort_action_returns_popup_text() {
+    let rules = PopupRules::from_toml_str(RULES).unwrap();
+    let session = create_session();
+    session.inject_fault(
+        MockFaultRule::popup(1, "Error", Some("Delivery 80001234 is locked by USER1"))
+            .on_op("start_transaction"),
+    );
+    session.start_transaction("VL02N".to_string()).unwrap();
+
+    let err = rules.handle_popups(&session).unwrap_err();
+
+    assert!(err.message().to_string().contains("delivery_locked"));
+    assert!(err
+        .message()
+        .to_string()
+        .contains("Delivery 80001234 is locked by USER1"));
+}
+
+#[test]
+fn test_guard_handles_popup_after_action() -> Result<()> {
+    let rules = PopupRules::from_toml_str(RULES).unwrap();
+    let mock = flow_session();
+    let session = PopupGuardSapSession::new(&mock, &rules);
+
+    session
+        .find_by_id("wnd[0]/tbar[1]/btn[8]".to_string())?
+        .press()?;
+    assert_eq!(mock.current_screen().as_deref(), Some("save_changes"));
+
+    // The popup is handled before the next interaction
+    let statusbar = session.find_by_id("wnd[0]/sbar".to_string())?;
+    assert_eq!(mock.current_screen().as_deref(), Some("selection"));
+    assert!(mock.component("wnd[1]").is_none());
+    assert_eq!(statusbar.get_text()?, "Changes saved");
+
+    Ok(())
+}
+
+#[test]
+fn test_guard_leaves_unmatched_popups_open() -> Result<()> {
+    let rules = PopupRules::from_toml_str("replace_defaults = true").unwrap();
+    let mock = flow_session();
+    let session = PopupGuardSapSession::new(&mock, &rules);
+
+    session
+        .find_by_id("wnd[0]/tbar[1]/btn[8]".to_string())?
+        .press()?;
+
+    assert!(session.find_by_id("wnd[1]".to_string()).is_ok());
+    assert_eq!(mock.current_screen().as_deref(), Some("save_changes"));
+
+    Ok(())
+}
+
+#[test]
+fn test_guard_aborts_session_actions() {
+    let rules = PopupRules::from_toml_str(RULES).unwrap();
+    let mock = create_session();
+    mock.inject_fault(
+        MockFaultRule::popup(1, "Error", Some("No authorization")).on_op("start_transaction"),
+    );
+    let session = PopupGuardSapSession::new(&mock, &rules);
+
+    let err = session.start_transaction("VL02N".to_string()).unwrap_err();
+
+    assert!(err.message().to_string().contains("No authorization"));
+}
+
+#[test]
+fn test_check_wnd_uses_popup_rules() -> Result<()> {
+    let mut session = create_session();
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Log Off");
+    add(
+        &mut session,
+        "wnd[1]/usr/btnSPOP-OPTION2",
+        "GuiButton",
+        "No",
+    );
+    session.inject_fault(MockFaultRule::status("Log off cancelled").on_op("press"));
+    let params = ParamsStruct {
+        instance_id: "rs".to_string(),
+        client_id: "100".to_string(),
+        user: "TESTUSER".to_string(),
+        pass: "secret".to_string(),
+        language: "EN".to_string(),
+    };
+
+    let result = check_wnd(&session, 1, &params)?;
+
+    assert!(result.bchgb);
+    assert_eq!(session.fault_rules()[0].fired(), 1);
+    assert_eq!(session.status_text(), "Log off cancelled");
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use sap_automation::app::profile_arg;
+use sap_automation::utils::config_types::SapConfig;
+use std::fs;
+
+const PROFILE_CONFIG: &str = r#"
+[global]
+instance_id = "rs"
+reports_dir = "C:\\Reports"
+
+[profile.es1]
+instance_id = "ES1"
+client = "100"
+language = "de"
+connection = "ES1 - Development"
+
+[profile.prod]
+instance_id = "PRD"
+credentials = "PRD_SERVICE"
+"#;
+
+fn load_config(name: &str, content: &str) -> SapConfig {
+    let path = std::env::temp_dir().join(format!("sap_profiles_{}.toml", name));
+    fs::write(&path, content).unwrap();
+    SapConfig::load_from_path(path.to_str().unwrap()).unwrap()
+}
+
+#[test]
+fn test_profiles_are_loaded() {
+    let config = load_config("loaded", PROFILE_CONFIG);
+
+    assert_eq!(config.profile_names(), vec!["es1", "prod"]);
+    assert!(config.check_profile("es1").is_ok());
+    let error = config.check_profile("qa").unwrap_err().to_string();
+    assert!(error.contains("es1, prod"), "{}", error);
+}
+
+#[test]
+fn test_without_profile_global_settings_are_used() {
+    let config = load_config("global", PROFILE_CONFIG);
+
+    assert_eq!(config.active_profile_name(), None);
+    assert_eq!(config.get_instance_id(), "rs");
+    assert_eq!(config.get_client(), "025");
+    assert_eq!(config.get_language(), "EN");
+    assert_eq!(config.get_connection(), None);
+    assert_eq!(config.get_credential_id(), "rs");
+}
+
+#[test]
+fn test_active_profile_settings() {
+    let mut config = load_config("active", PROFILE_CONFIG);
+    config.active_profile = Some("es1".to_string());
+
+    assert_eq!(config.get_instance_id(), "ES1");
+    assert_eq!(config.get_client(), "100");
+    assert_eq!(config.get_language(), "DE");
+    assert_eq!(
+        config.get_connection().as_deref(),
+        Some("ES1 - Development")
+    );
+    assert_eq!(config.get_credential_id(), "ES1");
+
+    config.active_profile = Some("prod".to_string());
+    assert_eq!(config.get_instance_id(), "PRD");
+    assert_eq!(config.get_client(), "025", "Not set in the profile");
+    assert_eq!(config.get_credential_id(), "PRD_SERVICE");
+}
+
+#[test]
+fn test_default_profile_from_global() {
+    let content = PROFILE_CONFIG.replace(
+        "instance_id = \"rs\"",
+        "instance_id = \"rs\"\nprofile = \"prod\"",
+    );
+    let mut config = load_config("default", &content);
+
+    assert_eq!(config.active_profile_name().as_deref(), Some("prod"));
+    assert_eq!(config.get_instance_id(), "PRD");
+
+    config.active_profile = Some("es1".to_string());
+    assert_eq!(
+        config.get_instance_id(),
+        "ES1",
+        "The profile chosen for the run comes first"
+    );
+}
+
+#[test]
+fn test_profiles_of_one_instance_need_own_credentials_per_client() {
+    let content = format!(
+        "{}\n[profile.es1_025]\ninstance_id = \"ES1\"\nclient = \"025\"\n",
+        PROFILE_CONFIG
+    );
+    let config = load_config("shared_credentials", &content);
+
+    let error = config.check_profile("es1_025").unwrap_err().to_string();
+    assert!(error.contains("'es1'"), "{}", error);
+    assert!(config.check_profile("es1").is_err());
+    assert!(config.check_profile("prod").is_ok());
+
+    let content = format!("{}credentials = \"ES1_025\"\n", content);
+    let config = load_config("own_credentials", &content);
+
+    assert!(config.check_profile("es1_025").is_ok());
+    assert!(config.check_profile("es1").is_ok());
+}
+
+#[test]
+fn test_profiles_survive_save() {
+    let config = load_config("save", PROFILE_CONFIG);
+    let path = std::env::temp_dir().join("sap_profiles_saved.toml");
+    config.save_to_path(path.to_str().unwrap()).unwrap();
+
+    let mut saved = SapConfig::load_from_path(path.to_str().unwrap()).unwrap();
+    saved.active_profile = Some("es1".to_string());
+
+    assert_eq!(saved.profile_names(), vec!["es1", "prod"]);
+    assert_eq!(saved.get_client(), "100");
+    assert_eq!(
+        fs::read_to_string(&path)
+            .unwrap()
+            .matches("[profile.es1]")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn test_profile_arg() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        profile_arg(args(&["--profile", "es1"])).as_deref(),
+        Some("es1")
+    );
+    assert_eq!(
+        profile_arg(args(&["--profile=prod"])).as_deref(),
+        Some("prod")
+    );
+    assert_eq!(profile_arg(args(&["--verbose"])), None);
+    assert_eq!(profile_arg(args(&["--profile"])), None);
+}
@@ -0,0 +1,95 @@
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_impl::{MockComponent, MockSapSession};
+use sap_automation::utils::sap_recording::{read_trace, RecordingSapSession, TraceRecorder};
+use sap_automation::utils::sap_tcode_utils::assert_tcode;
+use serde_json::json;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use windows::core::Result;
+
+// Create an empty temporary directory for a trace
+fn trace_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sap_trace_tests_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+// Create a mock session with a status bar and a text field
+fn create_session() -> MockSapSession {
+    let mut session = MockSapSession::new("Trace Session");
+    for (id, r_type, text) in [
+        ("wnd[0]", "GuiMainWindow", "SAP Easy Access"),
+        ("wnd[0]/sbar", "GuiStatusbar", ""),
+        ("wnd[0]/usr/txtField", "GuiTextField", "old"),
+    ] {
+        let name = id.rsplit('/').next().unwrap_or(id);
+        let mut component = MockComponent::new(id, name, r_type);
+        component.text = text.to_string();
+        session.add_component(id, Rc::new(RefCell::new(component)));
+    }
+    session
+}
+
+#[test]
+fn test_recording_writes_jsonl_trace() -> Result<()> {
+    let dir = trace_dir("writes");
+    let recorder = Rc::new(TraceRecorder::create(&dir, "test_run").unwrap());
+    let session = RecordingSapSession::new(create_session(), recorder.clone());
+
+    session
+        .find_by_id("wnd[0]/usr/txtField".to_string())?
+        .set_text("new".to_string())?;
+    session.send_v_key(0, 8)?;
+    assert!(session.find_by_id("wnd[1]".to_string()).is_err());
+
+    let entries = read_trace(recorder.path()).unwrap();
+    let ops: Vec<&str> = entries.iter().map(|e| e.op.as_str()).collect();
+    assert_eq!(
+        ops,
+        vec!["find_by_id", "set_text", "send_v_key", "find_by_id"]
+    );
+
+    assert!(entries.iter().all(|e| e.run_id == "test_run"));
+    assert_eq!(
+        entries.iter().map(|e| e.seq).collect::<Vec<_>>(),
+        vec![1, 2, 3, 4]
+    );
+    assert_eq!(entries[1].id.as_deref(), Some("wnd[0]/usr/txtField"));
+    assert_eq!(entries[1].args, vec![json!("new")]);
+    assert_eq!(entries[2].args, vec![json!(0), json!(8)]);
+    assert!(
+        entries[3].error.is_some(),
+        "Missing components should be recorded as errors"
+    );
+    assert!(entries[3].result.is_none());
+
+    assert_eq!(session.inner().key_history(), vec![(0, 8)]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(())
+}
+
+#[test]
+fn test_recording_wraps_borrowed_session() -> Result<()> {
+    let dir = trace_dir("borrowed");
+    let recorder = Rc::new(TraceRecorder::create(&dir, "tcode_run").unwrap());
+    let mock = create_session();
+    let session = RecordingSapSession::new(&mock as &dyn SapSession, recorder.clone());
+
+    let result = assert_tcode(&session, "VT11", Some(0))?;
+    assert!(result);
+
+    let entries = read_trace(recorder.path()).unwrap();
+    assert_eq!(entries[0].op, "start_transaction");
+    assert_eq!(entries[0].args, vec![json!("VT11")]);
+    let status = entries
+        .iter()
+        .find(|e| e.op == "status_message")
+        .expect("status bar message should be recorded");
+    assert_eq!(status.id.as_deref(), Some("wnd[0]/sbar"));
+    assert_eq!(status.result.as_ref().unwrap()["text"], json!(""));
+
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use chrono::NaiveDate;
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_impl::{MockComponent, MockSapSession};
+use sap_automation::utils::sap_recording::{read_trace, RecordingSapSession, TraceRecorder};
+use sap_automation::utils::sap_replay::ReplaySapSession;
+use sap_automation::vt11::{run_export, VT11Params};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use windows::core::Result;
+
+// Create an empty temporary directory for a trace
+fn trace_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sap_replay_tests_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+// Load the VT11 flow fixture, starting on SAP Easy Access
+fn create_vt11_flow_session() -> MockSapSession {
+    let path = format!(
+        "{}/tests/fixtures/vt11_flow.toml",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    MockSapSession::from_flow_file("VT11 Flow", &path).expect("fixture should load")
+}
+
+fn vt11_params(day: u32) -> VT11Params {
+    VT11Params {
+        start_date: NaiveDate::from_ymd_opt(2024, 3, day).unwrap(),
+        end_date: NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+        by_date: true,
+        ..Default::default()
+    }
+}
+
+// Record a VT11 export against the mock flow and return the trace path
+fn record_vt11_run(dir: &Path) -> PathBuf {
+    let recorder = Rc::new(TraceRecorder::create(dir, "vt11_replay").unwrap());
+    let session = RecordingSapSession::new(create_vt11_flow_session(), recorder.clone());
+    let result = run_export(&session, &vt11_params(1)).unwrap();
+    assert!(!result, "Recorded run should find no shipments");
+    recorder.path().to_path_buf()
+}
+
+#[test]
+fn test_replay_recorded_vt11_run() -> Result<()> {
+    let dir = trace_dir("vt11");
+    let path = record_vt11_run(&dir);
+    let replay = ReplaySapSession::from_file(&path).unwrap();
+
+    let result = run_export(&replay, &vt11_params(1))?;
+
+    assert!(!result, "Replay should return the recorded outcome");
+    assert_eq!(replay.remaining(), 0);
+    assert!(replay.divergence().is_none());
+    replay.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_replay_detects_divergence() {
+    let dir = trace_dir("diverge");
+    let path = record_vt11_run(&dir);
+    let replay = ReplaySapSession::from_file(&path).unwrap();
+
+    // A different start date is typed into the selection screen
+    let result = run_export(&replay, &vt11_params(2));
+
+    assert!(result.is_err(), "Replay should fail on an unrecorded call");
+    let divergence = replay.divergence().expect("divergence should be reported");
+    assert!(divergence.contains("set_text"), "{}", divergence);
+    assert!(divergence.contains("03/02/2024"), "{}", divergence);
+    assert!(replay.finish().is_err());
+}
+
+#[test]
+fn test_replay_reports_unused_entries() -> Result<()> {
+    let dir = trace_dir("early");
+    let path = record_vt11_run(&dir);
+    let replay = ReplaySapSession::from_file(&path).unwrap();
+
+    replay.start_transaction("VT11".to_string())?;
+
+    assert!(replay.remaining() > 0);
+    assert!(replay.divergence().is_none());
+    assert!(
+        replay.finish().is_err(),
+        "Unreplayed entries should fail finish"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_replay_returns_recorded_errors() -> Result<()> {
+    let dir = trace_dir("errors");
+    let recorder = Rc::new(TraceRecorder::create(&dir, "errors").unwrap());
+    let mut session = MockSapSession::new("Error Session");
+    session.add_component(
+        "wnd[0]",
+        Rc::new(RefCell::new(MockComponent::new(
+            "wnd[0]",
+            "wnd[0]",
+            "GuiMainWindow",
+        ))),
+    );
+    let session = RecordingSapSession::new(session, recorder.clone());
+    let recorded = session
+        .find_by_id("wnd[1]".to_string())
+        .err()
+        .expect("missing window should fail");
+    session.find_by_id("wnd[0]".to_string())?.send_v_key(0)?;
+
+    let replay = ReplaySapSession::new(read_trace(recorder.path()).unwrap());
+    let replayed = replay
+        .find_by_id("wnd[1]".to_string())
+        .err()
+        .expect("recorded error should be replayed");
+    assert_eq!(replayed.code(), recorded.code());
+    assert!(
+        replay.divergence().is_none(),
+        "A recorded error is not a divergence"
+    );
+
+    replay.find_by_id("wnd[0]".to_string())?.send_v_key(0)?;
+    replay.finish()?;
+
+    Ok(())
+}
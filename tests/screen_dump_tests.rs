@@ -0,0 +1,115 @@
+use sap_automation::utils::sap_ctrl_utils::paste_values_with_scroll;
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_screen_dump::{DumpComponent, ScreenDump};
+use windows::core::Result;
+
+fn fixture_path() -> String {
+    format!(
+        "{}/tests/fixtures/vl06o_selection_dump.json",
+        env!("CARGO_MANIFEST_DIR")
+    )
+}
+
+// Load the VL06O selection screen (with the multiple selection popup open)
+fn create_dump_session() -> MockSapSession {
+    MockSapSession::from_dump_file("VL06O Dump", &fixture_path()).expect("fixture should load")
+}
+
+const TABLE_ID: &str = "tabsTAB_STRIP/tabpSIVA/ssubSCREEN_HEADER:SAPLALDB:3010/tblSAPLALDBSINGLE";
+
+#[test]
+fn test_dump_loads_components_with_short_ids() -> Result<()> {
+    let session = create_dump_session();
+
+    assert_eq!(session.info()?.transaction()?, "VL06O");
+    let window = session.find_by_id("wnd[0]".to_string())?;
+    assert_eq!(window.get_text()?, "List of Outbound Deliveries");
+
+    let date = session.find_by_id("wnd[0]/usr/ctxtIT_WADAT-LOW".to_string())?;
+    assert_eq!(date.r_type()?, "GuiCTextField");
+    assert_eq!(date.name()?, "IT_WADAT-LOW");
+    assert_eq!(date.get_text()?, "03/01/2024");
+
+    let children = session.children("wnd[0]/usr");
+    assert_eq!(children.len(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_keeps_changeable_state() -> Result<()> {
+    let session = create_dump_session();
+
+    let display = session.find_by_id("wnd[0]/usr/txtIT_VSTEL-TEXT".to_string())?;
+    assert!(!display.changeable()?);
+    let input = session.find_by_id("wnd[0]/usr/ctxtIT_VBELN-LOW".to_string())?;
+    assert!(input.changeable()?);
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_table_cells() -> Result<()> {
+    let session = create_dump_session();
+
+    let table = session.find_by_id(format!("wnd[1]/usr/{}", TABLE_ID))?;
+    assert_eq!(table.row_count()?, 3);
+    assert_eq!(table.visible_row_count()?, 3);
+    assert_eq!(table.get_cell_value(0, "1".to_string())?, "80001234");
+
+    let cells = session.find_all_by_name("wnd[1]", "RSCSEL_255-SLOW_I", "GuiCTextField");
+    assert_eq!(cells.len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_paste_values_into_dumped_popup() -> Result<()> {
+    let session = create_dump_session();
+    let values = vec!["80005555".to_string(), "80006666,".to_string()];
+
+    let pasted = paste_values_with_scroll(&session, 1, TABLE_ID, &values, 3)?;
+
+    assert!(pasted);
+    let second = session
+        .component(&format!(
+            "wnd[1]/usr/{}/ctxtRSCSEL_255-SLOW_I[1,1]",
+            TABLE_ID
+        ))
+        .unwrap();
+    assert_eq!(second.borrow().text, "80006666");
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_round_trip() {
+    let dump = ScreenDump::from_file(&fixture_path()).unwrap();
+    let dir = std::env::temp_dir().join("sap_screen_dump_tests");
+    let path = dir.join("vl06o_selection_dump.json");
+
+    dump.save(&path).unwrap();
+    let reloaded = ScreenDump::from_file(path.to_str().unwrap()).unwrap();
+
+    assert_eq!(reloaded, dump);
+}
+
+#[test]
+fn test_dump_rejects_invalid_json() {
+    assert!(ScreenDump::from_json_str("{ \"windows\": [ { \"id\": 5 } ] }").is_err());
+
+    let minimal = ScreenDump::from_json_str(
+        r#"{ "windows": [ { "id": "wnd[0]", "type": "GuiMainWindow", "name": "wnd[0]" } ] }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        minimal.windows[0],
+        DumpComponent {
+            id: "wnd[0]".to_string(),
+            r_type: "GuiMainWindow".to_string(),
+            name: "wnd[0]".to_string(),
+            ..Default::default()
+        }
+    );
+}
@@ -0,0 +1,165 @@
+use chrono::NaiveDate;
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_flow::MockScreenFlow;
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_tcode_utils::{assert_tcode, check_tcode};
+use sap_automation::vt11::{run_export, VT11Params};
+use windows::core::Result;
+
+// Load the VT11 flow fixture, starting on SAP Easy Access
+fn create_vt11_flow_session() -> MockSapSession {
+    let path = format!(
+        "{}/tests/fixtures/vt11_flow.toml",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    MockSapSession::from_flow_file("VT11 Flow", &path).expect("fixture should load")
+}
+
+#[test]
+fn test_flow_starts_on_initial_screen() -> Result<()> {
+    let session = create_vt11_flow_session();
+
+    assert_eq!(session.current_screen().as_deref(), Some("easy_access"));
+    assert_eq!(session.info()?.transaction()?, "SESSION_MANAGER");
+    assert!(session
+        .find_by_id("wnd[0]/usr/ctxtK_DATEN-LOW".to_string())
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_flow_start_transaction_changes_screen() -> Result<()> {
+    let session = create_vt11_flow_session();
+
+    let result = check_tcode(&session, "VT11", Some(true), None)?;
+
+    assert!(result, "check_tcode should start VT11");
+    assert_eq!(session.current_screen().as_deref(), Some("vt11_selection"));
+    assert_eq!(
+        session.find_by_id("wnd[0]".to_string())?.get_text()?,
+        "Shipment List: Planning"
+    );
+
+    session.end_transaction()?;
+    assert_eq!(session.current_screen().as_deref(), Some("easy_access"));
+    assert_eq!(session.info()?.transaction()?, "SESSION_MANAGER");
+
+    Ok(())
+}
+
+#[test]
+fn test_flow_unknown_tcode_sets_status() -> Result<()> {
+    let session = create_vt11_flow_session();
+
+    let result = assert_tcode(&session, "VT11X", Some(0))?;
+
+    assert!(!result, "assert_tcode should fail for an unknown tCode");
+    assert_eq!(session.status_text(), "Transaction VT11X does not exist");
+    assert_eq!(session.current_screen().as_deref(), Some("easy_access"));
+
+    Ok(())
+}
+
+#[test]
+fn test_flow_run_export_no_shipments() -> Result<()> {
+    let session = create_vt11_flow_session();
+    let params = VT11Params {
+        start_date: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+        end_date: NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+        by_date: true,
+        ..Default::default()
+    };
+
+    let result = run_export(&session, &params)?;
+
+    assert!(!result, "Export should stop when no shipments are found");
+    assert_eq!(session.closed_windows(), vec![1]);
+    assert_eq!(
+        session.current_screen().as_deref(),
+        Some("vt11_selection"),
+        "Closing the popup should return to the selection screen"
+    );
+    let low = session.component("wnd[0]/usr/ctxtK_DATEN-LOW").unwrap();
+    assert_eq!(
+        low.borrow().text,
+        "03/01/2024",
+        "Entered values should survive the popup"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_flow_popup_closes_without_transition() -> Result<()> {
+    let flow = MockScreenFlow::from_toml_str(
+        r#"
+        initial = "popup"
+
+        [[screen]]
+        name = "popup"
+        components = [
+            { id = "wnd[0]", type = "GuiMainWindow" },
+            { id = "wnd[0]/sbar", type = "GuiStatusbar" },
+            { id = "wnd[1]", type = "GuiModalWindow", text = "System Messages" },
+            { id = "wnd[1]/usr/txtMESSTXT1", type = "GuiTextField" },
+        ]
+
+        [[transition]]
+        action = "vkey"
+        window = 0
+        key = 11
+        status = "Delivery 80001234 has been saved"
+        "#,
+    )
+    .expect("flow should parse");
+    let session = MockSapSession::with_flow("Popup Flow", flow);
+
+    session.close_window(1)?;
+    assert!(session.find_by_id("wnd[1]".to_string()).is_err());
+    assert!(session
+        .find_by_id("wnd[1]/usr/txtMESSTXT1".to_string())
+        .is_err());
+
+    session.send_v_key(0, 11)?;
+    assert_eq!(session.status_text(), "Delivery 80001234 has been saved");
+    assert_eq!(session.current_screen().as_deref(), Some("popup"));
+
+    Ok(())
+}
+
+#[test]
+fn test_flow_rejects_unknown_screens() {
+    let result = MockScreenFlow::from_toml_str(
+        r#"
+        initial = "start"
+
+        [[screen]]
+        name = "start"
+
+        [[transition]]
+        action = "press"
+        id = "wnd[0]/tbar[1]/btn[8]"
+        to = "missing"
+        "#,
+    );
+    assert!(
+        result.is_err(),
+        "Transitions to unknown screens should fail"
+    );
+
+    let result = MockScreenFlow::from_toml_str(
+        r#"
+        initial = "a"
+
+        [[screen]]
+        name = "a"
+        base = "b"
+
+        [[screen]]
+        name = "b"
+        base = "a"
+        "#,
+    );
+    assert!(result.is_err(), "Cyclic base screens should fail");
+}
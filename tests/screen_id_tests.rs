@@ -0,0 +1,226 @@
+mod common;
+
+use common::add;
+use sap_automation::utils::sap_constants::ParamsStruct;
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_screen_dump::ScreenDump;
+use sap_automation::utils::sap_screens::{
+    active_window, ScreenId, BIN_STATUS_LIST, EASY_ACCESS, LOGIN, LOG_OFF, VL06O_DELIVERY_LIST,
+    VT11_SHIPMENT_LIST,
+};
+use sap_automation::utils::sap_wnd_utils::{check_export_window, check_wnd};
+use windows::core::Result;
+
+fn params() -> ParamsStruct {
+    ParamsStruct {
+        instance_id: "rs".to_string(),
+        client_id: "100".to_string(),
+        user: "TESTUSER".to_string(),
+        pass: "secret".to_string(),
+        language: "DE".to_string(),
+    }
+}
+
+#[test]
+fn test_session_info_reports_screen_and_logon() -> Result<()> {
+    let mut session = MockSapSession::new("Info Session");
+    session.set_transaction("VL06O");
+    session.set_screen("SAPLSLVC_FULLSCREEN", 500);
+    session.set_logon("PRD", "100", "TESTUSER", "DE");
+
+    let info = session.info()?;
+    assert_eq!(info.transaction()?, "VL06O");
+    assert_eq!(info.program()?, "SAPLSLVC_FULLSCREEN");
+    assert_eq!(info.screen_number()?, 500);
+    assert_eq!(info.system_name()?, "PRD");
+    assert_eq!(info.client()?, "100");
+    assert_eq!(info.user()?, "TESTUSER");
+    assert_eq!(info.language()?, "DE");
+
+    let screen = ScreenId::current(&session)?;
+    assert_eq!(screen, ScreenId::new("SAPLSLVC_FULLSCREEN", 500));
+    assert_eq!(screen.to_string(), "SAPLSLVC_FULLSCREEN/0500");
+
+    Ok(())
+}
+
+#[test]
+fn test_flow_screens_report_program_and_dynpro() -> Result<()> {
+    let path = format!(
+        "{}/tests/fixtures/vt11_flow.toml",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let session = MockSapSession::from_flow_file("VT11 Flow", &path).unwrap();
+    assert!(EASY_ACCESS.is(&ScreenId::current(&session)?));
+
+    session.start_transaction("VT11".to_string())?;
+    assert_eq!(
+        ScreenId::current(&session)?,
+        ScreenId::new("", 0),
+        "Screens without a program clear it"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_screen_matches_by_program_before_title() -> Result<()> {
+    let mut session = MockSapSession::new("German Session");
+    add(&mut session, "wnd[0]", "GuiMainWindow", "Anmeldung");
+    session.set_screen("SAPMSYST", 20);
+
+    assert_eq!(active_window(&session)?, Some(0));
+    assert!(LOGIN.matches(&session, 0, "Anmeldung")?);
+    assert!(!EASY_ACCESS.matches(&session, 0, "Anmeldung")?);
+
+    // Only the active window is identified by program, others by title
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Abmelden");
+    assert!(!LOGIN.matches(&session, 0, "Anmeldung")?);
+    assert!(LOGIN.matches(&session, 0, "SAP")?);
+
+    Ok(())
+}
+
+#[test]
+fn test_screen_ignores_title_when_program_is_known() -> Result<()> {
+    let mut session = MockSapSession::new("English Session");
+    add(&mut session, "wnd[0]", "GuiMainWindow", "SAP Easy Access");
+    session.set_screen("SAPMSYST", 20);
+
+    assert!(!EASY_ACCESS.matches(&session, 0, "SAP Easy Access")?);
+    assert!(LOGIN.matches(&session, 0, "SAP Easy Access")?);
+
+    // Without a program the title is compared
+    session.set_screen("", 0);
+    assert!(EASY_ACCESS.matches(&session, 0, "SAP Easy Access")?);
+
+    Ok(())
+}
+
+#[test]
+fn test_shared_alv_screens_are_told_apart_by_transaction() -> Result<()> {
+    let mut session = MockSapSession::new("German Session");
+    add(
+        &mut session,
+        "wnd[0]",
+        "GuiMainWindow",
+        "Transportliste: Planung",
+    );
+    session.set_screen("SAPLKKBL", 500);
+    session.set_transaction("VT11");
+
+    assert!(VT11_SHIPMENT_LIST.matches(&session, 0, "Transportliste: Planung")?);
+    assert!(!BIN_STATUS_LIST.matches(&session, 0, "Transportliste: Planung")?);
+
+    session.set_transaction("LX03");
+    assert!(BIN_STATUS_LIST.matches(&session, 0, "Lagerplatzstatusreport: Übersicht")?);
+    assert!(!VT11_SHIPMENT_LIST.matches(&session, 0, "Shipment List: Planning")?);
+
+    session.set_screen("SAPLSLVC_FULLSCREEN", 500);
+    session.set_transaction("VL06O");
+    assert!(VL06O_DELIVERY_LIST.matches(&session, 0, "Auslieferungen")?);
+    session.set_transaction("MB51");
+    assert!(!VL06O_DELIVERY_LIST.matches(&session, 0, "List of Outbound Deliveries")?);
+
+    Ok(())
+}
+
+#[test]
+fn test_check_wnd_handles_german_log_off_popup() -> Result<()> {
+    let mut session = MockSapSession::new("German Session");
+    add(&mut session, "wnd[0]", "GuiMainWindow", "SAP Easy Access");
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Abmelden");
+    add(
+        &mut session,
+        "wnd[1]/usr/btnSPOP-OPTION2",
+        "GuiButton",
+        "Nein",
+    );
+    session.set_screen(LOG_OFF.program, LOG_OFF.screen_number);
+
+    let result = check_wnd(&session, 1, &params())?;
+
+    assert!(result.bchgb, "The log off popup should be recognized");
+    assert!(!result.msg.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_check_wnd_fills_german_login_screen() -> Result<()> {
+    let mut session = MockSapSession::new("German Session");
+    add(&mut session, "wnd[0]", "GuiMainWindow", "SAP-Anmeldung");
+    add(
+        &mut session,
+        "wnd[0]/usr/txtRSYST-BNAME",
+        "GuiTextField",
+        "",
+    );
+    add(
+        &mut session,
+        "wnd[0]/usr/txtRSYST-LANGU",
+        "GuiTextField",
+        "",
+    );
+    session.set_screen("SAPMSYST", 20);
+
+    let result = check_wnd(&session, 0, &params())?;
+
+    assert!(result.bchgb);
+    let user = session.component("wnd[0]/usr/txtRSYST-BNAME").unwrap();
+    assert_eq!(user.borrow().text, "TESTUSER");
+    let language = session.component("wnd[0]/usr/txtRSYST-LANGU").unwrap();
+    assert_eq!(language.borrow().text, "DE");
+
+    Ok(())
+}
+
+#[test]
+fn test_check_export_window_identifies_save_list_popup() -> Result<()> {
+    let radio =
+        "wnd[1]/usr/subSUBSCREEN_STEPLOOP:SAPLSPO5:0150/sub:SAPLSPO5:0150/radSPOPLI-SELFLAG[1,0]";
+    let mut session = MockSapSession::new("German Session");
+    session.set_transaction("VT11");
+    add(
+        &mut session,
+        "wnd[0]",
+        "GuiMainWindow",
+        "Transportliste: Planung",
+    );
+    add(
+        &mut session,
+        "wnd[1]",
+        "GuiModalWindow",
+        "Liste in Datei sichern...",
+    );
+    add(&mut session, radio, "GuiRadioButton", "Unkonvertiert");
+    add(&mut session, "wnd[1]/tbar[0]/btn[0]", "GuiButton", "Weiter");
+    session.set_screen("SAPLSPO5", 110);
+
+    assert!(check_export_window(&session, "VT11", &VT11_SHIPMENT_LIST)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_screen_dump_keeps_program() -> Result<()> {
+    let dump = ScreenDump::from_json_str(
+        r#"{
+            "transaction": "VL06O",
+            "program": "SAPLSLVC_FULLSCREEN",
+            "screen_number": 500,
+            "windows": [{ "id": "wnd[0]", "type": "GuiMainWindow", "name": "wnd[0]" }]
+        }"#,
+    )
+    .unwrap();
+
+    let session = dump.to_mock_session("Dump Session");
+
+    assert_eq!(
+        ScreenId::current(&session)?,
+        ScreenId::new("SAPLSLVC_FULLSCREEN", 500)
+    );
+
+    Ok(())
+}
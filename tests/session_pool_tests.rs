@@ -0,0 +1,251 @@
+mod common;
+
+use common::add;
+use sap_automation::utils::config_types::SapConfig;
+use sap_automation::utils::sap_mock_faults::MockFaultRule;
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_session_pool::{
+    close_session, max_sessions, open_session, pool_ids, run_parallel, SessionPool,
+    DEFAULT_MAX_SESSIONS,
+};
+use sap_automation::utils::sap_status::{MessageKind, StatusMessage};
+use sap_automation::utils::sequence_config::sequence_steps;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use windows::core::Result;
+
+fn create_session() -> MockSapSession {
+    let mut session = common::create_session("Pool Session", "SESSION_MANAGER", "SAP Easy Access");
+    add(&mut session, "wnd[0]/tbar[0]/okcd", "GuiOkCodeField", "");
+    session
+}
+
+fn session_ids(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| format!("/app/con[0]/ses[{}]", i))
+        .collect()
+}
+
+#[test]
+fn test_pool_needs_a_session() {
+    assert!(SessionPool::new(Vec::new()).is_err());
+}
+
+#[test]
+fn test_acquire_and_release() -> Result<()> {
+    let pool = SessionPool::new(session_ids(2))?;
+
+    let first = pool.acquire();
+    let second = pool.try_acquire().expect("A second session is free");
+    assert_ne!(first.id(), second.id());
+    assert_eq!(pool.in_use(), 2);
+    assert!(pool.try_acquire().is_none(), "Both sessions are leased");
+
+    let released = first.id().to_string();
+    drop(first);
+    assert_eq!(pool.in_use(), 1);
+    assert_eq!(pool.try_acquire().expect("Released session").id(), released);
+
+    Ok(())
+}
+
+#[test]
+fn test_acquire_waits_for_release() -> Result<()> {
+    let pool = SessionPool::new(session_ids(1))?;
+    let lease = pool.acquire();
+
+    thread::scope(|scope| {
+        let waiter = scope.spawn(|| pool.acquire().id().to_string());
+        thread::sleep(Duration::from_millis(50));
+        assert!(!waiter.is_finished(), "The only session is still leased");
+
+        drop(lease);
+        assert_eq!(waiter.join().unwrap(), "/app/con[0]/ses[0]");
+    });
+
+    Ok(())
+}
+
+#[test]
+fn test_run_parallel_keeps_job_order() -> Result<()> {
+    let pool = SessionPool::new(session_ids(3))?;
+    let jobs = ["VT11", "ZMDESNR", "VL06O", "VT11"];
+
+    let results = run_parallel(&pool, &jobs, |_, job| job.to_lowercase());
+
+    assert_eq!(results, vec!["vt11", "zmdesnr", "vl06o", "vt11"]);
+    assert_eq!(pool.in_use(), 0, "All sessions are returned to the pool");
+
+    Ok(())
+}
+
+#[test]
+fn test_run_parallel_never_shares_a_session() -> Result<()> {
+    let pool = SessionPool::new(session_ids(2))?;
+    let jobs: Vec<usize> = (0..8).collect();
+    let busy = Mutex::new(Vec::<String>::new());
+    let running = AtomicUsize::new(0);
+    let most_running = AtomicUsize::new(0);
+
+    run_parallel(&pool, &jobs, |session_id, _| {
+        {
+            let mut busy = busy.lock().unwrap();
+            assert!(
+                !busy.iter().any(|id| id == session_id),
+                "{} is driven by two jobs",
+                session_id
+            );
+            busy.push(session_id.to_string());
+        }
+        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+        most_running.fetch_max(now, Ordering::SeqCst);
+
+        thread::sleep(Duration::from_millis(20));
+
+        running.fetch_sub(1, Ordering::SeqCst);
+        busy.lock().unwrap().retain(|id| id != session_id);
+    });
+
+    assert!(most_running.load(Ordering::SeqCst) <= 2);
+    assert!(busy.lock().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_open_session_sends_new_session_command() -> Result<()> {
+    let session = create_session();
+
+    open_session(&session)?;
+
+    let okcd = session.component("wnd[0]/tbar[0]/okcd").unwrap();
+    assert_eq!(okcd.borrow().text, "/oSESSION_MANAGER");
+    assert_eq!(session.keys_sent_to(0), vec![0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_close_session_ends_it() -> Result<()> {
+    let session = create_session();
+
+    close_session(&session)?;
+
+    let okcd = session.component("wnd[0]/tbar[0]/okcd").unwrap();
+    assert_eq!(okcd.borrow().text, "/i");
+    assert_eq!(session.keys_sent_to(0), vec![0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_pool_holds_only_own_and_opened_sessions() {
+    let opened = vec![
+        "/app/con[0]/ses[3]".to_string(),
+        "/app/con[0]/ses[4]".to_string(),
+    ];
+
+    assert_eq!(
+        pool_ids("/app/con[0]/ses[1]", &opened, 3),
+        [
+            "/app/con[0]/ses[1]",
+            "/app/con[0]/ses[3]",
+            "/app/con[0]/ses[4]"
+        ]
+    );
+    assert_eq!(
+        pool_ids("/app/con[0]/ses[1]", &opened, 2),
+        ["/app/con[0]/ses[1]", "/app/con[0]/ses[3]"]
+    );
+    assert_eq!(
+        pool_ids("/app/con[0]/ses[1]", &[], 3),
+        ["/app/con[0]/ses[1]"]
+    );
+
+    let pool = SessionPool::new(pool_ids("/app/con[0]/ses[1]", &opened, 3))
+        .unwrap()
+        .with_opened(opened.clone());
+    assert_eq!(pool.opened(), opened);
+}
+
+#[test]
+fn test_sequence_steps_keep_configured_order() {
+    let options: Vec<String> = ["2", "9", "1", "7", "5", "9"]
+        .iter()
+        .map(|option| option.to_string())
+        .collect();
+
+    assert_eq!(
+        sequence_steps(&options, true),
+        vec![
+            vec!["2".to_string(), "9".to_string()],
+            vec!["1".to_string()],
+            vec!["7".to_string()],
+            vec!["5".to_string()],
+            vec!["9".to_string()],
+        ]
+    );
+    assert_eq!(sequence_steps(&options, false).len(), options.len());
+}
+
+#[test]
+fn test_parallel_flag_is_read_as_bool() {
+    let path = std::env::temp_dir().join("sap_session_pool_parallel.toml");
+    for (value, expected) in [
+        ("true", Some(true)),
+        ("\"TRUE\"", Some(true)),
+        ("\" True \"", Some(true)),
+        ("false", Some(false)),
+        ("\"yes\"", None),
+    ] {
+        std::fs::write(&path, format!("[sequence]\nparam_parallel = {}\n", value)).unwrap();
+        let config = SapConfig::load_from_path(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            config.sequence.unwrap().parallel,
+            expected,
+            "param_parallel = {}",
+            value
+        );
+        assert_eq!(config.dropped_keys.len(), usize::from(expected.is_none()));
+    }
+}
+
+#[test]
+fn test_open_session_reports_session_limit() {
+    let session = create_session();
+    session.inject_fault(
+        MockFaultRule::message(StatusMessage::new(
+            MessageKind::Error,
+            "14",
+            "008",
+            "Maximum number of sessions reached",
+        ))
+        .on_op("send_v_key")
+        .on_id("wnd[0]"),
+    );
+
+    let error = open_session(&session).unwrap_err();
+
+    assert!(error
+        .message()
+        .to_string()
+        .contains("Maximum number of sessions reached"));
+}
+
+#[test]
+fn test_max_sessions_from_config() {
+    let mut config = SapConfig::default();
+    assert_eq!(max_sessions(&config), DEFAULT_MAX_SESSIONS);
+
+    config.global.as_mut().unwrap().run.max_sessions = Some(4);
+    assert_eq!(max_sessions(&config), 4);
+
+    config.global.as_mut().unwrap().run.max_sessions = Some(0);
+    assert_eq!(
+        max_sessions(&config),
+        DEFAULT_MAX_SESSIONS,
+        "A limit of zero sessions is ignored"
+    );
+}
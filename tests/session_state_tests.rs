@@ -0,0 +1,171 @@
+mod common;
+
+use common::add;
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_faults::{MockFaultRule, E_FAIL, RPC_E_CALL_REJECTED};
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_session_state::{return_to_easy_access, SessionState};
+use sap_automation::utils::sap_tcode_utils::check_tcode;
+use windows::core::{Result, HRESULT};
+
+fn create_session(transaction: &str) -> MockSapSession {
+    common::create_session("State Session", transaction, "SAP")
+}
+
+#[test]
+fn test_login_screen() -> Result<()> {
+    let session = create_session("S000");
+
+    let state = SessionState::detect(&session)?;
+
+    assert_eq!(state, SessionState::LoginScreen);
+    assert!(state.is_connected());
+    assert!(!state.is_logged_in());
+    assert!(!state.can_start_transaction());
+
+    Ok(())
+}
+
+#[test]
+fn test_easy_access() -> Result<()> {
+    let session = create_session("SESSION_MANAGER");
+    assert_eq!(SessionState::detect(&session)?, SessionState::EasyAccess);
+
+    let mut session = create_session("SMEN");
+    session.set_screen("SAPLSMTR_NAVIGATION", 100);
+    assert_eq!(
+        SessionState::detect(&session)?,
+        SessionState::EasyAccess,
+        "The menu is also recognized by program and dynpro"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_in_transaction() -> Result<()> {
+    let mut session = create_session("VT11");
+    session.set_screen("SAPMV56A", 1011);
+
+    let state = SessionState::detect(&session)?;
+
+    assert_eq!(
+        state,
+        SessionState::InTransaction {
+            tcode: "VT11".to_string(),
+            program: "SAPMV56A".to_string(),
+            dynpro: 1011,
+        }
+    );
+    assert_eq!(state.tcode(), Some("VT11"));
+    assert!(state.can_start_transaction());
+    assert_eq!(state.to_string(), "transaction VT11 (SAPMV56A/1011)");
+
+    Ok(())
+}
+
+#[test]
+fn test_modal_popup() -> Result<()> {
+    let mut session = create_session("VL06O");
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Information");
+
+    let state = SessionState::detect(&session)?;
+
+    assert_eq!(
+        state,
+        SessionState::ModalPopup {
+            wnd: 1,
+            title: "Information".to_string()
+        }
+    );
+    assert!(state.is_logged_in());
+    assert!(!state.can_start_transaction());
+
+    Ok(())
+}
+
+#[test]
+fn test_password_change() -> Result<()> {
+    let mut session = create_session("S000");
+    add(&mut session, "wnd[1]", "GuiModalWindow", "SAP");
+    add(
+        &mut session,
+        "wnd[1]/usr/pwdRSYST-NCODE",
+        "GuiPasswordField",
+        "",
+    );
+
+    assert_eq!(
+        SessionState::detect(&session)?,
+        SessionState::PasswordChange
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_disconnected_session() -> Result<()> {
+    let session = create_session("VT11");
+    session
+        .inject_fault(MockFaultRule::error(E_FAIL, "The RPC server is unavailable").on_op("info"));
+
+    let state = SessionState::detect(&session)?;
+
+    assert_eq!(state, SessionState::Disconnected);
+    assert!(!state.is_connected());
+
+    Ok(())
+}
+
+#[test]
+fn test_busy_session_is_an_error() {
+    let session = create_session("VT11");
+    session.inject_fault(
+        MockFaultRule::error(RPC_E_CALL_REJECTED, "Call was rejected by callee").on_op("info"),
+    );
+
+    let err = SessionState::detect(&session).unwrap_err();
+
+    assert_eq!(err.code(), HRESULT(RPC_E_CALL_REJECTED));
+}
+
+#[test]
+fn test_return_to_easy_access() -> Result<()> {
+    let mut session = create_session("VT11");
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Information");
+
+    let state = return_to_easy_access(&session)?;
+
+    assert_eq!(state, SessionState::EasyAccess);
+    assert_eq!(session.closed_windows(), vec![1]);
+    assert_eq!(session.info()?.transaction()?, "SESSION_MANAGER");
+
+    Ok(())
+}
+
+#[test]
+fn test_return_to_easy_access_leaves_login_screen() -> Result<()> {
+    let session = create_session("S000");
+
+    assert_eq!(return_to_easy_access(&session)?, SessionState::LoginScreen);
+    assert_eq!(session.info()?.transaction()?, "S000");
+
+    Ok(())
+}
+
+#[test]
+fn test_check_tcode_requires_login() -> Result<()> {
+    let session = create_session("S000");
+
+    assert!(!check_tcode(&session, "VT11", Some(true), None)?);
+    assert_eq!(
+        session.info()?.transaction()?,
+        "S000",
+        "No transaction is started on the logon screen"
+    );
+
+    let session = create_session("SESSION_MANAGER");
+    assert!(check_tcode(&session, "VT11", Some(true), None)?);
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use common::add;
+use sap_automation::utils::config_types::SapConfig;
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_session_state::SessionState;
+use sap_automation::utils::sap_session_watchdog::{
+    max_relogins, SessionWatchdog, DEFAULT_MAX_RELOGINS,
+};
+use std::cell::Cell;
+use windows::core::{Error, Result, HRESULT};
+
+fn create_session(transaction: &str) -> MockSapSession {
+    common::create_session("Watchdog Session", transaction, "SAP")
+}
+
+/// Re-login that takes the mock session straight to SAP Easy Access
+fn relogin(session: &dyn SapSession) -> Result<()> {
+    session.start_transaction("SESSION_MANAGER".to_string())
+}
+
+fn step_error() -> Error {
+    Error::new(HRESULT(-2147467259), "Step failed".into())
+}
+
+#[test]
+fn test_ready_session_is_not_logged_in_again() -> Result<()> {
+    let session = create_session("SESSION_MANAGER");
+    let mut watchdog = SessionWatchdog::new(3, relogin);
+
+    assert_eq!(watchdog.ensure_ready(&session)?, SessionState::EasyAccess);
+    assert_eq!(watchdog.relogins(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_healthy_session_stays_in_its_transaction() -> Result<()> {
+    let session = create_session("VT11");
+    let mut watchdog = SessionWatchdog::new(3, relogin);
+
+    let state = watchdog.ensure_ready(&session)?;
+
+    assert_eq!(state.tcode(), Some("VT11"));
+    assert_eq!(session.info()?.transaction()?, "VT11");
+    assert_eq!(watchdog.relogins(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_logged_off_session_is_logged_in_again() -> Result<()> {
+    let session = create_session("S000");
+    let mut watchdog = SessionWatchdog::new(3, relogin);
+
+    assert_eq!(watchdog.ensure_ready(&session)?, SessionState::EasyAccess);
+    assert_eq!(watchdog.relogins(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_gives_up_after_max_relogins() {
+    let session = create_session("S000");
+    let attempts = Cell::new(0);
+    // The logon never gets past the logon screen
+    let mut watchdog = SessionWatchdog::new(2, |_| {
+        attempts.set(attempts.get() + 1);
+        Ok(())
+    });
+
+    let error = watchdog.ensure_ready(&session).unwrap_err();
+
+    assert_eq!(attempts.get(), 2);
+    assert!(error
+        .message()
+        .to_string()
+        .contains("giving up after 2 re-logins"));
+}
+
+#[test]
+fn test_failed_login_is_not_retried() {
+    let session = create_session("S000");
+    let attempts = Cell::new(0);
+    let mut watchdog = SessionWatchdog::new(3, |_| {
+        attempts.set(attempts.get() + 1);
+        Err(Error::new(
+            HRESULT(-2147467259),
+            "Name or password is incorrect".into(),
+        ))
+    });
+
+    assert!(watchdog.ensure_ready(&session).is_err());
+    assert_eq!(attempts.get(), 1, "A wrong password must not lock the user");
+}
+
+#[test]
+fn test_password_change_is_not_usable() {
+    let mut session = create_session("S000");
+    add(
+        &mut session,
+        "wnd[0]/usr/pwdRSYST-NCODE",
+        "GuiPasswordField",
+        "",
+    );
+    let mut watchdog = SessionWatchdog::new(3, relogin);
+
+    let error = watchdog.ensure_ready(&session).unwrap_err();
+
+    assert!(error
+        .message()
+        .to_string()
+        .contains("password change required"));
+    assert_eq!(watchdog.relogins(), 0);
+}
+
+#[test]
+fn test_step_is_resumed_after_logoff() -> Result<()> {
+    let session = create_session("SESSION_MANAGER");
+    let mut watchdog = SessionWatchdog::new(3, relogin);
+    let runs = Cell::new(0);
+
+    let result = watchdog.run_step(&session, || {
+        runs.set(runs.get() + 1);
+        if runs.get() == 1 {
+            // The session times out while the step runs
+            session.start_transaction("S000".to_string())?;
+            return Err(step_error());
+        }
+        Ok("exported")
+    })?;
+
+    assert_eq!(result, "exported");
+    assert_eq!(runs.get(), 2);
+    assert_eq!(watchdog.relogins(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_step_errors_on_a_logged_in_session_are_returned() {
+    let session = create_session("SESSION_MANAGER");
+    let mut watchdog = SessionWatchdog::new(3, relogin);
+    let runs = Cell::new(0);
+
+    let result: Result<()> = watchdog.run_step(&session, || {
+        runs.set(runs.get() + 1);
+        Err(step_error())
+    });
+
+    assert_eq!(result.unwrap_err().message().to_string(), "Step failed");
+    assert_eq!(runs.get(), 1);
+    assert_eq!(watchdog.relogins(), 0);
+}
+
+#[test]
+fn test_relogin_cap_is_per_step() -> Result<()> {
+    let session = create_session("SESSION_MANAGER");
+    let mut watchdog = SessionWatchdog::new(1, relogin);
+
+    for _ in 0..3 {
+        // Each step is logged off once and completes after logging in again
+        let logged_off = Cell::new(false);
+        watchdog.run_step(&session, || {
+            if !logged_off.replace(true) {
+                session.start_transaction("S000".to_string())?;
+                return Err(step_error());
+            }
+            Ok(())
+        })?;
+    }
+
+    assert_eq!(watchdog.relogins(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_max_relogins_from_config() {
+    let mut config = SapConfig::default();
+    assert_eq!(max_relogins(&config), DEFAULT_MAX_RELOGINS);
+
+    config.global.as_mut().unwrap().run.max_relogin_attempts = Some(5);
+    assert_eq!(max_relogins(&config), 5);
+
+    let path = std::env::temp_dir().join("sap_watchdog_relogins.toml");
+    std::fs::write(&path, "[global]\nmax_relogin_attempts = \"many\"\n").unwrap();
+    let config = SapConfig::load_from_path(path.to_str().unwrap()).unwrap();
+    assert_eq!(max_relogins(&config), DEFAULT_MAX_RELOGINS);
+    assert!(config.dropped_keys[0].starts_with("global.max_relogin_attempts"));
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use common::add;
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_faults::MockFaultRule;
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_status::{
+    read_status, MessageKind, StatusMessage, TRANSACTION_NOT_FOUND,
+};
+use sap_automation::utils::sap_tcode_utils::{assert_tcode, variant_select};
+use windows::core::Result;
+
+fn create_session() -> MockSapSession {
+    common::create_session("Status Session", "S000", "SAP Easy Access")
+}
+
+fn not_found(tcode: &str) -> StatusMessage {
+    StatusMessage::new(
+        MessageKind::Error,
+        "00",
+        "343",
+        &format!("Transaction {} does not exist", tcode),
+    )
+    .with_params(&[tcode])
+}
+
+#[test]
+fn test_message_kind_from_code() {
+    assert_eq!(MessageKind::from_code("S"), MessageKind::Success);
+    assert_eq!(MessageKind::from_code("e"), MessageKind::Error);
+    assert_eq!(MessageKind::from_code("W"), MessageKind::Warning);
+    assert_eq!(MessageKind::from_code("A"), MessageKind::Abort);
+    assert_eq!(MessageKind::from_code("X"), MessageKind::Abort);
+    assert_eq!(
+        MessageKind::from_code(""),
+        MessageKind::Info,
+        "Messages without a type are reported as Info"
+    );
+}
+
+#[test]
+fn test_message_matches_class_and_number() {
+    let message = not_found("VT11X");
+
+    assert!(message.is(TRANSACTION_NOT_FOUND));
+    assert!(message.is(("00", "0343")), "Leading zeros are ignored");
+    assert!(!message.is(("00", "344")));
+    assert!(message.is_error());
+    assert_eq!(
+        message.to_string(),
+        "E(00/343): Transaction VT11X does not exist"
+    );
+}
+
+#[test]
+fn test_read_typed_status_message() -> Result<()> {
+    let session = create_session();
+    assert_eq!(read_status(&session, 0)?, None, "Empty status bar");
+    assert_eq!(read_status(&session, 1)?, None, "Window without status bar");
+
+    session.set_status_message(&not_found("VT11X"));
+
+    let message = read_status(&session, 0)?.unwrap();
+    assert_eq!(message, not_found("VT11X"));
+    assert_eq!(message.params, vec!["VT11X"]);
+    assert_eq!(session.status_text(), "Transaction VT11X does not exist");
+
+    let statusbar = session.component("wnd[0]/sbar").unwrap();
+    assert_eq!(statusbar.borrow().properties["MessageType"], "E");
+    assert_eq!(statusbar.borrow().properties["MessageId"], "00");
+    assert_eq!(statusbar.borrow().properties["MessageNumber"], "343");
+
+    Ok(())
+}
+
+#[test]
+fn test_plain_status_text_has_no_message_class() -> Result<()> {
+    let session = create_session();
+    session.set_status_message(&not_found("VT11X"));
+    session.inject_fault(MockFaultRule::status("Data was saved").on_op("start_transaction"));
+
+    session.start_transaction("VT11".to_string())?;
+
+    let message = session.status_message().unwrap();
+    assert_eq!(message.kind, MessageKind::Info);
+    assert_eq!(message.message_class, "");
+    assert_eq!(message.text, "Data was saved");
+
+    Ok(())
+}
+
+#[test]
+fn test_status_message_requires_status_bar() {
+    let session = create_session();
+    let window = session.find_by_id("wnd[0]".to_string()).unwrap();
+    assert!(window.status_message().is_err());
+}
+
+#[test]
+fn test_assert_tcode_fails_on_error_message() -> Result<()> {
+    let session = create_session();
+    session.inject_fault(MockFaultRule::message(not_found("VT11X")).on_op("start_transaction"));
+
+    assert!(!assert_tcode(&session, "VT11X", Some(0))?);
+
+    let session = create_session();
+    assert!(
+        assert_tcode(&session, "VT11", Some(0))?,
+        "An empty status bar means the transaction started"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_variant_select_fails_on_error_message() -> Result<()> {
+    let mut session = create_session();
+    add(
+        &mut session,
+        "wnd[0]/tbar[1]/btn[17]",
+        "GuiButton",
+        "Get Variant",
+    );
+    add(&mut session, "wnd[1]/usr/txtV-LOW", "GuiTextField", "");
+    add(
+        &mut session,
+        "wnd[1]/tbar[0]/btn[8]",
+        "GuiButton",
+        "Execute",
+    );
+    session.inject_fault(
+        MockFaultRule::message(StatusMessage::new(
+            MessageKind::Error,
+            "DB",
+            "612",
+            "No values found",
+        ))
+        .on_op("press")
+        .on_id("wnd[1]/tbar[0]/btn[8]"),
+    );
+
+    assert!(!variant_select(&session, "VT11", "DAILY_SHIP")?);
+
+    Ok(())
+}
+
+#[test]
+fn test_flow_transition_sets_typed_message() -> Result<()> {
+    let path = format!(
+        "{}/tests/fixtures/vt11_flow.toml",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let session = MockSapSession::from_flow_file("Flow Session", &path).unwrap();
+
+    session.start_transaction("VT11X".to_string())?;
+
+    let message = read_status(&session, 0)?.unwrap();
+    assert!(message.is(TRANSACTION_NOT_FOUND));
+    assert_eq!(message.params, vec!["VT11X"]);
+
+    session.start_transaction("VT11".to_string())?;
+    assert_eq!(
+        read_status(&session, 0)?,
+        None,
+        "Entering a screen without a status clears the message"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{add, create_session};
+use sap_automation::utils::sap_mock_impl::{MockComponent, MockSapSession};
+use sap_automation::utils::sap_tcode_utils::{assert_tcode, variant_select};
+use sap_automation::vt11::{run_export, VT11Params};
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::rc::Rc;
+use windows::core::Result;
+
+// Look up a component that was added to the session
+fn session_field(session: &MockSapSession, id: &str) -> Rc<RefCell<MockComponent>> {
+    session.component(id).expect("component should exist")
+}
+
+// Create a mock session sitting on the VT11 selection screen
+fn create_vt11_session() -> MockSapSession {
+    let mut session = create_session("VT11 Session", "VT11", "Shipment List: Planning");
+    add(
+        &mut session,
+        "wnd[0]/usr/ctxtK_DATEN-LOW",
+        "GuiCTextField",
+        "",
+    );
+    add(
+        &mut session,
+        "wnd[0]/usr/ctxtK_DATEN-HIGH",
+        "GuiCTextField",
+        "",
+    );
+    add(
+        &mut session,
+        "wnd[0]/tbar[1]/btn[8]",
+        "GuiButton",
+        "Execute",
+    );
+    session
+}
+
+#[test]
+fn test_run_export_no_shipments_found() -> Result<()> {
+    let mut session = create_vt11_session();
+    let low = session_field(&session, "wnd[0]/usr/ctxtK_DATEN-LOW");
+    let high = session_field(&session, "wnd[0]/usr/ctxtK_DATEN-HIGH");
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Information");
+    add(
+        &mut session,
+        "wnd[1]/usr/txtMESSTXT1",
+        "GuiTextField",
+        "No shipments were found for the selection criteria",
+    );
+
+    let params = VT11Params {
+        start_date: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+        end_date: NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+        by_date: true,
+        ..Default::default()
+    };
+
+    let result = run_export(&session, &params)?;
+
+    assert!(!result, "Export should stop when no shipments are found");
+    assert_eq!(low.borrow().text, "03/01/2024", "Start date should be set");
+    assert_eq!(high.borrow().text, "03/05/2024", "End date should be set");
+    assert_eq!(
+        session.closed_windows(),
+        vec![1],
+        "The information popup should be closed"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_run_export_same_dates_blanks_high() -> Result<()> {
+    let mut session = create_vt11_session();
+    let high = session_field(&session, "wnd[0]/usr/ctxtK_DATEN-HIGH");
+    high.borrow_mut().text = "12/31/2023".to_string();
+    add(
+        &mut session,
+        "wnd[1]/usr/txtMESSTXT1",
+        "GuiTextField",
+        "No shipments were found for the selection criteria",
+    );
+
+    let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    let params = VT11Params {
+        start_date: date,
+        end_date: date,
+        by_date: true,
+        ..Default::default()
+    };
+
+    run_export(&session, &params)?;
+
+    assert_eq!(
+        high.borrow().text,
+        "",
+        "End date should be blank for a single day"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_run_export_full_flow() -> Result<()> {
+    let path = format!(
+        "{}/tests/fixtures/vt11_export_flow.toml",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let session =
+        MockSapSession::from_flow_file("VT11 Export", &path).expect("fixture should load");
+
+    // The mock does not write the export, so put the reports directory under a file
+    // where it cannot be created and save_sap_file does not wait for the file
+    let blocker = env::temp_dir().join("vt11_export_no_reports_dir");
+    fs::write(&blocker, b"").unwrap();
+    env::set_var("USERPROFILE", &blocker);
+
+    let params = VT11Params {
+        start_date: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+        end_date: NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+        by_date: true,
+        layout_row: Some("/DAILY".to_string()),
+        ..Default::default()
+    };
+
+    let result = run_export(&session, &params)?;
+
+    assert!(result, "Export should be saved");
+    assert_eq!(
+        session.current_screen().as_deref(),
+        Some("shipment_list"),
+        "All popups should be closed after saving"
+    );
+    assert_eq!(
+        session.keys_sent_to(2),
+        vec![0],
+        "The layout search should be confirmed"
+    );
+    assert_eq!(
+        session.keys_sent_to(3),
+        vec![2],
+        "The found layout should be picked"
+    );
+    assert_eq!(
+        session.keys_sent_to(1),
+        vec![2],
+        "The layout should be applied"
+    );
+    assert!(
+        session.closed_windows().is_empty(),
+        "No popup should be left to close"
+    );
+    assert_eq!(session.status_text(), "2,048 bytes transmitted");
+
+    Ok(())
+}
+
+#[test]
+fn test_assert_tcode_not_found() -> Result<()> {
+    let session = create_vt11_session();
+    session_field(&session, "wnd[0]/sbar").borrow_mut().text =
+        "Transaction VT11X does not exist".to_string();
+
+    let result = assert_tcode(&session, "VT11X", Some(0))?;
+
+    assert!(
+        !result,
+        "assert_tcode should fail when the tCode does not exist"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_variant_select_fills_popup() -> Result<()> {
+    let mut session = create_vt11_session();
+    add(
+        &mut session,
+        "wnd[0]/tbar[1]/btn[17]",
+        "GuiButton",
+        "Get Variant",
+    );
+    let variant = add(&mut session, "wnd[1]/usr/txtV-LOW", "GuiTextField", "");
+    let user = add(
+        &mut session,
+        "wnd[1]/usr/txtENAME-LOW",
+        "GuiTextField",
+        "SOMEUSER",
+    );
+    add(
+        &mut session,
+        "wnd[1]/tbar[0]/btn[8]",
+        "GuiButton",
+        "Execute",
+    );
+
+    let result = variant_select(&session, "VT11", "DAILY_SHIP")?;
+
+    assert!(result, "Variant selection should succeed");
+    assert_eq!(
+        variant.borrow().text,
+        "DAILY_SHIP",
+        "Variant name should be set"
+    );
+    assert_eq!(user.borrow().text, "", "Username should be cleared");
+
+    Ok(())
+}
+
+#[test]
+fn test_variant_select_missing_button() -> Result<()> {
+    let session = create_vt11_session();
+
+    let result = variant_select(&session, "VT11", "DAILY_SHIP")?;
+
+    assert!(
+        !result,
+        "Variant selection should fail without the variant button"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use common::add;
+use sap_automation::utils::config_types::SapConfig;
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_faults::{MockFaultRule, E_FAIL, RPC_E_CALL_REJECTED};
+use sap_automation::utils::sap_mock_impl::MockSapSession;
+use sap_automation::utils::sap_wait::{poll_until, wait_until, WaitCondition, WaitTimeouts};
+use std::fs;
+use std::time::{Duration, Instant};
+use windows::core::{Result, HRESULT};
+
+const TIMEOUT: Duration = Duration::from_millis(500);
+const POLL: Duration = Duration::from_millis(10);
+
+fn create_session() -> MockSapSession {
+    let mut session = common::create_session("Wait Session", "S000", "SAP Easy Access");
+    add(&mut session, "wnd[1]", "GuiModalWindow", "Information");
+    session
+}
+
+#[test]
+fn test_wait_for_session_not_busy() -> Result<()> {
+    let session = create_session();
+    session.set_busy_for(3);
+
+    assert!(wait_until(
+        &session,
+        &WaitCondition::NotBusy,
+        TIMEOUT,
+        POLL
+    )?);
+    assert!(!session.busy()?);
+
+    Ok(())
+}
+
+#[test]
+fn test_wait_times_out() -> Result<()> {
+    let session = create_session();
+    session.set_busy_for(u32::MAX);
+
+    let start = Instant::now();
+    let timeout = Duration::from_millis(50);
+    assert!(!wait_until(
+        &session,
+        &WaitCondition::NotBusy,
+        timeout,
+        POLL
+    )?);
+    assert!(start.elapsed() >= timeout);
+
+    Ok(())
+}
+
+#[test]
+fn test_wait_retries_rejected_calls() -> Result<()> {
+    let session = create_session();
+    session.inject_fault(
+        MockFaultRule::error(RPC_E_CALL_REJECTED, "Call was rejected by callee")
+            .on_op("find_by_id")
+            .times(2),
+    );
+
+    assert!(wait_until(
+        &session,
+        &WaitCondition::WindowExists(1),
+        TIMEOUT,
+        POLL
+    )?);
+    assert_eq!(session.fault_rules()[0].fired(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_wait_returns_other_errors() {
+    let session = create_session();
+    session.inject_fault(MockFaultRule::error(E_FAIL, "Connection lost").on_op("busy"));
+
+    let err = wait_until(&session, &WaitCondition::NotBusy, TIMEOUT, POLL).unwrap_err();
+    assert_eq!(err.code(), HRESULT(E_FAIL));
+}
+
+#[test]
+fn test_wait_for_windows() -> Result<()> {
+    let session = create_session();
+
+    assert!(wait_until(
+        &session,
+        &WaitCondition::WindowExists(1),
+        TIMEOUT,
+        POLL
+    )?);
+    assert!(!wait_until(
+        &session,
+        &WaitCondition::WindowExists(2),
+        POLL,
+        POLL
+    )?);
+
+    session.close_window(1)?;
+    assert!(wait_until(
+        &session,
+        &WaitCondition::WindowClosed(1),
+        TIMEOUT,
+        POLL
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn test_wait_for_text_and_transaction() -> Result<()> {
+    let session = create_session();
+    session.inject_fault(
+        MockFaultRule::status("Transaction VT11 started")
+            .on_op("start_transaction")
+            .times(1),
+    );
+
+    let status = WaitCondition::TextContains {
+        id: "wnd[0]/sbar".to_string(),
+        text: "VT11".to_string(),
+    };
+    assert!(!wait_until(&session, &status, POLL, POLL)?);
+
+    session.start_transaction("VT11".to_string())?;
+    assert!(wait_until(&session, &status, TIMEOUT, POLL)?);
+    assert!(wait_until(
+        &session,
+        &WaitCondition::Transaction("vt11".to_string()),
+        TIMEOUT,
+        POLL
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn test_wait_for_file_size_to_settle() -> Result<()> {
+    let dir = std::env::temp_dir().join("sap_wait_tests");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("export.xlsx");
+    let _ = fs::remove_file(&path);
+    let session = create_session();
+    let written = WaitCondition::FileStable {
+        path: path.clone(),
+        stable_for: Duration::from_millis(30),
+    };
+
+    assert!(!wait_until(
+        &session,
+        &written,
+        Duration::from_millis(50),
+        POLL
+    )?);
+
+    fs::write(&path, b"PK export").unwrap();
+    assert!(wait_until(&session, &written, TIMEOUT, POLL)?);
+
+    fs::remove_file(&path).unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_poll_until_checks_at_least_once() -> Result<()> {
+    let mut calls = 0;
+    assert!(poll_until(
+        || {
+            calls += 1;
+            Ok(true)
+        },
+        Duration::ZERO,
+        POLL
+    )?);
+    assert_eq!(calls, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_timeouts_from_global_config() {
+    let path = std::env::temp_dir().join("sap_wait_timeouts.toml");
+    std::fs::write(
+        &path,
+        "[global]\nwait_timeout_ms = 2500\nsave_timeout_ms = \"soon\"\n",
+    )
+    .unwrap();
+    let config = SapConfig::load_from_path(path.to_str().unwrap()).unwrap();
+
+    let timeouts = WaitTimeouts::from_config(&config);
+
+    assert_eq!(timeouts.default, Duration::from_millis(2500));
+    assert_eq!(
+        timeouts.save,
+        WaitTimeouts::default().save,
+        "Invalid values keep the default"
+    );
+    assert!(config.dropped_keys[0].starts_with("global.save_timeout_ms"));
+    assert_eq!(timeouts.poll, WaitTimeouts::default().poll);
+}
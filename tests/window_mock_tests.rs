@@ -0,0 +1,71 @@
+mod common;
+
+use common::{add, create_session};
+use sap_automation::utils::sap_interfaces::SapSession;
+use sap_automation::utils::sap_mock_impl::{MockSapSession, MockWindowEvent};
+use sap_automation::utils::sap_wnd_utils::close_popups;
+use windows::core::Result;
+
+// Create a mock session with a main window and two popups
+fn create_popup_session() -> MockSapSession {
+    let mut session = create_session("Window Session", "S000", "");
+    add(&mut session, "wnd[1]", "GuiModalWindow", "");
+    add(&mut session, "wnd[2]", "GuiModalWindow", "");
+    session
+}
+
+#[test]
+fn test_send_v_key_records_window_index() -> Result<()> {
+    let session = create_popup_session();
+
+    session.send_v_key(0, 8)?;
+    session.send_v_key(1, 16)?;
+    session.find_by_id("wnd[0]".to_string())?.send_v_key(11)?;
+
+    assert_eq!(session.key_history(), vec![(0, 8), (1, 16), (0, 11)]);
+    assert_eq!(session.keys_sent_to(0), vec![8, 11]);
+    assert_eq!(session.keys_sent_to(1), vec![16]);
+    assert!(session.keys_sent_to(2).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_send_v_key_missing_window() {
+    let session = create_popup_session();
+
+    assert!(session.send_v_key(3, 0).is_err());
+    assert!(session.close_window(3).is_err());
+    assert!(session.window_events().is_empty());
+}
+
+#[test]
+fn test_close_all_windows_highest_first() -> Result<()> {
+    let session = create_popup_session();
+
+    session.close_all_windows()?;
+
+    assert_eq!(session.closed_windows(), vec![2, 1]);
+    assert!(
+        session.key_history().is_empty(),
+        "Closing windows should not send keys"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_close_popups_records_events() -> Result<()> {
+    let session = create_popup_session();
+
+    close_popups(&session, Some(1), None)?;
+
+    let events = session.window_events();
+    assert_eq!(events[0], MockWindowEvent::Close { window: 1 });
+    assert!(session.closed_windows().iter().all(|w| *w == 1 || *w == 2));
+
+    session.clear_window_events();
+    assert!(session.window_events().is_empty());
+
+    Ok(())
+}